@@ -1,13 +1,30 @@
+mod compat;
+mod constraints;
+mod identifier;
+mod metadata;
+mod natural_language;
+mod pairing;
 mod public_handle;
+mod selection;
 mod testcase;
 
+pub use constraints::{parse_constraints, Constraint};
+pub use identifier::ClashIdentifier;
+pub use metadata::ClashMetadata;
+pub use pairing::{pair_validators, TestcasePair};
 pub use public_handle::PublicHandle;
+pub use selection::{PlainNumbers, TestSelection};
 use serde::{Deserialize, Serialize};
 use testcase::deserialize_testcases;
-pub use testcase::Testcase;
+pub use testcase::{testcase_warnings, Testcase};
 
 /// `Clash` represents a deserialized Clash of Code or I/O puzzle.
+///
+/// Deserialization goes through a more lenient intermediate representation
+/// that also accepts the JSON of very old contributions (see `compat.rs`),
+/// while serialization always produces the format of the current API.
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(try_from = "compat::ClashJson")]
 pub struct Clash {
     id: u32,
     #[serde(rename = "publicHandle")]
@@ -22,24 +39,52 @@ pub struct Clash {
     downvotes: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
 enum PuzzleType {
-    #[serde(rename = "CLASHOFCODE")]
+    #[default]
     Clash,
-    #[serde(rename = "PUZZLE_INOUT")]
     ClassicInOut,
+    // Other contribution types (multiplayer, optimization, ...) can be fetched
+    // but coctus does not know anything special about them. The type is kept
+    // so that it is written back as it was.
+    Other(String),
+}
+
+impl From<String> for PuzzleType {
+    fn from(puzzle_type: String) -> Self {
+        match puzzle_type.as_str() {
+            "CLASHOFCODE" => PuzzleType::Clash,
+            "PUZZLE_INOUT" => PuzzleType::ClassicInOut,
+            _ => PuzzleType::Other(puzzle_type),
+        }
+    }
+}
+
+impl From<PuzzleType> for String {
+    fn from(puzzle_type: PuzzleType) -> Self {
+        match puzzle_type {
+            PuzzleType::Clash => "CLASHOFCODE".to_string(),
+            PuzzleType::ClassicInOut => "PUZZLE_INOUT".to_string(),
+            PuzzleType::Other(puzzle_type) => puzzle_type,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ClashVersion {
+    #[serde(default)]
     version: u32,
     data: ClashData,
     #[serde(rename = "statementHTML")]
     statement_html: Option<String>,
 }
 
+// The aliases and defaults are for very old contributions which use
+// different key casing or lack some of the fields entirely.
 #[derive(Debug, Serialize, Deserialize)]
 struct ClashData {
+    #[serde(default)]
     title: String,
 
     // apparently some of these fields are missing in very old clashes, default to false
@@ -50,18 +95,29 @@ struct ClashData {
     #[serde(default)]
     shortest: bool,
 
+    #[serde(default)]
     statement: String,
 
-    #[serde(rename = "testCases")]
+    // Classic puzzles can split their statement into more sections than
+    // clashes do, the statement being what is left over
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    goal: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rules: Option<String>,
+    #[serde(alias = "example", default, skip_serializing_if = "Option::is_none")]
+    examples: Option<String>,
+
+    #[serde(rename = "testCases", alias = "testcases", alias = "test_cases")]
     #[serde(deserialize_with = "deserialize_testcases")]
     testcases: Vec<Testcase>,
 
+    #[serde(default)]
     constraints: Option<String>,
-    #[serde(rename = "stubGenerator")]
+    #[serde(rename = "stubGenerator", alias = "stub_generator", default)]
     stub_generator: Option<String>,
-    #[serde(rename = "inputDescription")]
+    #[serde(rename = "inputDescription", alias = "input_description", default)]
     input_description: String,
-    #[serde(rename = "outputDescription")]
+    #[serde(rename = "outputDescription", alias = "output_description", default)]
     output_description: String,
 }
 
@@ -70,6 +126,10 @@ impl Clash {
         &self.last_version.data.testcases
     }
 
+    pub fn public_handle(&self) -> &PublicHandle {
+        &self.public_handle
+    }
+
     pub fn codingame_link(&self) -> String {
         format!("https://www.codingame.com/contribute/view/{}", self.public_handle)
     }
@@ -82,6 +142,30 @@ impl Clash {
         &self.last_version.data.statement
     }
 
+    /// The statement rendered as HTML like on codingame.com, if the clash
+    /// has it (the input and output descriptions, the constraints and the
+    /// example are part of it).
+    pub fn statement_html(&self) -> Option<&str> {
+        non_empty(&self.last_version.statement_html)
+    }
+
+    /// The goal section of a classic puzzle, if it has one. Clashes only
+    /// have a [`statement`](Clash::statement).
+    pub fn goal(&self) -> Option<&str> {
+        non_empty(&self.last_version.data.goal)
+    }
+
+    /// The rules section of a classic puzzle, if it has one.
+    pub fn rules(&self) -> Option<&str> {
+        non_empty(&self.last_version.data.rules)
+    }
+
+    /// Examples written into the statement of a classic puzzle, if it has
+    /// any. Clashes use their first testcase as the example.
+    pub fn examples(&self) -> Option<&str> {
+        non_empty(&self.last_version.data.examples)
+    }
+
     pub fn constraints(&self) -> Option<&str> {
         self.last_version.data.constraints.as_deref()
     }
@@ -113,4 +197,239 @@ impl Clash {
     pub fn is_reverse_only(&self) -> bool {
         self.is_reverse() && !self.is_fastest() && !self.is_shortest()
     }
+
+    /// ISO 639-1 code of the natural language of the statement (such as
+    /// `"en"` or `"fr"`), guessed from its most common words. `None` if the
+    /// statement is too short to tell.
+    pub fn statement_language(&self) -> Option<&'static str> {
+        let data = &self.last_version.data;
+        let text = [self.goal(), Some(self.statement()), self.rules()]
+            .into_iter()
+            .flatten()
+            .chain([data.input_description.as_str(), data.output_description.as_str()])
+            .collect::<Vec<_>>()
+            .join("\n");
+        natural_language::detect(&text)
+    }
+
+    pub fn metadata(&self) -> ClashMetadata {
+        ClashMetadata::from(self)
+    }
+
+    /// Version number of the contribution, which CodinGame increments every
+    /// time the clash is edited.
+    pub fn version(&self) -> u32 {
+        self.last_version.version
+    }
+
+    /// Names of the parts of the puzzle that differ between `self` and
+    /// `other` (usually an older and a newer copy of the same clash). Votes
+    /// and other metadata are not compared.
+    pub fn changed_parts(&self, other: &Clash) -> Vec<&'static str> {
+        let (a, b) = (&self.last_version.data, &other.last_version.data);
+        let same_testcases = a.testcases.len() == b.testcases.len()
+            && a.testcases.iter().zip(&b.testcases).all(|(ta, tb)| {
+                (&ta.title, &ta.test_in, &ta.test_out, ta.is_validator)
+                    == (&tb.title, &tb.test_in, &tb.test_out, tb.is_validator)
+            });
+        [
+            ("title", a.title == b.title),
+            ("modes", (a.fastest, a.shortest, a.reverse) == (b.fastest, b.shortest, b.reverse)),
+            ("goal", a.goal == b.goal),
+            ("statement", a.statement == b.statement),
+            ("rules", a.rules == b.rules),
+            ("examples", a.examples == b.examples),
+            ("input description", a.input_description == b.input_description),
+            ("output description", a.output_description == b.output_description),
+            ("constraints", a.constraints == b.constraints),
+            ("stub generator", a.stub_generator == b.stub_generator),
+            ("testcases", same_testcases),
+        ]
+        .into_iter()
+        .filter_map(|(part, same)| (!same).then_some(part))
+        .collect()
+    }
+}
+
+fn non_empty(section: &Option<String>) -> Option<&str> {
+    section.as_deref().filter(|text| !text.trim().is_empty())
+}
+
+/// Deserializes only the testcases from the JSON of a clash.
+///
+/// This gives the same testcases as deserializing the whole [`Clash`] and
+/// calling [`Clash::testcases`], but it is faster for clashes with long
+/// statements because nothing else gets allocated or unescaped (see
+/// `benches/load_testcases.rs`).
+///
+/// # Examples
+///
+/// ```
+/// let json = r#"{
+///     "publicHandle": "abc123",
+///     "lastVersion": {
+///         "data": {
+///             "statement": "Print the input.",
+///             "testCases": [{ "title": "Simple", "testIn": "1", "testOut": "1" }]
+///         }
+///     }
+/// }"#;
+/// let testcases = clashlib::clash::testcases_from_json(json).unwrap();
+/// assert_eq!(testcases[0].title, "Simple");
+/// ```
+pub fn testcases_from_json(json: &str) -> serde_json::Result<Vec<Testcase>> {
+    let parsed: compat::TestcasesJson = serde_json::from_str(json)?;
+    parsed.into_testcases().ok_or_else(|| {
+        serde::de::Error::custom("contribution has no lastVersion and no testCases at the top level")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{testcase_warnings, Clash};
+    use crate::test_helper::sample_puzzle;
+
+    #[test]
+    fn deserialize_current_clash() {
+        let clash = sample_puzzle("stub_and_solution_tester").unwrap();
+        assert_eq!(clash.title(), "Boggus test");
+        assert_eq!(clash.testcases().len(), 8);
+        assert!(clash.is_fastest());
+    }
+
+    #[test]
+    fn deserialize_old_clash_without_last_version() {
+        let clash = sample_puzzle("old_clash_without_last_version").unwrap();
+        assert_eq!(clash.title(), "Ancient clash");
+        assert_eq!(clash.statement(), "Print the input.");
+        assert_eq!(clash.input_description(), "");
+        assert_eq!(clash.testcases().len(), 2);
+        assert_eq!(clash.testcases()[0].title, "Simple");
+        assert!(!clash.testcases()[0].is_validator);
+        assert!(clash.testcases()[1].is_validator);
+        assert_eq!(clash.testcases()[1].number, 1);
+        assert!(!clash.is_reverse());
+    }
+
+    #[test]
+    fn degenerate_testcases_are_warned_about() {
+        let clash = sample_puzzle("draft_without_testcases").unwrap();
+        assert!(clash.testcases().is_empty());
+        assert_eq!(testcase_warnings(clash.testcases()), vec!["The clash has no testcases"]);
+
+        let clash = sample_puzzle("draft_with_empty_testcases").unwrap();
+        assert_eq!(
+            testcase_warnings(clash.testcases()),
+            vec![
+                "\"Test 1\" has an empty input and expected output",
+                "\"Validator 1\" has an empty expected output",
+            ]
+        );
+        assert!(testcase_warnings(sample_puzzle("stub_and_solution_tester").unwrap().testcases()).is_empty());
+    }
+
+    #[test]
+    fn deserialize_old_clash_with_snake_case_keys() {
+        let clash = sample_puzzle("old_clash_snake_case_keys").unwrap();
+        assert_eq!(clash.title(), "Snake case");
+        assert_eq!(clash.stub_generator(), Some("read n:int\nwrite answer"));
+        assert_eq!(clash.output_description(), "The number");
+        let testcase = &clash.testcases()[0];
+        assert_eq!(testcase.title, "Test 1");
+        assert_eq!(testcase.test_in, "5");
+        assert_eq!(testcase.test_out, "5");
+    }
+
+    #[test]
+    fn deserialize_classic_puzzle_sections() {
+        let puzzle = sample_puzzle("classic_puzzle_sections").unwrap();
+        assert_eq!(puzzle.metadata().puzzle_type, "classic");
+        assert_eq!(puzzle.goal(), Some("Find the largest number."));
+        assert_eq!(
+            puzzle.rules(),
+            Some("Numbers are separated by spaces.\nThere is at least one number.")
+        );
+        assert_eq!(puzzle.examples(), Some("For `3 9 2` the answer is `9`."));
+        assert_eq!(puzzle.statement(), "");
+
+        let clash = sample_puzzle("stub_and_solution_tester").unwrap();
+        assert_eq!((clash.goal(), clash.rules(), clash.examples()), (None, None, None));
+        let json = serde_json::to_value(&clash).unwrap();
+        assert!(json["lastVersion"]["data"].get("goal").is_none());
+    }
+
+    #[test]
+    fn unknown_puzzle_types_are_kept() {
+        let clash = sample_puzzle("stub_and_solution_tester").unwrap();
+        let mut json = serde_json::to_value(&clash).unwrap();
+        assert_eq!(json["type"], "CLASHOFCODE");
+        json["type"] = "MULTIPLAYER".into();
+        let clash: Clash = serde_json::from_value(json).unwrap();
+        assert_eq!(clash.metadata().puzzle_type, "other");
+        assert_eq!(serde_json::to_value(&clash).unwrap()["type"], "MULTIPLAYER");
+    }
+
+    #[test]
+    fn deserialized_old_clash_serializes_to_current_format() {
+        let clash = sample_puzzle("old_clash_without_last_version").unwrap();
+        let json = serde_json::to_value(&clash).unwrap();
+        assert_eq!(json["lastVersion"]["data"]["title"], "Ancient clash");
+        assert_eq!(json["lastVersion"]["data"]["testCases"][1]["isValidator"], true);
+    }
+
+    #[test]
+    fn changed_parts_of_edited_clash() {
+        let old = sample_puzzle("stub_and_solution_tester").unwrap();
+        let mut new = sample_puzzle("stub_and_solution_tester").unwrap();
+        assert!(old.changed_parts(&new).is_empty());
+
+        new.upvotes += 1;
+        new.last_version.version += 1;
+        assert!(old.changed_parts(&new).is_empty());
+
+        new.last_version.data.statement.push('!');
+        new.last_version.data.testcases[2].test_out.push('x');
+        assert_eq!(old.changed_parts(&new), vec!["statement", "testcases"]);
+
+        new.last_version.data.testcases.pop();
+        assert_eq!(old.changed_parts(&new), vec!["statement", "testcases"]);
+    }
+
+    #[test]
+    fn deserialize_clash_without_any_puzzle_data_fails() {
+        let json = r#"{ "id": 1, "publicHandle": "abc123", "type": "CLASHOFCODE" }"#;
+        let err = serde_json::from_str::<super::Clash>(json).unwrap_err();
+        assert!(err.to_string().contains("lastVersion"));
+    }
+
+    #[test]
+    fn testcases_from_json_match_the_full_clash() {
+        for name in [
+            "stub_and_solution_tester",
+            "old_clash_without_last_version",
+            "old_clash_snake_case_keys",
+            "classic_puzzle_sections",
+        ] {
+            let path = format!("fixtures/puzzles/{name}.json");
+            let json = std::fs::read_to_string(path).unwrap();
+            let clash = sample_puzzle(name).unwrap();
+            let testcases = super::testcases_from_json(&json).unwrap();
+            let fields = |t: &super::Testcase| {
+                (
+                    t.index,
+                    t.number,
+                    t.title.clone(),
+                    t.test_in.clone(),
+                    t.test_out.clone(),
+                    t.is_validator,
+                )
+            };
+            assert_eq!(
+                testcases.iter().map(fields).collect::<Vec<_>>(),
+                clash.testcases().iter().map(fields).collect::<Vec<_>>()
+            );
+        }
+        let json = r#"{ "id": 1, "publicHandle": "abc123", "type": "CLASHOFCODE" }"#;
+        assert!(super::testcases_from_json(json).is_err());
+    }
 }
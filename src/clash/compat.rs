@@ -0,0 +1,95 @@
+use serde::Deserialize;
+
+use super::testcase::deserialize_testcases;
+use super::{Clash, ClashData, ClashVersion, PublicHandle, PuzzleType, Testcase};
+
+/// Lenient representation of the contribution JSON returned by CodinGame.
+///
+/// Contributions from the early days of Clash of Code don't always follow the
+/// current schema: some use snake_case keys, some lack the vote counts or the
+/// puzzle type, and some don't have a `lastVersion` at all, with the puzzle
+/// data stored at the top level instead.
+#[derive(Deserialize)]
+pub(super) struct ClashJson {
+    #[serde(default)]
+    id: u32,
+    #[serde(rename = "publicHandle", alias = "public_handle")]
+    public_handle: PublicHandle,
+    #[serde(rename = "lastVersion", alias = "last_version", alias = "latestVersion", default)]
+    last_version: Option<ClashVersion>,
+    #[serde(rename = "type", alias = "puzzle_type", default)]
+    puzzle_type: PuzzleType,
+    #[serde(rename = "upVotes", alias = "upvotes", alias = "up_votes", default)]
+    upvotes: i32,
+    #[serde(rename = "downVotes", alias = "downvotes", alias = "down_votes", default)]
+    downvotes: i32,
+    // Only used when there is no `lastVersion`
+    #[serde(flatten)]
+    top_level_data: Option<ClashData>,
+}
+
+impl TryFrom<ClashJson> for Clash {
+    type Error = String;
+
+    fn try_from(json: ClashJson) -> Result<Self, Self::Error> {
+        let last_version = match (json.last_version, json.top_level_data) {
+            (Some(version), _) => version,
+            (None, Some(data)) => ClashVersion {
+                version: 0,
+                data,
+                statement_html: None,
+            },
+            (None, None) => {
+                return Err(format!(
+                    "contribution {} has no lastVersion and no puzzle data (testCases) at the top level",
+                    json.public_handle
+                ))
+            }
+        };
+
+        Ok(Clash {
+            id: json.id,
+            public_handle: json.public_handle,
+            last_version,
+            puzzle_type: json.puzzle_type,
+            upvotes: json.upvotes,
+            downvotes: json.downvotes,
+        })
+    }
+}
+
+/// Just the testcases of the contribution JSON, with the same leniency as
+/// [`ClashJson`]. All other fields are skipped without being unescaped or
+/// allocated, which is where most of the time of deserializing a clash with a
+/// long statement goes.
+#[derive(Deserialize)]
+pub(super) struct TestcasesJson {
+    #[serde(rename = "lastVersion", alias = "last_version", alias = "latestVersion", default)]
+    last_version: Option<VersionTestcases>,
+    // Only used when there is no `lastVersion`
+    #[serde(rename = "testCases", alias = "testcases", alias = "test_cases", default)]
+    top_level_testcases: Option<TestcaseList>,
+}
+
+#[derive(Deserialize)]
+struct VersionTestcases {
+    data: DataTestcases,
+}
+
+#[derive(Deserialize)]
+struct DataTestcases {
+    #[serde(rename = "testCases", alias = "testcases", alias = "test_cases")]
+    testcases: TestcaseList,
+}
+
+#[derive(Deserialize)]
+struct TestcaseList(#[serde(deserialize_with = "deserialize_testcases")] Vec<Testcase>);
+
+impl TestcasesJson {
+    pub(super) fn into_testcases(self) -> Option<Vec<Testcase>> {
+        match (self.last_version, self.top_level_testcases) {
+            (Some(version), _) => Some(version.data.testcases.0),
+            (None, top_level) => top_level.map(|list| list.0),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+/// Bounds of one quantity in the constraints of a clash, such as
+/// `1 ≤ N ≤ 10^6`.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Constraint {
+    /// What is constrained, for example `N` or `Length of S`.
+    pub subject: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// The upper bound as written in the constraints (like `10^6`).
+    pub max_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    Greater,
+}
+
+/// Parses the bounds out of the constraints of a clash. Every line is
+/// expected to compare a quantity to numbers, like `1 ≤ N ≤ 10^6`,
+/// `0 < [[M]] < 2*10^5` or `Length of S <= 1000`, lines that don't look
+/// like that are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::clash::parse_constraints;
+///
+/// let constraints = parse_constraints("1 ≤ [[N]] ≤ {{10^6}}\nS only contains letters");
+/// assert_eq!(constraints.len(), 1);
+/// assert_eq!(constraints[0].subject, "N");
+/// assert_eq!(constraints[0].max, Some(1e6));
+/// ```
+pub fn parse_constraints(text: &str) -> Vec<Constraint> {
+    let text = ["[[", "]]", "{{", "}}", "<<", ">>", "`"]
+        .iter()
+        .fold(text.to_string(), |text, markup| text.replace(markup, ""));
+    text.lines().filter_map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Option<Constraint> {
+    let mut parts = Vec::new();
+    let mut comparisons = Vec::new();
+    let mut rest = line;
+    while let Some((idx, operator)) = find_comparison(rest) {
+        parts.push(rest[..idx].trim());
+        comparisons.push(operator.1);
+        rest = &rest[idx + operator.0.len()..];
+    }
+    parts.push(rest.trim().trim_end_matches('.'));
+
+    let mut constraint = Constraint {
+        subject: String::new(),
+        min: None,
+        max: None,
+        max_text: None,
+    };
+    for (pair, comparison) in parts.windows(2).zip(comparisons) {
+        let (left, right) = (pair[0], pair[1]);
+        let (bound, subject, is_max) = match (parse_number(left), parse_number(right)) {
+            (Some(_), Some(_)) | (None, None) => continue,
+            (Some(_), None) => (left, right, comparison == Comparison::Greater),
+            (None, Some(_)) => (right, left, comparison == Comparison::Less),
+        };
+        if subject.is_empty() {
+            continue
+        }
+        constraint.subject = subject.to_string();
+        let value = parse_number(bound);
+        if is_max {
+            constraint.max = value;
+            constraint.max_text = Some(bound.to_string());
+        } else {
+            constraint.min = value;
+        }
+    }
+    (!constraint.subject.is_empty()).then_some(constraint)
+}
+
+/// Position and (text, direction) of the first comparison operator in `s`.
+fn find_comparison(s: &str) -> Option<(usize, (&'static str, Comparison))> {
+    const OPERATORS: [(&str, Comparison); 6] = [
+        ("<=", Comparison::Less),
+        (">=", Comparison::Greater),
+        ("≤", Comparison::Less),
+        ("≥", Comparison::Greater),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+    ];
+    OPERATORS
+        .iter()
+        .filter_map(|operator| s.find(operator.0).map(|idx| (idx, *operator)))
+        // Longer operators first so that `<=` doesn't get parsed as `<`
+        .min_by_key(|(idx, operator)| (*idx, std::cmp::Reverse(operator.0.len())))
+}
+
+/// Parses numbers like `1000`, `-5`, `1e6`, `10^6`, `10⁶` and `2*10^5`.
+fn parse_number(s: &str) -> Option<f64> {
+    let mut number = String::new();
+    let mut in_superscript = false;
+    for c in s.chars().filter(|c| !matches!(c, ' ' | ',' | '_')) {
+        match "⁰¹²³⁴⁵⁶⁷⁸⁹".chars().position(|sup| sup == c) {
+            Some(digit) => {
+                if !in_superscript {
+                    number.push('^');
+                }
+                in_superscript = true;
+                number.push(char::from_digit(digit as u32, 10).expect("digit should be below 10"));
+            }
+            None => {
+                in_superscript = false;
+                number.push(c);
+            }
+        }
+    }
+    number
+        .split(['*', '×', 'x'])
+        .map(|factor| match factor.split_once('^') {
+            Some((base, exponent)) => Some(base.parse::<f64>().ok()?.powf(exponent.parse().ok()?)),
+            None => factor.parse::<f64>().ok().filter(|value| value.is_finite()),
+        })
+        .product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(text: &str) -> Vec<(String, Option<f64>, Option<f64>)> {
+        parse_constraints(text)
+            .into_iter()
+            .map(|constraint| (constraint.subject, constraint.min, constraint.max))
+            .collect()
+    }
+
+    #[test]
+    fn two_sided_bounds() {
+        assert_eq!(
+            bounds("1 ≤ N ≤ 10^6\n0 < [[M]] < 2*10^5\n-100 <= x,y <= 100"),
+            vec![
+                ("N".to_string(), Some(1.0), Some(1e6)),
+                ("M".to_string(), Some(0.0), Some(2e5)),
+                ("x,y".to_string(), Some(-100.0), Some(100.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn one_sided_and_reversed_bounds() {
+        assert_eq!(
+            bounds("Length of S <= 1000.\nN ≥ 3\n1e9 > K"),
+            vec![
+                ("Length of S".to_string(), None, Some(1000.0)),
+                ("N".to_string(), Some(3.0), None),
+                ("K".to_string(), None, Some(1e9)),
+            ]
+        );
+    }
+
+    #[test]
+    fn unparsable_lines_are_skipped() {
+        assert!(bounds("S contains only lowercase letters\nA < B").is_empty());
+        assert_eq!(parse_constraints("1 ≤ N ≤ {{10^6}}")[0].max_text.as_deref(), Some("10^6"));
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(parse_number("1 000 000"), Some(1e6));
+        assert_eq!(parse_number("2×10^9"), Some(2e9));
+        assert_eq!(parse_number("10⁶"), Some(1e6));
+        assert_eq!(parse_number("N"), None);
+        assert_eq!(parse_number("10^N"), None);
+    }
+}
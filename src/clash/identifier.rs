@@ -0,0 +1,148 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use super::PublicHandle;
+
+/// Anything that points at a puzzle on codingame.com: its handle or a link
+/// to it. Only [ClashIdentifier::Handle] can be used as is, the others have
+/// to be looked up on codingame.com first (see
+/// `clashlib::client::resolve_identifier`).
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::clash::ClashIdentifier;
+///
+/// let id: ClashIdentifier = "https://www.codingame.com/training/easy/the-descent".parse().unwrap();
+/// assert_eq!(id, ClashIdentifier::PuzzleSlug("the-descent".to_string()));
+///
+/// let id: ClashIdentifier = "https://www.codingame.com/contribute/view/682102420fbce0fce95e0ee56095ea2b9924"
+///     .parse()
+///     .unwrap();
+/// assert_eq!(id.handle().unwrap().to_string(), "682102420fbce0fce95e0ee56095ea2b9924");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ClashIdentifier {
+    /// A bare handle or a link with one, like
+    /// `https://www.codingame.com/contribute/view/<handle>`.
+    Handle(PublicHandle),
+    /// The handle of a Clash of Code game from a link like
+    /// `https://www.codingame.com/clashofcode/clash/report/<handle>`. It is
+    /// not the handle of the puzzle that was played.
+    ClashReport(String),
+    /// The name of a classic puzzle in links like
+    /// `https://www.codingame.com/training/easy/<slug>` and
+    /// `https://www.codingame.com/ide/puzzle/<slug>`.
+    PuzzleSlug(String),
+}
+
+impl ClashIdentifier {
+    /// The handle, if it doesn't need to be looked up.
+    pub fn handle(&self) -> Option<&PublicHandle> {
+        match self {
+            ClashIdentifier::Handle(handle) => Some(handle),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ClashIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ClashIdentifier::Handle(handle) => write!(f, "clash {handle}"),
+            ClashIdentifier::ClashReport(game) => write!(f, "Clash of Code game {game}"),
+            ClashIdentifier::PuzzleSlug(slug) => write!(f, "puzzle {slug:?}"),
+        }
+    }
+}
+
+impl From<PublicHandle> for ClashIdentifier {
+    fn from(handle: PublicHandle) -> Self {
+        ClashIdentifier::Handle(handle)
+    }
+}
+
+impl FromStr for ClashIdentifier {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim_start_matches('\u{feff}').trim();
+        if !s.contains('/') {
+            return Ok(ClashIdentifier::Handle(PublicHandle::from_str(s)?))
+        }
+        let without_query = s.split(['?', '#']).next().unwrap_or(s);
+        let without_scheme = without_query.split_once("://").map_or(without_query, |(_, rest)| rest);
+        let mut segments = without_scheme.split('/').filter(|segment| !segment.is_empty());
+        let host = segments.next().unwrap_or_default();
+        let segments: Vec<&str> = segments.collect();
+        let is_hex = |segment: &str| segment.chars().all(|ch| ch.is_ascii_hexdigit());
+
+        if host.ends_with("codingame.com") {
+            match segments.as_slice() {
+                ["clashofcode", "clash", "report", game] | ["clashofcode", "clash", game] if is_hex(game) => {
+                    return Ok(ClashIdentifier::ClashReport(game.to_string()))
+                }
+                ["training", _, slug] | ["ide", "puzzle", slug] if is_slug(slug) => {
+                    return Ok(ClashIdentifier::PuzzleSlug(slug.to_string()))
+                }
+                _ => {}
+            }
+        }
+        // Links to contributions (and any other links that end in a handle)
+        match segments.is_empty() {
+            true => Err(anyhow!("no handle found in {:?}", s)),
+            false => PublicHandle::from_handle_or_url(without_query).map(ClashIdentifier::Handle),
+        }
+    }
+}
+
+fn is_slug(segment: &str) -> bool {
+    segment.chars().all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> ClashIdentifier {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn identifiers_from_links() {
+        let handle = || ClashIdentifier::Handle(PublicHandle::from_str("abc123").unwrap());
+        assert_eq!(parse(" abc123\n"), handle());
+        assert_eq!(parse("https://www.codingame.com/contribute/view/abc123/?a=b#c"), handle());
+        assert_eq!(parse("codingame.com/contribute/view/abc123"), handle());
+        assert_eq!(
+            parse("https://www.codingame.com/clashofcode/clash/report/9876fed"),
+            ClashIdentifier::ClashReport("9876fed".to_string())
+        );
+        assert_eq!(
+            parse("https://www.codingame.com/clashofcode/clash/9876fed"),
+            ClashIdentifier::ClashReport("9876fed".to_string())
+        );
+        assert_eq!(
+            parse("https://www.codingame.com/ide/puzzle/power-of-thor-episode-1"),
+            ClashIdentifier::PuzzleSlug("power-of-thor-episode-1".to_string())
+        );
+        assert_eq!(
+            parse("https://www.codingame.com/training/medium/there-is-no-spoon-episode-1?utm=x"),
+            ClashIdentifier::PuzzleSlug("there-is-no-spoon-episode-1".to_string())
+        );
+    }
+
+    #[test]
+    fn identifiers_reject_other_text() {
+        for text in [
+            "",
+            "xyz",
+            "https://example.com/list.txt",
+            "https://www.codingame.com/training/easy/The_Descent",
+        ] {
+            assert!(text.parse::<ClashIdentifier>().is_err(), "{text:?} should be refused");
+        }
+    }
+}
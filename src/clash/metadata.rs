@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::{Clash, PublicHandle, PuzzleType};
+
+/// Normalized summary of a [Clash] for scripting and for building indexes
+/// of the stored clashes.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+pub struct ClashMetadata {
+    pub public_handle: PublicHandle,
+    pub title: String,
+    /// Version number of the contribution (incremented on every edit).
+    pub version: u32,
+    /// `"clash"`, `"classic"` or `"other"`.
+    pub puzzle_type: &'static str,
+    /// Game modes of the clash in the order fastest, shortest, reverse.
+    pub modes: Vec<&'static str>,
+    /// Number of testcases that are not validators.
+    pub num_tests: usize,
+    pub num_validators: usize,
+    pub upvotes: i32,
+    pub downvotes: i32,
+    pub has_stub_generator: bool,
+    /// Length of the statement in characters (formatting tags included),
+    /// with the goal and rules of a classic puzzle counted in.
+    pub statement_length: usize,
+    /// Guessed language of the statement, see [`Clash::statement_language`].
+    pub statement_language: Option<&'static str>,
+    /// Number of words in the statement, the input and output descriptions
+    /// and the constraints.
+    pub statement_words: usize,
+    /// Estimated time it takes to read the statement in seconds, at
+    /// [READING_WORDS_PER_MINUTE].
+    pub reading_time_secs: u64,
+}
+
+/// Reading speed the reading time of a statement is estimated with. Typical
+/// for technical text, which is read more slowly than prose.
+pub const READING_WORDS_PER_MINUTE: usize = 200;
+
+impl ClashMetadata {
+    pub fn reading_time(&self) -> Duration {
+        Duration::from_secs(self.reading_time_secs)
+    }
+}
+
+impl From<&Clash> for ClashMetadata {
+    fn from(clash: &Clash) -> Self {
+        let modes = [
+            ("fastest", clash.is_fastest()),
+            ("shortest", clash.is_shortest()),
+            ("reverse", clash.is_reverse()),
+        ]
+        .into_iter()
+        .filter_map(|(mode, enabled)| enabled.then_some(mode))
+        .collect();
+        let puzzle_type = match clash.puzzle_type {
+            PuzzleType::Clash => "clash",
+            PuzzleType::ClassicInOut => "classic",
+            PuzzleType::Other(_) => "other",
+        };
+        let num_validators = clash.testcases().iter().filter(|test| test.is_validator).count();
+        let statement_words = [
+            clash.goal(),
+            Some(clash.statement()),
+            clash.rules(),
+            Some(clash.input_description()),
+            Some(clash.output_description()),
+            clash.constraints(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(count_words)
+        .sum();
+
+        ClashMetadata {
+            public_handle: clash.public_handle.clone(),
+            title: clash.title().to_string(),
+            version: clash.version(),
+            puzzle_type,
+            modes,
+            num_tests: clash.testcases().len() - num_validators,
+            num_validators,
+            upvotes: clash.upvotes,
+            downvotes: clash.downvotes,
+            has_stub_generator: clash.stub_generator().is_some_and(|stub| !stub.trim().is_empty()),
+            statement_length: [clash.goal(), Some(clash.statement()), clash.rules()]
+                .into_iter()
+                .flatten()
+                .map(|text| text.chars().count())
+                .sum(),
+            statement_language: clash.statement_language(),
+            statement_words,
+            reading_time_secs: (statement_words * 60).div_ceil(READING_WORDS_PER_MINUTE) as u64,
+        }
+    }
+}
+
+/// Words are separated by whitespace. Formatting tags such as `[[N]]` stick
+/// to their words, and tokens without letters or digits (like a lone `-`)
+/// are not counted.
+fn count_words(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::sample_puzzle;
+
+    #[test]
+    fn metadata_of_sample_clash() {
+        let clash = sample_puzzle("stub_and_solution_tester").unwrap();
+        let meta = clash.metadata();
+        assert_eq!(meta.title, clash.title());
+        assert_eq!(meta.num_tests + meta.num_validators, clash.testcases().len());
+        assert!(meta.modes.iter().all(|mode| ["fastest", "shortest", "reverse"].contains(mode)));
+        let json = serde_json::to_value(&meta).unwrap();
+        assert_eq!(json["public_handle"], clash.public_handle().to_string());
+    }
+
+    #[test]
+    fn statement_words_and_reading_time() {
+        assert_eq!(count_words("Print [[N]] times `{{x}}` - twice.\n\n1 ≤ N ≤ 10"), 8);
+        assert_eq!(count_words(" \n "), 0);
+
+        let meta = sample_puzzle("stub_and_solution_tester").unwrap().metadata();
+        assert!(meta.statement_words > 0);
+        let secs = meta.statement_words as f64 * 60.0 / READING_WORDS_PER_MINUTE as f64;
+        assert_eq!(meta.reading_time_secs, secs.ceil() as u64);
+    }
+}
@@ -0,0 +1,90 @@
+//! Guessing the natural language a statement is written in.
+//!
+//! Most contributions are in English or French, so a count of very common
+//! words of each language is enough to tell them apart without a model.
+
+/// Common words that are unlikely to appear in the other languages (or in
+/// code and variable names), by ISO 639-1 code. A word that is common in two
+/// of the languages (like "en" or "le", which are Spanish and French) is in
+/// neither list.
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "of", "to", "is", "in", "each", "that", "for", "with", "are", "on", "you", "your",
+            "given", "number", "print", "output", "line", "lines", "if", "it", "be", "which", "all", "from",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "les", "une", "des", "et", "est", "du", "dans", "pour", "qui", "sur", "avec", "au", "aux", "ce",
+            "cette", "sont", "chaque", "entier", "ligne", "lignes", "vous", "votre", "afficher", "sortie",
+            "entrée", "à", "il", "elle", "pas",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "los", "las", "por", "con", "cada", "número", "línea", "salida", "entrada", "del", "una",
+            "para",
+        ],
+    ),
+];
+
+/// Fewer matching words than this are not enough to decide.
+const MIN_MATCHES: usize = 3;
+
+/// ISO 639-1 code of the language `text` is most likely written in, or `None`
+/// if the text is too short (or too ambiguous) to tell.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let mut scores: Vec<(&'static str, usize)> = STOPWORDS
+        .iter()
+        .map(|(lang, stopwords)| {
+            (*lang, words.iter().filter(|word| stopwords.contains(&word.as_str())).count())
+        })
+        .collect();
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    match scores.as_slice() {
+        [(lang, best), (_, second), ..] if *best >= MIN_MATCHES && *best > 2 * second => Some(lang),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_english_and_french() {
+        let english = "Given a list of numbers, print the sum of each line. The output should be one number.";
+        let french =
+            "Étant donné une liste de nombres, afficher la somme de chaque ligne. La sortie est un nombre.";
+        assert_eq!(detect(english), Some("en"));
+        assert_eq!(detect(french), Some("fr"));
+    }
+
+    #[test]
+    fn short_or_mixed_texts_are_undecided() {
+        assert_eq!(detect("Print N"), None);
+        assert_eq!(detect("[[N]] {{42}} <<x>>"), None);
+        assert_eq!(detect("the and of / les des et"), None);
+    }
+
+    #[test]
+    fn stopwords_belong_to_one_language() {
+        for (idx, (lang, stopwords)) in STOPWORDS.iter().enumerate() {
+            for (other, other_stopwords) in &STOPWORDS[idx + 1..] {
+                let shared: Vec<_> = stopwords.iter().filter(|word| other_stopwords.contains(word)).collect();
+                assert!(shared.is_empty(), "{lang} and {other} share {shared:?}");
+            }
+        }
+        // Words that are also Spanish don't make French look Spanish
+        assert_eq!(detect("Il y a un nombre en entrée, si la ligne est vide afficher 0"), Some("fr"));
+    }
+}
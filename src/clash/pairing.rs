@@ -0,0 +1,154 @@
+use super::Testcase;
+
+/// A test and the validator that CodinGame made to go with it. Both are
+/// identified by their [`Testcase::index`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestcasePair {
+    pub test: usize,
+    pub validator: usize,
+}
+
+/// Pairs each validator with the test it is a variation of.
+///
+/// Tests and validators with the same title (apart from the words "test"
+/// and "validator", like "Test 3" and "Validator 3" or "Big numbers" and
+/// "Big numbers validator") are paired first, and the ones that are left
+/// are paired by their [`Testcase::number`] like in the CodinGame IDE.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::clash::{pair_validators, Testcase, TestcasePair};
+///
+/// let testcase = |index, number, title: &str, is_validator| Testcase {
+///     index,
+///     number,
+///     title: title.to_string(),
+///     test_in: String::new(),
+///     test_out: String::new(),
+///     is_validator,
+/// };
+/// let testcases = [
+///     testcase(1, 1, "Test 1", false),
+///     testcase(2, 1, "Validator 1", true),
+///     testcase(3, 2, "Test 2", false),
+/// ];
+/// assert_eq!(pair_validators(&testcases), vec![TestcasePair { test: 1, validator: 2 }]);
+/// ```
+pub fn pair_validators(testcases: &[Testcase]) -> Vec<TestcasePair> {
+    let tests: Vec<&Testcase> = testcases.iter().filter(|testcase| !testcase.is_validator).collect();
+    let validators: Vec<&Testcase> = testcases.iter().filter(|testcase| testcase.is_validator).collect();
+    let mut test_paired = vec![false; tests.len()];
+    let mut pairs: Vec<Option<TestcasePair>> = vec![None; validators.len()];
+
+    let mut pair_by = |key: &dyn Fn(&Testcase) -> Option<String>| {
+        for (pair, validator) in pairs.iter_mut().zip(&validators) {
+            let Some(validator_key) = pair.is_none().then(|| key(validator)).flatten() else {
+                continue
+            };
+            let test = tests
+                .iter()
+                .enumerate()
+                .position(|(idx, test)| !test_paired[idx] && key(test).as_ref() == Some(&validator_key));
+            if let Some(test) = test {
+                test_paired[test] = true;
+                *pair = Some(TestcasePair {
+                    test: tests[test].index,
+                    validator: validator.index,
+                });
+            }
+        }
+    };
+    pair_by(&title_key);
+    pair_by(&|testcase| Some(testcase.number.to_string()));
+
+    let mut pairs: Vec<TestcasePair> = pairs.into_iter().flatten().collect();
+    pairs.sort_by_key(|pair| pair.test);
+    pairs
+}
+
+/// The title of a testcase without the words that tell tests and validators
+/// apart, `None` if nothing else is left of it.
+fn title_key(testcase: &Testcase) -> Option<String> {
+    let title = testcase.title.to_lowercase();
+    let words: Vec<&str> = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && !matches!(*word, "test" | "validator"))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::testcase;
+
+    fn testcases(titles: &[(&str, bool)]) -> Vec<Testcase> {
+        let (mut num_tests, mut num_validators) = (0, 0);
+        titles
+            .iter()
+            .enumerate()
+            .map(|(idx, (title, is_validator))| {
+                let counter = if *is_validator {
+                    &mut num_validators
+                } else {
+                    &mut num_tests
+                };
+                *counter += 1;
+                Testcase {
+                    number: *counter,
+                    title: title.to_string(),
+                    is_validator: *is_validator,
+                    ..testcase(idx + 1, "", "")
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn titles_take_precedence_over_numbers() {
+        let testcases = testcases(&[
+            ("Simple", false),
+            ("Big numbers", false),
+            ("Big Numbers (validator)", true),
+            ("Simple validator", true),
+        ]);
+        assert_eq!(
+            pair_validators(&testcases),
+            vec![
+                TestcasePair {
+                    test: 1,
+                    validator: 4
+                },
+                TestcasePair {
+                    test: 2,
+                    validator: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn leftovers_are_paired_by_number() {
+        let testcases = testcases(&[
+            ("Test 1", false),
+            ("Validator 1", true),
+            ("Edge case", false),
+            ("Another one", true),
+            ("Extra test", false),
+        ]);
+        assert_eq!(
+            pair_validators(&testcases),
+            vec![
+                TestcasePair {
+                    test: 1,
+                    validator: 2
+                },
+                TestcasePair {
+                    test: 3,
+                    validator: 4
+                },
+            ]
+        );
+    }
+}
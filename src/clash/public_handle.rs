@@ -18,13 +18,43 @@ use serde::{Deserialize, Deserializer, Serialize};
 /// let invalid_handle = PublicHandle::from_str("xyz");
 /// assert!(invalid_handle.is_err());
 /// ```
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct PublicHandle(String);
 
+impl PublicHandle {
+    /// Parses a handle that may also be given as a link to the clash, such as
+    /// `https://www.codingame.com/contribute/view/<handle>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use clashlib::clash::PublicHandle;
+    ///
+    /// let url = "https://www.codingame.com/contribute/view/682102420fbce0fce95e0ee56095ea2b9924";
+    /// let handle = PublicHandle::from_handle_or_url(url).unwrap();
+    /// assert_eq!(handle.to_string(), "682102420fbce0fce95e0ee56095ea2b9924");
+    /// ```
+    pub fn from_handle_or_url(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        let without_query = s.split(['?', '#']).next().unwrap_or(s);
+        let last_segment = without_query.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+        if last_segment.is_empty() {
+            return Err(anyhow!("no handle found in {:?}", s))
+        }
+        PublicHandle::from_str(last_segment)
+    }
+}
+
 impl FromStr for PublicHandle {
     type Err = anyhow::Error;
+    /// Surrounding whitespace and a leading byte order mark are ignored so
+    /// that handles can be read from files that have been edited by hand.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        let s = s.trim_start_matches('\u{feff}').trim();
+        if s.is_empty() {
+            Err(anyhow!("handle is empty"))
+        } else if s.chars().all(|ch| ch.is_ascii_hexdigit()) {
             Ok(PublicHandle(String::from(s)))
         } else {
             Err(anyhow!("valid handles only contain characters 0-9 and a-f"))
@@ -47,3 +77,29 @@ impl<'de> Deserialize<'de> for PublicHandle {
         FromStr::from_str(&s).map_err(serde::de::Error::custom)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handle_from_url_ignores_query_and_trailing_slash() {
+        let url = "https://www.codingame.com/contribute/view/abc123/?a=b#c";
+        assert_eq!(PublicHandle::from_handle_or_url(url).unwrap().to_string(), "abc123");
+        assert_eq!(PublicHandle::from_handle_or_url(" abc123 ").unwrap().to_string(), "abc123");
+    }
+
+    #[test]
+    fn handle_from_url_rejects_other_text() {
+        assert!(PublicHandle::from_handle_or_url("https://example.com/list.txt").is_err());
+        assert!(PublicHandle::from_handle_or_url("").is_err());
+    }
+
+    #[test]
+    fn handle_ignores_whitespace_and_bom() {
+        let handle = PublicHandle::from_str("\u{feff}abc123\r\n").unwrap();
+        assert_eq!(handle.to_string(), "abc123");
+        assert!(PublicHandle::from_str(" \n").is_err());
+        assert!(PublicHandle::from_str("abc 123").is_err());
+    }
+}
@@ -0,0 +1,334 @@
+use anyhow::{anyhow, Result};
+
+use super::{Clash, Testcase};
+
+/// What a plain number like `3` refers to in a [TestSelection]. Numbers
+/// with a prefix mean the same either way: `#3` is the third testcase, `t2`
+/// the second test and `v1` the first validator.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PlainNumbers {
+    /// The position among all testcases, validators included.
+    #[default]
+    Testcases,
+    /// The number of a test, like in the CodinGame IDE.
+    Tests,
+}
+
+/// The testcases that a command works with, parsed from selection
+/// expressions like `2-5,!4` or picked with `--tests`, `--validators` or
+/// `--all`.
+///
+/// Besides single testcases an expression can be a range like `2-5` or
+/// `v3-` (from the third validator on), `*`, `t*` or `v*` for all
+/// testcases, tests or validators, or any of these prefixed with `!` to
+/// leave the testcases out. If every expression is an exclusion, they are
+/// left out of all testcases.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::clash::{PlainNumbers, TestSelection};
+///
+/// let selection = TestSelection::parse(["1-10", "!4"], PlainNumbers::Testcases).unwrap();
+/// assert_ne!(selection, TestSelection::all());
+/// assert!(TestSelection::parse(["t1-v2"], PlainNumbers::Testcases).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestSelection {
+    /// Testcases to pick, all of them if there are none.
+    included: Vec<Expression>,
+    excluded: Vec<Expression>,
+}
+
+/// Which testcases the numbers of an expression count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Any,
+    Test,
+    Validator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expression {
+    /// Every testcase of the kind (`*`, `t*` or `v*`).
+    Every(Kind),
+    /// A single testcase (when `first == last`) or a range of them. Both
+    /// ends have to exist, `last` is `None` for open ranges like `2-`.
+    Range {
+        kind: Kind,
+        first: usize,
+        last: Option<usize>,
+    },
+}
+
+impl TestSelection {
+    pub fn all() -> Self {
+        TestSelection {
+            included: Vec::new(),
+            excluded: Vec::new(),
+        }
+    }
+
+    /// Every test, none of the validators.
+    pub fn tests() -> Self {
+        TestSelection {
+            included: vec![Expression::Every(Kind::Test)],
+            excluded: Vec::new(),
+        }
+    }
+
+    pub fn validators() -> Self {
+        TestSelection {
+            included: vec![Expression::Every(Kind::Validator)],
+            excluded: Vec::new(),
+        }
+    }
+
+    /// Parses selection expressions (see [TestSelection]). Whether the
+    /// testcases exist is only checked once they are selected.
+    pub fn parse<'a>(
+        expressions: impl IntoIterator<Item = &'a str>,
+        plain_numbers: PlainNumbers,
+    ) -> Result<Self> {
+        let mut selection = TestSelection::all();
+        let mut only_exclusions = true;
+        for expression in expressions {
+            let expression = expression.trim();
+            match expression.strip_prefix('!') {
+                Some(rest) => selection.excluded.push(Expression::parse(rest, plain_numbers)?),
+                None => {
+                    only_exclusions = false;
+                    selection.included.push(Expression::parse(expression, plain_numbers)?);
+                }
+            }
+        }
+        if only_exclusions {
+            selection.included.clear();
+        }
+        Ok(selection)
+    }
+
+    /// Finds the testcase that `selector` (like `3` or `v1`) refers to and
+    /// returns its position in `testcases`.
+    pub fn resolve(testcases: &[Testcase], selector: &str, plain_numbers: PlainNumbers) -> Result<usize> {
+        let (kind, number) = parse_selector(selector, plain_numbers)?;
+        find(testcases, kind, number)
+    }
+
+    /// Positions in `testcases` of the selected testcases, in the order they
+    /// are first picked. Fails if a single testcase or an end of a range
+    /// doesn't exist, or if nothing is picked out of testcases that exist.
+    pub fn positions(&self, testcases: &[Testcase]) -> Result<Vec<usize>> {
+        let mut selected: Vec<usize> = Vec::new();
+        let mut excluded: Vec<usize> = Vec::new();
+        if self.included.is_empty() {
+            selected = (0..testcases.len()).collect();
+        }
+        for (expressions, target) in [(&self.included, &mut selected), (&self.excluded, &mut excluded)] {
+            for expression in expressions {
+                for position in expression.positions(testcases)? {
+                    if !target.contains(&position) {
+                        target.push(position);
+                    }
+                }
+            }
+        }
+        selected.retain(|position| !excluded.contains(position));
+        if selected.is_empty() && !testcases.is_empty() {
+            return Err(anyhow!("The selection leaves out every testcase"))
+        }
+        Ok(selected)
+    }
+
+    /// The selected testcases of `testcases` (see [TestSelection::positions]).
+    pub fn select<'t>(&self, testcases: &'t [Testcase]) -> Result<Vec<&'t Testcase>> {
+        Ok(self.positions(testcases)?.into_iter().map(|position| &testcases[position]).collect())
+    }
+}
+
+impl Default for TestSelection {
+    fn default() -> Self {
+        TestSelection::all()
+    }
+}
+
+impl Clash {
+    /// The testcases of the clash that `selection` picks.
+    pub fn select_testcases(&self, selection: &TestSelection) -> Result<Vec<&Testcase>> {
+        selection.select(self.testcases())
+    }
+}
+
+impl Expression {
+    fn parse(expression: &str, plain_numbers: PlainNumbers) -> Result<Self> {
+        match expression.to_ascii_lowercase().as_str() {
+            "*" => return Ok(Expression::Every(Kind::Any)),
+            "t*" | "test*" => return Ok(Expression::Every(Kind::Test)),
+            "v*" | "validator*" => return Ok(Expression::Every(Kind::Validator)),
+            _ => {}
+        }
+        let Some((start, end)) = expression.split_once('-') else {
+            let (kind, number) = parse_selector(expression, plain_numbers)?;
+            return Ok(Expression::Range {
+                kind,
+                first: number,
+                last: Some(number),
+            })
+        };
+        let (kind, first) = parse_selector(start, plain_numbers)?;
+        let last = match end.trim() {
+            "" => None,
+            end => match parse_selector(end, plain_numbers)? {
+                (end_kind, last) if end_kind == kind => Some(last),
+                _ => {
+                    return Err(anyhow!(
+                        "Invalid range {expression:?} (both ends have to be tests, validators or testcases)"
+                    ))
+                }
+            },
+        };
+        if last.is_some_and(|last| last < first) {
+            return Err(anyhow!("Invalid range {expression:?} (the first testcase comes after the last one)"))
+        }
+        Ok(Expression::Range { kind, first, last })
+    }
+
+    fn positions(self, testcases: &[Testcase]) -> Result<Vec<usize>> {
+        let (kind, first, last) = match self {
+            Expression::Every(kind) => (kind, 1, usize::MAX),
+            Expression::Range { kind, first, last } => {
+                find(testcases, kind, first)?;
+                if let Some(last) = last {
+                    find(testcases, kind, last)?;
+                }
+                (kind, first, last.unwrap_or(usize::MAX))
+            }
+        };
+        Ok(testcases
+            .iter()
+            .enumerate()
+            .filter(|(_, testcase)| {
+                number_in(testcase, kind).is_some_and(|number| (first..=last).contains(&number))
+            })
+            .map(|(position, _)| position)
+            .collect())
+    }
+}
+
+/// Splits a selector like `v3` into the kind of testcases it refers to and
+/// the number.
+fn parse_selector(selector: &str, plain_numbers: PlainNumbers) -> Result<(Kind, usize)> {
+    let selector = selector.trim().to_ascii_lowercase();
+    let invalid = || anyhow!("Invalid testcase {selector:?} (expected a number like 3, t3 or v1)");
+    let (kind, number) = match selector.find(|c: char| c.is_ascii_digit()) {
+        Some(idx) => selector.split_at(idx),
+        None => return Err(invalid()),
+    };
+    let number: usize = number.parse().map_err(|_| invalid())?;
+    let kind = match (kind, plain_numbers) {
+        ("", PlainNumbers::Testcases) | ("#", _) => Kind::Any,
+        ("", PlainNumbers::Tests) | ("t" | "test", _) => Kind::Test,
+        ("v" | "validator", _) => Kind::Validator,
+        _ => return Err(invalid()),
+    };
+    Ok((kind, number))
+}
+
+fn find(testcases: &[Testcase], kind: Kind, number: usize) -> Result<usize> {
+    let position = testcases.iter().position(|testcase| number_in(testcase, kind) == Some(number));
+    position.ok_or_else(|| {
+        let num_validators = testcases.iter().filter(|testcase| testcase.is_validator).count();
+        let name = match kind {
+            Kind::Any => format!("testcase #{number}"),
+            Kind::Test => format!("test {number}"),
+            Kind::Validator => format!("validator {number}"),
+        };
+        anyhow!(
+            "There is no {name} (the clash has {} tests and {} validators)",
+            testcases.len() - num_validators,
+            num_validators
+        )
+    })
+}
+
+/// The number of `testcase` among the testcases of `kind`, if it is one of
+/// them.
+fn number_in(testcase: &Testcase, kind: Kind) -> Option<usize> {
+    match kind {
+        Kind::Any => Some(testcase.index),
+        Kind::Test => (!testcase.is_validator).then_some(testcase.number),
+        Kind::Validator => testcase.is_validator.then_some(testcase.number),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::testcase;
+
+    fn testcases() -> Vec<Testcase> {
+        [
+            ("Test 1", false),
+            ("Validator 1", true),
+            ("Edge case", false),
+            ("Validator 2", true),
+        ]
+        .into_iter()
+        .zip([1, 1, 2, 2])
+        .enumerate()
+        .map(|(idx, ((title, is_validator), number))| Testcase {
+            number,
+            title: title.to_string(),
+            is_validator,
+            ..testcase(idx + 1, "", "")
+        })
+        .collect()
+    }
+
+    fn select(plain_numbers: PlainNumbers, expressions: &[&str]) -> Result<Vec<usize>> {
+        TestSelection::parse(expressions.iter().copied(), plain_numbers)?.positions(&testcases())
+    }
+
+    #[test]
+    fn plain_numbers_and_prefixes() {
+        let testcases = testcases();
+        assert_eq!(TestSelection::resolve(&testcases, "2", PlainNumbers::Testcases).unwrap(), 1);
+        assert_eq!(TestSelection::resolve(&testcases, "2", PlainNumbers::Tests).unwrap(), 2);
+        for plain_numbers in [PlainNumbers::Testcases, PlainNumbers::Tests] {
+            assert_eq!(TestSelection::resolve(&testcases, "V2", plain_numbers).unwrap(), 3);
+            assert_eq!(TestSelection::resolve(&testcases, "t1", plain_numbers).unwrap(), 0);
+            assert_eq!(TestSelection::resolve(&testcases, "#3", plain_numbers).unwrap(), 2);
+        }
+        for selector in ["v0", "x1", "first"] {
+            assert!(TestSelection::resolve(&testcases, selector, PlainNumbers::Testcases).is_err());
+        }
+        assert!(TestSelection::resolve(&testcases, "3", PlainNumbers::Tests).is_err());
+    }
+
+    #[test]
+    fn ranges_and_exclusions() {
+        use PlainNumbers::*;
+        assert_eq!(select(Testcases, &["1-3", "!2"]).unwrap(), vec![0, 2]);
+        assert_eq!(select(Testcases, &["3", "1"]).unwrap(), vec![2, 0]);
+        assert_eq!(select(Testcases, &["2-"]).unwrap(), vec![1, 2, 3]);
+        assert_eq!(select(Tests, &["1-2"]).unwrap(), vec![0, 2]);
+        assert_eq!(select(Testcases, &["v1-"]).unwrap(), vec![1, 3]);
+        assert_eq!(select(Testcases, &["!v*"]).unwrap(), vec![0, 2]);
+        assert_eq!(select(Testcases, &["2-2"]).unwrap(), vec![1]);
+        assert_eq!(select(Testcases, &["t*", "2", "1-2"]).unwrap(), vec![0, 2, 1]);
+        assert_eq!(select(Testcases, &["*", "!t1-t2", "!#4"]).unwrap(), vec![1]);
+
+        for expressions in [["1-5"], ["5-"], ["t1-v2"], ["!x"], ["3-1"], ["!*"]] {
+            assert!(select(Testcases, &expressions).is_err(), "{expressions:?} should be refused");
+        }
+    }
+
+    #[test]
+    fn tests_validators_and_all() {
+        let testcases = testcases();
+        assert_eq!(TestSelection::tests().positions(&testcases).unwrap(), vec![0, 2]);
+        assert_eq!(TestSelection::validators().positions(&testcases).unwrap(), vec![1, 3]);
+        assert_eq!(TestSelection::all().positions(&testcases).unwrap(), vec![0, 1, 2, 3]);
+        assert_eq!(TestSelection::all().positions(&[]).unwrap(), Vec::<usize>::new());
+    }
+}
@@ -7,31 +7,71 @@ pub struct Testcase {
     /// `index` is the number of the test/validator, starting from 1.
     #[serde(skip_serializing, skip_deserializing)]
     pub index: usize,
+    /// `number` is the number of the test among the tests (or the validator
+    /// among the validators), starting from 1. This is how the CodinGame IDE
+    /// numbers them.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub number: usize,
     /// `title` is a human readable name for the test/validator
-    #[serde(deserialize_with = "deserialize_testcase_title")]
+    #[serde(deserialize_with = "deserialize_testcase_title", default)]
     pub title: String,
     /// `test_in` is the input that a solution reads from STDIN
-    #[serde(rename = "testIn")]
+    #[serde(rename = "testIn", alias = "test_in")]
     pub test_in: String,
     /// `test_out` is the output that a solution is expected to print to STDOUT
-    #[serde(rename = "testOut")]
+    #[serde(rename = "testOut", alias = "test_out")]
     pub test_out: String,
     /// `is_validator` is true for testcases that are not normally visible when
     /// solving a puzzle on CodinGame.
-    #[serde(rename = "isValidator")]
+    #[serde(rename = "isValidator", alias = "is_validator", default)]
     pub is_validator: bool,
 }
 
 pub fn deserialize_testcases<'de, D: Deserializer<'de>>(de: D) -> Result<Vec<Testcase>, D::Error> {
     let mut testcases = Vec::<Testcase>::deserialize(de)?;
 
+    let (mut num_tests, mut num_validators) = (0, 0);
     for (i, testcase) in testcases.iter_mut().enumerate() {
         testcase.index = i + 1;
+        let counter = if testcase.is_validator {
+            &mut num_validators
+        } else {
+            &mut num_tests
+        };
+        *counter += 1;
+        testcase.number = *counter;
+        // Some very old clashes don't have titles for their testcases at all
+        if testcase.title.is_empty() {
+            testcase.title = format!("Test {}", testcase.index);
+        }
     }
 
     Ok(testcases)
 }
 
+/// Problems that make `testcases` unfit for checking a solution, which
+/// unfinished drafts tend to have: no testcases at all, only validators, or
+/// testcases with an empty input or expected output.
+pub fn testcase_warnings(testcases: &[Testcase]) -> Vec<String> {
+    if testcases.is_empty() {
+        return vec!["The clash has no testcases".to_string()]
+    }
+    let mut warnings = Vec::new();
+    if testcases.iter().all(|testcase| testcase.is_validator) {
+        warnings.push("The clash has no tests, only validators".to_string());
+    }
+    for testcase in testcases {
+        let empty = match (testcase.test_in.trim().is_empty(), testcase.test_out.trim().is_empty()) {
+            (true, true) => "input and expected output",
+            (true, false) => "input",
+            (false, true) => "expected output",
+            (false, false) => continue,
+        };
+        warnings.push(format!("{:?} has an empty {empty}", testcase.title));
+    }
+    warnings
+}
+
 // Workaround for some old clashes which have testcase title as
 // { "title": { "2": "The Actual Title" } } for whatever reason
 fn deserialize_testcase_title<'de, D: Deserializer<'de>>(de: D) -> Result<String, D::Error> {
@@ -0,0 +1,273 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::clash::{Clash, ClashIdentifier, PublicHandle};
+
+const FIND_CONTRIBUTION_URL: &str = "https://www.codingame.com/services/Contribution/findContribution";
+const FIND_CLASH_REPORT_URL: &str =
+    "https://www.codingame.com/services/ClashOfCode/findClashReportInfoByHandle";
+const FIND_PUZZLE_URL: &str = "https://www.codingame.com/services/Puzzle/findProgressByPrettyId";
+
+/// Arguments of the findContribution endpoint: the handle and whether the
+/// data of the latest version should be included.
+#[derive(Serialize)]
+struct FindContributionRequest<'a>(&'a PublicHandle, bool);
+
+/// The shapes of findContribution responses that are told apart before the
+/// contribution itself gets deserialized.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FindContributionResponse {
+    /// Unknown handles get a `null` instead of an error.
+    NotFound,
+    Contribution {
+        #[serde(rename = "publicHandle", alias = "public_handle")]
+        public_handle: String,
+    },
+    Error(ApiError),
+}
+
+/// The body of the errors that the CodinGame API responds with.
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    #[serde(default)]
+    code: Option<String>,
+    message: String,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "{} ({})", self.message, code),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// A contribution downloaded with [find_contribution].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Contribution {
+    pub clash: Clash,
+    /// The response exactly as the API returned it, for storing as-is.
+    pub json: String,
+}
+
+/// Download a clash (or a classic puzzle) from codingame.com.
+///
+/// Fails with a descriptive error if the contribution does not exist or if
+/// the response is not a contribution that can be deserialized into a
+/// [Clash].
+pub fn find_contribution(handle: &PublicHandle) -> Result<Contribution> {
+    find_contribution_with_cookie(handle, None)
+}
+
+/// Same as [find_contribution] but logged in with the session in `cookie`
+/// (the value of a Cookie header of a browser that is logged in to
+/// codingame.com), so that unpublished contributions of the user can be
+/// downloaded too.
+pub fn find_contribution_with_cookie(handle: &PublicHandle, cookie: Option<&str>) -> Result<Contribution> {
+    let body = serde_json::to_string(&FindContributionRequest(handle, true))?;
+    let json = post_json(FIND_CONTRIBUTION_URL, &body, cookie)?;
+    let clash = parse_find_contribution_response(handle, &json)?;
+    Ok(Contribution { clash, json })
+}
+
+/// Sends `body` to an endpoint of the CodinGame API and returns the body of
+/// the response.
+fn post_json(url: &str, body: &str, cookie: Option<&str>) -> Result<String> {
+    let mut req = ureq::post(url).set("Content-Type", "application/json");
+    if let Some(cookie) = cookie {
+        req = req.set("Cookie", cookie);
+    }
+    match req.send_string(body) {
+        Err(ureq::Error::Status(status, res)) => {
+            let url = res.get_url().to_owned();
+            let status_text = res.status_text().to_owned();
+            let error = res.into_string().ok().and_then(|body| serde_json::from_str::<ApiError>(&body).ok());
+            match error {
+                Some(error) => Err(anyhow!("HTTP {} {} from {}: {}", status, status_text, url, error)),
+                None => Err(anyhow!("HTTP {} {} from {}", status, status_text, url)),
+            }
+        }
+        res => Ok(res?.into_string()?),
+    }
+}
+
+/// Finds out the handle of the puzzle that `identifier` points at. Links to
+/// Clash of Code games and classic puzzles are looked up on codingame.com,
+/// handles are returned as they are.
+pub fn resolve_identifier(identifier: &ClashIdentifier) -> Result<PublicHandle> {
+    let (url, body) = match identifier {
+        ClashIdentifier::Handle(handle) => return Ok(handle.clone()),
+        ClashIdentifier::ClashReport(game) => (FIND_CLASH_REPORT_URL, serde_json::json!([game])),
+        ClashIdentifier::PuzzleSlug(slug) => (FIND_PUZZLE_URL, serde_json::json!([slug, null])),
+    };
+    let json =
+        post_json(url, &body.to_string(), None).with_context(|| format!("Unable to look up {identifier}"))?;
+    parse_lookup_response(&json).with_context(|| format!("Unable to find out the puzzle of {identifier}"))
+}
+
+/// Picks the handle of the puzzle out of the response of a game report or a
+/// puzzle page. The responses have the puzzle nested in them (next to the
+/// players, whose handles are of codingamers), so this looks for a handle
+/// under a key that names a contribution or a puzzle.
+fn parse_lookup_response(body: &str) -> Result<PublicHandle> {
+    let value: serde_json::Value =
+        serde_json::from_str(body).context("The response from codingame.com is not JSON")?;
+    if value.is_null() {
+        return Err(anyhow!("codingame.com doesn't know it"))
+    }
+    if let Some(handle) = contribution_handle(&value) {
+        return handle.parse()
+    }
+    match serde_json::from_value::<ApiError>(value) {
+        Ok(error) => Err(anyhow!("codingame.com responded with an error: {error}")),
+        Err(_) => Err(anyhow!("The response from codingame.com has no handle of a puzzle in it")),
+    }
+}
+
+fn contribution_handle(value: &serde_json::Value) -> Option<&str> {
+    let names_puzzle = |key: &str| {
+        let key = key.to_ascii_lowercase();
+        key.contains("contribution") || key.contains("puzzle")
+    };
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object {
+                match value {
+                    serde_json::Value::String(handle)
+                        if names_puzzle(key) && key.to_ascii_lowercase().ends_with("handle") =>
+                    {
+                        return Some(handle)
+                    }
+                    serde_json::Value::Object(nested) if names_puzzle(key) => {
+                        if let Some(serde_json::Value::String(handle)) = nested.get("publicHandle") {
+                            return Some(handle)
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            object.values().find_map(contribution_handle)
+        }
+        serde_json::Value::Array(values) => values.iter().find_map(contribution_handle),
+        _ => None,
+    }
+}
+
+/// Download the JSON of a clash (or a classic puzzle) from codingame.com.
+///
+/// The returned string is exactly what the API returned so that it can be
+/// stored as-is and deserialized into a [Clash] later. It is checked to be
+/// a valid clash first, see [find_contribution].
+pub fn fetch_clash_json(handle: &PublicHandle) -> Result<String> {
+    Ok(find_contribution(handle)?.json)
+}
+
+/// Deserializes the body of a successful findContribution response for
+/// `handle`, telling apart the responses for contributions that don't exist
+/// and errors from a contribution whose JSON has an unexpected shape.
+pub fn parse_find_contribution_response(handle: &PublicHandle, body: &str) -> Result<Clash> {
+    let response: FindContributionResponse = serde_json::from_str(body).map_err(|_| {
+        anyhow!("Unexpected response from codingame.com for clash {handle}: it is neither a contribution nor an error")
+    })?;
+    match response {
+        FindContributionResponse::NotFound => Err(anyhow!("Clash {handle} was not found on codingame.com")),
+        FindContributionResponse::Error(error) => {
+            Err(anyhow!("codingame.com was unable to find clash {handle}: {error}"))
+        }
+        FindContributionResponse::Contribution { public_handle } if public_handle != handle.to_string() => {
+            Err(anyhow!("codingame.com responded with clash {public_handle} when asked for {handle}"))
+        }
+        FindContributionResponse::Contribution { .. } => serde_json::from_str(body).with_context(|| {
+            format!("The contribution JSON of clash {handle} from codingame.com has an unexpected shape")
+        }),
+    }
+}
+
+/// Download a plain text document such as a list of clash handles.
+pub fn fetch_text(url: &str) -> Result<String> {
+    match ureq::get(url).call() {
+        Err(ureq::Error::Status(status, res)) => {
+            Err(anyhow!("HTTP {} {} from {}", status, res.status_text(), res.get_url()))
+        }
+        res => Ok(res?.into_string()?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn handle(handle: &str) -> PublicHandle {
+        PublicHandle::from_str(handle).unwrap()
+    }
+
+    fn response(name: &str) -> String {
+        std::fs::read_to_string(format!("fixtures/responses/{name}.json")).unwrap()
+    }
+
+    #[test]
+    fn request_body() {
+        let handle = handle("90435e82d1d5e3fe5f9d3dd813770f0d5a7d2");
+        let body = serde_json::to_string(&FindContributionRequest(&handle, true)).unwrap();
+        assert_eq!(body, r#"["90435e82d1d5e3fe5f9d3dd813770f0d5a7d2",true]"#);
+    }
+
+    #[test]
+    fn contribution_response() {
+        let body = std::fs::read_to_string("fixtures/puzzles/stub_and_solution_tester.json").unwrap();
+        let clash = parse_find_contribution_response(&handle("90435e82d1d5e3fe5f9d3dd813770f0d5a7d2"), &body);
+        assert_eq!(clash.unwrap().title(), "Boggus test");
+
+        let err = parse_find_contribution_response(&handle("abc"), &body).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("responded with clash 90435e82d1d5e3fe5f9d3dd813770f0d5a7d2"));
+    }
+
+    #[test]
+    fn not_found_responses() {
+        let err = parse_find_contribution_response(&handle("abc"), &response("find_contribution_null"));
+        assert_eq!(err.unwrap_err().to_string(), "Clash abc was not found on codingame.com");
+
+        let err = parse_find_contribution_response(&handle("abc"), &response("find_contribution_error"));
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "codingame.com was unable to find clash abc: Contribution not found (CONTRIBUTION_NOT_FOUND)"
+        );
+    }
+
+    #[test]
+    fn lookup_responses() {
+        let report = r#"{"publicHandle": "1234", "players": [{"codingamerHandle": "aaaa"}],
+            "puzzle": {"id": 7, "publicHandle": "90435e82d1d5e3fe5f9d3dd813770f0d5a7d2"}}"#;
+        assert_eq!(
+            parse_lookup_response(report).unwrap().to_string(),
+            "90435e82d1d5e3fe5f9d3dd813770f0d5a7d2"
+        );
+        let puzzle = r#"{"prettyId": "the-descent", "contributor": {"publicHandle": "bbbb"},
+            "contributionPublicHandle": "cccc"}"#;
+        assert_eq!(parse_lookup_response(puzzle).unwrap().to_string(), "cccc");
+
+        assert!(parse_lookup_response("null").unwrap_err().to_string().contains("doesn't know"));
+        let err = parse_lookup_response(&response("find_contribution_error")).unwrap_err();
+        assert!(err.to_string().contains("Contribution not found"));
+        let err = parse_lookup_response(r#"{"contributor": {"publicHandle": "bbbb"}}"#).unwrap_err();
+        assert!(err.to_string().contains("no handle of a puzzle"));
+    }
+
+    #[test]
+    fn unexpected_responses() {
+        let err = parse_find_contribution_response(&handle("abc"), "[1, 2, 3]").unwrap_err();
+        assert!(err.to_string().contains("neither a contribution nor an error"));
+
+        // A contribution without any testcases
+        let err = parse_find_contribution_response(&handle("abc"), r#"{"publicHandle": "abc"}"#).unwrap_err();
+        assert!(err.to_string().contains("unexpected shape"));
+        assert!(format!("{err:#}").contains("no lastVersion"));
+    }
+}
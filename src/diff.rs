@@ -0,0 +1,544 @@
+//! Comparison of the expected and the actual output of a solution.
+//!
+//! Like CodinGame, outputs are compared line by line: the n:th line of the
+//! actual output is always paired with the n:th line of the expected output.
+//! Lines that differ are further split into [`Span`]s that tell which parts
+//! of them match, character by character, word by word or not at all
+//! depending on the [`Granularity`].
+
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+
+use itertools::{EitherOrBoth, Itertools};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HunkKind {
+    /// The line is the same in both outputs.
+    Equal,
+    /// The actual output has a line that is not in the expected output.
+    Insert,
+    /// The actual output is missing a line of the expected output.
+    Delete,
+    /// Both outputs have the line but it is different.
+    Change,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpanKind {
+    /// Text that is in both lines.
+    Equal,
+    /// Text that is only in the actual line.
+    Insert,
+    /// Text that is only in the expected line.
+    Delete,
+}
+
+/// How finely changed lines are split into spans.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Granularity {
+    /// Every character that differs gets its own span.
+    #[default]
+    Char,
+    /// Words (and the whitespace between them) are compared as a whole, so a
+    /// misspelled word shows up as one changed word.
+    Word,
+    /// Changed lines are not split at all.
+    Line,
+}
+
+impl Granularity {
+    /// Word granularity for outputs made of several words or numbers per
+    /// line (such as sentences), character granularity otherwise.
+    pub fn guess(expected: &str) -> Self {
+        match expected.lines().any(|line| line.trim().contains(' ')) {
+            true => Granularity::Word,
+            false => Granularity::Char,
+        }
+    }
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "char" => Ok(Granularity::Char),
+            "word" => Ok(Granularity::Word),
+            "line" => Ok(Granularity::Line),
+            _ => Err(format!("invalid diff granularity {s:?} (expected char, word or line)")),
+        }
+    }
+}
+
+/// A difference between two outputs that is invisible (or nearly so) when
+/// they are printed, see [`invisible_differences`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum InvisibleDifference {
+    /// One of the outputs starts with U+FEFF.
+    ByteOrderMark,
+    /// One of the outputs has carriage returns (`\r`) in its line endings,
+    /// other than in plain CRLF line endings.
+    LineEndings,
+    /// One of the outputs has spaces or tabs at the end of lines.
+    TrailingWhitespace,
+}
+
+impl std::fmt::Display for InvisibleDifference {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InvisibleDifference::ByteOrderMark => write!(f, "a byte order mark (U+FEFF)"),
+            InvisibleDifference::LineEndings => write!(f, "line endings (\\r)"),
+            InvisibleDifference::TrailingWhitespace => write!(f, "whitespace at the end of lines"),
+        }
+    }
+}
+
+/// Tells if `expected` and `actual` differ only in ways that are hard to
+/// see in a diff. Returns the kinds of differences that have to be ignored
+/// for the outputs to be equal, or nothing if they differ in other ways too
+/// (or not at all).
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::diff::{invisible_differences, InvisibleDifference};
+///
+/// let differences = invisible_differences("1\n2", "1 \r\r\n2");
+/// assert_eq!(differences, vec![InvisibleDifference::LineEndings, InvisibleDifference::TrailingWhitespace]);
+/// assert!(invisible_differences("1\n2", "1 \n3").is_empty());
+/// ```
+pub fn invisible_differences(expected: &str, actual: &str) -> Vec<InvisibleDifference> {
+    type Normalization = fn(&str) -> String;
+    let normalizations: [(InvisibleDifference, Normalization); 3] = [
+        (InvisibleDifference::ByteOrderMark, |text| text.replace('\u{feff}', "")),
+        (InvisibleDifference::LineEndings, |text| {
+            let mut text = text.to_string();
+            while text.contains("\r\n") {
+                text = text.replace("\r\n", "\n");
+            }
+            text.replace('\r', "\n")
+        }),
+        (InvisibleDifference::TrailingWhitespace, |text| {
+            text.split('\n').map(|line| line.trim_end_matches([' ', '\t'])).join("\n")
+        }),
+    ];
+
+    if expected == actual {
+        return Vec::new()
+    }
+    let (mut expected, mut actual) = (expected.to_string(), actual.to_string());
+    let mut differences = Vec::new();
+    for (difference, normalize) in normalizations {
+        let (normalized_expected, normalized_actual) = (normalize(&expected), normalize(&actual));
+        if normalized_expected != expected || normalized_actual != actual {
+            differences.push(difference);
+        }
+        (expected, actual) = (normalized_expected, normalized_actual);
+    }
+    match expected == actual {
+        true => differences,
+        false => Vec::new(),
+    }
+}
+
+/// How an actual output differs from the expected one, described in terms
+/// that stay the same when the same bug shows up in testcases with different
+/// outputs ("off by +1 on the last line"). Used for grouping failures that
+/// likely have the same cause.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::diff::FailurePattern;
+///
+/// let pattern = FailurePattern::of("1\n2\n3", "1\n2\n4");
+/// assert_eq!(pattern.to_string(), "off by +1 on the last line");
+/// assert_eq!(pattern, FailurePattern::of("10\n20", "10\n21"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailurePattern {
+    differences: BTreeSet<(LinePosition, LineDifference)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LinePosition {
+    Only,
+    First,
+    Middle,
+    Last,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LineDifference {
+    OffBy(i64),
+    Number,
+    Case,
+    Whitespace,
+    Different,
+    Missing,
+    Extra,
+}
+
+impl FailurePattern {
+    /// Compares the outputs line by line, ignoring trailing whitespace at the
+    /// end of the outputs.
+    pub fn of(expected: &str, actual: &str) -> Self {
+        let expected: Vec<&str> = expected.trim_end().lines().collect();
+        let actual: Vec<&str> = actual.trim_end().lines().collect();
+        let position = |idx: usize| match idx {
+            _ if expected.len() <= 1 => LinePosition::Only,
+            0 => LinePosition::First,
+            _ if idx + 1 >= expected.len() => LinePosition::Last,
+            _ => LinePosition::Middle,
+        };
+        let mut differences = BTreeSet::new();
+        for (idx, pair) in expected.iter().zip_longest(actual.iter()).enumerate() {
+            let difference = match pair {
+                EitherOrBoth::Both(exp, act) if exp == act => continue,
+                EitherOrBoth::Both(exp, act) => line_difference(exp, act),
+                EitherOrBoth::Left(_) => LineDifference::Missing,
+                EitherOrBoth::Right(_) => LineDifference::Extra,
+            };
+            let position = match difference {
+                LineDifference::Missing | LineDifference::Extra => LinePosition::Last,
+                _ => position(idx),
+            };
+            differences.insert((position, difference));
+        }
+        FailurePattern { differences }
+    }
+
+    /// How much the patterns have in common, from 0.0 (nothing) to 1.0 (the
+    /// outputs differ in exactly the same ways).
+    pub fn similarity(&self, other: &FailurePattern) -> f64 {
+        let union = self.differences.union(&other.differences).count();
+        if union == 0 {
+            return 1.0
+        }
+        self.differences.intersection(&other.differences).count() as f64 / union as f64
+    }
+}
+
+fn line_difference(expected: &str, actual: &str) -> LineDifference {
+    if let (Ok(exp), Ok(act)) = (expected.trim().parse::<i64>(), actual.trim().parse::<i64>()) {
+        return LineDifference::OffBy(act.saturating_sub(exp))
+    }
+    if expected.trim().parse::<f64>().is_ok() && actual.trim().parse::<f64>().is_ok() {
+        LineDifference::Number
+    } else if expected.to_lowercase() == actual.to_lowercase() {
+        LineDifference::Case
+    } else if expected.split_whitespace().eq(actual.split_whitespace()) {
+        LineDifference::Whitespace
+    } else {
+        LineDifference::Different
+    }
+}
+
+impl std::fmt::Display for FailurePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.differences.is_empty() {
+            return write!(f, "only whitespace at the end differs")
+        }
+        let descriptions = self.differences.iter().map(|(position, difference)| {
+            let on_line = match position {
+                LinePosition::Only => "",
+                LinePosition::First => " on the first line",
+                LinePosition::Middle => " in the middle",
+                LinePosition::Last => " on the last line",
+            };
+            match difference {
+                LineDifference::OffBy(delta) => format!("off by {delta:+}{on_line}"),
+                LineDifference::Number => format!("a different number{on_line}"),
+                LineDifference::Case => format!("different letter case{on_line}"),
+                LineDifference::Whitespace => format!("different spacing{on_line}"),
+                LineDifference::Different => format!("different output{on_line}"),
+                LineDifference::Missing => "missing lines at the end".to_string(),
+                LineDifference::Extra => "extra lines at the end".to_string(),
+            }
+        });
+        write!(f, "{}", descriptions.unique().join(", "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub kind: SpanKind,
+    pub text: String,
+}
+
+/// One line of the comparison. Every hunk covers exactly one line of the
+/// expected output, the actual output, or both.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hunk {
+    pub kind: HunkKind,
+    /// 1-based line number in the expected output (`None` for inserted lines).
+    pub expected_line: Option<usize>,
+    /// 1-based line number in the actual output (`None` for deleted lines).
+    pub actual_line: Option<usize>,
+    /// The text of the line (including the line ending, if any). Only
+    /// [`HunkKind::Change`] hunks have more than one span.
+    pub spans: Vec<Span>,
+}
+
+impl Hunk {
+    /// The line as it is in the expected output (empty for inserted lines).
+    pub fn expected(&self) -> String {
+        self.text_without(SpanKind::Insert)
+    }
+
+    /// The line as it is in the actual output (empty for deleted lines).
+    pub fn actual(&self) -> String {
+        self.text_without(SpanKind::Delete)
+    }
+
+    fn text_without(&self, excluded: SpanKind) -> String {
+        self.spans
+            .iter()
+            .filter(|span| span.kind != excluded)
+            .map(|span| span.text.as_str())
+            .collect()
+    }
+}
+
+/// Compares `expected` and `actual` line by line with character
+/// granularity.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::diff::{compute, HunkKind};
+///
+/// let hunks = compute("1\n2\n3\n", "1\n4\n");
+/// let kinds: Vec<HunkKind> = hunks.iter().map(|hunk| hunk.kind).collect();
+/// assert_eq!(kinds, vec![HunkKind::Equal, HunkKind::Change, HunkKind::Delete]);
+/// assert_eq!(hunks[2].expected(), "3\n");
+/// ```
+pub fn compute(expected: &str, actual: &str) -> Vec<Hunk> {
+    compute_with(expected, actual, Granularity::Char)
+}
+
+/// Compares `expected` and `actual` line by line, splitting changed lines
+/// into spans with the given granularity.
+pub fn compute_with(expected: &str, actual: &str, granularity: Granularity) -> Vec<Hunk> {
+    let mut expected_lines = expected.split_inclusive('\n');
+    let mut actual_lines = actual.split_inclusive('\n');
+    let mut hunks = Vec::new();
+
+    for line_number in 1.. {
+        let hunk = match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => break,
+            (Some(exp), None) => Hunk {
+                kind: HunkKind::Delete,
+                expected_line: Some(line_number),
+                actual_line: None,
+                spans: vec![span(SpanKind::Delete, exp)],
+            },
+            (None, Some(act)) => Hunk {
+                kind: HunkKind::Insert,
+                expected_line: None,
+                actual_line: Some(line_number),
+                spans: vec![span(SpanKind::Insert, act)],
+            },
+            (Some(exp), Some(act)) if exp == act => Hunk {
+                kind: HunkKind::Equal,
+                expected_line: Some(line_number),
+                actual_line: Some(line_number),
+                spans: vec![span(SpanKind::Equal, act)],
+            },
+            (Some(exp), Some(act)) => Hunk {
+                kind: HunkKind::Change,
+                expected_line: Some(line_number),
+                actual_line: Some(line_number),
+                spans: match granularity {
+                    Granularity::Char => line_spans(exp, act),
+                    Granularity::Word => word_spans(exp, act),
+                    Granularity::Line => vec![span(SpanKind::Delete, exp), span(SpanKind::Insert, act)],
+                },
+            },
+        };
+        hunks.push(hunk);
+    }
+
+    hunks
+}
+
+/// Character level differences between two lines.
+pub fn line_spans(expected: &str, actual: &str) -> Vec<Span> {
+    dissimilar::diff(expected, actual)
+        .into_iter()
+        .map(|chunk| match chunk {
+            dissimilar::Chunk::Equal(text) => span(SpanKind::Equal, text),
+            dissimilar::Chunk::Insert(text) => span(SpanKind::Insert, text),
+            dissimilar::Chunk::Delete(text) => span(SpanKind::Delete, text),
+        })
+        .collect()
+}
+
+/// Word level differences between two lines. Runs of whitespace count as
+/// words of their own.
+pub fn word_spans(expected: &str, actual: &str) -> Vec<Span> {
+    // Every distinct word is encoded as a single character so that the
+    // character diff of the encoded lines is a word diff of the originals
+    let mut words: Vec<&str> = Vec::new();
+    let mut codes: HashMap<&str, char> = HashMap::new();
+    let [expected_codes, actual_codes] = [expected, actual].map(|line| -> Option<String> {
+        split_words(line)
+            .map(|word| match codes.get(word) {
+                Some(code) => Some(*code),
+                None => {
+                    // Planes 1 and up have no surrogates to skip
+                    let code = char::from_u32(0x10000 + words.len() as u32)?;
+                    words.push(word);
+                    codes.insert(word, code);
+                    Some(code)
+                }
+            })
+            .collect()
+    });
+    // Lines with more distinct words than there are characters to encode
+    // them with are too long for a word diff to be readable anyway
+    let (Some(expected_codes), Some(actual_codes)) = (expected_codes, actual_codes) else {
+        return line_spans(expected, actual)
+    };
+    let decode =
+        |codes: &str| -> String { codes.chars().map(|code| words[code as usize - 0x10000]).collect() };
+
+    dissimilar::diff(&expected_codes, &actual_codes)
+        .into_iter()
+        .map(|chunk| match chunk {
+            dissimilar::Chunk::Equal(codes) => span(SpanKind::Equal, &decode(codes)),
+            dissimilar::Chunk::Insert(codes) => span(SpanKind::Insert, &decode(codes)),
+            dissimilar::Chunk::Delete(codes) => span(SpanKind::Delete, &decode(codes)),
+        })
+        .collect()
+}
+
+/// Splits `line` into alternating runs of whitespace and non-whitespace.
+fn split_words(line: &str) -> impl Iterator<Item = &str> {
+    let mut rest = line;
+    std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let end = rest.find(|c: char| c.is_whitespace() != first.is_whitespace()).unwrap_or(rest.len());
+        let (word, tail) = rest.split_at(end);
+        rest = tail;
+        Some(word)
+    })
+}
+
+fn span(kind: SpanKind, text: &str) -> Span {
+    Span {
+        kind,
+        text: text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_numbers_of_extra_and_missing_lines() {
+        let hunks = compute("a\n", "a\nb\nc");
+        assert_eq!(hunks.len(), 3);
+        assert_eq!(hunks[1].kind, HunkKind::Insert);
+        assert_eq!((hunks[1].expected_line, hunks[1].actual_line), (None, Some(2)));
+        assert_eq!(hunks[2].actual(), "c");
+
+        let hunks = compute("a\nb\n", "");
+        assert!(hunks.iter().all(|hunk| hunk.kind == HunkKind::Delete));
+        assert_eq!(hunks[1].expected_line, Some(2));
+    }
+
+    #[test]
+    fn changed_line_spans() {
+        let hunks = compute("hello world\n", "hello there\n");
+        assert_eq!(hunks[0].kind, HunkKind::Change);
+        assert_eq!(hunks[0].expected(), "hello world\n");
+        assert_eq!(hunks[0].actual(), "hello there\n");
+        assert_eq!(hunks[0].spans.first(), Some(&span(SpanKind::Equal, "hello ")));
+        assert!(hunks[0].spans.iter().any(|span| span.kind == SpanKind::Delete));
+        assert!(hunks[0].spans.iter().any(|span| span.kind == SpanKind::Insert));
+    }
+
+    #[test]
+    fn missing_trailing_newline_is_a_change() {
+        let hunks = compute("5\n", "5");
+        assert_eq!(hunks[0].kind, HunkKind::Change);
+        assert_eq!(hunks[0].spans, vec![span(SpanKind::Equal, "5"), span(SpanKind::Delete, "\n")]);
+    }
+
+    #[test]
+    fn word_granularity_changes_whole_words() {
+        let hunks = compute_with("the quick brown fox\n", "the quack brown fox\n", Granularity::Word);
+        assert_eq!(
+            hunks[0].spans,
+            vec![
+                span(SpanKind::Equal, "the "),
+                span(SpanKind::Delete, "quick"),
+                span(SpanKind::Insert, "quack"),
+                span(SpanKind::Equal, " brown fox\n"),
+            ]
+        );
+        assert_eq!(hunks[0].actual(), "the quack brown fox\n");
+    }
+
+    #[test]
+    fn line_granularity_does_not_split() {
+        let hunks = compute_with("1 2 3\n", "1 2 4\n", Granularity::Line);
+        assert_eq!(
+            hunks[0].spans,
+            vec![
+                span(SpanKind::Delete, "1 2 3\n"),
+                span(SpanKind::Insert, "1 2 4\n")
+            ]
+        );
+    }
+
+    #[test]
+    fn invisible_differences_are_categorized() {
+        assert_eq!(invisible_differences("abc", "\u{feff}abc"), vec![InvisibleDifference::ByteOrderMark]);
+        assert_eq!(invisible_differences("a\nb", "a\rb"), vec![InvisibleDifference::LineEndings]);
+        assert_eq!(invisible_differences("ab", "a\rb"), Vec::new());
+        assert_eq!(invisible_differences("a\nb", "a\r\nb"), vec![InvisibleDifference::LineEndings]);
+        assert_eq!(
+            invisible_differences("a\t\nb", "a\nb"),
+            vec![InvisibleDifference::TrailingWhitespace]
+        );
+        assert_eq!(invisible_differences("abc", "abc"), Vec::new());
+    }
+
+    #[test]
+    fn granularity_guess() {
+        assert_eq!(Granularity::guess("Hello world\n"), Granularity::Word);
+        assert_eq!(Granularity::guess("42\nabc\n"), Granularity::Char);
+        assert_eq!("WORD".parse(), Ok(Granularity::Word));
+        assert!("byte".parse::<Granularity>().is_err());
+    }
+
+    #[test]
+    fn failure_patterns_describe_the_difference() {
+        assert_eq!(FailurePattern::of("5", "4").to_string(), "off by -1");
+        assert_eq!(FailurePattern::of("a\nb", "a").to_string(), "missing lines at the end");
+        assert_eq!(
+            FailurePattern::of("Yes\n1 2\nx", "yes\n1  2\nx\ny").to_string(),
+            "different letter case on the first line, different spacing in the middle, extra lines at the end"
+        );
+        assert_eq!(FailurePattern::of("0.5\n", "0.50").to_string(), "a different number");
+    }
+
+    #[test]
+    fn failure_pattern_similarity() {
+        let off_by_one = FailurePattern::of("1\n2\n3", "1\n2\n4");
+        assert_eq!(off_by_one.similarity(&FailurePattern::of("7\n8", "7\n9")), 1.0);
+        assert_eq!(off_by_one.similarity(&FailurePattern::of("1\n2\n3", "1\n2\n5")), 0.0);
+        let also_first_line = FailurePattern::of("1\n2\n3", "x\n2\n4");
+        assert_eq!(off_by_one.similarity(&also_first_line), 0.5);
+    }
+}
@@ -1,5 +1,90 @@
+mod archive;
+mod build_dirs;
+mod calibration;
+mod clash_session;
+mod clipboard;
+mod config;
+mod config_archive;
+mod custom_testcases;
+mod duration;
+mod failure_clusters;
 mod formatter;
-mod lines_with_endings;
+mod handle_prefix;
+mod heuristics;
+mod history;
+mod html_statement;
+mod journal;
+mod json_file;
+mod json_output;
+mod last_run;
+mod login;
+mod numbering;
 mod outputstyle;
+mod overlays;
+mod remembered_runs;
+mod reverse_game;
+mod review;
+mod run_report;
+mod search_index;
+mod session;
+mod snapshot;
+mod storage;
+mod stub_corpus;
+mod stub_layout;
+mod stub_playground;
+mod tags;
+mod temp_source;
+#[cfg(test)]
+mod test_helper;
+mod testcase_files;
+mod tracked_solution;
+mod trash;
+mod tui;
+mod workspace;
 
-pub use outputstyle::OutputStyle;
+pub use archive::Archive;
+pub use build_dirs::BuildDirs;
+pub use calibration::Calibration;
+pub use clash_session::{format_clock, ClashSession};
+pub use clipboard::copy_to_clipboard;
+pub use config::{Config, Theme, DEFAULT_CONFIG};
+pub use config_archive::ConfigArchive;
+pub use custom_testcases::CustomTestcases;
+pub use duration::{format_duration, format_reading_time, parse_duration};
+pub use failure_clusters::{FailureCluster, FailureClusters};
+pub use handle_prefix::resolve_handle_prefix;
+pub use heuristics::is_unrelated_solution;
+pub use history::{
+    aggregate_stats, format_utc_timestamp, write_badge_svg, write_stats_csv, ClashStats, GroupStats, History,
+    RunOutcome, RunRecord,
+};
+pub use html_statement::format_statement_html;
+pub use journal::{ChangelogEntry, Journal};
+pub use json_output::{
+    schema_for_command, RunReport, StubProblem, StubSummary, TestcaseReport, SCHEMA_COMMANDS,
+};
+pub use last_run::LastRun;
+pub use login::Login;
+pub use numbering::Numbering;
+pub use outputstyle::{file_url, DiffMode, OutputStyle, StatementSection};
+pub use overlays::{Overlays, Provenance};
+pub use remembered_runs::{RememberedRun, RememberedRuns};
+pub use reverse_game::{ReverseCommand, ReverseGames, REVERSE_HELP};
+pub use review::{review_schedule, ReviewItem};
+pub use run_report::{
+    ClashConstraints, JsonReporter, ReproductionLine, RunReporter, RunSummary, TextReporter,
+};
+pub use search_index::SearchIndex;
+pub use session::AutoAdvanceSession;
+pub use snapshot::ClashSnapshot;
+pub use storage::{clash_files, Storage};
+pub use stub_corpus::{ParseExpectations, ParseReport};
+pub use stub_layout::StubLayout;
+pub use stub_playground::{PlaygroundInput, StubPlayground, PLAYGROUND_HELP};
+pub use tags::Tags;
+pub use temp_source::TempSource;
+pub use testcase_files::TestcaseFiles;
+pub use tracked_solution::TrackedSolution;
+pub use trash::{Operation, Trash};
+pub use tui::{notification, terminal_size, FileWatcher, Notify, TestStatus, Transition, TuiScreen};
+pub use workspace::Workspace;
@@ -0,0 +1,138 @@
+use std::io::{Read, Write};
+use std::path::{Component, Path};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use clashlib::clash::PublicHandle;
+
+/// Directory of the clash files inside an archive.
+const CLASH_DIR: &str = "clashes";
+/// Name of the file with the handle of the current clash inside an archive.
+const CURRENT_FILE: &str = "current";
+
+/// The contents of an archive made by `coctus export`: a gzipped tarball of
+/// `clashes/<handle>.json` files and a `current` file with the handle of the
+/// selected clash.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Archive {
+    /// Handles and the contents of the clash files, sorted by handle.
+    pub clashes: Vec<(String, Vec<u8>)>,
+    pub current: Option<String>,
+}
+
+impl Archive {
+    pub fn write<W: Write>(&self, out: W) -> Result<()> {
+        let mut builder =
+            tar::Builder::new(flate2::write::GzEncoder::new(out, flate2::Compression::default()));
+        let mut append = |path: String, contents: &[u8]| -> Result<()> {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, &path, contents)
+                .with_context(|| format!("Unable to add {path} to the archive"))
+        };
+        for (handle, contents) in &self.clashes {
+            append(format!("{CLASH_DIR}/{handle}.json"), contents)?;
+        }
+        if let Some(current) = &self.current {
+            append(CURRENT_FILE.to_string(), format!("{current}\n").as_bytes())?;
+        }
+        builder.into_inner()?.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// Reads an archive, refusing any entries other than the ones that
+    /// `coctus export` writes so that importing an archive from someone else
+    /// can't write outside of the clash directory.
+    pub fn read<R: Read>(input: R) -> Result<Self> {
+        let mut archive = Archive::default();
+        let mut tarball = tar::Archive::new(flate2::read::GzDecoder::new(input));
+        for entry in tarball.entries().context("Unable to read the archive")? {
+            let mut entry = entry.context("Unable to read the archive")?;
+            if entry.header().entry_type().is_dir() {
+                continue
+            }
+            let path = entry.path()?.into_owned();
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            match entry_kind(&path) {
+                Some(EntryKind::Current) => {
+                    let current = String::from_utf8_lossy(&contents);
+                    let handle = PublicHandle::from_str(&current)
+                        .with_context(|| format!("Invalid current clash in the archive: {current:?}"))?;
+                    archive.current = Some(handle.to_string());
+                }
+                Some(EntryKind::Clash(handle)) => archive.clashes.push((handle, contents)),
+                None => return Err(anyhow!("Unexpected file {:?} in the archive", path)),
+            }
+        }
+        archive.clashes.sort();
+        Ok(archive)
+    }
+}
+
+enum EntryKind {
+    Current,
+    Clash(String),
+}
+
+fn entry_kind(path: &Path) -> Option<EntryKind> {
+    let components: Vec<&str> = path
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    match components.as_slice() {
+        [CURRENT_FILE] => Some(EntryKind::Current),
+        [CLASH_DIR, file_name] => {
+            let handle = PublicHandle::from_str(file_name.strip_suffix(".json")?).ok()?;
+            Some(EntryKind::Clash(handle.to_string()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_round_trip() {
+        let archive = Archive {
+            clashes: vec![
+                ("abc".to_string(), b"{\"a\": 1}".to_vec()),
+                ("def".to_string(), b"{}".to_vec()),
+            ],
+            current: Some("def".to_string()),
+        };
+        let mut bytes = Vec::new();
+        archive.write(&mut bytes).unwrap();
+        assert_eq!(&bytes[..2], [0x1f, 0x8b], "archive should be gzipped");
+        assert_eq!(Archive::read(bytes.as_slice()).unwrap(), archive);
+    }
+
+    #[test]
+    fn unexpected_entries_are_refused() {
+        for path in [
+            "clashes/notahandle.json",
+            "clashes/abc.txt",
+            "config.toml",
+            "../clashes/abc.json",
+        ] {
+            let mut builder =
+                tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), Default::default()));
+            let mut header = tar::Header::new_gnu();
+            header.set_size(2);
+            // `append_data` refuses `..`, the path has to be set by hand
+            header.as_gnu_mut().unwrap().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_cksum();
+            builder.append(&header, &b"{}"[..]).unwrap();
+            let bytes = builder.into_inner().unwrap().finish().unwrap();
+            assert!(Archive::read(bytes.as_slice()).is_err(), "{path} should be refused");
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+
+/// Name of the file that records when a build directory was last used.
+const LAST_USED_FILE: &str = ".coctus-last-used";
+
+/// Build directories given to the build and run commands of solutions in
+/// `$COCTUS_BUILD_DIR` (and the `{build_dir}` placeholder), one per clash so
+/// that sessions practicing different clashes at the same time don't
+/// overwrite each other's executables.
+pub struct BuildDirs {
+    root: PathBuf,
+}
+
+impl BuildDirs {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Returns the build directory of the clash, creating it if necessary,
+    /// and marks it as used at `now`.
+    pub fn prepare(&self, handle: &str, now: SystemTime) -> Result<PathBuf> {
+        let dir = self.root.join(handle);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Unable to create build directory {:?}", dir))?;
+        let secs = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        std::fs::write(dir.join(LAST_USED_FILE), secs.to_string())?;
+        Ok(dir)
+    }
+
+    /// Deletes the build directories that were last used more than `max_age`
+    /// before `now` and returns their paths. Directories that were never
+    /// marked as used are left alone, they were not created by coctus.
+    pub fn clean(&self, max_age: Duration, now: SystemTime) -> Result<Vec<PathBuf>> {
+        if !self.root.exists() {
+            return Ok(Vec::new())
+        }
+        let mut removed = Vec::new();
+        for entry in std::fs::read_dir(&self.root)? {
+            let dir = entry?.path();
+            let Some(last_used) = last_used(&dir) else {
+                continue
+            };
+            if now.duration_since(last_used).is_ok_and(|age| age > max_age) {
+                std::fs::remove_dir_all(&dir)
+                    .with_context(|| format!("Unable to remove build directory {:?}", dir))?;
+                removed.push(dir);
+            }
+        }
+        removed.sort();
+        Ok(removed)
+    }
+}
+
+fn last_used(dir: &Path) -> Option<SystemTime> {
+    let secs = std::fs::read_to_string(dir.join(LAST_USED_FILE)).ok()?.trim().parse().ok()?;
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(86400);
+
+    #[test]
+    fn build_dirs_are_per_clash() {
+        let root = tempfile::tempdir().unwrap();
+        let dirs = BuildDirs::new(root.path().join("build"));
+        let now = SystemTime::now();
+        let a = dirs.prepare("abc", now).unwrap();
+        let b = dirs.prepare("def", now).unwrap();
+        assert_ne!(a, b);
+        assert!(a.is_dir() && b.is_dir());
+        assert_eq!(dirs.prepare("abc", now).unwrap(), a);
+    }
+
+    #[test]
+    fn only_unused_dirs_are_cleaned() {
+        let root = tempfile::tempdir().unwrap();
+        let dirs = BuildDirs::new(root.path().to_path_buf());
+        let now = SystemTime::UNIX_EPOCH + 100 * DAY;
+        let old = dirs.prepare("old", now - 10 * DAY).unwrap();
+        let recent = dirs.prepare("recent", now - DAY).unwrap();
+        std::fs::create_dir(root.path().join("unmarked")).unwrap();
+
+        assert_eq!(dirs.clean(7 * DAY, now).unwrap(), vec![old.clone()]);
+        assert!(!old.exists());
+        assert!(recent.exists());
+        assert!(root.path().join("unmarked").exists());
+    }
+}
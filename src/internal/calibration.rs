@@ -0,0 +1,138 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// How long the reference workload takes on the machine the default
+/// timeouts were chosen on (with a release build of coctus).
+const REFERENCE_TIME: Duration = Duration::from_millis(50);
+
+/// Bounds of the scaling factor, so that a measurement disturbed by some
+/// other program can't make the timeouts useless.
+const MIN_SCALE: f64 = 0.5;
+const MAX_SCALE: f64 = 10.0;
+
+/// Speed of this machine compared to the reference machine, measured by
+/// `coctus calibrate`. Default timeouts are multiplied by the scale.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    pub scale: f64,
+    /// Seconds the reference workload took.
+    pub measured_secs: f64,
+}
+
+impl Calibration {
+    /// Runs the reference workload a few times and compares the fastest run
+    /// to the reference time.
+    pub fn measure() -> Self {
+        let fastest = (0..5)
+            .map(|_| {
+                let start = Instant::now();
+                std::hint::black_box(reference_workload(std::hint::black_box(2_000_000)));
+                start.elapsed()
+            })
+            .min()
+            .expect("workload should be run at least once");
+        Self::from_measurement(fastest)
+    }
+
+    pub fn from_measurement(measured: Duration) -> Self {
+        let scale = measured.as_secs_f64() / REFERENCE_TIME.as_secs_f64();
+        Calibration {
+            scale: scale.clamp(MIN_SCALE, MAX_SCALE),
+            measured_secs: measured.as_secs_f64(),
+        }
+    }
+
+    /// A scale outside of the bounds (from editing the file by hand) is
+    /// clamped to them. A scale that is not a number at all is an error.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let Some(mut calibration) = json_file::load::<Calibration>(path, "calibration")? else {
+            return Ok(None)
+        };
+        if calibration.scale.is_nan() {
+            return Err(anyhow!("Invalid scale in {:?} (run `coctus calibrate` again)", path))
+        }
+        calibration.scale = calibration.scale.clamp(MIN_SCALE, MAX_SCALE);
+        Ok(Some(calibration))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+
+    /// Timeouts too long to scale stay as long as they can be.
+    pub fn scale_timeout(&self, timeout: Duration) -> Duration {
+        Duration::try_from_secs_f64(timeout.as_secs_f64() * self.scale).unwrap_or(Duration::MAX)
+    }
+}
+
+/// A mix of arithmetic, branching and memory accesses similar to what clash
+/// solutions spend their time on: a sieve of Eratosthenes followed by
+/// sorting the primes by their digit sums.
+fn reference_workload(limit: usize) -> u64 {
+    let mut is_prime = vec![true; limit];
+    let mut primes = Vec::new();
+    for n in 2..limit {
+        if is_prime[n] {
+            primes.push(n as u64);
+            for multiple in (n * n..limit).step_by(n) {
+                is_prime[multiple] = false;
+            }
+        }
+    }
+    let digit_sum = |mut n: u64| {
+        let mut sum = 0;
+        while n > 0 {
+            sum += n % 10;
+            n /= 10;
+        }
+        sum
+    };
+    primes.sort_by_key(|&prime| (digit_sum(prime), std::cmp::Reverse(prime)));
+    primes.iter().step_by(1000).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_is_clamped() {
+        assert_eq!(Calibration::from_measurement(REFERENCE_TIME * 2).scale, 2.0);
+        assert_eq!(Calibration::from_measurement(Duration::ZERO).scale, MIN_SCALE);
+        assert_eq!(Calibration::from_measurement(REFERENCE_TIME * 100).scale, MAX_SCALE);
+    }
+
+    #[test]
+    fn timeouts_are_scaled() {
+        let calibration = Calibration::from_measurement(REFERENCE_TIME * 3);
+        assert_eq!(calibration.scale_timeout(Duration::from_secs(5)), Duration::from_secs(15));
+    }
+
+    #[test]
+    fn scales_from_the_file_are_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("calibration.json");
+        let load = |scale: &str| {
+            std::fs::write(&path, format!(r#"{{"scale":{scale},"measured_secs":0.1}}"#)).unwrap();
+            Calibration::load(&path).map(|calibration| calibration.unwrap().scale)
+        };
+        assert_eq!(load("2.0").unwrap(), 2.0);
+        assert_eq!(load("-3.0").unwrap(), MIN_SCALE);
+        assert_eq!(load("1e300").unwrap(), MAX_SCALE);
+        // JSON has no NaN, but a scale that isn't a number still has to fail
+        assert!(load("\"NaN\"").is_err());
+
+        let calibration = Calibration::from_measurement(REFERENCE_TIME * 2);
+        assert_eq!(calibration.scale_timeout(Duration::MAX), Duration::MAX);
+    }
+
+    #[test]
+    fn workload_is_deterministic() {
+        assert_eq!(reference_workload(1000), reference_workload(1000));
+    }
+}
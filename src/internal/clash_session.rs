@@ -0,0 +1,106 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// A timed attempt at a clash started with `coctus clash start`. It is stored
+/// in the data directory, so that the clock keeps running between
+/// invocations until `coctus run` passes every testcase of the clash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClashSession {
+    pub handle: String,
+    /// Seconds since the unix epoch when the session started.
+    pub started_at: u64,
+    /// Seconds there are for solving the clash.
+    pub time_limit: u64,
+}
+
+impl ClashSession {
+    pub fn start(handle: String, time_limit: Duration, now: SystemTime) -> Self {
+        ClashSession {
+            handle,
+            started_at: now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            time_limit: time_limit.as_secs(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        json_file::load(path, "clash session")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+
+    /// Ends the session stored in `path`, if there is one.
+    pub fn remove(path: &Path) -> Result<()> {
+        match std::fs::remove_file(path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                Err(err).with_context(|| format!("Unable to remove clash session {:?}", path))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn time_limit(&self) -> Duration {
+        Duration::from_secs(self.time_limit)
+    }
+
+    pub fn elapsed(&self, now: SystemTime) -> Duration {
+        let started_at = UNIX_EPOCH + Duration::from_secs(self.started_at);
+        now.duration_since(started_at).unwrap_or_default()
+    }
+
+    /// Time left before the limit, `None` once the time is up.
+    pub fn remaining(&self, now: SystemTime) -> Option<Duration> {
+        self.time_limit().checked_sub(self.elapsed(now)).filter(|left| !left.is_zero())
+    }
+
+    /// How the session is going, like "12:31 left" or "time is up (2:10 over)".
+    pub fn clock(&self, now: SystemTime) -> String {
+        match self.remaining(now) {
+            // Rounded up, so that the clock shows 0:00 only once the time is up
+            Some(left) => {
+                format!("{} left", format_clock(Duration::from_secs(left.as_secs_f64().ceil() as u64)))
+            }
+            None => format!("time is up ({} over)", format_clock(self.elapsed(now) - self.time_limit())),
+        }
+    }
+}
+
+/// Formats a duration like a countdown clock: `9:05` or `1:02:03`.
+pub fn format_clock(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, m, s) => format!("{m}:{s:02}"),
+        (h, m, s) => format!("{h}:{m:02}:{s:02}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_counts_down_to_the_limit() {
+        let start = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let session = ClashSession::start("abc".to_string(), Duration::from_secs(15 * 60), start);
+        let after = |secs| start + Duration::from_secs(secs);
+        assert_eq!(session.remaining(after(29)), Some(Duration::from_secs(871)));
+        assert_eq!(session.clock(after(29)), "14:31 left");
+        assert_eq!(session.remaining(after(900)), None);
+        assert_eq!(session.clock(after(1030)), "time is up (2:10 over)");
+        assert_eq!(format_clock(Duration::from_secs(3723)), "1:02:03");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clash_session.json");
+        session.save(&path).unwrap();
+        assert_eq!(ClashSession::load(&path).unwrap(), Some(session));
+        ClashSession::remove(&path).unwrap();
+        ClashSession::remove(&path).unwrap();
+        assert_eq!(ClashSession::load(&path).unwrap(), None);
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Context, Result};
+
+/// Copies `text` to the system clipboard by piping it to the first clipboard
+/// command that is installed. Returns the name of the command.
+pub fn copy_to_clipboard(text: &str) -> Result<String> {
+    let commands =
+        clipboard_commands(|var| std::env::var(var).ok(), cfg!(target_os = "macos"), cfg!(windows))?;
+    pipe_to_first_installed(&commands, text)
+}
+
+fn pipe_to_first_installed(commands: &[Vec<String>], text: &str) -> Result<String> {
+    for command in commands {
+        let mut child = match Command::new(&command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err).with_context(|| format!("Unable to run {}", command[0])),
+        };
+        child
+            .stdin
+            .take()
+            .expect("stdin should be piped")
+            .write_all(text.as_bytes())
+            .with_context(|| format!("Unable to write to {}", command[0]))?;
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(anyhow!("{} failed to copy to the clipboard ({status})", command[0]))
+        }
+        return Ok(command[0].clone())
+    }
+    let tried = commands.iter().map(|command| command[0].as_str()).collect::<Vec<_>>().join(", ");
+    Err(anyhow!(
+        "No clipboard command found (tried {tried}). Install one or set COCTUS_CLIPBOARD to a command \
+        that copies its stdin."
+    ))
+}
+
+/// Commands that copy their stdin to the clipboard, in the order they are
+/// tried: $COCTUS_CLIPBOARD if it is set, otherwise the clipboard commands of
+/// the platform (and of the display server on other systems than macOS and
+/// Windows).
+fn clipboard_commands(
+    env: impl Fn(&str) -> Option<String>,
+    macos: bool,
+    windows: bool,
+) -> Result<Vec<Vec<String>>> {
+    if let Some(command) = env("COCTUS_CLIPBOARD").filter(|command| !command.trim().is_empty()) {
+        let args = shlex::split(&command).with_context(|| format!("Invalid COCTUS_CLIPBOARD {command:?}"))?;
+        return Ok(vec![args])
+    }
+    let mut commands: Vec<&[&str]> = Vec::new();
+    if macos {
+        commands.push(&["pbcopy"]);
+    } else if windows {
+        commands.push(&["clip"]);
+    } else {
+        if env("WAYLAND_DISPLAY").is_some() {
+            commands.push(&["wl-copy"]);
+        }
+        if env("DISPLAY").is_some() {
+            commands.push(&["xclip", "-selection", "clipboard"]);
+            commands.push(&["xsel", "--clipboard", "--input"]);
+        }
+        commands.push(&["termux-clipboard-set"]);
+    }
+    Ok(commands
+        .into_iter()
+        .map(|command| command.iter().map(ToString::to_string).collect())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        |name| vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.to_string())
+    }
+
+    #[test]
+    fn clipboard_commands_of_the_platform() {
+        let names =
+            |commands: Vec<Vec<String>>| commands.into_iter().map(|c| c[0].clone()).collect::<Vec<_>>();
+        assert_eq!(names(clipboard_commands(env(&[]), true, false).unwrap()), vec!["pbcopy"]);
+        assert_eq!(
+            names(
+                clipboard_commands(env(&[("WAYLAND_DISPLAY", "wayland-0"), ("DISPLAY", ":0")]), false, false)
+                    .unwrap()
+            ),
+            vec!["wl-copy", "xclip", "xsel", "termux-clipboard-set"]
+        );
+        assert_eq!(
+            names(clipboard_commands(env(&[]), false, false).unwrap()),
+            vec!["termux-clipboard-set"]
+        );
+        assert_eq!(
+            clipboard_commands(env(&[("COCTUS_CLIPBOARD", "tmux load-buffer -")]), true, false).unwrap(),
+            vec![vec!["tmux", "load-buffer", "-"]]
+        );
+    }
+
+    #[test]
+    fn copying_skips_missing_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clipboard");
+        let command = format!("sh -c 'cat > {}'", path.display());
+        let commands = clipboard_commands(env(&[("COCTUS_CLIPBOARD", &command)]), false, false).unwrap();
+        let missing = vec!["coctus-no-such-clipboard".to_string()];
+        assert_eq!(
+            pipe_to_first_installed(&[missing.clone(), commands[0].clone()], "1 2\n3\n").unwrap(),
+            "sh"
+        );
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "1 2\n3\n");
+        assert!(pipe_to_first_installed(&[missing], "").unwrap_err().to_string().contains("tried"));
+    }
+}
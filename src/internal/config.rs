@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use ansi_term::Color;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer};
+
+use super::{json_file, DiffMode, Numbering};
+
+/// Contents of `config.toml` in the config dir. Every option can also be
+/// given with a command line flag or an environment variable, which take
+/// precedence over the config file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Never download anything from codingame.com (`$COCTUS_OFFLINE`).
+    pub offline: bool,
+    /// Download clashes that are missing when running `show` or `run`
+    /// (`--fetch-missing` or `$COCTUS_FETCH_MISSING`).
+    pub fetch_missing: bool,
+    /// Default for `run --diff-style` (`$COCTUS_DIFF_STYLE`).
+    #[serde(deserialize_with = "deser_diff_mode")]
+    pub diff_style: Option<DiffMode>,
+    /// Default for `--numbering` of `run` and `showtests`.
+    pub numbering: Numbering,
+    /// Default for `start --workspace`.
+    pub workspace: Option<PathBuf>,
+    /// Days after which unused build directories are deleted.
+    pub build_dir_max_age_days: Option<u64>,
+    /// Read-only clash collection shared with other users
+    /// (`$COCTUS_SHARED_DIR`).
+    pub shared_dir: Option<PathBuf>,
+    /// Build and run commands of solutions by language, used instead of the
+    /// default commands in the stub configs (`run --language`).
+    pub languages: BTreeMap<String, LanguageCommands>,
+    /// Colors of the output.
+    pub theme: Theme,
+}
+
+/// The `[theme]` table of the config file. Colors that are not set keep
+/// their defaults, and none of them are used with `$NO_COLOR`.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Parts of the output that match the expected output.
+    #[serde(deserialize_with = "deser_color")]
+    pub diff_correct: Option<Color>,
+    /// Extra output in the color diff.
+    #[serde(deserialize_with = "deser_color")]
+    pub diff_wrong: Option<Color>,
+    /// Changed spans and extra lines in the symbols diff.
+    #[serde(deserialize_with = "deser_color")]
+    pub diff_changed: Option<Color>,
+    /// Whether links are clickable, `None` to detect whether the terminal
+    /// supports them (`$COCTUS_HYPERLINKS`).
+    pub hyperlinks: Option<bool>,
+}
+
+/// A `[languages.NAME]` table of the config file.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LanguageCommands {
+    pub build: Option<String>,
+    pub run: Option<String>,
+}
+
+/// Written by `coctus init`. All options are commented out so that the
+/// defaults keep applying until the user decides otherwise.
+pub const DEFAULT_CONFIG: &str = r##"# Configuration file of coctus. Command line flags and environment variables
+# take precedence over the options set here.
+
+# Never download anything from codingame.com (same as COCTUS_OFFLINE=1)
+# offline = false
+
+# Download clashes that are not stored locally when running `coctus show` or
+# `coctus run` (same as --fetch-missing or COCTUS_FETCH_MISSING=1)
+# fetch_missing = false
+
+# How `coctus run` highlights differences in the output: "color" or "symbols"
+# (same as --diff-style or COCTUS_DIFF_STYLE)
+# diff_style = "color"
+
+# How testcases are numbered by `coctus run` and `coctus showtests`: "flat"
+# (#1, #2, ... validators included) or "cg" (Test 1.., Validator 1.. like in
+# the CodinGame IDE). Same as --numbering
+# numbering = "flat"
+
+# Directory in which `coctus start` creates a directory for each clash, with
+# the statement, the stub and the solution (same as --workspace)
+# workspace = "/home/me/clashes"
+
+# Build and run commands of solutions get a build directory of their own for
+# each clash in COCTUS_BUILD_DIR (and {build_dir} in the commands). Build
+# directories that have not been used for this many days are deleted
+# build_dir_max_age_days = 7
+
+# Read the clashes (and their tags) from a collection shared with other users,
+# for example on a network drive. The shared collection is never modified:
+# everything else, such as the selected clash and the history of runs, is still
+# kept in the data directory of each user (same as COCTUS_SHARED_DIR)
+# shared_dir = "/mnt/club/coctus"
+
+# Build and run commands of solutions in each language, used by `coctus run`
+# for solution files created by `coctus start` and with --language. They
+# replace the default commands of the language. {file} is replaced with the
+# path of the solution file, {executable} with a path in the build directory of
+# the clash and {build_dir} with the build directory itself
+# [languages.python]
+# run = "python3 {file}"
+#
+# [languages.rust]
+# build = "rustc -O -o {executable} {file}"
+# run = "{executable}"
+
+# Colors of the diffs that `coctus run` prints: a name (black, red, green,
+# yellow, blue, purple, cyan or white), an ANSI 256 color number such as "208"
+# or "#rrggbb". Setting NO_COLOR turns all colors off
+# [theme]
+# diff_correct = "cyan"
+# diff_wrong = "#ff8800"
+# diff_changed = "208"
+#
+# Whether the links to clashes and local files are clickable (OSC 8
+# hyperlinks). Detected from the terminal when not set, same as
+# COCTUS_HYPERLINKS=1 or 0
+# hyperlinks = true
+"##;
+
+impl Config {
+    /// Path of the config file in `config_dir`.
+    pub fn file(config_dir: &Path) -> PathBuf {
+        config_dir.join("config.toml")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        match json_file::read_if_exists(path)? {
+            Some(contents) => {
+                toml::from_str(&contents).with_context(|| format!("Invalid config file {:?}", path))
+            }
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+fn deser_diff_mode<'de, D>(deserializer: D) -> Result<Option<DiffMode>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: String = Deserialize::deserialize(deserializer)?;
+    DiffMode::from_str(&value, true).map(Some).map_err(serde::de::Error::custom)
+}
+
+fn deser_color<'de, D>(deserializer: D) -> Result<Option<Color>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: String = Deserialize::deserialize(deserializer)?;
+    parse_color(&value).map(Some).map_err(serde::de::Error::custom)
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    let color = match value.to_ascii_lowercase().as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "purple" => Color::Purple,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        hex if hex.starts_with('#') && hex.len() == 7 && hex.is_ascii() => {
+            let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16);
+            match (channel(1), channel(3), channel(5)) {
+                (Ok(r), Ok(g), Ok(b)) => Color::RGB(r, g, b),
+                _ => return Err(format!("invalid color {value:?}")),
+            }
+        }
+        number => match number.parse::<u8>() {
+            Ok(number) => Color::Fixed(number),
+            Err(_) => return Err(format!("invalid color {value:?}, use a name, a number or #rrggbb")),
+        },
+    };
+    Ok(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_only_has_comments() {
+        let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert!(!config.offline);
+        assert!(!config.fetch_missing);
+        assert_eq!(config.diff_style, None);
+    }
+
+    #[test]
+    fn uncommented_default_config_is_valid() {
+        let uncommented =
+            DEFAULT_CONFIG.replace("# offline", "offline").replace("# diff_style", "diff_style");
+        let uncommented = uncommented.replace("# workspace", "workspace").replace("# build_dir", "build_dir");
+        let uncommented = uncommented
+            .replace("# shared_dir", "shared_dir")
+            .replace("# [languages", "[languages");
+        let uncommented = uncommented.replace("# run =", "run =").replace("# build =", "build =");
+        let uncommented = uncommented.replace("# [theme]", "[theme]").replace("# diff_", "diff_");
+        let uncommented = uncommented.replace("# hyperlinks =", "hyperlinks =");
+        let config: Config = toml::from_str(&uncommented).unwrap();
+        assert_eq!(config.diff_style, Some(DiffMode::Color));
+        assert_eq!(config.workspace, Some(PathBuf::from("/home/me/clashes")));
+        assert_eq!(config.build_dir_max_age_days, Some(7));
+        assert_eq!(config.shared_dir, Some(PathBuf::from("/mnt/club/coctus")));
+        assert_eq!(config.languages["python"].run.as_deref(), Some("python3 {file}"));
+        assert_eq!(config.languages["python"].build, None);
+        assert_eq!(config.languages["rust"].build.as_deref(), Some("rustc -O -o {executable} {file}"));
+        let theme = Theme {
+            diff_correct: Some(Color::Cyan),
+            diff_wrong: Some(Color::RGB(255, 136, 0)),
+            diff_changed: Some(Color::Fixed(208)),
+            hyperlinks: Some(true),
+        };
+        assert_eq!(config.theme, theme);
+    }
+
+    #[test]
+    fn unknown_options_are_rejected() {
+        assert!(toml::from_str::<Config>("ofline = true").is_err());
+        assert!(toml::from_str::<Config>("diff_style = \"rainbow\"").is_err());
+        assert!(toml::from_str::<Config>("[languages.python]\ncommand = \"python3\"").is_err());
+        assert!(toml::from_str::<Config>("[theme]\ndiff_wrong = \"#ff88\"").is_err());
+        assert!(toml::from_str::<Config>("[theme]\ndiff_wrong = \"256\"").is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use std::io::{Read, Write};
+use std::path::{Component, Path};
+
+use anyhow::{anyhow, Context, Result};
+
+use super::Config;
+
+/// Version of the config file format. Bump it together with a new entry in
+/// [MIGRATIONS] when an option is renamed or changes its meaning, so that
+/// archives made by older versions of coctus can still be imported.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Upgrades a config file (parsed into a TOML table) by one version.
+type Migration = fn(&mut toml::Table) -> Result<()>;
+
+/// `MIGRATIONS[n]` upgrades a config file of version `n + 1` to version
+/// `n + 2`. There have been no incompatible changes yet.
+const MIGRATIONS: &[Migration] = &[];
+
+/// Name of the file with the config version inside an archive.
+const VERSION_FILE: &str = "config_version";
+const CONFIG_FILE: &str = "config.toml";
+const STUB_TEMPLATES_DIR: &str = "stub_templates";
+
+/// The contents of an archive made by `coctus config export`: a gzipped
+/// tarball of the config file (which has the commands of the languages) and
+/// the stub template overrides, for moving them to another machine. The
+/// login is left out on purpose.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConfigArchive {
+    /// [CONFIG_VERSION] of the coctus that made the archive.
+    pub config_version: u32,
+    pub config: Option<String>,
+    /// Paths relative to the stub templates directory (like
+    /// `python/stub_config.toml`) and the contents of the files, sorted by
+    /// path.
+    pub stub_templates: Vec<(String, Vec<u8>)>,
+}
+
+impl ConfigArchive {
+    /// Collects the config file and the stub templates of `config_dir`.
+    pub fn of_dir(config_dir: &Path) -> Result<Self> {
+        let config_path = config_dir.join(CONFIG_FILE);
+        let config = match config_path.exists() {
+            true => Some(
+                std::fs::read_to_string(&config_path)
+                    .with_context(|| format!("Unable to read {:?}", config_path))?,
+            ),
+            false => None,
+        };
+        let mut stub_templates = Vec::new();
+        collect_files(&config_dir.join(STUB_TEMPLATES_DIR), "", &mut stub_templates)?;
+        stub_templates.sort();
+        Ok(ConfigArchive {
+            config_version: CONFIG_VERSION,
+            config,
+            stub_templates,
+        })
+    }
+
+    pub fn write<W: Write>(&self, out: W) -> Result<()> {
+        let mut builder =
+            tar::Builder::new(flate2::write::GzEncoder::new(out, flate2::Compression::default()));
+        let mut append = |path: String, contents: &[u8]| -> Result<()> {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, &path, contents)
+                .with_context(|| format!("Unable to add {path} to the archive"))
+        };
+        append(VERSION_FILE.to_string(), format!("{}\n", self.config_version).as_bytes())?;
+        if let Some(config) = &self.config {
+            append(CONFIG_FILE.to_string(), config.as_bytes())?;
+        }
+        for (path, contents) in &self.stub_templates {
+            append(format!("{STUB_TEMPLATES_DIR}/{path}"), contents)?;
+        }
+        builder.into_inner()?.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// Reads an archive, refusing any entries other than the ones that
+    /// `coctus config export` writes so that importing an archive can't write
+    /// outside of the config directory.
+    pub fn read<R: Read>(input: R) -> Result<Self> {
+        let mut config_version = None;
+        let mut config = None;
+        let mut stub_templates = Vec::new();
+        let mut tarball = tar::Archive::new(flate2::read::GzDecoder::new(input));
+        for entry in tarball.entries().context("Unable to read the archive")? {
+            let mut entry = entry.context("Unable to read the archive")?;
+            if entry.header().entry_type().is_dir() {
+                continue
+            }
+            let path = entry.path()?.into_owned();
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            let components = normal_components(&path)
+                .with_context(|| format!("Unexpected file {:?} in the archive", path))?;
+            match components.as_slice() {
+                [VERSION_FILE] => {
+                    let version = String::from_utf8_lossy(&contents);
+                    config_version = Some(
+                        version
+                            .trim()
+                            .parse()
+                            .with_context(|| format!("Invalid config version {version:?} in the archive"))?,
+                    );
+                }
+                [CONFIG_FILE] => {
+                    config = Some(String::from_utf8(contents).context("The config file is not valid UTF-8")?)
+                }
+                [STUB_TEMPLATES_DIR, _language, _file, ..] => {
+                    stub_templates.push((components[1..].join("/"), contents));
+                }
+                _ => return Err(anyhow!("Unexpected file {:?} in the archive", path)),
+            }
+        }
+        stub_templates.sort();
+        Ok(ConfigArchive {
+            config_version: config_version.context("The archive has no config version")?,
+            config,
+            stub_templates,
+        })
+    }
+
+    /// Upgrades the config file to the current [CONFIG_VERSION] and checks
+    /// that it is valid.
+    pub fn migrate(&mut self) -> Result<()> {
+        if let Some(config) = &self.config {
+            let migrated = migrate_config(config, self.config_version, MIGRATIONS)?;
+            toml::from_str::<Config>(&migrated).context("Invalid config file in the archive")?;
+            self.config = Some(migrated);
+        }
+        self.config_version = CONFIG_VERSION;
+        Ok(())
+    }
+
+    /// Languages that have stub templates in the archive.
+    pub fn languages(&self) -> Vec<&str> {
+        let mut languages: Vec<&str> =
+            self.stub_templates.iter().filter_map(|(path, _)| path.split('/').next()).collect();
+        languages.dedup();
+        languages
+    }
+}
+
+/// Brings `config` of version `from` up to date with `migrations`. A config
+/// that needs no migrating is returned as is, comments included.
+fn migrate_config(config: &str, from: u32, migrations: &[Migration]) -> Result<String> {
+    let current = migrations.len() as u32 + 1;
+    if from == 0 || from > current {
+        return Err(anyhow!(
+            "The archive has a config of version {from}, but this version of coctus only knows versions up \
+            to {current}"
+        ))
+    }
+    if from == current {
+        return Ok(config.to_string())
+    }
+    let mut table: toml::Table = toml::from_str(config).context("Invalid config file in the archive")?;
+    for (idx, migration) in migrations.iter().enumerate().skip(from as usize - 1) {
+        migration(&mut table)
+            .with_context(|| format!("Unable to migrate the config to version {}", idx + 2))?;
+    }
+    Ok(toml::to_string(&table)?)
+}
+
+fn collect_files(dir: &Path, prefix: &str, files: &mut Vec<(String, Vec<u8>)>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(())
+    }
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Unable to read {:?}", dir))? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue
+        };
+        let relative = format!("{prefix}{name}");
+        if path.is_dir() {
+            collect_files(&path, &format!("{relative}/"), files)?;
+        } else if !prefix.is_empty() {
+            // Files directly in the stub templates directory are not used
+            files.push((relative, std::fs::read(&path)?));
+        }
+    }
+    Ok(())
+}
+
+fn normal_components(path: &Path) -> Option<Vec<&str>> {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_archive_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(CONFIG_FILE), "offline = true\n").unwrap();
+        std::fs::write(dir.path().join("login.json"), "{}").unwrap();
+        let python_dir = dir.path().join("stub_templates/python");
+        std::fs::create_dir_all(&python_dir).unwrap();
+        std::fs::write(python_dir.join("stub_config.toml"), "name = \"python\"").unwrap();
+        std::fs::write(python_dir.join("main.py.jinja"), "{{ code }}").unwrap();
+
+        let archive = ConfigArchive::of_dir(dir.path()).unwrap();
+        assert_eq!(archive.config.as_deref(), Some("offline = true\n"));
+        assert_eq!(archive.stub_templates.len(), 2);
+        assert_eq!(archive.stub_templates[0].0, "python/main.py.jinja");
+        assert_eq!(archive.languages(), vec!["python"]);
+
+        let mut bytes = Vec::new();
+        archive.write(&mut bytes).unwrap();
+        let mut read = ConfigArchive::read(bytes.as_slice()).unwrap();
+        assert_eq!(read, archive);
+        read.migrate().unwrap();
+        assert_eq!(read, archive);
+    }
+
+    #[test]
+    fn old_configs_are_migrated() {
+        assert_eq!(CONFIG_VERSION as usize, MIGRATIONS.len() + 1);
+
+        let rename_offline: Migration = |table| {
+            if let Some(value) = table.remove("no_network") {
+                table.insert("offline".to_string(), value);
+            }
+            Ok(())
+        };
+        let migrations = [rename_offline];
+        let config = "# A comment\nno_network = true\n";
+        assert_eq!(migrate_config(config, 1, &migrations).unwrap(), "offline = true\n");
+        assert_eq!(migrate_config(config, 2, &migrations).unwrap(), config);
+        assert!(migrate_config(config, 3, &migrations).unwrap_err().to_string().contains("up to 2"));
+    }
+
+    #[test]
+    fn invalid_archives_are_refused() {
+        let archive = |entries: &[(&str, &str)]| {
+            let mut builder =
+                tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), Default::default()));
+            for (path, contents) in entries {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(contents.len() as u64);
+                // `append_data` refuses `..`, the path has to be set by hand
+                header.as_gnu_mut().unwrap().name[..path.len()].copy_from_slice(path.as_bytes());
+                header.set_cksum();
+                builder.append(&header, contents.as_bytes()).unwrap();
+            }
+            builder.into_inner().unwrap().finish().unwrap()
+        };
+        for path in [
+            "login.json",
+            "stub_templates/stub_config.toml",
+            "stub_templates/../../x",
+        ] {
+            let bytes = archive(&[(VERSION_FILE, "1"), (path, "")]);
+            assert!(ConfigArchive::read(bytes.as_slice()).is_err(), "{path} should be refused");
+        }
+        assert!(ConfigArchive::read(archive(&[(CONFIG_FILE, "")]).as_slice()).is_err());
+
+        let bytes = archive(&[(VERSION_FILE, "1"), (CONFIG_FILE, "ofline = true")]);
+        let mut config_archive = ConfigArchive::read(bytes.as_slice()).unwrap();
+        assert!(config_archive.migrate().is_err());
+    }
+}
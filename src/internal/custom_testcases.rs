@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use anyhow::Result;
+use clashlib::clash::Testcase;
+
+use super::json_file;
+
+/// Testcases that the user saved for a clash (with `coctus tweak
+/// --save-as-custom`), stored as a JSON array in a file of their own so that
+/// refreshing the clash never loses them.
+pub struct CustomTestcases;
+
+impl CustomTestcases {
+    /// Loads the custom testcases in `path`, numbered to come after the
+    /// `existing` testcases of the clash.
+    pub fn load(path: &Path, existing: &[Testcase]) -> Result<Vec<Testcase>> {
+        let mut testcases: Vec<Testcase> = json_file::load(path, "custom testcases")?.unwrap_or_default();
+        let num_tests = existing.iter().filter(|testcase| !testcase.is_validator).count();
+        for (idx, testcase) in testcases.iter_mut().enumerate() {
+            testcase.index = existing.len() + idx + 1;
+            testcase.number = num_tests + idx + 1;
+            testcase.is_validator = false;
+        }
+        Ok(testcases)
+    }
+
+    /// Adds `testcase` to the end of the custom testcases in `path`.
+    pub fn append(path: &Path, testcase: &Testcase) -> Result<()> {
+        let mut testcases = Self::load(path, &[])?;
+        testcases.push(testcase.clone());
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        json_file::save(path, &testcases)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::test_helper::testcase;
+
+    #[test]
+    fn custom_testcases_come_after_the_clash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("custom/abc.json");
+        assert!(CustomTestcases::load(&path, &[]).unwrap().is_empty());
+        CustomTestcases::append(&path, &testcase(0, "Custom 1 in", "Custom 1 out")).unwrap();
+        CustomTestcases::append(&path, &testcase(0, "Custom 2 in", "Custom 2 out")).unwrap();
+
+        let validator = Testcase {
+            number: 1,
+            is_validator: true,
+            ..testcase(2, "", "")
+        };
+        let existing = [testcase(1, "", ""), validator];
+        let custom = CustomTestcases::load(&path, &existing).unwrap();
+        assert_eq!(custom.len(), 2);
+        assert_eq!((custom[1].index, custom[1].number), (4, 3));
+        assert_eq!(custom[1].test_in, "Custom 2 in");
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+/// Parses a human friendly duration such as `90`, `90s`, `15m`, `1h30m` or
+/// `0.5s`. A number without a unit is interpreted as seconds.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("duration can't be empty".to_string())
+    }
+
+    let mut total_secs = 0.0;
+    let mut number = String::new();
+    for ch in text.chars() {
+        if ch.is_ascii_digit() || ch == '.' {
+            number.push(ch);
+            continue
+        }
+        let unit_secs = match ch {
+            'h' => 3600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            other => return Err(format!("unknown duration unit '{other}' (expected h, m or s)")),
+        };
+        total_secs += parse_number(&number)? * unit_secs;
+        number.clear();
+    }
+    if !number.is_empty() {
+        total_secs += parse_number(&number)?;
+    }
+
+    Duration::try_from_secs_f64(total_secs).map_err(|_| format!("duration {text:?} is too long"))
+}
+
+fn parse_number(number: &str) -> Result<f64, String> {
+    number.parse::<f64>().map_err(|_| format!("invalid number '{number}' in duration"))
+}
+
+/// Formats a duration as `1h02m03s`, `2m03s` or `3.4s`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, _) => format!("{:.1}s", duration.as_secs_f64()),
+        (0, m, s) => format!("{m}m{s:02}s"),
+        (h, m, s) => format!("{h}h{m:02}m{s:02}s"),
+    }
+}
+
+/// Formats an estimated reading time briefly: seconds under a minute and
+/// whole minutes (rounded up) otherwise, like `45s` or `3m`.
+pub fn format_reading_time(duration: Duration) -> String {
+    match duration.as_secs() {
+        secs @ 0..=59 => format!("{secs}s"),
+        secs => format!("{}m", secs.div_ceil(60)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_plain_seconds() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("0.5"), Ok(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn parse_with_units() {
+        assert_eq!(parse_duration("15m"), Ok(Duration::from_secs(15 * 60)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(90 * 60)));
+        assert_eq!(parse_duration("2m10s"), Ok(Duration::from_secs(130)));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("5 minutes").is_err());
+        assert!(parse_duration("1..2s").is_err());
+    }
+
+    #[test]
+    fn parse_too_long() {
+        assert_eq!(
+            parse_duration("99999999999999999999h"),
+            Err("duration \"99999999999999999999h\" is too long".to_string())
+        );
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(format_duration(Duration::from_millis(3400)), "3.4s");
+        assert_eq!(format_duration(Duration::from_secs(123)), "2m03s");
+        assert_eq!(format_duration(Duration::from_secs(3723)), "1h02m03s");
+        assert_eq!(format_reading_time(Duration::from_secs(45)), "45s");
+        assert_eq!(format_reading_time(Duration::from_secs(60)), "1m");
+        assert_eq!(format_reading_time(Duration::from_secs(61)), "2m");
+    }
+}
@@ -0,0 +1,67 @@
+use clashlib::diff::FailurePattern;
+
+/// Failures that are at least this similar are considered the same failure.
+const SIMILARITY_THRESHOLD: f64 = 0.75;
+
+/// Testcases that failed in the same way, labeled as in the results.
+#[derive(Debug)]
+pub struct FailureCluster {
+    pub pattern: FailurePattern,
+    pub labels: Vec<String>,
+}
+
+/// Groups the wrong outputs of a run by how they differ from the expected
+/// outputs, so that a failure repeated in many testcases only needs to be
+/// shown once.
+#[derive(Debug, Default)]
+pub struct FailureClusters {
+    clusters: Vec<FailureCluster>,
+}
+
+impl FailureClusters {
+    /// Adds a failure to the first cluster with a similar pattern, or to a
+    /// new one. Returns the cluster if the failure was similar to an earlier
+    /// one.
+    pub fn add(&mut self, label: String, pattern: FailurePattern) -> Option<&FailureCluster> {
+        let similar = self
+            .clusters
+            .iter()
+            .position(|cluster| cluster.pattern.similarity(&pattern) >= SIMILARITY_THRESHOLD);
+        match similar {
+            Some(idx) => {
+                self.clusters[idx].labels.push(label);
+                Some(&self.clusters[idx])
+            }
+            None => {
+                self.clusters.push(FailureCluster {
+                    pattern,
+                    labels: vec![label],
+                });
+                None
+            }
+        }
+    }
+
+    /// Clusters with more than one failure, in the order they were started.
+    pub fn repeated(&self) -> impl Iterator<Item = &FailureCluster> {
+        self.clusters.iter().filter(|cluster| cluster.labels.len() > 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn similar_failures_are_clustered() {
+        let mut clusters = FailureClusters::default();
+        assert!(clusters.add("#1".to_string(), FailurePattern::of("1\n2", "1\n3")).is_none());
+        assert!(clusters.add("#2".to_string(), FailurePattern::of("a", "b")).is_none());
+        let cluster = clusters.add("#3".to_string(), FailurePattern::of("5\n6\n7", "5\n6\n8")).unwrap();
+        assert_eq!(cluster.labels, vec!["#1", "#3"]);
+
+        let repeated: Vec<_> = clusters.repeated().collect();
+        assert_eq!(repeated.len(), 1);
+        assert_eq!(repeated[0].pattern.to_string(), "off by +1 on the last line");
+    }
+}
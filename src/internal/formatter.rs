@@ -15,6 +15,122 @@ lazy_static! {
     static ref RE_SPACES: Regex = Regex::new(r" +").unwrap();
     static ref RE_NONWHITESPACE: Regex = Regex::new(r"[^\r\n ]+").unwrap();
     static ref RE_NEWLINES: Regex = Regex::new(r"\n\n\n+").unwrap();
+    static ref RE_LIST_ITEM: Regex = Regex::new(r"(?m)^( ?)([-*+•]|\d{1,3}[.)]) +(\S)").unwrap();
+}
+
+// Internal tags for list markers inserted by `format_lists`. Control characters
+// can't appear in statements so they never clash with CodinGame's own tags.
+const LIST_MARKER_OPEN: &str = "\x01";
+const LIST_MARKER_CLOSE: &str = "\x02";
+
+/// A formatting problem found in a text with CodinGame formatting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatProblem {
+    /// Byte offset of the problem in the text, see [line_and_column].
+    pub position: usize,
+    pub kind: FormatProblemKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatProblemKind {
+    /// ```text``` style monospace that CodinGame does not support.
+    ObsoleteMonospace,
+    /// A closing tag that does not match the most recently opened tag.
+    MismatchedClose { opening: String, closing: String },
+    /// An opening tag without any closing tag after it.
+    NeverClosed(String),
+    /// An opening tag that is still open at the end of the text.
+    UnclosedAtEnd(String),
+}
+
+impl std::fmt::Display for FormatProblemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FormatProblemKind::ObsoleteMonospace => write!(f, "obsolete ``` formatting"),
+            FormatProblemKind::MismatchedClose { opening, closing } => {
+                write!(f, "tried to close {:?} with {:?}", opening, closing)
+            }
+            FormatProblemKind::NeverClosed(tag) => write!(f, "ignoring {:?} that is never closed", tag),
+            FormatProblemKind::UnclosedAtEnd(tag) => write!(f, "{:?} was never closed", tag),
+        }
+    }
+}
+
+/// Converts a byte offset in `text` into 1-based line and column numbers
+/// (the column is counted in characters).
+pub fn line_and_column(text: &str, position: usize) -> (usize, usize) {
+    let before = &text[..position];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+/// Finds all formatting problems in `text`. Unlike [format_cg], which only
+/// warns about the first problem, this reports every one of them with
+/// positions relative to the original text.
+pub fn lint_cg(text: &str) -> Vec<FormatProblem> {
+    let mut problems: Vec<FormatProblem> = RE_MONOSPACE_OLD
+        .find_iter(text)
+        .map(|m| FormatProblem {
+            position: m.start(),
+            kind: FormatProblemKind::ObsoleteMonospace,
+        })
+        .collect();
+    let (_, tag_problems) = paint_parts(text, &cg_tag_pairs(&OutputStyle::plain()));
+    problems.extend(tag_problems);
+    problems.sort_by_key(|problem| problem.position);
+    problems
+}
+
+/// Example input and output that the author wrote into a statement as
+/// monospace blocks.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StatementExample {
+    pub input: Option<String>,
+    pub output: Option<String>,
+}
+
+/// Finds the monospace blocks of `text` that are labeled as the example input
+/// or output, for example with "Input:" or "Sortie :" on the line before the
+/// block. Only the first block of each kind is used. The contents are
+/// normalized with [normalize_example].
+pub fn statement_example(text: &str) -> StatementExample {
+    let text = text.replace("```", "`");
+    let mut example = StatementExample::default();
+    let mut prev_end = 0;
+    for caps in RE_MONOSPACE.captures_iter(&text) {
+        let block = caps.get(0).expect("capture group 0 should always match");
+        let label = text[prev_end..block.start()]
+            .lines()
+            .map(str::trim)
+            .rev()
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+            .to_lowercase();
+        prev_end = block.end();
+        let last_position = |keywords: [&str; 2]| keywords.iter().filter_map(|kw| label.rfind(kw)).max();
+        let contents = RE_ALL_BUT_MONOSPACE.replace_all(&caps[1], |caps: &regex::Captures| {
+            caps.iter().skip(1).flatten().map(|m| m.as_str()).collect::<String>()
+        });
+        let slot = match (last_position(["input", "entrée"]), last_position(["output", "sortie"])) {
+            (Some(input), Some(output)) if input > output => &mut example.input,
+            (Some(_), None) => &mut example.input,
+            (_, Some(_)) => &mut example.output,
+            (None, None) => continue,
+        };
+        slot.get_or_insert_with(|| normalize_example(&contents));
+    }
+    example
+}
+
+/// Removes trailing whitespace from every line and the empty lines at the
+/// start and the end, so that examples can be compared with testcases.
+pub fn normalize_example(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let first = lines.iter().position(|line| !line.is_empty()).unwrap_or(lines.len());
+    let last = lines.iter().rposition(|line| !line.is_empty()).map_or(first, |idx| idx + 1);
+    lines[first..last].join("\n")
 }
 
 /// Formats `text` that contains CodinGame formatting into a string
@@ -33,7 +149,8 @@ pub fn format_cg(text: &str, ostyle: &OutputStyle) -> String {
 
     let mut text = format_edit_monospace(text);
     text = format_trim_consecutive_spaces(&text);
-    text = format_monospace_padding(&text);
+    text = format_lists(&text);
+    text = format_monospace_padding(&text, ostyle.max_monospace_width);
     text = format_paint(&text, ostyle);
     format_remove_excessive_newlines(&text)
 }
@@ -74,43 +191,127 @@ fn format_trim_consecutive_spaces(text: &str) -> String {
         .to_string()
 }
 
+/// Indents manual bullet lists (lines starting with `-`, `*`, `+` or `•`) and
+/// numbered lists (`1.` or `1)`) and marks their markers for painting. Bullets
+/// are all rendered as `•`. Runs after consecutive spaces have been collapsed,
+/// so an item indented by any amount of spaces becomes a second level item.
+/// Monospace blocks are left as-is.
+fn format_lists(text: &str) -> String {
+    RE_BACKTICK
+        .replace_all(text, |caps: &regex::Captures| {
+            if let Some(monospace_text) = caps.get(1) {
+                return monospace_text.as_str().to_string()
+            }
+            let Some(part) = caps.get(2) else {
+                return String::new()
+            };
+            // The first line of a part that follows a monospace block on the
+            // same line is not the start of a line
+            let starts_line = part.start() == 0 || text[..part.start()].ends_with('\n');
+            let (head, rest) = match (starts_line, part.as_str().find('\n')) {
+                (true, _) => ("", part.as_str()),
+                (false, Some(idx)) => part.as_str().split_at(idx),
+                (false, None) => (part.as_str(), ""),
+            };
+            let rest = RE_LIST_ITEM.replace_all(rest, |item: &regex::Captures| {
+                let indent = if item[1].is_empty() { "  " } else { "    " };
+                let marker = match &item[2] {
+                    "-" | "*" | "+" | "•" => "•",
+                    number => number,
+                };
+                format!("{indent}{LIST_MARKER_OPEN}{marker}{LIST_MARKER_CLOSE} {}", &item[3])
+            });
+            format!("{head}{rest}")
+        })
+        .to_string()
+}
+
 /// Pads lines in multiline monospace blocks with spaces to make them the same
 /// length. Attempts to factor in that formatting tags are going to be deleted.
-fn format_monospace_padding(text: &str) -> String {
+/// Lines wider than `max_width` are cut, with a hint after the block.
+fn format_monospace_padding(text: &str, max_width: Option<usize>) -> String {
     RE_MONOSPACE
         .replace_all(text, |caps: &regex::Captures| {
-            let lines: Vec<&str> = caps[1].split('\n').collect();
+            let mut was_cut = false;
+            let lines: Vec<String> = caps[1]
+                .split('\n')
+                .map(|line| match max_width {
+                    Some(width) if clean_line_size(line) > width => {
+                        was_cut = true;
+                        cut_monospace_line(line, width)
+                    }
+                    _ => line.to_string(),
+                })
+                .collect();
             let padding = lines.iter().map(|line| clean_line_size(line)).max().unwrap_or(0);
             let formatted_lines = lines
                 .iter()
-                .map(|&line| {
-                    // Consider using .chars.count instead of .len
-                    let offset = line.len() - clean_line_size(line);
+                .map(|line| {
+                    let offset = line.chars().count() - clean_line_size(line);
                     format!("`{:<width$}`", line, width = padding + offset)
                 })
                 .collect::<Vec<String>>()
                 .join("\n");
-            formatted_lines
+            match was_cut {
+                true => format!("{formatted_lines}\n→ (wider than the terminal, use --width 0 or a pager)"),
+                false => formatted_lines,
+            }
         })
         .to_string()
 }
 
-/// Calculate the length of a string (in bytes) without CodinGame's formatting
-/// tags.
+/// Calculate the length of a string (in characters) without CodinGame's
+/// formatting tags.
 fn clean_line_size(line: &str) -> usize {
     let amount_tag_blocks: usize = RE_ALL_BUT_MONOSPACE.find_iter(line).count();
 
-    line.len() - 4 * amount_tag_blocks
+    line.chars().count() - 4 * amount_tag_blocks
 }
 
-fn paint_parts<'a>(text: &'a str, style_tag_pairs: &[(Style, &str, &str)]) -> Vec<ansi_term::ANSIString<'a>> {
+/// Cuts a line of a monospace block to `width` characters, the last one being
+/// →. Formatting tags don't count towards the width and the ones that would
+/// be left open get closed.
+fn cut_monospace_line(line: &str, width: usize) -> String {
+    const TAGS: [(&str, &str); 3] = [("[[", "]]"), ("{{", "}}"), ("<<", ">>")];
+    let mut cut = String::new();
+    let mut open_tags: Vec<&str> = Vec::new();
+    let mut num_chars = 0;
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        if let Some((tag_open, tag_close)) = TAGS.iter().find(|(tag_open, _)| rest.starts_with(tag_open)) {
+            cut.push_str(tag_open);
+            open_tags.push(tag_close);
+            rest = &rest[tag_open.len()..];
+        } else if let Some(tag_close) = open_tags.last().filter(|tag_close| rest.starts_with(**tag_close)) {
+            cut.push_str(tag_close);
+            rest = &rest[tag_close.len()..];
+            open_tags.pop();
+        } else if num_chars + 1 >= width {
+            break
+        } else {
+            cut.push(c);
+            num_chars += 1;
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    for tag_close in open_tags.iter().rev() {
+        cut.push_str(tag_close);
+    }
+    cut.push('→');
+    cut
+}
+
+fn paint_parts<'a>(
+    text: &'a str,
+    style_tag_pairs: &[(Style, &str, &str)],
+) -> (Vec<ansi_term::ANSIString<'a>>, Vec<FormatProblem>) {
     let mut parts = Vec::<ansi_term::ANSIString<'a>>::new();
+    let mut problems = Vec::<FormatProblem>::new();
 
     let mut cur_style = Style::default();
     let mut buffer = String::new();
     let mut skip_until = 0;
-    let mut num_warnings = 0;
-    let mut stack: Vec<(Style, &str)> = vec![]; // Stack of (pre_style, opening_tag)
+    let mut stack: Vec<(Style, &str, usize)> = vec![]; // Stack of (pre_style, opening_tag, position)
 
     for (i, c) in text.char_indices() {
         // Skip formatting tags by not adding them to the buffer.
@@ -122,7 +323,7 @@ fn paint_parts<'a>(text: &'a str, style_tag_pairs: &[(Style, &str, &str)]) -> Ve
         for (style, tag_open, tag_close) in style_tag_pairs {
             if slice.starts_with(tag_close) {
                 // Does this opening tag match the top of the stack?
-                if let Some((style, opening)) = stack.to_owned().last() {
+                if let Some((style, opening, _)) = stack.to_owned().last() {
                     if opening == tag_open {
                         stack.pop();
                         // Paint and go back to the previous style
@@ -137,16 +338,13 @@ fn paint_parts<'a>(text: &'a str, style_tag_pairs: &[(Style, &str, &str)]) -> Ve
                         // Closing tag doesn't match the opening tag: ignore it and treat it as a normal
                         // character
                         // For example: `a\n>>b` (ok), or <<a[[b>>c]] (invalid).
-
-                        if num_warnings == 0 {
-                            eprintln!(
-                                "{} Bad formatting: tried to close {:?} with {:?}",
-                                Style::new().on(ansi_term::Color::Red).paint("WARNING"),
-                                opening,
-                                tag_close,
-                            );
-                        }
-                        num_warnings += 1;
+                        problems.push(FormatProblem {
+                            position: i,
+                            kind: FormatProblemKind::MismatchedClose {
+                                opening: opening.to_string(),
+                                closing: tag_close.to_string(),
+                            },
+                        });
                     }
                 }
             }
@@ -165,7 +363,7 @@ fn paint_parts<'a>(text: &'a str, style_tag_pairs: &[(Style, &str, &str)]) -> Ve
                     buffer.clear();
                     // push cur_style to the stack to go back to it later on
                     // then update the color to paint the next buffer
-                    stack.push((cur_style, tag_open));
+                    stack.push((cur_style, tag_open, i));
                     cur_style = nested_style(style, &cur_style);
 
                     // Found a valid tag, skip it
@@ -173,14 +371,10 @@ fn paint_parts<'a>(text: &'a str, style_tag_pairs: &[(Style, &str, &str)]) -> Ve
                 } else {
                     // Opening tag that is never closed: ignore it and treat it as a normal
                     // character
-                    if num_warnings == 0 {
-                        eprintln!(
-                            "{} Bad formatting: ignoring {:?} that is never closed",
-                            Style::new().on(ansi_term::Color::Red).paint("WARNING"),
-                            tag_open
-                        );
-                    }
-                    num_warnings += 1;
+                    problems.push(FormatProblem {
+                        position: i,
+                        kind: FormatProblemKind::NeverClosed(tag_open.to_string()),
+                    });
                 }
                 break
             }
@@ -190,34 +384,41 @@ fn paint_parts<'a>(text: &'a str, style_tag_pairs: &[(Style, &str, &str)]) -> Ve
         }
     }
 
-    for (_, tag_open) in stack {
+    for (_, tag_open, position) in stack {
         // Opening tag was never closed
-        if num_warnings == 0 {
-            eprintln!(
-                "{} Bad formatting: {:?} was never closed",
-                Style::new().on(ansi_term::Color::Red).paint("WARNING"),
-                tag_open
-            );
-        }
-        num_warnings += 1;
+        problems.push(FormatProblem {
+            position,
+            kind: FormatProblemKind::UnclosedAtEnd(tag_open.to_string()),
+        });
     }
 
     if !buffer.is_empty() {
         parts.push(cur_style.paint(buffer.to_string()));
     }
 
-    parts
+    (parts, problems)
 }
 
-fn format_paint(text: &str, ostyle: &OutputStyle) -> String {
-    let tag_pairs = vec![
+fn cg_tag_pairs(ostyle: &OutputStyle) -> Vec<(Style, &'static str, &'static str)> {
+    vec![
         (ostyle.monospace, "`", "`"),
         (ostyle.variable, "[[", "]]"),
         (ostyle.constant, "{{", "}}"),
         (ostyle.bold, "<<", ">>"),
-    ];
+        (ostyle.list_marker, LIST_MARKER_OPEN, LIST_MARKER_CLOSE),
+    ]
+}
 
-    let parts = paint_parts(text, &tag_pairs);
+fn format_paint(text: &str, ostyle: &OutputStyle) -> String {
+    let (parts, problems) = paint_parts(text, &cg_tag_pairs(ostyle));
+    // Only the first problem, use `lint_cg` to find all of them
+    if let Some(problem) = problems.first() {
+        eprintln!(
+            "{} Bad formatting: {}",
+            Style::new().on(ansi_term::Color::Red).paint("WARNING"),
+            problem.kind
+        );
+    }
     ansi_term::ANSIStrings(&parts).to_string()
 }
 
@@ -286,6 +487,19 @@ mod tests {
         assert!(!formatted_text.contains('`'));
     }
 
+    #[test]
+    fn wide_monospace_lines_are_cut() {
+        let mut ostyle = OutputStyle::plain();
+        ostyle.max_monospace_width = Some(6);
+        let formatted = format_cg("Art:\n`#########\n#[[x]]#\n##`\nDone", &ostyle);
+        assert_eq!(
+            formatted,
+            "Art:\n\n#####→\n#x#   \n##    \n→ (wider than the terminal, use --width 0 or a pager)\n\nDone"
+        );
+        assert_eq!(cut_monospace_line("ab<<cdef>>gh", 4), "ab<<c>>→");
+        assert_eq!(cut_monospace_line("┌──────┐", 3), "┌─→");
+    }
+
     #[test]
     fn format_monospace_adds_newline_if_there_is_none() {
         let text = "I have `no whitespace`";
@@ -338,6 +552,25 @@ mod tests {
         assert_eq!(formatted_text, expected);
     }
 
+    #[test]
+    fn format_lists_indents_items() {
+        let text = "Steps:\n- first\n * nested\n2) second\n10. tenth\n-5 is not an item";
+        let expected = "Steps:\n  \x01•\x02 first\n    \x01•\x02 nested\n  \x012)\x02 second\n  \x0110.\x02 tenth\n-5 is not an item";
+        assert_eq!(format_lists(text), expected);
+    }
+
+    #[test]
+    fn format_lists_skips_monospace() {
+        let text = "`- not\n1. a list`\n\n- item";
+        assert_eq!(format_lists(text), "`- not\n1. a list`\n\n  \x01•\x02 item");
+    }
+
+    #[test]
+    fn format_cg_paints_list_markers() {
+        let formatted = format_cg("Rules:\n- <<bold>> item\n- [[x]]", &OutputStyle::plain());
+        assert_eq!(formatted, "Rules:\n  • bold item\n  • x");
+    }
+
     #[test]
     fn format_deals_with_newspaces() {
         let text = "Text with many\n\n\n\n\nnewlines\n\n";
@@ -362,7 +595,7 @@ mod tests {
             (green, "`", "`"),
         ];
 
-        let parts = paint_parts("vv<<RED>>ww`GREEN`xx[[BLUE]]yy{{DEFAULT}}zz", &tag_pairs);
+        let (parts, _) = paint_parts("vv<<RED>>ww`GREEN`xx[[BLUE]]yy{{DEFAULT}}zz", &tag_pairs);
         println!("\n{}", ansi_term::ANSIStrings(&parts));
         assert_eq!(parts[0], ansi_term::ANSIString::from("vv"));
         assert_eq!(parts[1], red.paint("RED"));
@@ -384,7 +617,7 @@ mod tests {
 
         let tag_pairs = vec![(outer_style, "`", "`"), (inner_style, "<<", ">>")];
 
-        let parts = paint_parts("AA`BB<<CC>>DD`EE", &tag_pairs);
+        let (parts, _) = paint_parts("AA`BB<<CC>>DD`EE", &tag_pairs);
         println!("\n{}", ansi_term::ANSIStrings(&parts));
         assert_eq!(parts[0], ansi_term::ANSIString::from("AA"));
         assert_eq!(parts[1], outer_style.paint("BB"));
@@ -394,6 +627,29 @@ mod tests {
         assert_eq!(parts.len(), 5);
     }
 
+    #[test]
+    fn lint_reports_all_problems_with_positions() {
+        let text = "[[AA>>BB]]\n```x```\n<<never";
+        let problems = lint_cg(text);
+        let kinds: Vec<String> = problems.iter().map(|problem| problem.kind.to_string()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                r#"tried to close "[[" with ">>""#,
+                "obsolete ``` formatting",
+                r#"ignoring "<<" that is never closed"#,
+            ]
+        );
+        assert_eq!(line_and_column(text, problems[0].position), (1, 5));
+        assert_eq!(line_and_column(text, problems[1].position), (2, 1));
+        assert_eq!(line_and_column(text, problems[2].position), (3, 1));
+    }
+
+    #[test]
+    fn lint_finds_nothing_in_valid_formatting() {
+        assert!(lint_cg("Print [[N]] <<times>> `{{X}}`").is_empty());
+    }
+
     #[test]
     /// Test formatting that really shouldn't exist – it doesn't really matter
     /// what the output is (since the formatting is not well-defined anyway)
@@ -416,4 +672,16 @@ mod tests {
             println!(" {}. {:?} becomes \"{}\"", idx + 1, original, formatted);
         }
     }
+
+    #[test]
+    fn labeled_example_blocks() {
+        let statement = "Sum the numbers.\n\nExample input:\n`3\n1 2 3  \n`\nExample output:\n`6`\nThe `sum` is what matters.";
+        let example = statement_example(statement);
+        assert_eq!(example.input.as_deref(), Some("3\n1 2 3"));
+        assert_eq!(example.output.as_deref(), Some("6"));
+
+        let french = "Entrée :\n```[[N]] = 2```\nRien d'autre.";
+        assert_eq!(statement_example(french).input.as_deref(), Some("N = 2"));
+        assert_eq!(statement_example("Print `x` times").input, None);
+    }
 }
@@ -0,0 +1,62 @@
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+
+/// How many of the matching handles are listed when a prefix is ambiguous.
+const MAX_LISTED_CANDIDATES: usize = 10;
+
+/// Finds the handle among `stored` that starts with `prefix`, like git does
+/// with short commit hashes. Returns `None` if no handle matches and fails if
+/// more than one does (unless one of them is exactly `prefix`).
+pub fn resolve_handle_prefix<'a>(
+    prefix: &str,
+    stored: impl IntoIterator<Item = &'a str>,
+) -> Result<Option<&'a str>> {
+    let mut candidates: Vec<&str> = stored.into_iter().filter(|handle| handle.starts_with(prefix)).collect();
+    if let Some(exact) = candidates.iter().find(|handle| **handle == prefix) {
+        return Ok(Some(exact))
+    }
+    match candidates.len() {
+        0 => Ok(None),
+        1 => Ok(candidates.pop()),
+        num_candidates => {
+            candidates.sort_unstable();
+            let mut listed = candidates
+                .iter()
+                .take(MAX_LISTED_CANDIDATES)
+                .map(|handle| format!("  {handle}"))
+                .join("\n");
+            if num_candidates > MAX_LISTED_CANDIDATES {
+                listed += &format!("\n  ...and {} more", num_candidates - MAX_LISTED_CANDIDATES);
+            }
+            Err(anyhow!(
+                "Handle {prefix:?} is ambiguous, it matches {num_candidates} clashes:\n{listed}"
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STORED: [&str; 3] = ["682102420fbce0fce95e", "6821ffff", "ab12"];
+
+    #[test]
+    fn unique_prefix() {
+        assert_eq!(resolve_handle_prefix("6821024", STORED).unwrap(), Some(STORED[0]));
+        assert_eq!(resolve_handle_prefix("ab12", STORED).unwrap(), Some("ab12"));
+        assert_eq!(resolve_handle_prefix("cd", STORED).unwrap(), None);
+    }
+
+    #[test]
+    fn ambiguous_prefix_lists_candidates() {
+        let err = resolve_handle_prefix("6821", STORED).unwrap_err().to_string();
+        assert!(err.contains("matches 2 clashes"));
+        assert!(err.contains("  682102420fbce0fce95e\n  6821ffff"));
+    }
+
+    #[test]
+    fn exact_match_wins() {
+        assert_eq!(resolve_handle_prefix("ab", ["ab", "abc"]).unwrap(), Some("ab"));
+    }
+}
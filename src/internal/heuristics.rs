@@ -0,0 +1,57 @@
+use regex::Regex;
+
+/// Returns the identifiers in `names` that don't appear anywhere in `source`
+/// as a whole word.
+///
+/// A solution written for a clash refers to (most of) the variables of the
+/// clash's stub, so a solution that is missing all of them was probably
+/// written for another clash.
+pub fn missing_identifiers<'a>(source: &str, names: &'a [String]) -> Vec<&'a str> {
+    names
+        .iter()
+        .filter(|name| {
+            let pattern = format!(r"\b{}\b", regex::escape(name));
+            !Regex::new(&pattern)
+                .expect("escaped identifier should be a valid regex")
+                .is_match(source)
+        })
+        .map(String::as_str)
+        .collect()
+}
+
+/// True when `source` doesn't look like a solution for a stub with the
+/// variables `names`: none of them are used and there was at least one.
+pub fn is_unrelated_solution(source: &str, names: &[String]) -> bool {
+    !names.is_empty() && missing_identifiers(source, names).len() == names.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn identifiers_must_be_whole_words() {
+        let source = "n = int(input())\nfor i in range(n):\n    row_count = input()\n";
+        let vars = names(&["n", "row", "i", "count"]);
+        assert_eq!(missing_identifiers(source, &vars), vec!["row", "count"]);
+    }
+
+    #[test]
+    fn sigils_are_not_part_of_the_identifier() {
+        let vars = names(&["x"]);
+        assert!(missing_identifiers("my $x = <STDIN>;", &vars).is_empty());
+        assert_eq!(missing_identifiers("let $x_y = 1", &vars), vec!["x"]);
+    }
+
+    #[test]
+    fn unrelated_solution() {
+        let vars = names(&["width", "height"]);
+        assert!(is_unrelated_solution("n = int(input())", &vars));
+        assert!(!is_unrelated_solution("width = int(input())", &vars));
+        assert!(!is_unrelated_solution("anything", &[]));
+    }
+}
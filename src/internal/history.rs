@@ -0,0 +1,394 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clashlib::solution::FailureCategory;
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// A single `coctus run` of a clash. The history is stored as JSON lines so
+/// that recording a run only has to append to the file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub handle: String,
+    /// Seconds since the unix epoch when the run finished.
+    pub timestamp: u64,
+    pub num_passed: usize,
+    pub num_tests: usize,
+    /// Seconds between selecting the clash and this run, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solve_time: Option<u64>,
+    /// Size of the solution source in bytes, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_size: Option<u64>,
+    /// Language of the solution file, if the run used one (`--language` or
+    /// a remembered solution).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Hash of the clash file the testcases were loaded from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clash_hash: Option<String>,
+    /// The clash file changed while the tests were running, so the results
+    /// may not belong to any single version of the clash.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
+    /// How many of the testcases that were run failed in each way.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failures: BTreeMap<FailureCategory, usize>,
+}
+
+/// How a `coctus run` of a clash went, the parts of its [RunRecord] that
+/// come from running the testcases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOutcome {
+    pub num_passed: usize,
+    pub num_tests: usize,
+    pub failures: BTreeMap<FailureCategory, usize>,
+    pub source_size: Option<u64>,
+    pub language: Option<String>,
+    pub clash_hash: String,
+    pub stale: bool,
+}
+
+impl RunRecord {
+    /// Stale runs never count as solving the clash.
+    pub fn is_solved(&self) -> bool {
+        !self.stale && self.num_tests > 0 && self.num_passed == self.num_tests
+    }
+}
+
+pub struct History;
+
+impl History {
+    pub fn append(path: &Path, record: &RunRecord) -> Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Unable to open history file {:?}", path))?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Vec<RunRecord>> {
+        let Some(contents) = json_file::read_if_exists(path)? else {
+            return Ok(Vec::new())
+        };
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Unable to deserialize run on line {} of {:?}", idx + 1, path))
+            })
+            .collect()
+    }
+}
+
+/// Statistics of all recorded runs of one clash.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ClashStats {
+    pub attempts: usize,
+    pub solved: bool,
+    /// Shortest solve time (in seconds) among the runs that passed all tests.
+    pub best_time: Option<u64>,
+    /// Smallest source size (in bytes) among the runs that passed all tests.
+    pub best_size: Option<u64>,
+    pub last_run: u64,
+}
+
+/// Aggregates `records` into per-clash statistics, keyed by handle.
+pub fn aggregate_stats(records: &[RunRecord]) -> BTreeMap<String, ClashStats> {
+    let mut stats = BTreeMap::<String, ClashStats>::new();
+    for record in records {
+        let entry = stats.entry(record.handle.clone()).or_default();
+        entry.attempts += 1;
+        entry.last_run = entry.last_run.max(record.timestamp);
+        if record.is_solved() {
+            entry.solved = true;
+            entry.best_time = min_option(entry.best_time, record.solve_time);
+            entry.best_size = min_option(entry.best_size, record.source_size);
+        }
+    }
+    stats
+}
+
+/// Totals of a group of clashes, such as the clashes of one mode.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GroupStats {
+    pub attempted: usize,
+    pub solved: usize,
+    /// Average of the best solve times (in seconds) of the solved clashes
+    /// whose solve time is known.
+    pub average_time: Option<u64>,
+}
+
+impl GroupStats {
+    pub fn of<'a>(clashes: impl IntoIterator<Item = &'a ClashStats>) -> Self {
+        let mut group = GroupStats::default();
+        let mut times = Vec::new();
+        for clash in clashes {
+            group.attempted += 1;
+            if clash.solved {
+                group.solved += 1;
+                times.extend(clash.best_time);
+            }
+        }
+        if !times.is_empty() {
+            group.average_time = Some(times.iter().sum::<u64>() / times.len() as u64);
+        }
+        group
+    }
+}
+
+fn min_option(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Column names of the CSV export. Columns may be added at the end but the
+/// existing ones should never be renamed or reordered.
+pub const CSV_HEADER: [&str; 7] = [
+    "handle",
+    "title",
+    "attempts",
+    "solved",
+    "best_time_secs",
+    "best_size_bytes",
+    "last_run",
+];
+
+/// Writes one CSV row per clash. `title` looks up the title of a clash by its
+/// handle (clashes that are no longer stored locally get an empty title).
+pub fn write_stats_csv<W: Write>(
+    mut out: W,
+    stats: &BTreeMap<String, ClashStats>,
+    title: impl Fn(&str) -> Option<String>,
+) -> Result<()> {
+    writeln!(out, "{}", CSV_HEADER.join(","))?;
+    for (handle, clash) in stats {
+        let fields = [
+            handle.clone(),
+            title(handle).unwrap_or_default(),
+            clash.attempts.to_string(),
+            clash.solved.to_string(),
+            clash.best_time.map(|t| t.to_string()).unwrap_or_default(),
+            clash.best_size.map(|s| s.to_string()).unwrap_or_default(),
+            format_utc_timestamp(clash.last_run),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+/// Quotes a CSV field when it contains a delimiter, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Writes a badge in the style of shields.io, such as "coctus | 412 solved",
+/// for the README of a repository of solutions.
+pub fn write_badge_svg<W: Write>(mut out: W, label: &str, message: &str) -> Result<()> {
+    // Rough width of 11px Verdana, which is what the badges are rendered in
+    let text_width = |text: &str| text.chars().count() * 7;
+    let label_width = text_width(label) + 10;
+    let message_width = text_width(message) + 10;
+    let width = label_width + message_width;
+    let (label, message) = (xml_escape(label), xml_escape(message));
+    write!(
+        out,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+<title>{label}: {message}</title>
+<linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+<clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+<g clip-path="url(#r)">
+<rect width="{label_width}" height="20" fill="#555"/>
+<rect x="{label_width}" width="{message_width}" height="20" fill="#f2bb13"/>
+<rect width="{width}" height="20" fill="url(#s)"/>
+</g>
+<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+<text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text>
+<text x="{label_x}" y="14">{label}</text>
+<text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text>
+<text x="{message_x}" y="14">{message}</text>
+</g>
+</svg>
+"##,
+        label_x = label_width as f64 / 2.0,
+        message_x = label_width as f64 + message_width as f64 / 2.0,
+    )?;
+    Ok(())
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Formats seconds since the unix epoch as an ISO 8601 UTC timestamp.
+pub fn format_utc_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let secs_of_day = secs % 86400;
+    // Civil-from-days conversion by Howard Hinnant
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        handle: &str,
+        timestamp: u64,
+        num_passed: usize,
+        solve_time: Option<u64>,
+        size: Option<u64>,
+    ) -> RunRecord {
+        RunRecord {
+            handle: handle.to_string(),
+            timestamp,
+            num_passed,
+            num_tests: 3,
+            solve_time,
+            source_size: size,
+            language: None,
+            clash_hash: None,
+            stale: false,
+            failures: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn history_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        assert!(History::load(&path).unwrap().is_empty());
+        let mut solved = record("abc", 2, 3, Some(60), Some(42));
+        solved.clash_hash = Some("cbf29ce484222325".to_string());
+        let mut failed = record("abc", 1, 2, None, None);
+        failed.failures.insert(FailureCategory::PresentationError, 1);
+        let runs = vec![failed, solved];
+        for run in &runs {
+            History::append(&path, run).unwrap();
+        }
+        assert_eq!(History::load(&path).unwrap(), runs);
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.lines().next().unwrap().contains(r#""failures":{"presentation-error":1}"#));
+    }
+
+    #[test]
+    fn aggregate_only_counts_solved_runs_for_bests() {
+        let runs = vec![
+            record("abc", 10, 3, Some(300), Some(100)),
+            record("abc", 30, 1, Some(5), Some(5)),
+            record("abc", 20, 3, Some(200), Some(120)),
+            record("def", 15, 0, None, None),
+        ];
+        let stats = aggregate_stats(&runs);
+        assert_eq!(
+            stats["abc"],
+            ClashStats {
+                attempts: 3,
+                solved: true,
+                best_time: Some(200),
+                best_size: Some(100),
+                last_run: 30,
+            }
+        );
+        assert_eq!(stats["def"].attempts, 1);
+        assert!(!stats["def"].solved);
+        assert_eq!(stats["def"].best_time, None);
+    }
+
+    #[test]
+    fn group_stats_average_the_solved_clashes() {
+        let runs = vec![
+            record("abc", 10, 3, Some(300), None),
+            record("abc", 20, 3, Some(200), None),
+            record("def", 15, 3, Some(100), None),
+            record("ghi", 15, 3, None, None),
+            record("jkl", 15, 0, Some(5), None),
+        ];
+        let stats = aggregate_stats(&runs);
+        assert_eq!(
+            GroupStats::of(stats.values()),
+            GroupStats {
+                attempted: 4,
+                solved: 3,
+                average_time: Some(150),
+            }
+        );
+        assert_eq!(GroupStats::of(&[]).average_time, None);
+    }
+
+    #[test]
+    fn stale_runs_are_not_solves() {
+        let mut run = record("abc", 10, 3, Some(300), Some(100));
+        run.stale = true;
+        let stats = aggregate_stats(&[run]);
+        assert_eq!(stats["abc"].attempts, 1);
+        assert!(!stats["abc"].solved);
+    }
+
+    #[test]
+    fn csv_schema_is_stable() {
+        let runs = vec![
+            record("abc", 1700000000, 3, Some(61), None),
+            record("def", 0, 1, None, None),
+        ];
+        let mut out = Vec::new();
+        write_stats_csv(&mut out, &aggregate_stats(&runs), |handle| {
+            (handle == "abc").then(|| "Say \"hi\", world".to_string())
+        })
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "handle,title,attempts,solved,best_time_secs,best_size_bytes,last_run\n\
+             abc,\"Say \"\"hi\"\", world\",1,true,61,,2023-11-14T22:13:20Z\n\
+             def,,1,false,,,1970-01-01T00:00:00Z\n"
+        );
+    }
+
+    #[test]
+    fn badge_svg() {
+        let mut out = Vec::new();
+        write_badge_svg(&mut out, "coctus", "3 solved & <counting>").unwrap();
+        let svg = String::from_utf8(out).unwrap();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains("<title>coctus: 3 solved &amp; &lt;counting&gt;</title>"));
+        assert!(svg.contains(r#"width="209""#));
+    }
+
+    #[test]
+    fn utc_timestamps() {
+        assert_eq!(format_utc_timestamp(951782400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_utc_timestamp(1735689599), "2024-12-31T23:59:59Z");
+    }
+}
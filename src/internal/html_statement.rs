@@ -0,0 +1,247 @@
+use ansi_term::Style;
+
+use super::outputstyle::OutputStyle;
+
+/// Renders the `statementHTML` of a clash for the terminal, for
+/// `coctus show --prefer-html`. Some contributions only look right on
+/// codingame.com, where the HTML is shown instead of the plain statement.
+///
+/// `<b>` and `<strong>` are bold, `<var>` and `<const>` are painted like
+/// `[[variables]]` and `{{constants}}` and `<pre>` blocks like monospace
+/// blocks. Paragraphs, line breaks and list items keep the text apart, and
+/// every other tag is left out.
+pub fn format_statement_html(html: &str, ostyle: &OutputStyle) -> String {
+    let mut renderer = Renderer {
+        ostyle,
+        out: String::new(),
+        styles: Vec::new(),
+        pre: None,
+        pending_space: false,
+    };
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        renderer.text(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            // A lone < is text
+            renderer.text(&rest[start..]);
+            rest = "";
+            break
+        };
+        renderer.tag(&rest[start + 1..start + end]);
+        rest = &rest[start + end + 1..];
+    }
+    renderer.text(rest);
+    renderer.out.trim_end().to_string()
+}
+
+struct Renderer<'a> {
+    ostyle: &'a OutputStyle,
+    out: String,
+    /// Styles of the tags that are open, the innermost one is used.
+    styles: Vec<(String, Style)>,
+    /// Text of the `<pre>` block that is open.
+    pre: Option<String>,
+    /// Whether there was whitespace before the next word.
+    pending_space: bool,
+}
+
+impl Renderer<'_> {
+    fn text(&mut self, text: &str) {
+        let text = decode_entities(text);
+        if let Some(pre) = &mut self.pre {
+            pre.push_str(&text);
+            return
+        }
+        let style = self.styles.last().map(|(_, style)| *style).unwrap_or_default();
+        for (idx, word) in text.split(char::is_whitespace).enumerate() {
+            if idx > 0 {
+                self.pending_space = true;
+            }
+            if word.is_empty() {
+                continue
+            }
+            if std::mem::take(&mut self.pending_space) && !self.out.is_empty() && !self.out.ends_with('\n') {
+                self.out.push(' ');
+            }
+            self.out.push_str(&style.paint(word).to_string());
+        }
+    }
+
+    fn tag(&mut self, tag: &str) {
+        let tag = tag.trim();
+        let (is_closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, tag.trim_end_matches('/')),
+        };
+        let name = tag
+            .split(|ch: char| ch.is_whitespace())
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if let Some(pre) = self.pre.as_mut().filter(|_| name != "pre") {
+            // Markup inside of monospace blocks is not styled
+            if name == "br" {
+                pre.push('\n');
+            }
+            return
+        }
+        match name.as_str() {
+            "pre" if is_closing => {
+                let pre = self.pre.take().unwrap_or_default();
+                self.end_lines(2);
+                self.out.push_str(&self.monospace_block(&pre));
+                self.end_lines(2);
+            }
+            "pre" => self.pre = Some(String::new()),
+            "br" => {
+                self.out.push('\n');
+                self.pending_space = false;
+            }
+            "p" | "div" | "ul" | "ol" | "table" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.end_lines(2);
+                self.inline(
+                    is_closing,
+                    &name,
+                    match name.starts_with('h') {
+                        true => Some(self.ostyle.title),
+                        false => None,
+                    },
+                );
+            }
+            "li" | "tr" => {
+                self.end_lines(1);
+                if name == "li" && !is_closing {
+                    let marker = self.ostyle.list_marker.paint("•");
+                    self.out.push_str(&format!("  {marker} "));
+                }
+            }
+            "b" | "strong" => self.inline(is_closing, &name, Some(self.ostyle.bold)),
+            "var" => self.inline(is_closing, &name, Some(self.ostyle.variable)),
+            "const" => self.inline(is_closing, &name, Some(self.ostyle.constant)),
+            "code" => self.inline(is_closing, &name, Some(self.ostyle.monospace)),
+            _ => {}
+        }
+    }
+
+    /// Opens or closes a tag that might style the text inside of it.
+    fn inline(&mut self, is_closing: bool, name: &str, style: Option<Style>) {
+        match is_closing {
+            // Unmatched closing tags are ignored like browsers do
+            true => {
+                if let Some(idx) = self.styles.iter().rposition(|(open, _)| open == name) {
+                    self.styles.truncate(idx);
+                }
+            }
+            false => {
+                let inherited = self.styles.last().map(|(_, style)| *style).unwrap_or_default();
+                self.styles.push((name.to_string(), style.unwrap_or(inherited)));
+            }
+        }
+    }
+
+    /// Makes the output end in at least `count` newlines, unless it is
+    /// empty.
+    fn end_lines(&mut self, count: usize) {
+        self.pending_space = false;
+        if self.out.is_empty() {
+            return
+        }
+        let existing = self.out.len() - self.out.trim_end_matches('\n').len();
+        for _ in existing..count {
+            self.out.push('\n');
+        }
+    }
+
+    /// Lines of a monospace block padded to the same width (and cut at
+    /// [OutputStyle::max_monospace_width]).
+    fn monospace_block(&self, text: &str) -> String {
+        let text = text.trim_matches('\n');
+        let mut was_cut = false;
+        let lines: Vec<String> = text
+            .split('\n')
+            .map(|line| match self.ostyle.max_monospace_width {
+                Some(width) if line.chars().count() > width => {
+                    was_cut = true;
+                    line.chars().take(width.saturating_sub(1)).chain(['→']).collect()
+                }
+                _ => line.to_string(),
+            })
+            .collect();
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        let mut block = lines
+            .iter()
+            .map(|line| self.ostyle.monospace.paint(format!("{line:<width$}")).to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+        if was_cut {
+            block.push_str("\n→ (wider than the terminal, use --width 0 or a pager)");
+        }
+        block
+    }
+}
+
+/// Replaces the HTML entities that statements use with the characters.
+/// Unknown entities are left as they are.
+fn decode_entities(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..].find(';').filter(|end| *end <= 8).and_then(|end| {
+            let ch = match &rest[1..end + 1] {
+                "lt" => '<',
+                "gt" => '>',
+                "amp" => '&',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                entity => {
+                    let code = match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => entity.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((ch, end + 2))
+        });
+        match decoded {
+            Some((ch, len)) => {
+                result.push(ch);
+                rest = &rest[len..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_paragraphs_lists_and_pre() {
+        let html = "<div class=\"statement-body\">\n  <p>Print   <var>n</var> &lt;= <const>10</const>\n  times.</p>\
+                    <ul><li>one</li><li><b>two</b></li></ul><pre>a\n bc</pre>Done<br>now &amp; &#33; &bogus;</div>";
+        assert_eq!(
+            format_statement_html(html, &OutputStyle::plain()),
+            "Print n <= 10 times.\n\n  • one\n  • two\n\na  \n bc\n\nDone\nnow & ! &bogus;"
+        );
+    }
+
+    #[test]
+    fn html_tags_are_painted() {
+        use ansi_term::Color::*;
+        let ostyle = OutputStyle::default();
+        let formatted = format_statement_html("<var>x</var> <strong>is <const>3</const></strong>", &ostyle);
+        let expected =
+            format!("{} {} {}", Yellow.paint("x"), Style::new().bold().paint("is"), Blue.paint("3"));
+        assert_eq!(formatted, expected);
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// A file of JSON values, one per line, so that recording an entry only has
+/// to append to it.
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn new(path: PathBuf) -> Self {
+        Journal { path }
+    }
+
+    pub fn append<T: Serialize>(&self, entry: &T) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Unable to open journal {:?}", self.path))?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    /// All the entries, oldest first.
+    pub fn load<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        if !self.path.exists() {
+            return Ok(Vec::new())
+        }
+        std::fs::read_to_string(&self.path)?
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Invalid entry on line {} of journal {:?}", idx + 1, self.path))
+            })
+            .collect()
+    }
+
+    /// Replaces all of the entries, for journals that only keep the latest
+    /// ones.
+    pub fn rewrite<T: Serialize>(&self, entries: &[T]) -> Result<()> {
+        let mut contents = String::new();
+        for entry in entries {
+            contents += &serde_json::to_string(entry)?;
+            contents.push('\n');
+        }
+        std::fs::write(&self.path, contents)?;
+        Ok(())
+    }
+}
+
+/// A change to the local collection of clashes, as shown by `coctus log`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ChangelogEntry {
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    /// The coctus command that made the change (`fetch`, `refresh`, ...).
+    pub command: String,
+    pub description: String,
+    /// Clashes that the change touched.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub handles: Vec<String>,
+    /// Host name of the machine the change was made on, useful when the data
+    /// directory is synced between machines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub machine: Option<String>,
+}
+
+impl ChangelogEntry {
+    pub fn new(command: &str, description: impl Into<String>, handles: Vec<String>) -> Self {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let machine = ["HOSTNAME", "COMPUTERNAME"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|name| !name.is_empty()));
+        ChangelogEntry {
+            timestamp,
+            command: command.to_string(),
+            description: description.into(),
+            handles,
+            machine,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn journal_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("nested/changelog.jsonl"));
+        assert!(journal.load::<ChangelogEntry>().unwrap().is_empty());
+        let entries = vec![
+            ChangelogEntry::new("fetch", "fetched 2 clashes", vec!["abc".to_string(), "def".to_string()]),
+            ChangelogEntry::new("undo", "undid fetch clash abc", Vec::new()),
+        ];
+        for entry in &entries {
+            journal.append(entry).unwrap();
+        }
+        assert_eq!(journal.load::<ChangelogEntry>().unwrap(), entries);
+
+        journal.rewrite(&entries[1..]).unwrap();
+        assert_eq!(journal.load::<ChangelogEntry>().unwrap(), entries[1..]);
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Reads the contents of `path`, `None` if the file doesn't exist yet.
+pub fn read_if_exists(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None)
+    }
+    let contents = std::fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?;
+    Ok(Some(contents))
+}
+
+/// Deserializes the JSON file at `path`, `None` if it doesn't exist yet.
+/// `what` is what the file contains for the error message, like "tags".
+pub fn load<T: DeserializeOwned>(path: &Path, what: &str) -> Result<Option<T>> {
+    let Some(contents) = read_if_exists(path)? else {
+        return Ok(None)
+    };
+    let value = serde_json::from_str(&contents)
+        .with_context(|| format!("Unable to deserialize {what} from {:?}", path))?;
+    Ok(Some(value))
+}
+
+/// Writes `value` into `path` as JSON.
+pub fn save<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(value)?)
+        .with_context(|| format!("Unable to write {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn missing_files_load_as_none() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("values.json");
+        assert_eq!(load::<BTreeMap<String, u32>>(&path, "values").unwrap(), None);
+
+        let values = BTreeMap::from([("a".to_string(), 1)]);
+        save(&path, &values).unwrap();
+        assert_eq!(load(&path, "values").unwrap(), Some(values));
+
+        std::fs::write(&path, "[").unwrap();
+        let err = load::<BTreeMap<String, u32>>(&path, "values").unwrap_err();
+        assert!(format!("{err:#}").starts_with("Unable to deserialize values from"));
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use clashlib::clash::ClashMetadata;
+use clashlib::solution::{FailureCategory, TestResultReport};
+use clashlib::stub::Diagnostic;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use super::ChangelogEntry;
+
+/// Summary printed by `coctus generate-stub --stored-clashes`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StubSummary {
+    pub language: String,
+    /// Number of stored clashes that have a stub generator.
+    pub clashes: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    pub problems: Vec<StubProblem>,
+}
+
+/// A problem with the stub of one clash.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StubProblem {
+    pub handle: String,
+    #[serde(flatten)]
+    pub diagnostic: Diagnostic,
+}
+
+/// Report printed by `coctus run --output-format json`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RunReport {
+    pub handle: String,
+    pub passed: usize,
+    /// Number of testcases that were picked to run, including the ones that
+    /// were not run because an earlier one failed.
+    pub total: usize,
+    /// How many of the failures fell into each category.
+    pub failures: BTreeMap<FailureCategory, usize>,
+    /// Seed of the order the testcases were run in, with --shuffle.
+    pub shuffle_seed: Option<u64>,
+    /// Whether the --suite-timeout stopped the run before all of the
+    /// testcases had been run.
+    pub suite_timed_out: bool,
+    /// Whether the clash changed while the testcases were running.
+    pub stale: bool,
+    /// The testcases that were run, in the order of the clash.
+    pub testcases: Vec<TestcaseReport>,
+}
+
+/// The result of one testcase in a [RunReport].
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TestcaseReport {
+    /// The testcase as it is labeled in the text output (see --numbering),
+    /// like "#3 Big numbers".
+    pub label: String,
+    /// Position of the testcase in the clash, starting from 1.
+    pub index: usize,
+    pub title: String,
+    pub is_validator: bool,
+    /// Time it took to run the testcase, not measured with --jobs.
+    pub duration_ms: Option<f64>,
+    #[serde(flatten)]
+    pub result: TestResultReport,
+}
+
+/// The commands that print JSON, for `coctus schema`.
+pub const SCHEMA_COMMANDS: &[&str] = &["generate-stub", "log", "meta", "run"];
+
+/// JSON Schema of the output of `command` (one of [SCHEMA_COMMANDS]). For
+/// commands that print JSON lines the schema is the one of a single line.
+pub fn schema_for_command(command: &str) -> Option<RootSchema> {
+    let schema = match command {
+        "generate-stub" => schemars::schema_for!(StubSummary),
+        "log" => schemars::schema_for!(ChangelogEntry),
+        "meta" => schemars::schema_for!(ClashMetadata),
+        "run" => schemars::schema_for!(RunReport),
+        _ => return None,
+    };
+    Some(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_command_has_a_schema() {
+        for command in SCHEMA_COMMANDS {
+            let schema = serde_json::to_value(schema_for_command(command).unwrap()).unwrap();
+            assert_eq!(schema["type"], "object", "schema of {command}");
+        }
+        assert!(schema_for_command("show").is_none());
+    }
+
+    #[test]
+    fn flattened_fields_are_in_the_schema() {
+        let schema = serde_json::to_value(schema_for_command("generate-stub").unwrap()).unwrap();
+        let problem = &schema["definitions"]["StubProblem"]["properties"];
+        assert!(problem["handle"].is_object());
+        assert!(problem["severity"].is_object());
+        assert!(problem["message"].is_object());
+
+        let schema = serde_json::to_value(schema_for_command("run").unwrap()).unwrap();
+        let testcase = &schema["definitions"]["TestcaseReport"]["properties"];
+        assert!(testcase["label"].is_object());
+        assert!(testcase["category"].is_object());
+        assert!(testcase["stdout"].is_object());
+    }
+}
@@ -0,0 +1,26 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// Outcome of the latest `coctus run`, remembered so that `run --failed` can
+/// pick up where it left off.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastRun {
+    pub handle: String,
+    /// Indices of the testcases that were run but didn't pass, including the
+    /// ones that were skipped because an earlier testcase failed.
+    pub failed: Vec<usize>,
+}
+
+impl LastRun {
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        json_file::load(path, "last run")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+}
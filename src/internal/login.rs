@@ -0,0 +1,99 @@
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// Name of the cookie that keeps a browser logged in to codingame.com, which
+/// is what a bare cookie value given to `coctus login` is taken to be.
+const REMEMBER_ME_COOKIE: &str = "rememberMe";
+
+/// The CodinGame session stored by `coctus login`, sent with the requests to
+/// codingame.com so that unpublished contributions of the user can be
+/// fetched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Login {
+    /// Value of the Cookie header, such as `rememberMe=...; cgSession=...`.
+    pub cookie: String,
+}
+
+impl Login {
+    /// Accepts either the whole Cookie header copied from a browser or just
+    /// the value of the `rememberMe` cookie.
+    pub fn from_cookie(cookie: &str) -> Result<Self> {
+        let cookie = cookie.trim().trim_start_matches("Cookie:").trim();
+        if cookie.is_empty() {
+            return Err(anyhow!("The cookie is empty"))
+        }
+        if cookie.contains(['\r', '\n']) {
+            return Err(anyhow!("The cookie can't contain line breaks"))
+        }
+        let cookie = match cookie.contains('=') {
+            true => cookie.to_string(),
+            false => format!("{REMEMBER_ME_COOKIE}={cookie}"),
+        };
+        Ok(Self { cookie })
+    }
+
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        json_file::load(path, "login").context("Unable to load the login (log in again)")
+    }
+
+    /// Saves the login so that only the current user can read it (on unix).
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(path).with_context(|| format!("Unable to write {:?}", path))?;
+        // The mode only applies to new files, one that already exists might
+        // have been readable by others
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(serde_json::to_string(self)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_values_are_remember_me_cookies() {
+        assert_eq!(Login::from_cookie(" abc123\n").unwrap().cookie, "rememberMe=abc123");
+        let header = "Cookie: rememberMe=abc; cgSession=def";
+        assert_eq!(Login::from_cookie(header).unwrap().cookie, "rememberMe=abc; cgSession=def");
+        assert!(Login::from_cookie("  ").is_err());
+        assert!(Login::from_cookie("a=b\nc=d").is_err());
+    }
+
+    #[test]
+    fn login_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("login.json");
+        assert_eq!(Login::load(&path).unwrap(), None);
+        let login = Login::from_cookie("abc").unwrap();
+        login.save(&path).unwrap();
+        assert_eq!(Login::load(&path).unwrap(), Some(login.clone()));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+            login.save(&path).unwrap();
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+}
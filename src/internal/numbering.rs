@@ -0,0 +1,102 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use clashlib::clash::{PlainNumbers, TestSelection, Testcase};
+use serde::Deserialize;
+
+/// How testcases are numbered when they are shown and selected.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Numbering {
+    /// One sequence for all testcases, validators included: #1, #2, ...
+    #[default]
+    Flat,
+    /// Tests and validators numbered separately like in the CodinGame IDE:
+    /// Test 1, Test 2, ..., Validator 1, Validator 2, ...
+    Cg,
+}
+
+impl Numbering {
+    /// Label of `testcase`, for example `#3 Validator 1` or `Validator 1`.
+    pub fn label(self, testcase: &Testcase) -> String {
+        match self {
+            Numbering::Flat => format!("#{} {}", testcase.index, testcase.title),
+            Numbering::Cg => {
+                let kind = if testcase.is_validator {
+                    "Validator"
+                } else {
+                    "Test"
+                };
+                let name = format!("{} {}", kind, testcase.number);
+                // Titles are usually the same as the name but not always
+                if testcase.title == name {
+                    name
+                } else {
+                    format!("{} ({})", name, testcase.title)
+                }
+            }
+        }
+    }
+
+    /// What plain numbers in testcase selections refer to.
+    pub fn plain_numbers(self) -> PlainNumbers {
+        match self {
+            Numbering::Flat => PlainNumbers::Testcases,
+            Numbering::Cg => PlainNumbers::Tests,
+        }
+    }
+
+    /// Finds the testcase that `selector` refers to and returns its position
+    /// in `testcases`. A plain number is interpreted according to the
+    /// numbering, see [`TestSelection::resolve`].
+    pub fn resolve(self, testcases: &[Testcase], selector: &str) -> Result<usize> {
+        TestSelection::resolve(testcases, selector, self.plain_numbers())
+    }
+
+    /// Parses testcase selection expressions like `2-5,!4` with plain
+    /// numbers interpreted according to the numbering.
+    pub fn selection<'a>(self, expressions: impl IntoIterator<Item = &'a str>) -> Result<TestSelection> {
+        TestSelection::parse(expressions, self.plain_numbers())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::test_helper::testcase;
+
+    fn testcases() -> Vec<Testcase> {
+        [
+            ("Test 1", false),
+            ("Validator 1", true),
+            ("Edge case", false),
+            ("Validator 2", true),
+        ]
+        .into_iter()
+        .zip([1, 1, 2, 2])
+        .enumerate()
+        .map(|(idx, ((title, is_validator), number))| Testcase {
+            number,
+            title: title.to_string(),
+            is_validator,
+            ..testcase(idx + 1, "", "")
+        })
+        .collect()
+    }
+
+    #[test]
+    fn labels() {
+        let testcases = testcases();
+        assert_eq!(Numbering::Flat.label(&testcases[1]), "#2 Validator 1");
+        assert_eq!(Numbering::Cg.label(&testcases[1]), "Validator 1");
+        assert_eq!(Numbering::Cg.label(&testcases[2]), "Test 2 (Edge case)");
+    }
+
+    #[test]
+    fn plain_numbers_follow_the_numbering() {
+        let testcases = testcases();
+        assert_eq!(Numbering::Flat.resolve(&testcases, "2").unwrap(), 1);
+        assert_eq!(Numbering::Cg.resolve(&testcases, "2").unwrap(), 2);
+        let selection = Numbering::Cg.selection(["1-2"]).unwrap();
+        assert_eq!(selection.positions(&testcases).unwrap(), vec![0, 2]);
+    }
+}
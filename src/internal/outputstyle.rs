@@ -1,10 +1,61 @@
+use std::collections::BTreeMap;
+
 use ansi_term::{Color, Style};
-use clashlib::clash::{Clash, Testcase};
-use clashlib::solution::TestResult;
+use clap::ValueEnum;
+use clashlib::clash::{Clash, Constraint, Testcase, TestcasePair};
+use clashlib::diff::{self, Granularity, Hunk, HunkKind, Span, SpanKind};
+use clashlib::solution::{FailureCategory, OutputChunk, OutputStream, TestResult};
+use itertools::Itertools;
+use regex::Regex;
 
 use super::formatter::show_whitespace;
-use super::lines_with_endings::LinesWithEndings;
-use crate::internal::formatter::format_cg;
+use super::{FailureCluster, Numbering, Provenance, Theme};
+use crate::internal::formatter::{format_cg, line_and_column, lint_cg, normalize_example, statement_example};
+
+/// How differences between the expected and the actual output of a solution
+/// are presented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffMode {
+    /// Correct parts are painted green and incorrect parts red.
+    Color,
+    /// Every line is prefixed with a gutter symbol (`+` for extra lines, `-`
+    /// for missing lines, `~` for changed lines) and changed spans are
+    /// underlined, so the diff is readable without telling red from green.
+    Symbols,
+}
+
+/// The parts of a clash statement that `print_sections` knows how to print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementSection {
+    Goal,
+    Statement,
+    Rules,
+    IoDescriptions,
+    Constraints,
+    Example,
+}
+
+impl StatementSection {
+    pub const ALL: &'static [StatementSection] = &[
+        StatementSection::Goal,
+        StatementSection::Statement,
+        StatementSection::Rules,
+        StatementSection::IoDescriptions,
+        StatementSection::Constraints,
+        StatementSection::Example,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StatementSection::Goal => "Goal",
+            StatementSection::Statement => "Statement",
+            StatementSection::Rules => "Rules",
+            StatementSection::IoDescriptions => "Input and output",
+            StatementSection::Constraints => "Constraints",
+            StatementSection::Example => "Example",
+        }
+    }
+}
 
 pub struct OutputStyle {
     pub title: Style,
@@ -23,10 +74,26 @@ pub struct OutputStyle {
     pub error: Style,
     pub stderr: Style,
     pub dim_color: Style,
+    pub list_marker: Style,
     pub diff_green: Style,
     pub diff_green_whitespace: Option<Style>,
     pub diff_red: Style,
     pub diff_red_whitespace: Option<Style>,
+    pub diff_changed: Style,
+    pub diff_gutter: Style,
+    pub grep_match: Style,
+    pub diff_mode: DiffMode,
+    /// How changed lines are split up, `None` to guess from the expected
+    /// output of each testcase.
+    pub diff_granularity: Option<Granularity>,
+    pub numbering: Numbering,
+    /// Whether links are made clickable with OSC 8 escape sequences.
+    pub hyperlinks: bool,
+    /// Testcases (by index) that come from overlays rather than the clash.
+    pub provenance: BTreeMap<usize, Provenance>,
+    /// Lines of monospace blocks that are wider than this are cut short so
+    /// that the terminal doesn't wrap them.
+    pub max_monospace_width: Option<usize>,
 }
 
 impl OutputStyle {
@@ -48,18 +115,41 @@ impl OutputStyle {
             error: Style::default(),
             stderr: Style::default(),
             dim_color: Style::default(),
+            list_marker: Style::default(),
             diff_green: Style::default(),
             diff_green_whitespace: Some(Style::default()),
             diff_red: Style::default(),
             diff_red_whitespace: Some(Style::default()),
+            diff_changed: Style::new().underline(),
+            diff_gutter: Style::default(),
+            grep_match: Style::default(),
+            diff_mode: DiffMode::Color,
+            diff_granularity: None,
+            numbering: Numbering::Flat,
+            hyperlinks: false,
+            provenance: BTreeMap::new(),
+            max_monospace_width: None,
         }
     }
     pub fn from_env(show_whitespace: bool) -> Self {
+        Self::themed(show_whitespace, &Theme::default())
+    }
+
+    /// Like [OutputStyle::from_env], with the colors of `theme` unless
+    /// `$NO_COLOR` turns colors off.
+    pub fn themed(show_whitespace: bool, theme: &Theme) -> Self {
         let mut ostyle = match std::env::var_os("NO_COLOR") {
-            Some(s) if s.is_empty() => OutputStyle::default(),
+            Some(s) if s.is_empty() => OutputStyle::default().with_theme(theme),
             Some(_) => OutputStyle::plain(),
-            None => OutputStyle::default(),
+            None => OutputStyle::default().with_theme(theme),
         };
+        if let Some(diff_mode) = std::env::var("COCTUS_DIFF_STYLE")
+            .ok()
+            .and_then(|value| DiffMode::from_str(&value, true).ok())
+        {
+            ostyle.diff_mode = diff_mode;
+        }
+        ostyle.hyperlinks = supports_hyperlinks(|name| std::env::var(name).ok(), theme.hyperlinks);
         if show_whitespace {
             ostyle.input_whitespace = ostyle.input_whitespace.or(Some(ostyle.input));
             ostyle.output_whitespace = ostyle.output_whitespace.or(Some(ostyle.output));
@@ -73,6 +163,19 @@ impl OutputStyle {
         }
         ostyle
     }
+
+    fn with_theme(mut self, theme: &Theme) -> Self {
+        for (style, color) in [
+            (&mut self.diff_green, theme.diff_correct),
+            (&mut self.diff_red, theme.diff_wrong),
+            (&mut self.diff_changed, theme.diff_changed),
+        ] {
+            if color.is_some() {
+                style.foreground = color;
+            }
+        }
+        self
+    }
 }
 
 impl Default for OutputStyle {
@@ -94,48 +197,215 @@ impl Default for OutputStyle {
             error: Style::new().on(Color::Red),
             stderr: Style::new().fg(Color::Red),
             dim_color: Style::new().fg(Color::RGB(50, 50, 50)),
+            list_marker: Style::new().fg(Color::Cyan).bold(),
             diff_green: Style::new().fg(Color::RGB(111, 255, 111)),
             diff_green_whitespace: Some(Style::new().fg(Color::RGB(0, 70, 0))),
             diff_red: Style::new().fg(Color::RGB(255, 111, 111)),
             diff_red_whitespace: Some(Style::new().fg(Color::Red).on(Color::RGB(70, 0, 0))),
+            diff_changed: Style::new().fg(Color::RGB(255, 111, 111)).underline(),
+            diff_gutter: Style::new().bold(),
+            grep_match: Style::new().fg(Color::Red).bold(),
+            diff_mode: DiffMode::Color,
+            diff_granularity: None,
+            numbering: Numbering::Flat,
+            hyperlinks: false,
+            provenance: BTreeMap::new(),
+            max_monospace_width: None,
         }
     }
 }
 
 impl OutputStyle {
     pub fn styled_testcase_title(&self, testcase: &Testcase) -> String {
-        self.title.paint(format!("#{} {}", testcase.index, testcase.title)).to_string()
+        let title = self.title.paint(self.numbering.label(testcase));
+        match self.provenance.get(&testcase.index) {
+            Some(provenance) => format!("{} {}", title, self.dim_color.paint(format!("[{provenance}]"))),
+            None => title.to_string(),
+        }
     }
 
+    /// The input of `testcase`, or a dim `(empty)` if it has none so that
+    /// it doesn't look like something is missing from the output.
     pub fn styled_testcase_input(&self, testcase: &Testcase) -> String {
-        show_whitespace(&testcase.test_in, &self.input, &self.input_whitespace)
+        match testcase.test_in.is_empty() {
+            true => self.dim_color.paint("(empty)").to_string(),
+            false => show_whitespace(&testcase.test_in, &self.input, &self.input_whitespace),
+        }
     }
 
     pub fn styled_testcase_output(&self, testcase: &Testcase) -> String {
-        show_whitespace(&testcase.test_out, &self.output, &self.output_whitespace)
+        match testcase.test_out.is_empty() {
+            true => self.dim_color.paint("(empty)").to_string(),
+            false => show_whitespace(&testcase.test_out, &self.output, &self.output_whitespace),
+        }
     }
 
     pub fn print_headers(&self, clash: &Clash) {
-        println!("{}\n", self.title.paint(format!("=== {} ===", clash.title())));
-        println!("{}\n", self.link.paint(clash.codingame_link()));
+        print!("{}", self.headers_text(clash));
     }
 
     pub fn print_statement(&self, clash: &Clash) {
-        println!("{}\n", format_cg(clash.statement(), self));
-        println!("{}\n{}\n", self.title.paint("Input:"), format_cg(clash.input_description(), self));
-        println!("{}\n{}\n", self.title.paint("Output:"), format_cg(clash.output_description(), self));
-        if let Some(constraints) = clash.constraints() {
-            println!("{}\n{}\n", self.title.paint("Constraints:"), format_cg(constraints, self));
+        self.print_sections(clash, StatementSection::ALL)
+    }
+
+    pub fn print_sections(&self, clash: &Clash, sections: &[StatementSection]) {
+        for section in sections {
+            print!("{}", self.section_text(clash, *section));
         }
+    }
 
-        let example = clash.testcases().first().expect("example puzzle should have at least one testcase");
-        println!(
-            "{}\n{}\n{}\n{}",
-            self.title.paint("Example:"),
-            self.styled_testcase_input(example),
-            self.title.paint("Expected output:"),
-            self.styled_testcase_output(example),
-        );
+    /// Everything `coctus show` prints for the clash, as a single string.
+    pub fn statement_text(&self, clash: &Clash) -> String {
+        let sections = StatementSection::ALL.iter().map(|section| self.section_text(clash, *section));
+        std::iter::once(self.headers_text(clash)).chain(sections).collect()
+    }
+
+    fn headers_text(&self, clash: &Clash) -> String {
+        let link = clash.codingame_link();
+        format!(
+            "{}\n\n{}\n\n",
+            self.title.paint(format!("=== {} ===", clash.title())),
+            self.hyperlink(&link, self.link.paint(&link))
+        )
+    }
+
+    /// `text` as a link to `url` if hyperlinks are enabled, as is otherwise.
+    pub fn hyperlink(&self, url: &str, text: impl std::fmt::Display) -> String {
+        match self.hyperlinks {
+            true => format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\"),
+            false => text.to_string(),
+        }
+    }
+
+    fn section_text(&self, clash: &Clash, section: StatementSection) -> String {
+        match section {
+            StatementSection::Goal => match clash.goal() {
+                Some(goal) => format!("{}\n{}\n\n", self.title.paint("Goal:"), format_cg(goal, self)),
+                None => String::new(),
+            },
+            // Classic puzzles may have all of their statement in the other sections
+            StatementSection::Statement if clash.statement().trim().is_empty() => String::new(),
+            StatementSection::Statement => format!("{}\n\n", format_cg(clash.statement(), self)),
+            StatementSection::Rules => match clash.rules() {
+                Some(rules) => format!("{}\n{}\n\n", self.title.paint("Rules:"), format_cg(rules, self)),
+                None => String::new(),
+            },
+            StatementSection::IoDescriptions => format!(
+                "{}\n{}\n\n{}\n{}\n\n",
+                self.title.paint("Input:"),
+                format_cg(clash.input_description(), self),
+                self.title.paint("Output:"),
+                format_cg(clash.output_description(), self)
+            ),
+            StatementSection::Constraints => match clash.constraints() {
+                Some(constraints) => {
+                    format!("{}\n{}\n\n", self.title.paint("Constraints:"), format_cg(constraints, self))
+                }
+                None => String::new(),
+            },
+            StatementSection::Example => match (clash.examples(), clash.testcases().first()) {
+                (Some(examples), _) => {
+                    format!("{}\n{}\n", self.title.paint("Examples:"), format_cg(examples, self))
+                }
+                (None, Some(example)) => format!(
+                    "{}\n{}\n{}\n{}\n",
+                    self.title.paint("Example:"),
+                    self.styled_testcase_input(example),
+                    self.title.paint("Expected output:"),
+                    self.styled_testcase_output(example),
+                ),
+                (None, None) => format!(
+                    "{}\n{}\n",
+                    self.title.paint("Example:"),
+                    self.dim_color.paint("(none, the clash has no testcases)")
+                ),
+            },
+        }
+    }
+
+    /// Prints the lines of the clash that match `pattern` with `context` lines
+    /// around them (like `grep -C`), under the name of the section they are
+    /// in. Sections are searched as plain text so that formatting tags and
+    /// colors never get in the way of a match. Returns the number of
+    /// matching lines.
+    pub fn print_grep(&self, clash: &Clash, pattern: &Regex, context: usize) -> usize {
+        let plain = OutputStyle::plain();
+        let mut num_matches = 0;
+        for section in StatementSection::ALL {
+            let text = plain.section_text(clash, *section);
+            let lines: Vec<&str> = text.lines().collect();
+            let shown = grep_context(&lines, pattern, context);
+            if shown.is_empty() {
+                continue
+            }
+            println!("{}", self.title.paint(format!("== {} ==", section.name())));
+            for line in shown {
+                match line {
+                    Some((idx, true)) => {
+                        num_matches += 1;
+                        let highlighted = pattern.replace_all(lines[idx], |caps: &regex::Captures| {
+                            self.grep_match.paint(&caps[0]).to_string()
+                        });
+                        println!("{}{}", self.dim_color.paint(format!("{}:", idx + 1)), highlighted);
+                    }
+                    Some((idx, false)) => {
+                        println!("{}{}", self.dim_color.paint(format!("{}-", idx + 1)), lines[idx])
+                    }
+                    None => println!("{}", self.dim_color.paint("--")),
+                }
+            }
+            println!();
+        }
+        num_matches
+    }
+
+    /// Prints every formatting problem in the texts of the clash, and any
+    /// example in the statement that doesn't match the first testcase, and
+    /// returns the total number of problems found.
+    pub fn print_lint_report(&self, clash: &Clash) -> usize {
+        let texts = [
+            ("Goal", clash.goal().unwrap_or_default()),
+            ("Statement", clash.statement()),
+            ("Rules", clash.rules().unwrap_or_default()),
+            ("Input description", clash.input_description()),
+            ("Output description", clash.output_description()),
+            ("Constraints", clash.constraints().unwrap_or_default()),
+            ("Examples", clash.examples().unwrap_or_default()),
+        ];
+        let mut num_problems = 0;
+        for (name, text) in texts {
+            let problems = lint_cg(text);
+            if problems.is_empty() {
+                continue
+            }
+            println!("{} ({})", self.title.paint(name), problems.len());
+            for problem in &problems {
+                let (line, column) = line_and_column(text, problem.position);
+                println!("  {} {}", self.dim_color.paint(format!("{line}:{column}")), problem.kind);
+            }
+            num_problems += problems.len();
+        }
+
+        let example = statement_example(clash.statement());
+        if let Some(testcase) = clash.testcases().first() {
+            let parts = [
+                ("Example input", example.input, &testcase.test_in),
+                ("Example output", example.output, &testcase.test_out),
+            ];
+            for (name, found, expected) in parts {
+                let Some(found) = found else { continue };
+                let expected = normalize_example(expected);
+                if found == expected {
+                    continue
+                }
+                println!("{} in the statement doesn't match {}", self.title.paint(name), testcase.title);
+                let mut compared = testcase.clone();
+                compared.test_out = format!("{expected}\n");
+                self.print_diff(&compared, &format!("{found}\n"));
+                num_problems += 1;
+            }
+        }
+        num_problems
     }
 
     pub fn print_testcases(&self, clash: &Clash, selection: Vec<usize>) {
@@ -160,50 +430,40 @@ impl OutputStyle {
         self.print_testcases(clash, selection);
     }
 
-    fn print_diff(&self, testcase: &Testcase, stdout: &str) {
-        use dissimilar::Chunk::*;
-        use itertools::EitherOrBoth::{Both, Left, Right};
-        use itertools::Itertools;
-
-        let diff_red = &self.diff_red;
-        let diff_ws_red = &self.diff_red_whitespace;
-        let diff_green = &self.diff_green;
-        let diff_ws_green = &self.diff_green_whitespace;
+    /// Prints the differences between two texts that are not outputs of a
+    /// solution (such as two versions of a stub) with gutter symbols, so that
+    /// missing lines show up as well.
+    pub fn print_text_diff(&self, expected: &str, actual: &str) {
+        self.print_diff_symbols(expected, actual);
+    }
 
+    fn print_diff(&self, testcase: &Testcase, stdout: &str) {
         if stdout.is_empty() {
             println!("{}", self.dim_color.paint("(no output)"));
             return
         }
 
-        let expected_lines = LinesWithEndings::from(&testcase.test_out);
-        let actual_lines = LinesWithEndings::from(stdout);
+        match self.diff_mode {
+            DiffMode::Color => self.print_diff_color(&testcase.test_out, stdout),
+            DiffMode::Symbols => self.print_diff_symbols(&testcase.test_out, stdout),
+        }
+    }
+
+    fn diff_hunks(&self, expected: &str, stdout: &str) -> Vec<Hunk> {
+        let granularity = self.diff_granularity.unwrap_or_else(|| Granularity::guess(expected));
+        diff::compute_with(expected, stdout, granularity)
+    }
 
+    fn print_diff_color(&self, expected: &str, stdout: &str) {
         let mut missing_lines = 0;
-        for either_or_both in expected_lines.zip_longest(actual_lines) {
-            match either_or_both {
-                Left(_) => missing_lines += 1,
-                Right(s) => print!("{}", show_whitespace(s, diff_red, diff_ws_red)),
-                Both(a, b) => {
-                    let mut prev_deleted = false;
-
-                    for chunk in dissimilar::diff(a, b) {
-                        match chunk {
-                            Equal(text) if prev_deleted => {
-                                let mut chars = text.chars();
-                                let first_char = chars.next().expect("diff chunk should not be empty");
-                                let rest = chars.as_str();
-                                print!("{}", show_whitespace(&first_char.to_string(), diff_red, diff_ws_red));
-                                if !rest.is_empty() {
-                                    print!("{}", show_whitespace(rest, diff_green, diff_ws_green));
-                                }
-                            }
-                            Equal(text) => print!("{}", show_whitespace(text, diff_green, diff_ws_green)),
-                            Insert(text) => print!("{}", show_whitespace(text, diff_red, diff_ws_red)),
-                            Delete(_) => {}
-                        }
-
-                        prev_deleted = matches!(chunk, Delete(_));
-                    }
+        for hunk in self.diff_hunks(expected, stdout) {
+            match hunk.kind {
+                HunkKind::Delete => missing_lines += 1,
+                HunkKind::Insert => {
+                    print!("{}", show_whitespace(&hunk.actual(), &self.diff_red, &self.diff_red_whitespace))
+                }
+                HunkKind::Equal | HunkKind::Change => {
+                    print!("{}", self.styled_spans(&hunk.spans, &self.diff_red))
                 }
             }
         }
@@ -218,6 +478,67 @@ impl OutputStyle {
         }
     }
 
+    fn print_diff_symbols(&self, expected: &str, stdout: &str) {
+        for hunk in self.diff_hunks(expected, stdout) {
+            let (gutter, raw_line, styled_line) = match hunk.kind {
+                HunkKind::Delete => {
+                    let line = hunk.expected();
+                    let styled = show_whitespace(&line, &self.dim_color, &self.diff_red_whitespace);
+                    ("-", line, styled)
+                }
+                HunkKind::Insert => {
+                    let line = hunk.actual();
+                    let styled = show_whitespace(&line, &self.diff_changed, &self.diff_red_whitespace);
+                    ("+", line, styled)
+                }
+                HunkKind::Equal => {
+                    let line = hunk.actual();
+                    let styled = show_whitespace(&line, &self.diff_green, &self.diff_green_whitespace);
+                    (" ", line, styled)
+                }
+                HunkKind::Change => ("~", hunk.actual(), self.styled_spans(&hunk.spans, &self.diff_changed)),
+            };
+            print!("{} {}", self.diff_gutter.paint(gutter), styled_line);
+            if !raw_line.ends_with('\n') {
+                println!()
+            }
+        }
+    }
+
+    /// Styles the actual line of a diff hunk so that the parts that match the
+    /// expected line are green and the parts that differ are painted with
+    /// `changed_style`.
+    fn styled_spans(&self, spans: &[Span], changed_style: &Style) -> String {
+        let diff_ws_red = &self.diff_red_whitespace;
+        let diff_green = &self.diff_green;
+        let diff_ws_green = &self.diff_green_whitespace;
+
+        let mut styled = String::new();
+        let mut prev_deleted = false;
+
+        for span in spans {
+            match span.kind {
+                // Mark the spot where something is missing from the actual line
+                SpanKind::Equal if prev_deleted => {
+                    let mut chars = span.text.chars();
+                    let first_char = chars.next().expect("diff span should not be empty");
+                    let rest = chars.as_str();
+                    styled += &show_whitespace(&first_char.to_string(), changed_style, diff_ws_red);
+                    if !rest.is_empty() {
+                        styled += &show_whitespace(rest, diff_green, diff_ws_green);
+                    }
+                }
+                SpanKind::Equal => styled += &show_whitespace(&span.text, diff_green, diff_ws_green),
+                SpanKind::Insert => styled += &show_whitespace(&span.text, changed_style, diff_ws_red),
+                SpanKind::Delete => {}
+            }
+
+            prev_deleted = span.kind == SpanKind::Delete;
+        }
+
+        styled
+    }
+
     pub fn print_result(&self, testcase: &Testcase, test_result: &TestResult) {
         let title = self.styled_testcase_title(testcase);
         match test_result {
@@ -230,24 +551,163 @@ impl OutputStyle {
                 println!(" {}", self.stderr.paint(error_msg));
             }
 
-            TestResult::WrongOutput { stdout, stderr } => {
-                println!("{} {}", self.failure.paint("FAIL"), title);
-                self.print_failure(testcase, stdout, stderr);
+            TestResult::WrongOutput {
+                stdout,
+                stderr,
+                merged,
+                invalid_utf8,
+            } => {
+                let category = test_result.failure_category(testcase).unwrap_or(FailureCategory::WrongAnswer);
+                println!("{} {}", self.failure.paint(format!("FAIL ({category})")), title);
+                self.print_failure(testcase, stdout, stderr, merged, *invalid_utf8);
             }
 
-            TestResult::RuntimeError { stdout, stderr } => {
-                println!("{} {}", self.error.paint("ERROR"), title);
-                self.print_failure(testcase, stdout, stderr);
+            TestResult::RuntimeError {
+                stdout,
+                stderr,
+                merged,
+                exit,
+                invalid_utf8,
+            } => {
+                println!("{} {}", self.error.paint(format!("ERROR ({exit})")), title);
+                self.print_failure(testcase, stdout, stderr, merged, *invalid_utf8);
             }
 
-            TestResult::Timeout { stdout, stderr } => {
+            TestResult::Timeout {
+                stdout,
+                stderr,
+                merged,
+                invalid_utf8,
+            } => {
                 println!("{} {}", self.error.paint("TIMEOUT"), title);
-                self.print_failure(testcase, stdout, stderr);
+                self.print_failure(testcase, stdout, stderr, merged, *invalid_utf8);
+            }
+
+            TestResult::Aborted { .. } => {
+                println!("{} {}", self.error.paint("ABORTED"), title);
+                println!(" {}", self.dim_color.paint("Stopped by the suite timeout before it finished"));
             }
+
+            // Kinds of failures that this version doesn't know the details of
+            _ => println!("{} {}", self.failure.paint("FAIL"), title),
+        }
+    }
+
+    /// Prints the result line of a wrong output that differs from the
+    /// expected output like an earlier failure did, pointing to that failure
+    /// instead of showing the diff again.
+    pub fn print_repeated_failure(
+        &self,
+        testcase: &Testcase,
+        test_result: &TestResult,
+        cluster: &FailureCluster,
+    ) {
+        let category = test_result.failure_category(testcase).unwrap_or(FailureCategory::WrongAnswer);
+        println!(
+            "{} {}",
+            self.failure.paint(format!("FAIL ({category})")),
+            self.styled_testcase_title(testcase)
+        );
+        println!(
+            " {}",
+            self.dim_color
+                .paint(format!("Same failure as {}: {}", cluster.labels[0], cluster.pattern))
+        );
+    }
+
+    /// Prints the failures that repeated across testcases, how many times
+    /// and in which testcases.
+    pub fn print_failure_patterns<'a>(&self, clusters: impl Iterator<Item = &'a FailureCluster>) {
+        let clusters: Vec<&FailureCluster> = clusters.collect();
+        if clusters.is_empty() {
+            return
+        }
+        println!("{}", self.secondary_title.paint("Failure patterns:"));
+        for cluster in clusters {
+            println!(" {} tests failed the same way: {}", cluster.labels.len(), cluster.pattern);
+            println!("   {}", self.dim_color.paint(cluster.labels.join(", ")));
+        }
+    }
+
+    /// Prints a hint about the size of the input of a test that timed out,
+    /// if the input is large or the constraints allow it to be.
+    pub fn print_timeout_hint(&self, testcase: &Testcase, constraints: &[Constraint]) {
+        if let Some(hint) = timeout_hint(&testcase.test_in, constraints) {
+            println!("{} {}", self.secondary_title.paint("HINT"), hint);
+        }
+    }
+
+    /// Prints how many of the failed testcases fell into each category, if
+    /// any failed.
+    pub fn print_failure_counts(&self, failures: &BTreeMap<FailureCategory, usize>) {
+        if failures.is_empty() {
+            return
+        }
+        let counts = failures.iter().map(|(category, count)| format!("{count} {category}")).join(", ");
+        println!("{} {}", self.secondary_title.paint("Failures:"), counts);
+    }
+
+    /// Prints the results of every test next to the result of its paired
+    /// validator, if any pair had a failure. `passed` tells whether the
+    /// testcase with the given index passed, pairs with a testcase that was
+    /// not run are left out.
+    pub fn print_pair_summary(
+        &self,
+        testcases: &[Testcase],
+        pairs: &[TestcasePair],
+        passed: impl Fn(usize) -> Option<bool>,
+    ) {
+        let results: Vec<(&Testcase, bool, &Testcase, bool)> = pairs
+            .iter()
+            .filter_map(|pair| {
+                let test = testcases.iter().find(|testcase| testcase.index == pair.test)?;
+                let validator = testcases.iter().find(|testcase| testcase.index == pair.validator)?;
+                Some((test, passed(pair.test)?, validator, passed(pair.validator)?))
+            })
+            .collect();
+        if results
+            .iter()
+            .all(|(_, test_passed, _, validator_passed)| *test_passed && *validator_passed)
+        {
+            return
+        }
+
+        let outcome = |passed: bool| match passed {
+            true => self.success.paint("PASS"),
+            false => self.failure.paint("FAIL"),
+        };
+        println!("{}", self.secondary_title.paint("Tests and their validators:"));
+        for (test, test_passed, validator, validator_passed) in &results {
+            println!(
+                "  {} {} / {} {}",
+                outcome(*test_passed),
+                self.styled_testcase_title(test),
+                outcome(*validator_passed),
+                self.styled_testcase_title(validator)
+            );
+        }
+        for (test, _, validator, _) in results
+            .iter()
+            .filter(|(_, test_passed, _, validator_passed)| *test_passed && !*validator_passed)
+        {
+            println!(
+                "{} {} passed but its validator {} failed, which is likely a sign of hardcoding the \
+                expected output of the test",
+                self.failure.paint("WARNING"),
+                self.numbering.label(test),
+                self.numbering.label(validator)
+            );
         }
     }
 
-    fn print_failure(&self, testcase: &Testcase, stdout: &str, stderr: &str) {
+    fn print_failure(
+        &self,
+        testcase: &Testcase,
+        stdout: &str,
+        stderr: &str,
+        merged: &[OutputChunk],
+        invalid_utf8: usize,
+    ) {
         println!(
             "{}\n{}\n{}\n{}",
             self.secondary_title.paint("===== INPUT ======"),
@@ -258,8 +718,30 @@ impl OutputStyle {
 
         println!("{}", &self.secondary_title.paint("===== STDOUT ====="));
         self.print_diff(testcase, stdout);
+        let differences = diff::invisible_differences(testcase.test_out.trim_end(), stdout);
+        if !differences.is_empty() {
+            println!(
+                "{} The outputs differ only in {}",
+                self.secondary_title.paint("NOTE"),
+                differences.iter().map(|difference| difference.to_string()).join(" and ")
+            );
+        }
+        if invalid_utf8 > 0 {
+            println!(
+                "{} {} of the output {} not valid UTF-8 (shown as \u{fffd})",
+                self.failure.paint("WARNING"),
+                match invalid_utf8 {
+                    1 => "1 byte".to_string(),
+                    n => format!("{n} bytes"),
+                },
+                if invalid_utf8 == 1 { "was" } else { "were" }
+            );
+        }
 
-        if !stderr.is_empty() {
+        if !merged.is_empty() && !stderr.is_empty() {
+            println!("{}", self.secondary_title.paint("===== MERGED ====="));
+            self.print_merged_output(merged);
+        } else if !stderr.is_empty() {
             println!(
                 "{}\n{}",
                 self.secondary_title.paint("===== STDERR ====="),
@@ -267,4 +749,234 @@ impl OutputStyle {
             );
         }
     }
+
+    /// Prints stdout and stderr interleaved, with every line prefixed by the
+    /// name of the stream it was written to.
+    fn print_merged_output(&self, merged: &[OutputChunk]) {
+        let text = merged
+            .iter()
+            .flat_map(|chunk| chunk.text.lines().map(move |line| (chunk.stream, line)));
+        for (stream, line) in text {
+            match stream {
+                OutputStream::Stdout => println!("{}{}", self.dim_color.paint("out| "), line),
+                OutputStream::Stderr => {
+                    println!("{}{}", self.dim_color.paint("err| "), self.stderr.paint(line))
+                }
+            }
+        }
+    }
+}
+
+/// The lines to show for a search of `lines`: `Some((idx, is_match))` for
+/// matching lines and the context lines around them, `None` for the gaps
+/// between groups of lines that are not next to each other.
+fn grep_context(lines: &[&str], pattern: &Regex, context: usize) -> Vec<Option<(usize, bool)>> {
+    let matching: Vec<usize> = (0..lines.len()).filter(|&idx| pattern.is_match(lines[idx])).collect();
+    let mut shown = Vec::new();
+    let mut next_unshown = 0;
+    for &idx in &matching {
+        let start = idx.saturating_sub(context).max(next_unshown);
+        let end = (idx + context + 1).min(lines.len());
+        if start > next_unshown && next_unshown > 0 {
+            shown.push(None);
+        }
+        shown.extend((start..end).map(|line| Some((line, matching.contains(&line)))));
+        next_unshown = next_unshown.max(end);
+    }
+    shown
+}
+
+/// Inputs with at least this many lines (or bytes) are large enough for slow
+/// I/O to matter.
+const LARGE_INPUT_LINES: usize = 10_000;
+const LARGE_INPUT_BYTES: usize = 1_000_000;
+/// Upper bounds of this size in the constraints usually rule out quadratic
+/// algorithms.
+const LARGE_BOUND: f64 = 1e5;
+
+fn timeout_hint(input: &str, constraints: &[Constraint]) -> Option<String> {
+    let num_lines = input.lines().count();
+    let lines = if num_lines == 1 { "line" } else { "lines" };
+    let input_size = format!("this input has {} {} ({} bytes)", num_lines, lines, input.len());
+    let largest_bound = constraints
+        .iter()
+        .filter(|constraint| constraint.max.is_some_and(|max| max >= LARGE_BOUND))
+        .max_by(|a, b| a.max.partial_cmp(&b.max).expect("bounds should not be NaN"));
+    match largest_bound {
+        Some(constraint) => Some(format!(
+            "The constraints allow {} up to {} and {input_size}, consider faster I/O or a more efficient algorithm",
+            constraint.subject,
+            constraint.max_text.as_deref().unwrap_or_default()
+        )),
+        None if num_lines >= LARGE_INPUT_LINES || input.len() >= LARGE_INPUT_BYTES => {
+            let mut hint = input_size;
+            hint[..1].make_ascii_uppercase();
+            Some(format!("{hint}, consider faster I/O"))
+        }
+        None => None,
+    }
+}
+
+/// Whether the terminal is known to support OSC 8 hyperlinks, judging by the
+/// environment variables that terminals set. `COCTUS_HYPERLINKS` (`0` to
+/// disable, anything else to enable) and then `configured` (the `hyperlinks`
+/// option of the theme) override the detection.
+fn supports_hyperlinks(var: impl Fn(&str) -> Option<String>, configured: Option<bool>) -> bool {
+    use std::io::IsTerminal;
+
+    if let Some(value) = var("COCTUS_HYPERLINKS") {
+        return !value.is_empty() && value != "0"
+    }
+    if let Some(enabled) = configured {
+        return enabled
+    }
+    if !std::io::stdout().is_terminal() || var("TERM").is_some_and(|term| term == "dumb") {
+        return false
+    }
+    let known_program = var("TERM_PROGRAM").is_some_and(|program| {
+        ["iTerm.app", "WezTerm", "vscode", "Hyper", "ghostty"].contains(&program.as_str())
+    });
+    let known_term = var("TERM").is_some_and(|term| {
+        ["xterm-kitty", "xterm-ghostty", "alacritty", "foot", "wezterm"].contains(&term.as_str())
+    });
+    // VTE based terminals (such as GNOME Terminal) added support in 0.50
+    let vte = var("VTE_VERSION")
+        .and_then(|version| version.parse::<u32>().ok())
+        .is_some_and(|version| version >= 5000);
+    let other = ["WT_SESSION", "KONSOLE_VERSION", "KITTY_WINDOW_ID", "DOMTERM"]
+        .iter()
+        .any(|name| var(name).is_some());
+    known_program || known_term || vte || other
+}
+
+/// `file://` URL of `path`, which should be absolute.
+pub fn file_url(path: &std::path::Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut url = String::from("file://");
+    if !path.starts_with('/') {
+        url.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                url.push(byte as char)
+            }
+            _ => url += &format!("%{byte:02X}"),
+        }
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        |name| vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.to_string())
+    }
+
+    #[test]
+    fn hyperlinks_can_be_forced() {
+        assert!(supports_hyperlinks(env(&[("COCTUS_HYPERLINKS", "1"), ("TERM", "dumb")]), None));
+        assert!(!supports_hyperlinks(
+            env(&[("COCTUS_HYPERLINKS", "0"), ("TERM_PROGRAM", "WezTerm")]),
+            None
+        ));
+        assert!(supports_hyperlinks(env(&[("TERM", "dumb")]), Some(true)));
+        assert!(!supports_hyperlinks(env(&[("COCTUS_HYPERLINKS", "0")]), Some(true)));
+    }
+
+    #[test]
+    fn theme_colors_replace_the_diff_colors() {
+        let theme = Theme {
+            diff_changed: Some(Color::Cyan),
+            ..Theme::default()
+        };
+        let ostyle = OutputStyle::default().with_theme(&theme);
+        assert_eq!(ostyle.diff_changed, Style::new().fg(Color::Cyan).underline());
+        assert_eq!(ostyle.diff_green, OutputStyle::default().diff_green);
+    }
+
+    #[test]
+    fn hyperlink_fallback() {
+        let mut ostyle = OutputStyle::plain();
+        assert_eq!(ostyle.hyperlink("https://example.com", "text"), "text");
+        ostyle.hyperlinks = true;
+        assert_eq!(
+            ostyle.hyperlink("https://example.com", "text"),
+            "\x1b]8;;https://example.com\x1b\\text\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn classic_puzzle_sections_are_shown_in_order() {
+        let json = std::fs::read_to_string("fixtures/puzzles/classic_puzzle_sections.json").unwrap();
+        let puzzle: Clash = serde_json::from_str(&json).unwrap();
+        let text = OutputStyle::plain().statement_text(&puzzle);
+        let positions: Vec<usize> = ["Goal:", "Rules:", "Input:", "Constraints:", "Examples:"]
+            .iter()
+            .map(|heading| text.find(heading).unwrap_or_else(|| panic!("no {heading} in {text:?}")))
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(text.contains("Goal:\nFind the largest number.\n\nRules:\n"));
+        // The written examples take the place of the first testcase
+        assert!(!text.contains("Expected output:"));
+    }
+
+    #[test]
+    fn drafts_without_testcases_have_no_example() {
+        let json = std::fs::read_to_string("fixtures/puzzles/draft_without_testcases.json").unwrap();
+        let draft: Clash = serde_json::from_str(&json).unwrap();
+        let text = OutputStyle::plain().statement_text(&draft);
+        assert!(text.contains("Example:\n(none, the clash has no testcases)\n"), "{text:?}");
+    }
+
+    #[test]
+    fn grep_context_merges_overlapping_groups() {
+        let lines = ["a", "match", "b", "c", "match", "d", "e", "f", "match"];
+        let pattern = Regex::new("match").unwrap();
+        let shown: Vec<Option<usize>> = grep_context(&lines, &pattern, 1)
+            .into_iter()
+            .map(|line| line.map(|(idx, _)| idx))
+            .collect();
+        assert_eq!(
+            shown,
+            vec![
+                Some(0),
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4),
+                Some(5),
+                None,
+                Some(7),
+                Some(8)
+            ]
+        );
+        assert_eq!(grep_context(&lines, &pattern, 0).iter().filter(|line| line.is_none()).count(), 2);
+        assert!(grep_context(&lines, &Regex::new("nothing").unwrap(), 3).is_empty());
+    }
+
+    #[test]
+    fn timeout_hints() {
+        let constraints = clashlib::clash::parse_constraints("1 ≤ N ≤ 10^6\n1 ≤ K ≤ 100");
+        assert_eq!(
+            timeout_hint("3\n1 2 3", &constraints).unwrap(),
+            "The constraints allow N up to 10^6 and this input has 2 lines (7 bytes), consider faster I/O \
+            or a more efficient algorithm"
+        );
+        let large_input = "1\n".repeat(LARGE_INPUT_LINES);
+        assert_eq!(
+            timeout_hint(&large_input, &constraints[1..]).unwrap(),
+            "This input has 10000 lines (20000 bytes), consider faster I/O"
+        );
+        assert_eq!(timeout_hint("3\n1 2 3", &constraints[1..]), None);
+    }
+
+    #[test]
+    fn file_urls_are_percent_encoded() {
+        let path = std::path::Path::new("/home/me/my clashes/abc.json");
+        assert_eq!(file_url(path), "file:///home/me/my%20clashes/abc.json");
+        assert_eq!(file_url(std::path::Path::new("C:\\Users\\é")), "file:///C:/Users/%C3%A9");
+    }
 }
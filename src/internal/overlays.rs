@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use clashlib::clash::Testcase;
+
+use super::json_file;
+
+/// Where a testcase that did not come from the clash itself came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Provenance {
+    /// The testcase was added by the overlay with this name.
+    Added(String),
+    /// The testcase of the clash was replaced by the overlay with this name.
+    Replaced(String),
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Provenance::Added(name) => write!(f, "overlay {name}"),
+            Provenance::Replaced(name) => write!(f, "replaced by overlay {name}"),
+        }
+    }
+}
+
+/// Alternative sets of testcases for stored clashes. Every overlay is a JSON
+/// array of testcases in `<dir>/<handle>/<name>.json`, in the same format as
+/// the testcases of a clash.
+///
+/// The overlays of a clash are applied in the order of their names. A
+/// testcase of an overlay replaces the testcase with the same title (keeping
+/// its place and number), and testcases with new titles are added as tests
+/// after all the others. Later overlays take precedence over earlier ones.
+pub struct Overlays {
+    dir: PathBuf,
+}
+
+impl Overlays {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn path(&self, handle: &str, name: &str) -> PathBuf {
+        self.dir.join(handle).join(format!("{name}.json"))
+    }
+
+    /// Names of the overlays of the clash in the order they are applied.
+    pub fn names(&self, handle: &str) -> Result<Vec<String>> {
+        let dir = self.dir.join(handle);
+        if !dir.exists() {
+            return Ok(Vec::new())
+        }
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn load(&self, handle: &str, name: &str) -> Result<Vec<Testcase>> {
+        Ok(json_file::load(&self.path(handle, name), "testcases")?.unwrap_or_default())
+    }
+
+    /// Applies every overlay of the clash to its `testcases`, returning the
+    /// provenance of each testcase (by index) that the overlays touched.
+    pub fn apply(&self, handle: &str, testcases: &mut Vec<Testcase>) -> Result<BTreeMap<usize, Provenance>> {
+        let mut provenance = BTreeMap::new();
+        for name in self.names(handle)? {
+            for overlay_testcase in self.load(handle, &name)? {
+                match testcases.iter_mut().find(|testcase| testcase.title == overlay_testcase.title) {
+                    Some(testcase) => {
+                        testcase.test_in = overlay_testcase.test_in;
+                        testcase.test_out = overlay_testcase.test_out;
+                        let replaced = match provenance.get(&testcase.index) {
+                            Some(Provenance::Added(_)) => Provenance::Added(name.clone()),
+                            _ => Provenance::Replaced(name.clone()),
+                        };
+                        provenance.insert(testcase.index, replaced);
+                    }
+                    None => {
+                        let num_tests = testcases.iter().filter(|testcase| !testcase.is_validator).count();
+                        let testcase = Testcase {
+                            index: testcases.len() + 1,
+                            number: num_tests + 1,
+                            is_validator: false,
+                            ..overlay_testcase
+                        };
+                        provenance.insert(testcase.index, Provenance::Added(name.clone()));
+                        testcases.push(testcase);
+                    }
+                }
+            }
+        }
+        Ok(provenance)
+    }
+
+    /// Adds `testcase` to the overlay, replacing the testcase of the overlay
+    /// that has the same title.
+    pub fn add(&self, handle: &str, name: &str, testcase: Testcase) -> Result<()> {
+        let mut testcases = self.load(handle, name)?;
+        match testcases.iter_mut().find(|existing| existing.title == testcase.title) {
+            Some(existing) => *existing = testcase,
+            None => testcases.push(testcase),
+        }
+        self.save(handle, name, &testcases)
+    }
+
+    /// Replaces the overlay with the testcases in the JSON file at `source`
+    /// and returns how many there were.
+    pub fn import(&self, handle: &str, name: &str, source: &Path) -> Result<usize> {
+        let testcases: Vec<Testcase> =
+            json_file::load(source, "testcases")?.with_context(|| format!("{:?} does not exist", source))?;
+        if testcases.is_empty() {
+            return Err(anyhow!("{:?} has no testcases to import", source))
+        }
+        self.save(handle, name, &testcases)?;
+        Ok(testcases.len())
+    }
+
+    fn save(&self, handle: &str, name: &str, testcases: &[Testcase]) -> Result<()> {
+        let path = self.path(handle, name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        json_file::save(&path, testcases)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::test_helper::testcase;
+
+    fn validator_1() -> Testcase {
+        Testcase {
+            number: 1,
+            title: "Validator 1".to_string(),
+            is_validator: true,
+            ..testcase(2, "Validator 1 in", "Validator 1 out")
+        }
+    }
+
+    fn clash_testcases() -> Vec<Testcase> {
+        vec![testcase(1, "Test 1 in", "Test 1 out"), validator_1()]
+    }
+
+    #[test]
+    fn overlays_replace_by_title_and_add_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        let overlays = Overlays::new(dir.path().to_path_buf());
+        overlays.add("abc", "easy", testcase(1, "easier", "Test 1 out")).unwrap();
+        let extra = Testcase {
+            title: "Extra".to_string(),
+            ..testcase(0, "Extra in", "Extra out")
+        };
+        overlays.add("abc", "easy", extra).unwrap();
+
+        let mut testcases = clash_testcases();
+        let provenance = overlays.apply("abc", &mut testcases).unwrap();
+        assert_eq!(testcases.len(), 3);
+        assert_eq!(testcases[0].test_in, "easier");
+        assert_eq!((testcases[2].index, testcases[2].number), (3, 2));
+        assert_eq!(provenance[&1], Provenance::Replaced("easy".to_string()));
+        assert_eq!(provenance[&3].to_string(), "overlay easy");
+        assert!(!provenance.contains_key(&2));
+    }
+
+    #[test]
+    fn later_overlays_take_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let overlays = Overlays::new(dir.path().to_path_buf());
+        let mut first = validator_1();
+        first.test_out = "first".to_string();
+        let mut second = first.clone();
+        second.test_out = "second".to_string();
+        overlays.add("abc", "b", second).unwrap();
+        overlays.add("abc", "a", first).unwrap();
+        assert_eq!(overlays.names("abc").unwrap(), vec!["a", "b"]);
+
+        let mut testcases = clash_testcases();
+        let provenance = overlays.apply("abc", &mut testcases).unwrap();
+        assert_eq!(testcases[1].test_out, "second");
+        assert!(testcases[1].is_validator);
+        assert_eq!(provenance[&2], Provenance::Replaced("b".to_string()));
+    }
+
+    #[test]
+    fn clashes_without_overlays_are_left_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let overlays = Overlays::new(dir.path().join("overlays"));
+        let mut testcases = clash_testcases();
+        assert!(overlays.apply("abc", &mut testcases).unwrap().is_empty());
+        assert_eq!(testcases.len(), 2);
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// How the solution of a clash was last run, so that `coctus run` without
+/// flags can run it the same way when the clash is revisited.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum RememberedRun {
+    /// A solution file run with the default commands of its language.
+    Solution { language: String, path: PathBuf },
+    /// Commands given with --command and --build-command.
+    Command {
+        command: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        build_command: Option<String>,
+        /// Where the commands were run, they may have relative paths in them.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dir: Option<PathBuf>,
+    },
+}
+
+impl std::fmt::Display for RememberedRun {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RememberedRun::Solution { language, path } => write!(f, "{} ({language})", path.display()),
+            RememberedRun::Command {
+                command,
+                build_command,
+                dir,
+            } => {
+                if let Some(build_command) = build_command {
+                    write!(f, "--build-command {build_command:?} ")?;
+                }
+                write!(f, "--command {command:?}")?;
+                match dir {
+                    Some(dir) => write!(f, " in {}", dir.display()),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl RememberedRun {
+    /// The directory the commands have to be run in, if it matters.
+    pub fn dir(&self) -> Option<&Path> {
+        match self {
+            RememberedRun::Solution { .. } => None,
+            RememberedRun::Command { dir, .. } => dir.as_deref(),
+        }
+    }
+}
+
+/// The [`RememberedRun`] of each clash. Stored as a JSON object keyed by
+/// handle.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RememberedRuns(BTreeMap<String, RememberedRun>);
+
+impl RememberedRuns {
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(json_file::load(path, "remembered runs")?.unwrap_or_default())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+
+    pub fn get(&self, handle: &str) -> Option<&RememberedRun> {
+        self.0.get(handle)
+    }
+
+    /// Remembers `run` for `handle`, returning whether anything changed.
+    pub fn remember(&mut self, handle: &str, run: RememberedRun) -> bool {
+        self.0.insert(handle.to_string(), run.clone()) != Some(run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remembered_runs_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("remembered_runs.json");
+        let mut runs = RememberedRuns::load(&path).unwrap();
+        let solution = RememberedRun::Solution {
+            language: "rust".to_string(),
+            path: PathBuf::from("/tmp/solution.rs"),
+        };
+        let command = RememberedRun::Command {
+            command: "python3 sol.py".to_string(),
+            build_command: None,
+            dir: Some(PathBuf::from("/tmp/solutions")),
+        };
+        assert!(runs.remember("abc", solution.clone()));
+        assert!(runs.remember("def", solution));
+        assert!(runs.remember("def", command.clone()));
+        assert!(!runs.remember("def", command.clone()));
+        runs.save(&path).unwrap();
+
+        let runs = RememberedRuns::load(&path).unwrap();
+        assert_eq!(runs.get("def"), Some(&command));
+        assert_eq!(
+            runs.get("def").unwrap().to_string(),
+            "--command \"python3 sol.py\" in /tmp/solutions"
+        );
+        assert_eq!(runs.get("abc").unwrap().to_string(), "/tmp/solution.rs (rust)");
+        assert_eq!(runs.get("ghi"), None);
+    }
+}
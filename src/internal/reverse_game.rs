@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use clashlib::clash::Testcase;
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// Progress of `coctus reverse` on every clash it has been played on, stored
+/// in the data directory so that a game can be continued later.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReverseGames {
+    games: BTreeMap<String, ReverseProgress>,
+}
+
+/// The tests of a clash that have been revealed and the guesses of the rule
+/// made along the way.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReverseProgress {
+    /// [Testcase::index] of the revealed tests in the order they were
+    /// revealed.
+    pub revealed: Vec<usize>,
+    pub guesses: Vec<Guess>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Guess {
+    pub rule: String,
+    /// Number of tests that had been revealed when the guess was made.
+    pub num_revealed: usize,
+}
+
+/// What can be typed at the prompt of `coctus reverse`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReverseCommand {
+    /// Reveal the next test (also an empty line).
+    Next,
+    /// Show the revealed tests again.
+    Seen,
+    /// Write down a guess of the rule.
+    Guess(String),
+    /// List the guesses so far.
+    Guesses,
+    /// Run the solution against the revealed tests.
+    Run,
+    Help,
+    Quit,
+}
+
+pub const REVERSE_HELP: &str = "\
+Commands:
+  next, n (or an empty line)  reveal the next test
+  seen, s                     show the revealed tests again
+  guess, g RULE               write down a guess of the rule
+  guesses                     list the guesses so far
+  run, r                      run the solution against the revealed tests
+  help, h                     show this help
+  quit, q (or Ctrl-D)         stop, the progress is kept for next time";
+
+impl ReverseGames {
+    /// A missing file means that no games have been played yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(json_file::load(path, "reverse games")?.unwrap_or_default())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+
+    pub fn progress(&self, handle: &str) -> ReverseProgress {
+        self.games.get(handle).cloned().unwrap_or_default()
+    }
+
+    pub fn set_progress(&mut self, handle: &str, progress: ReverseProgress) {
+        self.games.insert(handle.to_string(), progress);
+    }
+}
+
+impl ReverseProgress {
+    /// Reveals the first of `tests` that hasn't been revealed yet. `None` if
+    /// all of them have been.
+    pub fn reveal_next<'t>(&mut self, tests: &[&'t Testcase]) -> Option<&'t Testcase> {
+        let next = tests.iter().find(|test| !self.revealed.contains(&test.index))?;
+        self.revealed.push(next.index);
+        Some(next)
+    }
+
+    /// The revealed tests among `tests`, in the order they were revealed.
+    /// Tests that are gone (because the clash was edited) are left out.
+    pub fn revealed_tests<'t>(&self, tests: &[&'t Testcase]) -> Vec<&'t Testcase> {
+        self.revealed
+            .iter()
+            .filter_map(|index| tests.iter().find(|test| test.index == *index).copied())
+            .collect()
+    }
+
+    pub fn guess(&mut self, rule: &str) {
+        self.guesses.push(Guess {
+            rule: rule.to_string(),
+            num_revealed: self.revealed.len(),
+        });
+    }
+}
+
+impl std::str::FromStr for ReverseCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let line = line.trim();
+        let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let command = match word.to_ascii_lowercase().as_str() {
+            "" | "n" | "next" => ReverseCommand::Next,
+            "s" | "seen" => ReverseCommand::Seen,
+            "g" | "guess" if rest.is_empty() => {
+                return Err(anyhow!("Type the rule after {word}, like: {word} reverse the words"))
+            }
+            "g" | "guess" => return Ok(ReverseCommand::Guess(rest.to_string())),
+            "guesses" => ReverseCommand::Guesses,
+            "r" | "run" => ReverseCommand::Run,
+            "h" | "help" | "?" => ReverseCommand::Help,
+            "q" | "quit" | "exit" => ReverseCommand::Quit,
+            _ => return Err(anyhow!("Unknown command {word:?} (type help for the commands)")),
+        };
+        match rest {
+            "" => Ok(command),
+            _ => Err(anyhow!("{word} takes no arguments")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::test_helper::testcase;
+
+    fn test(index: usize) -> Testcase {
+        testcase(index, &index.to_string(), &index.to_string())
+    }
+
+    #[test]
+    fn tests_are_revealed_one_at_a_time() {
+        let testcases = [test(1), test(2), test(3)];
+        let tests: Vec<&Testcase> = testcases.iter().collect();
+        let mut progress = ReverseProgress {
+            revealed: vec![2],
+            ..Default::default()
+        };
+        assert_eq!(progress.reveal_next(&tests).map(|test| test.index), Some(1));
+        progress.guess("print the input");
+        assert_eq!(progress.reveal_next(&tests).map(|test| test.index), Some(3));
+        assert!(progress.reveal_next(&tests).is_none());
+        assert_eq!(progress.guesses[0].num_revealed, 2);
+
+        let revealed: Vec<usize> =
+            progress.revealed_tests(&tests[1..]).iter().map(|test| test.index).collect();
+        assert_eq!(revealed, vec![2, 3]);
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!("".parse::<ReverseCommand>().unwrap(), ReverseCommand::Next);
+        assert_eq!(" R ".parse::<ReverseCommand>().unwrap(), ReverseCommand::Run);
+        assert_eq!(
+            "g  double every digit ".parse::<ReverseCommand>().unwrap(),
+            ReverseCommand::Guess("double every digit".to_string())
+        );
+        assert!("guess".parse::<ReverseCommand>().is_err());
+        assert!("run now".parse::<ReverseCommand>().is_err());
+        assert!("solve".parse::<ReverseCommand>().is_err());
+    }
+}
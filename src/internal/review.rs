@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::RunRecord;
+
+/// Clashes that have been reviewed successfully this many days apart are
+/// considered learned and no longer scheduled.
+const MAX_INTERVAL_DAYS: u64 = 32;
+
+/// When a clash that was failed or solved slowly should be practiced again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewItem {
+    /// Day (since the unix epoch, in UTC) the review is due.
+    pub due_day: u64,
+    /// Days between the last attempt and the due day.
+    pub interval_days: u64,
+    /// Number of days on which the clash was attempted but not solved in time.
+    pub lapses: usize,
+}
+
+/// Schedules reviews of the clashes in the history with a simple spaced
+/// repetition scheme (Leitner boxes). The runs of each clash are grouped by
+/// day, and a day counts as good if a run solved the clash in at most
+/// `slow` after selecting it (runs with an unknown solve time count as fast).
+///
+/// A bad day schedules a review for the next day. A good day doubles the
+/// interval to the next review, and once it grows past a month the clash is
+/// learned. Clashes that have only had good days are never scheduled.
+pub fn review_schedule(records: &[RunRecord], slow: Duration) -> BTreeMap<String, ReviewItem> {
+    let mut days = BTreeMap::<&str, BTreeMap<u64, bool>>::new();
+    for record in records {
+        let good = record.is_solved() && !record.solve_time.is_some_and(|secs| secs > slow.as_secs());
+        let day = days.entry(&record.handle).or_default().entry(record.timestamp / 86400).or_default();
+        *day |= good;
+    }
+
+    let mut schedule = BTreeMap::new();
+    for (handle, days) in days {
+        let mut item: Option<ReviewItem> = None;
+        for (day, good) in days {
+            item = match (item, good) {
+                (None, true) => None,
+                (item, false) => Some(ReviewItem {
+                    due_day: day + 1,
+                    interval_days: 1,
+                    lapses: item.map_or(0, |item| item.lapses) + 1,
+                }),
+                (Some(item), true) => {
+                    let interval_days = item.interval_days * 2;
+                    (interval_days <= MAX_INTERVAL_DAYS).then_some(ReviewItem {
+                        due_day: day + interval_days,
+                        interval_days,
+                        lapses: item.lapses,
+                    })
+                }
+            };
+        }
+        if let Some(item) = item {
+            schedule.insert(handle.to_string(), item);
+        }
+    }
+    schedule
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(handle: &str, day: u64, num_passed: usize, solve_time: Option<u64>) -> RunRecord {
+        RunRecord {
+            handle: handle.to_string(),
+            timestamp: day * 86400 + 3600,
+            num_passed,
+            num_tests: 3,
+            solve_time,
+            source_size: None,
+            language: None,
+            clash_hash: None,
+            stale: false,
+            failures: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn failed_and_slow_clashes_get_scheduled() {
+        let slow = Duration::from_secs(600);
+        let records = vec![
+            // Failed, then solved on the same day: still a lapse
+            run("failed", 10, 1, Some(100)),
+            run("failed", 11, 3, Some(100)),
+            run("failed", 13, 3, Some(100)),
+            run("slow", 10, 3, Some(900)),
+            run("fast", 10, 1, None),
+            run("fast", 10, 3, Some(300)),
+        ];
+        let schedule = review_schedule(&records, slow);
+        assert_eq!(schedule.len(), 2);
+        let failed = &schedule["failed"];
+        assert_eq!((failed.due_day, failed.interval_days, failed.lapses), (17, 4, 1));
+        let slow_item = &schedule["slow"];
+        assert_eq!((slow_item.due_day, slow_item.interval_days, slow_item.lapses), (11, 1, 1));
+    }
+
+    #[test]
+    fn lapses_reset_the_interval_until_the_clash_is_learned() {
+        let slow = Duration::from_secs(600);
+        let mut records = vec![
+            run("abc", 0, 0, None),
+            run("abc", 1, 3, None),
+            run("abc", 3, 0, None),
+        ];
+        assert_eq!(review_schedule(&records, slow)["abc"].due_day, 4);
+        assert_eq!(review_schedule(&records, slow)["abc"].lapses, 2);
+        // Every good review doubles the interval, up to 32 days
+        let mut day = 3;
+        for interval in [1, 2, 4, 8, 16] {
+            day += interval;
+            records.push(run("abc", day, 3, Some(60)));
+        }
+        assert_eq!(review_schedule(&records, slow)["abc"].interval_days, 32);
+        records.push(run("abc", day + 32, 3, Some(60)));
+        assert!(review_schedule(&records, slow).is_empty());
+    }
+}
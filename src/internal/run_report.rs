@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use clashlib::clash::{self, Constraint, Testcase};
+use clashlib::diff::FailurePattern;
+use clashlib::solution::{FailureCategory, TestResult, TestResultReport};
+
+use super::{format_duration, FailureClusters, Numbering, OutputStyle, RunReport, TestcaseReport};
+
+/// Makes a command line for running a testcase by hand.
+pub type ReproductionLine<'a> = Box<dyn Fn(&Testcase) -> Result<String> + 'a>;
+
+/// The constraints of the clash, for the hints about timeouts. They are only
+/// read once a testcase times out.
+pub struct ClashConstraints<'a> {
+    read: Box<dyn Fn() -> Vec<Constraint> + 'a>,
+    constraints: Option<Vec<Constraint>>,
+}
+
+impl<'a> ClashConstraints<'a> {
+    pub fn new(read: impl Fn() -> Vec<Constraint> + 'a) -> Self {
+        ClashConstraints {
+            read: Box::new(read),
+            constraints: None,
+        }
+    }
+
+    fn get(&mut self) -> &[Constraint] {
+        self.constraints.get_or_insert_with(&self.read)
+    }
+}
+
+/// How a run of the testcases went, for [RunReporter::finish].
+pub struct RunSummary<'a> {
+    pub handle: String,
+    /// Every testcase of the clash, including the ones that were not picked.
+    pub all_testcases: &'a [Testcase],
+    /// Number of testcases that were picked to run.
+    pub num_tests: usize,
+    pub passed_indices: &'a [usize],
+    pub run_indices: &'a [usize],
+    pub failures: &'a BTreeMap<FailureCategory, usize>,
+    /// The --suite-timeout, if it stopped the run early.
+    pub suite_timed_out: Option<Duration>,
+    pub stale: bool,
+}
+
+/// Where `coctus run` reports the results: as text for people, or as JSON
+/// for editors and other programs.
+pub trait RunReporter {
+    /// Called with every testcase that was run in the order of the clash.
+    /// `duration` is the time it took, if it was measured.
+    fn testcase(
+        &mut self,
+        testcase: &Testcase,
+        test_result: &TestResult,
+        duration: Option<Duration>,
+    ) -> Result<()>;
+
+    /// Called once after the last testcase.
+    fn finish(&mut self, summary: &RunSummary) -> Result<()>;
+}
+
+/// The colored results, diffs and hints of `coctus run`.
+pub struct TextReporter<'a> {
+    pub ostyle: &'a OutputStyle,
+    /// Show the diff of every wrong output, also ones that repeat an earlier
+    /// failure.
+    pub all_diffs: bool,
+    pub constraints: ClashConstraints<'a>,
+    /// Line for running a crashed testcase by hand, if there is a command to
+    /// put in it.
+    pub reproduction_line: Option<ReproductionLine<'a>>,
+    pub failure_clusters: FailureClusters,
+}
+
+impl RunReporter for TextReporter<'_> {
+    fn testcase(&mut self, testcase: &Testcase, test_result: &TestResult, _: Option<Duration>) -> Result<()> {
+        let ostyle = self.ostyle;
+        let repeated = match test_result {
+            TestResult::WrongOutput { stdout, .. } => {
+                let pattern = FailurePattern::of(&testcase.test_out, stdout);
+                let label = ostyle.numbering.label(testcase);
+                self.failure_clusters.add(label, pattern).filter(|_| !self.all_diffs)
+            }
+            _ => None,
+        };
+        match repeated {
+            Some(cluster) => ostyle.print_repeated_failure(testcase, test_result, cluster),
+            None => ostyle.print_result(testcase, test_result),
+        }
+        if let TestResult::Timeout { .. } = test_result {
+            ostyle.print_timeout_hint(testcase, self.constraints.get());
+        }
+        let crashed = matches!(test_result, TestResult::RuntimeError { .. } | TestResult::UnableToRun { .. });
+        if let (true, Some(reproduction_line)) = (crashed, &self.reproduction_line) {
+            println!(
+                "{} {}",
+                ostyle.secondary_title.paint("Reproduce with:"),
+                reproduction_line(testcase)?
+            );
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, summary: &RunSummary) -> Result<()> {
+        let ostyle = self.ostyle;
+        let pairs = clash::pair_validators(summary.all_testcases);
+        ostyle.print_pair_summary(summary.all_testcases, &pairs, |index| {
+            summary.run_indices.contains(&index).then(|| summary.passed_indices.contains(&index))
+        });
+        println!("{}/{} tests passed", summary.passed_indices.len(), summary.num_tests);
+        ostyle.print_failure_counts(summary.failures);
+        ostyle.print_failure_patterns(self.failure_clusters.repeated());
+
+        if let Some(budget) = summary.suite_timed_out {
+            println!(
+                "{} Suite timeout of {} exceeded, {} tests were not run",
+                ostyle.error.paint("ABORTED"),
+                format_duration(budget),
+                summary.num_tests - summary.run_indices.len()
+            );
+        }
+        if summary.stale {
+            println!(
+                "{} The clash changed while the tests were running, so the results may mix testcases of \
+                two versions of it. Run the tests again.",
+                ostyle.failure.paint("STALE")
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Collects the results into a [RunReport] that is printed at the end.
+pub struct JsonReporter {
+    pub numbering: Numbering,
+    pub shuffle_seed: Option<u64>,
+    testcases: Vec<TestcaseReport>,
+}
+
+impl JsonReporter {
+    pub fn new(numbering: Numbering, shuffle_seed: Option<u64>) -> Self {
+        JsonReporter {
+            numbering,
+            shuffle_seed,
+            testcases: Vec::new(),
+        }
+    }
+
+    fn report(&mut self, summary: &RunSummary) -> RunReport {
+        RunReport {
+            handle: summary.handle.clone(),
+            passed: summary.passed_indices.len(),
+            total: summary.num_tests,
+            failures: summary.failures.clone(),
+            shuffle_seed: self.shuffle_seed,
+            suite_timed_out: summary.suite_timed_out.is_some(),
+            stale: summary.stale,
+            testcases: std::mem::take(&mut self.testcases),
+        }
+    }
+}
+
+impl RunReporter for JsonReporter {
+    fn testcase(
+        &mut self,
+        testcase: &Testcase,
+        test_result: &TestResult,
+        duration: Option<Duration>,
+    ) -> Result<()> {
+        self.testcases.push(TestcaseReport {
+            label: self.numbering.label(testcase),
+            index: testcase.index,
+            title: testcase.title.clone(),
+            is_validator: testcase.is_validator,
+            duration_ms: duration.map(|duration| duration.as_secs_f64() * 1000.0),
+            result: TestResultReport::new(testcase, test_result),
+        });
+        Ok(())
+    }
+
+    fn finish(&mut self, summary: &RunSummary) -> Result<()> {
+        println!("{}", serde_json::to_string_pretty(&self.report(summary))?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::test_helper::testcase;
+
+    #[test]
+    fn json_report_lists_the_testcases() {
+        let testcases = vec![testcase(1, "", "1"), testcase(2, "", "2")];
+        let wrong = TestResult::WrongOutput {
+            stdout: "3".to_string(),
+            stderr: String::new(),
+            merged: Vec::new(),
+            invalid_utf8: 0,
+        };
+        let mut reporter = JsonReporter::new(Numbering::Flat, None);
+        reporter
+            .testcase(&testcases[0], &TestResult::Success, Some(Duration::from_millis(12)))
+            .unwrap();
+        reporter.testcase(&testcases[1], &wrong, None).unwrap();
+
+        let failures = BTreeMap::from([(FailureCategory::WrongAnswer, 1)]);
+        let summary = RunSummary {
+            handle: "abc".to_string(),
+            all_testcases: &testcases,
+            num_tests: 2,
+            passed_indices: &[1],
+            run_indices: &[1, 2],
+            failures: &failures,
+            suite_timed_out: None,
+            stale: false,
+        };
+        let json = serde_json::to_value(reporter.report(&summary)).unwrap();
+        assert_eq!(json["passed"], 1);
+        assert_eq!(json["failures"]["wrong-answer"], 1);
+        assert_eq!(json["testcases"][0]["label"], "#1 Test 1");
+        assert_eq!(json["testcases"][0]["duration_ms"], 12.0);
+        assert_eq!(json["testcases"][1]["result"], "wrong-output");
+        assert_eq!(json["testcases"][1]["stdout"], "3");
+    }
+}
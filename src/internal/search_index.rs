@@ -0,0 +1,239 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::Result;
+use clashlib::clash::Clash;
+use serde::{Deserialize, Serialize};
+
+use super::clash_files;
+
+/// Words and other details of the locally stored clashes for `coctus search`
+/// and `coctus list`. Cached in the data directory so that they only have to
+/// read the clashes that changed since the previous time.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchIndex {
+    clashes: BTreeMap<String, IndexedClash>,
+}
+
+/// What [SearchIndex::update] found.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct IndexUpdate {
+    /// Whether the index changed and should be saved.
+    pub changed: bool,
+    /// Handles of the stored files that are not valid clashes.
+    pub unreadable: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedClash {
+    /// Modification time of the clash file in milliseconds since the unix
+    /// epoch, for telling if the entry is stale.
+    modified: u64,
+    pub title: String,
+    pub modes: Vec<String>,
+    /// Guessed language of the statement, see
+    /// [Clash::statement_language]. An index saved before this was added
+    /// fails to load and is rebuilt.
+    pub statement_language: Option<String>,
+    /// Number of words in the statement, see
+    /// [ClashMetadata::statement_words](clashlib::clash::ClashMetadata).
+    pub statement_words: usize,
+    reading_time_secs: u64,
+    /// Distinct lowercase words of the title.
+    title_words: BTreeSet<String>,
+    /// Distinct lowercase words of the statement, the input and output
+    /// descriptions and the constraints.
+    words: BTreeSet<String>,
+}
+
+impl IndexedClash {
+    fn new(clash: &Clash, modified: u64) -> Self {
+        let text = [
+            clash.goal(),
+            Some(clash.statement()),
+            clash.rules(),
+            Some(clash.input_description()),
+            Some(clash.output_description()),
+            clash.constraints(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("\n");
+        let meta = clash.metadata();
+        IndexedClash {
+            modified,
+            title: clash.title().to_string(),
+            modes: meta.modes.iter().map(ToString::to_string).collect(),
+            statement_language: meta.statement_language.map(str::to_owned),
+            statement_words: meta.statement_words,
+            reading_time_secs: meta.reading_time_secs,
+            title_words: words(clash.title()),
+            words: words(&text),
+        }
+    }
+
+    /// Estimated time it takes to read the statement.
+    pub fn reading_time(&self) -> Duration {
+        Duration::from_secs(self.reading_time_secs)
+    }
+
+    /// How well the clash matches the words of a query: 0 if any of them is
+    /// in neither the title nor the statement, more the more of them are in
+    /// the title. Query words match the words they are a prefix of, so "fib"
+    /// finds "Fibonacci".
+    fn score(&self, query: &BTreeSet<String>) -> usize {
+        let mut score = 0;
+        for word in query {
+            if has_prefix(&self.title_words, word) {
+                score += 2;
+            } else if has_prefix(&self.words, word) {
+                score += 1;
+            } else {
+                return 0
+            }
+        }
+        score
+    }
+}
+
+impl SearchIndex {
+    /// A missing or unreadable index is simply rebuilt, so it loads as empty.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Indexes the clashes in `clash_dir` that changed since they were last
+    /// indexed and forgets the ones that are gone. Files that are not valid
+    /// clashes are left out.
+    pub fn update(&mut self, clash_dir: &Path) -> Result<IndexUpdate> {
+        let mut changed = false;
+        let mut unreadable = Vec::new();
+        let mut seen = BTreeSet::new();
+        for (handle, path) in clash_files(clash_dir)? {
+            let handle = handle.as_str();
+            let modified = std::fs::metadata(&path)?
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+            seen.insert(handle.to_string());
+            if self.clashes.get(handle).is_some_and(|indexed| indexed.modified == modified) {
+                continue
+            }
+            let clash = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|contents| serde_json::from_str::<Clash>(&contents).ok());
+            // Invalid files get read again every time but they don't change the index
+            let previous = match clash {
+                Some(clash) => self.clashes.insert(handle.to_string(), IndexedClash::new(&clash, modified)),
+                None => {
+                    unreadable.push(handle.to_string());
+                    self.clashes.remove(handle)
+                }
+            };
+            changed |= previous.is_some() || self.clashes.contains_key(handle);
+        }
+        let num_indexed = self.clashes.len();
+        self.clashes.retain(|handle, _| seen.contains(handle));
+        Ok(IndexUpdate {
+            changed: changed || self.clashes.len() != num_indexed,
+            unreadable,
+        })
+    }
+
+    /// Every indexed clash by handle.
+    pub fn clashes(&self) -> impl Iterator<Item = (&str, &IndexedClash)> {
+        self.clashes.iter().map(|(handle, clash)| (handle.as_str(), clash))
+    }
+
+    /// The clashes that have every word of `query` in their title or
+    /// statement, the ones with more of the words in the title first.
+    pub fn search(&self, query: &str) -> Vec<(&str, &IndexedClash)> {
+        let query = words(query);
+        if query.is_empty() {
+            return Vec::new()
+        }
+        let mut hits: Vec<(usize, &str, &IndexedClash)> = self
+            .clashes
+            .iter()
+            .map(|(handle, clash)| (clash.score(&query), handle.as_str(), clash))
+            .filter(|(score, _, _)| *score > 0)
+            .collect();
+        hits.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+        hits.into_iter().map(|(_, handle, clash)| (handle, clash)).collect()
+    }
+}
+
+fn words(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn has_prefix(words: &BTreeSet<String>, prefix: &str) -> bool {
+    words.range(prefix.to_string()..).next().is_some_and(|word| word.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_updates_with_the_stored_clashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = |name: &str, handle: &str| {
+            let fixture = std::fs::read_to_string(format!("fixtures/puzzles/{name}.json")).unwrap();
+            std::fs::write(dir.path().join(format!("{handle}.json")), fixture).unwrap();
+        };
+        store("stub_and_solution_tester", "abc");
+        store("classic_puzzle_sections", "def");
+        std::fs::write(dir.path().join("broken.json"), "{").unwrap();
+
+        let mut index = SearchIndex::default();
+        let update = index.update(dir.path()).unwrap();
+        assert_eq!((update.changed, update.unreadable), (true, vec!["broken".to_string()]));
+        assert!(!index.update(dir.path()).unwrap().changed);
+
+        let handles = |query: &str| index.search(query).iter().map(|(handle, _)| *handle).collect::<Vec<_>>();
+        assert_eq!(handles("LARG numb"), vec!["def"]);
+        assert_eq!(handles("asdf"), vec!["abc"]);
+        assert_eq!(handles("largest asdf"), Vec::<&str>::new());
+        let (_, boggus) = index.search("boggus")[0];
+        assert_eq!(boggus.title, "Boggus test");
+        let fixture = std::fs::read_to_string("fixtures/puzzles/stub_and_solution_tester.json").unwrap();
+        let meta = serde_json::from_str::<Clash>(&fixture).unwrap().metadata();
+        assert_eq!(
+            (boggus.statement_words, boggus.reading_time()),
+            (meta.statement_words, meta.reading_time())
+        );
+
+        std::fs::remove_file(dir.path().join("def.json")).unwrap();
+        assert!(index.update(dir.path()).unwrap().changed);
+        assert!(index.search("largest").is_empty());
+    }
+
+    #[test]
+    fn title_matches_come_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixture = std::fs::read_to_string("fixtures/puzzles/classic_puzzle_sections.json").unwrap();
+        std::fs::write(dir.path().join("title.json"), &fixture).unwrap();
+        let statement_only = fixture.replace("\"Largest number\"", "\"Biggest value\"");
+        std::fs::write(dir.path().join("statement.json"), statement_only).unwrap();
+
+        let mut index = SearchIndex::default();
+        index.update(dir.path()).unwrap();
+        let hits: Vec<&str> = index.search("largest").iter().map(|(handle, _)| *handle).collect();
+        assert_eq!(hits, vec!["title", "statement"]);
+    }
+}
@@ -0,0 +1,40 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// State of an auto-advance practice session that spans several invocations
+/// of `coctus run --auto-advance`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AutoAdvanceSession {
+    /// Seconds since the unix epoch when the session started.
+    pub started_at: u64,
+    /// Number of clashes solved during the session.
+    pub clashes_solved: u32,
+}
+
+impl AutoAdvanceSession {
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        json_file::load(path, "session")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+
+    pub fn starting_at(time: SystemTime) -> Self {
+        let started_at = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        Self {
+            started_at,
+            clashes_solved: 0,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        let started_at = UNIX_EPOCH + Duration::from_secs(self.started_at);
+        SystemTime::now().duration_since(started_at).unwrap_or_default()
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+/// The contents of a clash file at the moment a command loaded it. Running a
+/// test suite can take a while, and if the clash gets re-fetched in the
+/// meantime the results may mix the testcases of two versions of the clash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClashSnapshot {
+    path: PathBuf,
+    hash: String,
+}
+
+impl ClashSnapshot {
+    pub fn new(path: &Path, contents: &[u8]) -> Self {
+        ClashSnapshot {
+            path: path.to_owned(),
+            hash: content_hash(contents),
+        }
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// Whether the clash file has changed (or disappeared) since the snapshot
+    /// was taken.
+    pub fn is_stale(&self) -> bool {
+        match std::fs::read(&self.path) {
+            Ok(contents) => content_hash(&contents) != self.hash,
+            Err(_) => true,
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash of `bytes` as 16 hex digits. Unlike the hashers of the
+/// standard library it is guaranteed to stay the same between Rust versions,
+/// so the hashes can be stored.
+pub fn content_hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_hashes() {
+        assert_eq!(content_hash(b""), "cbf29ce484222325");
+        assert_eq!(content_hash(b"a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn snapshot_goes_stale_when_the_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clash.json");
+        std::fs::write(&path, "{}").unwrap();
+        let snapshot = ClashSnapshot::new(&path, b"{}");
+        assert!(!snapshot.is_stale());
+        std::fs::write(&path, "{ }").unwrap();
+        assert!(snapshot.is_stale());
+        std::fs::remove_file(&path).unwrap();
+        assert!(snapshot.is_stale());
+    }
+}
@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use clashlib::clash::PublicHandle;
+
+/// Where the files of coctus are kept. Normally everything is in the data
+/// dir of the user, but the clash collection (the clashes and their tags)
+/// can also come from a shared directory, such as a network drive of a
+/// practice group. The shared collection is read-only: everything that is
+/// written (the selected clash, the history, solutions, ...) still goes in
+/// the data dir of each user.
+#[derive(Debug, Clone)]
+pub struct Storage {
+    user_dir: PathBuf,
+    shared_dir: Option<PathBuf>,
+}
+
+impl Storage {
+    pub fn new(user_dir: &Path, shared_dir: Option<&Path>) -> Self {
+        Storage {
+            user_dir: user_dir.to_owned(),
+            shared_dir: shared_dir.map(Path::to_owned),
+        }
+    }
+
+    /// Directory of the clash collection.
+    pub fn collection_dir(&self) -> &Path {
+        self.shared_dir.as_deref().unwrap_or(&self.user_dir)
+    }
+
+    /// Directory of the clash files in the collection.
+    pub fn clash_dir(&self) -> PathBuf {
+        self.collection_dir().join("clashes")
+    }
+
+    /// Handles of the stored clashes in order. Files in the clash dir that
+    /// are not named after a handle are left out.
+    pub fn stored_handles(&self) -> Result<impl Iterator<Item = PublicHandle>> {
+        let clash_dir = self.clash_dir();
+        let files = clash_files(&clash_dir).with_context(|| {
+            format!("No clashes stored in {:?} (run `coctus init` to get started)", clash_dir)
+        })?;
+        Ok(files.filter_map(|(name, _)| PublicHandle::from_str(&name).ok()))
+    }
+
+    /// Directory of the files of the user.
+    pub fn user_dir(&self) -> &Path {
+        &self.user_dir
+    }
+
+    pub fn is_shared(&self) -> bool {
+        self.shared_dir.is_some()
+    }
+
+    /// Fails if the clash collection can't be modified. `action` describes
+    /// the modification, like "fetch clashes".
+    pub fn check_writable(&self, action: &str) -> Result<()> {
+        match &self.shared_dir {
+            Some(shared_dir) => Err(anyhow!(
+                "Unable to {action}: the clash collection in {:?} is shared and read-only \
+                 (set with shared_dir in the config file or COCTUS_SHARED_DIR)",
+                shared_dir
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The `.json` files in `dir` sorted by name, with the name of each file
+/// without the extension.
+pub fn clash_files(dir: &Path) -> Result<impl Iterator<Item = (String, PathBuf)>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Unable to read {dir:?}"))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                files.push((name.to_owned(), path.clone()));
+            }
+        }
+    }
+    files.sort();
+    Ok(files.into_iter())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_collection_is_read_only() {
+        let storage = Storage::new(Path::new("/home/me/coctus"), Some(Path::new("/mnt/club")));
+        assert_eq!(storage.collection_dir(), Path::new("/mnt/club"));
+        assert_eq!(storage.user_dir(), Path::new("/home/me/coctus"));
+        let err = storage.check_writable("fetch clashes").unwrap_err();
+        assert!(err.to_string().starts_with("Unable to fetch clashes"));
+
+        let storage = Storage::new(Path::new("/home/me/coctus"), None);
+        assert_eq!(storage.collection_dir(), storage.user_dir());
+        assert!(storage.check_writable("fetch clashes").is_ok());
+    }
+
+    #[test]
+    fn stored_handles_come_from_the_clash_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(dir.path(), None);
+        assert!(storage.stored_handles().is_err());
+        std::fs::create_dir_all(storage.clash_dir()).unwrap();
+        for name in ["def.json", "abc.json", "notes.json", "fed.txt"] {
+            std::fs::write(storage.clash_dir().join(name), "{}").unwrap();
+        }
+        let handles: Vec<String> =
+            storage.stored_handles().unwrap().map(|handle| handle.to_string()).collect();
+        assert_eq!(handles, ["abc", "def"]);
+    }
+}
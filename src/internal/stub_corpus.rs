@@ -0,0 +1,195 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clashlib::clash::Clash;
+use clashlib::stub::{self, Diagnostic, Severity};
+
+use super::clash_files;
+
+/// Kinds of the parser problems of every clash that has any, keyed by the
+/// handle. Checked in to notice when a change to the parser breaks a stub
+/// generator that used to work.
+pub type ParseExpectations = BTreeMap<String, BTreeSet<String>>;
+
+/// Kind of the problem of a clash file that is not valid JSON (or not a
+/// clash), the details of the error are in the message.
+const UNREADABLE_KIND: &str = "unreadable clash file";
+
+/// What the stub generator parser made of a directory of clashes, for
+/// `coctus parse-all-stubs`.
+#[derive(Debug, Default)]
+pub struct ParseReport {
+    /// Number of clashes that have a stub generator, or that could not be
+    /// read at all.
+    pub clashes: usize,
+    /// Handles and messages of the problems, grouped by their kind.
+    by_kind: BTreeMap<String, Vec<(String, String)>>,
+    by_handle: ParseExpectations,
+}
+
+impl ParseReport {
+    /// Parses the stub generator of every clash in `clash_dir`. Generators
+    /// that make the parser panic are reported as errors, but the panic
+    /// message still goes through the panic hook. Clash files that can't be
+    /// read are errors of their own kind.
+    pub fn of_dir(clash_dir: &Path) -> Result<Self> {
+        let mut report = ParseReport::default();
+        for (handle, path) in clash_files(clash_dir)? {
+            let clash = std::fs::read_to_string(&path)
+                .with_context(|| format!("Unable to read {path:?}"))
+                .and_then(|contents| {
+                    serde_json::from_str::<Clash>(&contents)
+                        .with_context(|| format!("Unable to parse {path:?}"))
+                });
+            match clash {
+                Ok(clash) => {
+                    if let Some(generator) = clash.stub_generator() {
+                        report.add(&handle, stub::parse_with_diagnostics(generator));
+                    }
+                }
+                Err(err) => report.add_unreadable(&handle, format!("{err:#}")),
+            }
+        }
+        Ok(report)
+    }
+
+    fn add(&mut self, handle: &str, diagnostics: Vec<Diagnostic>) {
+        self.clashes += 1;
+        for diagnostic in diagnostics {
+            let kind = problem_kind(&diagnostic);
+            self.by_handle.entry(handle.to_string()).or_default().insert(kind.clone());
+            self.by_kind.entry(kind).or_default().push((handle.to_string(), diagnostic.message));
+        }
+    }
+
+    fn add_unreadable(&mut self, handle: &str, message: String) {
+        self.clashes += 1;
+        let kind = format!("{}: {UNREADABLE_KIND}", severity_name(Severity::Error));
+        self.by_handle.entry(handle.to_string()).or_default().insert(kind.clone());
+        self.by_kind.entry(kind).or_default().push((handle.to_string(), message));
+    }
+
+    /// Every kind of problem with the handles and the messages of the
+    /// clashes that have it, most common first.
+    pub fn kinds(&self) -> Vec<(&str, &[(String, String)])> {
+        let mut kinds: Vec<_> =
+            self.by_kind.iter().map(|(kind, problems)| (kind.as_str(), &problems[..])).collect();
+        kinds.sort_by_key(|(_, problems)| std::cmp::Reverse(problems.len()));
+        kinds
+    }
+
+    pub fn num_failed(&self) -> usize {
+        let prefix = format!("{}: ", severity_name(Severity::Error));
+        self.by_handle
+            .values()
+            .filter(|kinds| kinds.iter().any(|kind| kind.starts_with(&prefix)))
+            .count()
+    }
+
+    pub fn expectations(&self) -> &ParseExpectations {
+        &self.by_handle
+    }
+
+    /// Problems that are not in `expected`, as (handle, kind) pairs.
+    pub fn regressions<'a>(&'a self, expected: &'a ParseExpectations) -> Vec<(&'a str, &'a str)> {
+        difference(&self.by_handle, expected)
+    }
+
+    /// Expected problems that no longer happen (including the ones of clashes
+    /// that are gone), as (handle, kind) pairs.
+    pub fn fixed<'a>(&'a self, expected: &'a ParseExpectations) -> Vec<(&'a str, &'a str)> {
+        difference(expected, &self.by_handle)
+    }
+}
+
+fn difference<'a>(found: &'a ParseExpectations, expected: &'a ParseExpectations) -> Vec<(&'a str, &'a str)> {
+    let mut pairs = Vec::new();
+    for (handle, kinds) in found {
+        for kind in kinds {
+            if !expected.get(handle).is_some_and(|expected| expected.contains(kind)) {
+                pairs.push((handle.as_str(), kind.as_str()));
+            }
+        }
+    }
+    pairs
+}
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
+
+/// The message of a problem without the details that differ from one clash
+/// to the next: quoted parts become "…" and numbers become "N".
+fn problem_kind(diagnostic: &Diagnostic) -> String {
+    let mut kind = format!("{}: ", severity_name(diagnostic.severity));
+    let mut quote = None;
+    let mut chars = diagnostic.message.chars().peekable();
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) if c == q => {
+                kind.push_str(&format!("…{c}"));
+                quote = None;
+            }
+            Some(_) => {}
+            None if c == '\'' || c == '"' => {
+                kind.push(c);
+                quote = Some(c);
+            }
+            None if c.is_ascii_digit() => {
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+                kind.push('N');
+            }
+            None => kind.push(c),
+        }
+    }
+    kind
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problems_are_grouped_by_kind() {
+        let mut report = ParseReport::default();
+        report.add("a", stub::parse_with_diagnostics("read n:int\nfrob n\n"));
+        report.add("b", stub::parse_with_diagnostics("read n:int\nINPUT\nm: one\nk: two\n"));
+        report.add("c", stub::parse_with_diagnostics("read n:int\nwrite n\n"));
+        report.add("d", stub::parse_with_diagnostics("gameloop\n"));
+
+        assert_eq!(report.clashes, 4);
+        assert_eq!(report.num_failed(), 2);
+        let (kind, problems) = report.kinds()[0];
+        assert_eq!(kind, "warning: INPUT comment for \"…\", which is not read before it");
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[1].1, "INPUT comment for \"k\", which is not read before it");
+        assert!(!report.expectations().contains_key("c"));
+    }
+
+    #[test]
+    fn unreadable_clash_files_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::copy("fixtures/puzzles/stub_and_solution_tester.json", dir.path().join("good.json"))
+            .unwrap();
+        std::fs::write(dir.path().join("broken.json"), "{ not json").unwrap();
+        let report = ParseReport::of_dir(dir.path()).unwrap();
+        assert_eq!(report.clashes, 2);
+        assert_eq!(report.num_failed(), 1);
+        let kinds = &report.expectations()["broken"];
+        assert_eq!(kinds.iter().collect::<Vec<_>>(), ["error: unreadable clash file"]);
+    }
+
+    #[test]
+    fn stored_fixtures_match_the_checked_in_expectations() {
+        let report = ParseReport::of_dir(Path::new("fixtures/puzzles")).unwrap();
+        let contents = std::fs::read_to_string("fixtures/stub_parse_expectations.json").unwrap();
+        let expected: ParseExpectations = serde_json::from_str(&contents).unwrap();
+        assert!(report.clashes > 1);
+        assert_eq!(report.regressions(&expected), vec![]);
+        assert_eq!(report.fixed(&expected), vec![]);
+    }
+}
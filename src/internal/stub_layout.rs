@@ -0,0 +1,57 @@
+use std::path::{Path, PathBuf};
+
+/// Where `coctus generate-stub --all-languages --out-dir DIR` puts the
+/// stubs: one file per language named after the language and with its
+/// source file extension, like `DIR/python.py` and `DIR/cpp.cpp`. Naming the
+/// files after the languages keeps two languages with the same extension
+/// from overwriting each other.
+#[derive(Debug, Clone)]
+pub struct StubLayout {
+    dir: PathBuf,
+}
+
+impl StubLayout {
+    pub fn new(dir: &Path) -> Self {
+        StubLayout {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the stub of `language`, whose source files end in `ext`.
+    pub fn stub_path(&self, language: &str, ext: &str) -> PathBuf {
+        self.dir.join(stub_file_name(language, ext))
+    }
+}
+
+// Language names come from directory names, but the templates dir of the user
+// could in principle have one that is not a safe file name on another system
+fn stub_file_name(language: &str, ext: &str) -> String {
+    let name: String = language
+        .chars()
+        .map(|ch| match ch.is_alphanumeric() || "+#-_".contains(ch) {
+            true => ch,
+            false => '_',
+        })
+        .collect();
+    match ext.trim_start_matches('.') {
+        "" => name,
+        ext => format!("{name}.{ext}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stubs_are_named_after_their_language() {
+        let layout = StubLayout::new(Path::new("stubs"));
+        assert_eq!(layout.stub_path("python", "py"), Path::new("stubs/python.py"));
+        assert_eq!(stub_file_name("c#", ".cs"), "c#.cs");
+        assert_eq!(stub_file_name("my lang/2", ""), "my_lang_2");
+    }
+}
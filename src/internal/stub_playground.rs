@@ -0,0 +1,201 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clashlib::stub::{self, Diagnostic, StubConfig};
+
+/// The stub generator being written in `coctus stub-repl`, which is only
+/// parsed and rendered again when it has changed.
+#[derive(Debug, Default)]
+pub struct StubPlayground {
+    lines: Vec<String>,
+    /// The generator of the previous render.
+    rendered: Option<String>,
+    /// The last stub that could be rendered, new lines are compared to it.
+    last_stub: Option<String>,
+}
+
+/// The result of rendering the generator of a [StubPlayground].
+#[derive(Debug)]
+pub struct Rendering {
+    /// `None` if there were errors.
+    pub stub: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Whether each line of the stub is new since the previous rendering.
+    pub new_lines: Vec<bool>,
+}
+
+/// What can be typed at the prompt of `coctus stub-repl`: a line of the
+/// generator or one of the commands starting with a colon.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlaygroundInput {
+    Line(String),
+    Undo,
+    Clear,
+    Show,
+    Load(PathBuf),
+    Save(PathBuf),
+    Help,
+    Quit,
+}
+
+pub const PLAYGROUND_HELP: &str = "\
+Type the generator one line at a time, the stub is rendered after every line.
+Commands:
+  :undo, :u          remove the last line
+  :clear             start over with an empty generator
+  :show              print the generator with line numbers
+  :load FILE         replace the generator with the contents of FILE
+  :save FILE         write the generator into FILE
+  :help, :h          show this help
+  :quit, :q          stop (or Ctrl-D)";
+
+impl StubPlayground {
+    pub fn generator(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn set_generator(&mut self, generator: &str) {
+        self.lines = generator.lines().map(str::to_owned).collect();
+    }
+
+    pub fn push_line(&mut self, line: &str) {
+        self.lines.push(line.to_owned());
+    }
+
+    /// Removes the last line and returns it.
+    pub fn undo(&mut self) -> Option<String> {
+        self.lines.pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Renders the generator with `config`, or returns `None` if it is the
+    /// same as in the previous render.
+    pub fn render_if_changed(&mut self, config: &StubConfig) -> Option<Rendering> {
+        let generator = self.generator();
+        if self.rendered.as_ref() == Some(&generator) {
+            return None
+        }
+        let (stub, diagnostics) = stub::generate_with_diagnostics(config.clone(), &generator);
+        let new_lines = match &stub {
+            Some(stub) => new_lines(self.last_stub.as_deref(), stub),
+            None => Vec::new(),
+        };
+        self.rendered = Some(generator);
+        if stub.is_some() {
+            self.last_stub = stub.clone();
+        }
+        Some(Rendering {
+            stub,
+            diagnostics,
+            new_lines,
+        })
+    }
+}
+
+/// For every line of `current`, whether it is missing from `previous` (as
+/// many times as it appears in `current`). Every line is new if there is no
+/// previous stub.
+fn new_lines(previous: Option<&str>, current: &str) -> Vec<bool> {
+    let mut previous: Vec<&str> = previous.map_or_else(Vec::new, |previous| previous.lines().collect());
+    current
+        .lines()
+        .map(|line| match previous.iter().position(|old| *old == line) {
+            Some(idx) => {
+                previous.remove(idx);
+                false
+            }
+            None => true,
+        })
+        .collect()
+}
+
+impl std::str::FromStr for PlaygroundInput {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let Some(command) = line.trim().strip_prefix(':') else {
+            return Ok(PlaygroundInput::Line(line.trim_end().to_owned()))
+        };
+        let (name, arg) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+        let arg = arg.trim();
+        let path = || match arg {
+            "" => Err(anyhow!(":{name} needs a file name")),
+            _ => Ok(PathBuf::from(arg)),
+        };
+        let input = match name {
+            "u" | "undo" => PlaygroundInput::Undo,
+            "clear" => PlaygroundInput::Clear,
+            "show" => PlaygroundInput::Show,
+            "load" => return Ok(PlaygroundInput::Load(path()?)),
+            "save" => return Ok(PlaygroundInput::Save(path()?)),
+            "h" | "help" => PlaygroundInput::Help,
+            "q" | "quit" => PlaygroundInput::Quit,
+            _ => return Err(anyhow!("Unknown command :{name} (type :help for the commands)")),
+        };
+        match arg {
+            "" => Ok(input),
+            _ => Err(anyhow!(":{name} takes no arguments")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_changed_generators_are_rendered() {
+        let config = StubConfig::read_from_embedded("python").unwrap();
+        let mut playground = StubPlayground::default();
+        playground.push_line("read n:int");
+        let rendering = playground.render_if_changed(&config).unwrap();
+        assert!(rendering.stub.unwrap().contains("n = int(input())"));
+        assert!(rendering.new_lines.iter().all(|new| *new));
+        assert!(playground.render_if_changed(&config).is_none());
+
+        playground.push_line("write n");
+        let rendering = playground.render_if_changed(&config).unwrap();
+        let stub = rendering.stub.unwrap();
+        let new: Vec<&str> = stub
+            .lines()
+            .zip(&rendering.new_lines)
+            .filter(|(_, new)| **new)
+            .map(|(line, _)| line)
+            .collect();
+        assert_eq!(new, vec!["print(\"n\")"]);
+
+        playground.push_line("");
+        playground.push_line("loop");
+        let rendering = playground.render_if_changed(&config).unwrap();
+        assert!(rendering.stub.is_none());
+        assert!(!rendering.diagnostics.is_empty());
+        assert_eq!(playground.undo().as_deref(), Some("loop"));
+        playground.undo();
+        // Compared to the last stub that rendered, not to the broken one
+        let rendering = playground.render_if_changed(&config).unwrap();
+        assert!(rendering.new_lines.iter().all(|new| !new));
+    }
+
+    #[test]
+    fn parse_playground_input() {
+        assert_eq!(
+            "  read n:int  ".parse::<PlaygroundInput>().unwrap(),
+            PlaygroundInput::Line("  read n:int".to_string())
+        );
+        assert_eq!(":u".parse::<PlaygroundInput>().unwrap(), PlaygroundInput::Undo);
+        assert_eq!(
+            ":save gen.txt".parse::<PlaygroundInput>().unwrap(),
+            PlaygroundInput::Save(PathBuf::from("gen.txt"))
+        );
+        assert!(":load".parse::<PlaygroundInput>().is_err());
+        assert!(":show all".parse::<PlaygroundInput>().is_err());
+        assert!(":frobnicate".parse::<PlaygroundInput>().is_err());
+    }
+}
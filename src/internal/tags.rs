@@ -0,0 +1,66 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// User defined tags of locally stored clashes. Stored as a JSON object that
+/// maps each tag to the handles of the clashes that have it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Tags(BTreeMap<String, BTreeSet<String>>);
+
+impl Tags {
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(json_file::load(path, "tags")?.unwrap_or_default())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+
+    pub fn add(&mut self, tag: &str, handle: &str) {
+        self.0.entry(tag.to_string()).or_default().insert(handle.to_string());
+    }
+
+    /// Makes `handles` the only clashes tagged with `tag`.
+    pub fn replace(&mut self, tag: &str, handles: impl IntoIterator<Item = String>) {
+        let handles: BTreeSet<String> = handles.into_iter().collect();
+        match handles.is_empty() {
+            true => self.0.remove(tag),
+            false => self.0.insert(tag.to_string(), handles),
+        };
+    }
+
+    /// Handles of the clashes tagged with `tag`.
+    pub fn handles(&self, tag: &str) -> impl Iterator<Item = &str> {
+        self.0.get(tag).into_iter().flatten().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tags.json");
+        let mut tags = Tags::load(&path).unwrap();
+        tags.add("group", "abc");
+        tags.add("group", "def");
+        tags.add("group", "abc");
+        tags.save(&path).unwrap();
+
+        let tags = Tags::load(&path).unwrap();
+        assert_eq!(tags.handles("group").collect::<Vec<_>>(), vec!["abc", "def"]);
+        assert_eq!(tags.handles("other").count(), 0);
+
+        let mut tags = tags;
+        tags.replace("group", vec!["ghi".to_string()]);
+        assert_eq!(tags.handles("group").collect::<Vec<_>>(), vec!["ghi"]);
+        tags.replace("group", Vec::new());
+        assert_eq!(tags.handles("group").count(), 0);
+    }
+}
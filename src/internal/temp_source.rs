@@ -0,0 +1,62 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// A solution source file that only exists for the duration of a command.
+///
+/// The file lives in its own temporary directory which gets deleted (along
+/// with anything the solution's build command may have written there) when the
+/// `TempSource` is dropped.
+pub struct TempSource {
+    // Never read, but it has to be kept alive until we're done with the file.
+    _dir: tempfile::TempDir,
+    path: PathBuf,
+}
+
+impl TempSource {
+    /// Writes `source` into a new temporary file named `solution` (with
+    /// extension `ext` if one is given). On unix the file is made executable
+    /// so that scripts with a shebang line can be run directly.
+    pub fn new(source: &str, ext: Option<&str>) -> Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("coctus-")
+            .tempdir()
+            .context("Unable to create a temporary directory for the solution")?;
+        let mut path = dir.path().join("solution");
+        if let Some(ext) = ext {
+            path.set_extension(ext.trim_start_matches('.'));
+        }
+
+        let mut file = std::fs::File::create(&path)
+            .with_context(|| format!("Unable to create temporary solution file {:?}", &path))?;
+        file.write_all(source.as_bytes())?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(0o755))?;
+        }
+
+        Ok(Self { _dir: dir, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_source_is_removed_on_drop() {
+        let source = TempSource::new("echo hello", Some(".sh")).unwrap();
+        let path = source.path().to_owned();
+        assert_eq!(path.extension().unwrap(), "sh");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "echo hello");
+        drop(source);
+        assert!(!path.exists());
+    }
+}
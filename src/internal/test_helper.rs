@@ -0,0 +1,19 @@
+use clashlib::clash::Testcase;
+
+/// The testcase `index` of a clash without validators, with `test_in` as the
+/// input and `test_out` as the expected output. Tests that need some other
+/// title or a validator override the fields:
+/// `Testcase { is_validator: true, ..testcase(2, "", "") }`.
+///
+/// The same as `testcase` in the test helpers of clashlib, which are only
+/// compiled for the tests of the library.
+pub fn testcase(index: usize, test_in: &str, test_out: &str) -> Testcase {
+    Testcase {
+        index,
+        number: index,
+        title: format!("Test {index}"),
+        test_in: test_in.to_string(),
+        test_out: test_out.to_string(),
+        is_validator: false,
+    }
+}
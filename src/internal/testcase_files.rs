@@ -0,0 +1,53 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clashlib::clash::Testcase;
+
+/// The input and the expected output of a testcase written into files of
+/// their own, for feeding them to other tools.
+pub struct TestcaseFiles {
+    pub input: PathBuf,
+    pub expected_output: PathBuf,
+}
+
+impl TestcaseFiles {
+    /// Writes the files of `testcase` into `dir` as `testcase-<index>.in` and
+    /// `testcase-<index>.out`, replacing the ones written earlier. Both are
+    /// written as they are stored, so the input is the same bytes that the
+    /// runner writes to the STDIN of solutions.
+    pub fn write(dir: &Path, testcase: &Testcase) -> Result<Self> {
+        std::fs::create_dir_all(dir).with_context(|| format!("Unable to create directory {:?}", dir))?;
+        let files = Self {
+            input: dir.join(format!("testcase-{}.in", testcase.index)),
+            expected_output: dir.join(format!("testcase-{}.out", testcase.index)),
+        };
+        for (path, contents) in [
+            (&files.input, &testcase.test_in),
+            (&files.expected_output, &testcase.test_out),
+        ] {
+            std::fs::write(path, contents).with_context(|| format!("Unable to write {:?}", path))?;
+        }
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::test_helper::testcase;
+
+    #[test]
+    fn files_are_named_by_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let testcase = Testcase {
+            number: 1,
+            title: "Validator 1".to_string(),
+            is_validator: true,
+            ..testcase(3, "1 2", "3")
+        };
+        let files = TestcaseFiles::write(&dir.path().join("testcases"), &testcase).unwrap();
+        assert!(files.input.ends_with("testcases/testcase-3.in"));
+        assert_eq!(std::fs::read_to_string(&files.input).unwrap(), "1 2");
+        assert_eq!(std::fs::read_to_string(&files.expected_output).unwrap(), "3");
+    }
+}
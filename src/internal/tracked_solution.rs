@@ -0,0 +1,27 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// The solution file created by `coctus start` or `coctus init LANGUAGE`,
+/// remembered so that `run` can work out how to build and run it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackedSolution {
+    /// Absolute path of the solution file.
+    pub path: PathBuf,
+    pub language: String,
+    /// Handle of the clash the solution was started for.
+    pub handle: String,
+}
+
+impl TrackedSolution {
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        json_file::load(path, "tracked solution")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+}
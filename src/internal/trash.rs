@@ -0,0 +1,308 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::journal::Journal;
+use super::snapshot::content_hash;
+
+/// How many operations can be undone. The files of older operations are
+/// deleted for good.
+const MAX_OPERATIONS: usize = 20;
+
+/// Copies of files as they were before a command overwrote or deleted them,
+/// along with a journal of the operations so that `coctus undo` can restore
+/// them.
+pub struct Trash {
+    dir: PathBuf,
+}
+
+/// A command that changed files, as recorded in the journal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Operation {
+    /// Also the name of the directory with the copies of the files.
+    pub id: String,
+    pub description: String,
+    pub files: Vec<TrashedFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashedFile {
+    pub original: PathBuf,
+    /// Where the previous contents of the file are stored, `None` if the
+    /// file didn't exist before the operation.
+    pub copy: Option<PathBuf>,
+    /// Hash of the file right after the operation, `None` if the operation
+    /// deleted it. Files that have been changed since are not touched by
+    /// `undo` so that it never destroys any work of its own.
+    #[serde(default)]
+    pub after: Option<String>,
+}
+
+impl Trash {
+    pub fn new(dir: PathBuf) -> Self {
+        Trash { dir }
+    }
+
+    fn journal(&self) -> Journal {
+        Journal::new(self.dir.join("journal.jsonl"))
+    }
+
+    /// Starts recording an operation. Nothing is written until a file is
+    /// preserved.
+    pub fn begin(&self, description: impl Into<String>) -> Operation {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        Operation {
+            id: format!("{}-{:09}", now.as_secs(), now.subsec_nanos()),
+            description: description.into(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Copies `path` into the trash (or notes that it doesn't exist yet). Call
+    /// this before overwriting or deleting the file.
+    pub fn preserve(&self, operation: &mut Operation, path: &Path) -> Result<()> {
+        // Relative paths would be restored relative to wherever undo is run
+        let path = &std::env::current_dir()?.join(path);
+        if operation.files.iter().any(|file| file.original == *path) {
+            return Ok(())
+        }
+        let copy = match path.exists() {
+            true => {
+                let operation_dir = self.dir.join(&operation.id);
+                std::fs::create_dir_all(&operation_dir)?;
+                let copy = operation_dir.join(operation.files.len().to_string());
+                std::fs::copy(path, &copy)
+                    .with_context(|| format!("Unable to copy {:?} to the trash", path))?;
+                Some(copy)
+            }
+            false => None,
+        };
+        operation.files.push(TrashedFile {
+            original: path.to_owned(),
+            copy,
+            after: None,
+        });
+        Ok(())
+    }
+
+    /// Adds `operation` to the journal, unless it didn't touch any files.
+    /// Call this once the operation has written all of its files.
+    pub fn commit(&self, mut operation: Operation) -> Result<()> {
+        if operation.files.is_empty() {
+            return Ok(())
+        }
+        for file in &mut operation.files {
+            file.after = current_hash(&file.original);
+        }
+        self.journal().append(&operation)?;
+
+        let operations = self.operations()?;
+        if operations.len() > MAX_OPERATIONS {
+            let (expired, kept) = operations.split_at(operations.len() - MAX_OPERATIONS);
+            for operation in expired {
+                let operation_dir = self.dir.join(&operation.id);
+                if operation_dir.exists() {
+                    std::fs::remove_dir_all(operation_dir)?;
+                }
+            }
+            self.journal().rewrite(kept)?;
+        }
+        Ok(())
+    }
+
+    /// Runs `change` as an operation described by `description`. If it
+    /// fails, only the files that it changed before failing are kept so that
+    /// they can still be undone, and the operation is discarded if there are
+    /// none.
+    pub fn record<T>(
+        &self,
+        description: impl Into<String>,
+        change: impl FnOnce(&mut Operation) -> Result<T>,
+    ) -> Result<T> {
+        let mut operation = self.begin(description);
+        let result = change(&mut operation);
+        if result.is_err() {
+            operation.files.retain(|file| {
+                let changed = match &file.copy {
+                    Some(copy) => current_hash(&file.original) != current_hash(copy),
+                    None => file.original.exists(),
+                };
+                if let (false, Some(copy)) = (changed, &file.copy) {
+                    let _ = std::fs::remove_file(copy);
+                }
+                changed
+            });
+            if operation.files.is_empty() {
+                self.discard(operation)?;
+                return result
+            }
+        }
+        self.commit(operation)?;
+        result
+    }
+
+    /// Throws away the copies of an operation that won't be committed.
+    pub fn discard(&self, operation: Operation) -> Result<()> {
+        let operation_dir = self.dir.join(&operation.id);
+        if operation_dir.exists() {
+            std::fs::remove_dir_all(operation_dir)?;
+        }
+        Ok(())
+    }
+
+    /// The operations that can be undone, oldest first.
+    pub fn operations(&self) -> Result<Vec<Operation>> {
+        self.journal().load()
+    }
+
+    /// Restores the files of the most recent operation to what they were
+    /// before it and returns the operation (`None` if there is nothing to
+    /// undo), along with the files that were left alone because they have
+    /// changed since. The copies of those are kept in the trash so that they
+    /// can still be restored by hand.
+    pub fn undo(&self) -> Result<Option<(Operation, Vec<TrashedFile>)>> {
+        let mut operations = self.operations()?;
+        let Some(operation) = operations.pop() else {
+            return Ok(None)
+        };
+        let mut changed_since = Vec::new();
+        for file in operation.files.iter().rev() {
+            if current_hash(&file.original) != file.after {
+                changed_since.push(file.clone());
+                continue
+            }
+            match &file.copy {
+                Some(copy) => {
+                    if let Some(parent) = file.original.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::copy(copy, &file.original)
+                        .with_context(|| format!("Unable to restore {:?}", file.original))?;
+                    std::fs::remove_file(copy)?;
+                }
+                None if file.original.exists() => std::fs::remove_file(&file.original)
+                    .with_context(|| format!("Unable to remove {:?}", file.original))?,
+                None => {}
+            }
+        }
+        let operation_dir = self.dir.join(&operation.id);
+        if operation_dir.exists() && changed_since.iter().all(|file| file.copy.is_none()) {
+            std::fs::remove_dir_all(operation_dir)?;
+        }
+        self.journal().rewrite(&operations)?;
+        Ok(Some((operation, changed_since)))
+    }
+}
+
+fn current_hash(path: &Path) -> Option<String> {
+    std::fs::read(path).ok().map(|contents| content_hash(&contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_restores_overwritten_and_removes_created_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = Trash::new(dir.path().join("trash"));
+        let existing = dir.path().join("existing.json");
+        let created = dir.path().join("created.json");
+        std::fs::write(&existing, "old").unwrap();
+
+        let mut operation = trash.begin("fetch");
+        trash.preserve(&mut operation, &existing).unwrap();
+        trash.preserve(&mut operation, &created).unwrap();
+        std::fs::write(&existing, "new").unwrap();
+        std::fs::write(&created, "new").unwrap();
+        trash.commit(operation).unwrap();
+
+        let (undone, changed_since) = trash.undo().unwrap().unwrap();
+        assert_eq!(undone.description, "fetch");
+        assert!(changed_since.is_empty());
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "old");
+        assert!(!created.exists());
+        assert_eq!(trash.undo().unwrap(), None);
+    }
+
+    #[test]
+    fn only_the_latest_operations_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = Trash::new(dir.path().join("trash"));
+        let path = dir.path().join("file");
+        trash.commit(trash.begin("nothing")).unwrap();
+        for idx in 0..MAX_OPERATIONS + 2 {
+            std::fs::write(&path, idx.to_string()).unwrap();
+            let mut operation = trash.begin(format!("write {idx}"));
+            operation.id = format!("op{idx:03}");
+            trash.preserve(&mut operation, &path).unwrap();
+            trash.commit(operation).unwrap();
+        }
+        let operations = trash.operations().unwrap();
+        assert_eq!(operations.len(), MAX_OPERATIONS);
+        assert_eq!(operations[0].description, "write 2");
+        assert!(!dir.path().join("trash/op000").exists());
+        assert!(dir.path().join("trash/op002").exists());
+    }
+
+    #[test]
+    fn undo_keeps_files_changed_after_the_operation() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = Trash::new(dir.path().join("trash"));
+        let solution = dir.path().join("solution.py");
+
+        let restored = dir.path().join("restored.py");
+        std::fs::write(&solution, "old solution").unwrap();
+        std::fs::write(&restored, "old").unwrap();
+
+        let mut operation = trash.begin("start --force");
+        trash.preserve(&mut operation, &solution).unwrap();
+        trash.preserve(&mut operation, &restored).unwrap();
+        std::fs::write(&solution, "stub").unwrap();
+        std::fs::write(&restored, "new").unwrap();
+        trash.commit(operation).unwrap();
+        std::fs::write(&solution, "stub and a solution").unwrap();
+
+        let (_, changed_since) = trash.undo().unwrap().unwrap();
+        assert_eq!(changed_since.len(), 1);
+        assert_eq!(changed_since[0].original, solution);
+        assert_eq!(std::fs::read_to_string(&solution).unwrap(), "stub and a solution");
+        assert_eq!(std::fs::read_to_string(&restored).unwrap(), "old");
+        // The only copy of the old solution is still there
+        let copy = changed_since[0].copy.as_ref().unwrap();
+        assert_eq!(std::fs::read_to_string(copy).unwrap(), "old solution");
+    }
+
+    #[test]
+    fn failed_operations_keep_only_the_files_they_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = Trash::new(dir.path().join("trash"));
+        let [written, untouched] = ["written", "untouched"].map(|name| dir.path().join(name));
+        std::fs::write(&untouched, "old").unwrap();
+
+        let result: Result<()> = trash.record("nothing written", |operation| {
+            trash.preserve(operation, &untouched)?;
+            Err(anyhow::anyhow!("failed"))
+        });
+        assert!(result.is_err());
+        assert!(trash.operations().unwrap().is_empty());
+        assert_eq!(std::fs::read_dir(dir.path().join("trash")).unwrap().count(), 0);
+
+        let result: Result<()> = trash.record("half written", |operation| {
+            trash.preserve(operation, &written)?;
+            trash.preserve(operation, &untouched)?;
+            std::fs::write(&written, "new")?;
+            Err(anyhow::anyhow!("failed"))
+        });
+        assert!(result.is_err());
+        let operations = trash.operations().unwrap();
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].files.len(), 1);
+        trash.undo().unwrap();
+        assert!(!written.exists());
+        assert_eq!(std::fs::read_to_string(&untouched).unwrap(), "old");
+    }
+}
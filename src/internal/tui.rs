@@ -0,0 +1,266 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use clap::ValueEnum;
+use clashlib::clash::Testcase;
+use clashlib::solution::{FailureCategory, TestResult};
+
+use super::OutputStyle;
+
+/// Where a testcase is at in the current round of `coctus tui`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestStatus {
+    Pending,
+    Running,
+    Passed,
+    Failed(FailureCategory),
+    /// Stopped by the suite deadline, see [TestResult::Aborted].
+    Aborted,
+}
+
+impl TestStatus {
+    pub fn of(testcase: &Testcase, test_result: &TestResult) -> Self {
+        match test_result.failure_category(testcase) {
+            None if test_result.is_success() => TestStatus::Passed,
+            None => TestStatus::Aborted,
+            Some(category) => TestStatus::Failed(category),
+        }
+    }
+}
+
+/// Everything that `coctus tui` shows: the statement on top, the testcases
+/// with their statuses below it and a message at the bottom.
+pub struct TuiScreen {
+    pub title: String,
+    pub statement: String,
+    /// Labels of the testcases along with their statuses.
+    pub testcases: Vec<(String, TestStatus)>,
+    pub message: String,
+}
+
+impl TuiScreen {
+    /// The screen as `height` lines that are at most `width` characters wide
+    /// (not counting colors). The testcases are shown first, the statement
+    /// gets the lines that are left.
+    pub fn render(&self, ostyle: &OutputStyle, width: usize, height: usize) -> Vec<String> {
+        let width = width.max(1);
+        // Title, blank line, separator, blank line and the message
+        let available = height.saturating_sub(5);
+        let num_testcases = self.testcases.len().min(available);
+        let num_statement_lines = available - num_testcases;
+
+        let mut lines = vec![
+            ostyle.title.paint(truncate(&self.title, width)).to_string(),
+            String::new(),
+        ];
+        let mut statement = wrap(&self.statement, width);
+        if statement.len() > num_statement_lines {
+            statement.truncate(num_statement_lines);
+            if let Some(last) = statement.last_mut() {
+                *last = truncate(&format!("{last}…"), width);
+            }
+        }
+        statement.resize(num_statement_lines, String::new());
+        lines.extend(statement);
+        lines.push("─".repeat(width));
+        for (label, status) in self.testcases.iter().take(num_testcases) {
+            let (tag, style) = match status {
+                TestStatus::Pending => ("-".to_string(), ostyle.dim_color),
+                TestStatus::Running => ("RUNNING".to_string(), ostyle.title),
+                TestStatus::Passed => ("PASS".to_string(), ostyle.success),
+                TestStatus::Failed(category) => (format!("FAIL ({category})"), ostyle.failure),
+                TestStatus::Aborted => ("ABORTED".to_string(), ostyle.error),
+            };
+            let tag = truncate(&format!("{tag:<27}"), width);
+            let label = truncate(label, width - tag.chars().count());
+            lines.push(format!("{}{label}", style.paint(tag)));
+        }
+        lines.push(String::new());
+        lines.push(truncate(&self.message, width));
+        lines.truncate(height);
+        lines
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+// Splits overlong lines of `text` into lines of `width` characters
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let chars: Vec<char> = line.trim_end().chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(chars.chunks(width).map(|chunk| chunk.iter().collect::<String>()));
+    }
+    lines
+}
+
+/// Notices when files change by polling their modification times.
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let modified = modified(&path);
+                (path, modified)
+            })
+            .collect();
+        FileWatcher { files }
+    }
+
+    /// Whether any of the files has been modified, created or removed since
+    /// the watcher was created or this was last called.
+    pub fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (path, last_modified) in &mut self.files {
+            let modified = modified(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Width and height of the terminal, from $COLUMNS and $LINES or `stty size`
+/// and 80x24 if neither works.
+pub fn terminal_size() -> (usize, usize) {
+    let from_env = |var: &str| std::env::var(var).ok().and_then(|value| value.parse().ok());
+    if let (Some(width), Some(height)) = (from_env("COLUMNS"), from_env("LINES")) {
+        return (width, height)
+    }
+    let stty_size = std::fs::File::open("/dev/tty").ok().and_then(|tty| {
+        let output = std::process::Command::new("stty").arg("size").stdin(tty).output().ok()?;
+        let output = String::from_utf8(output.stdout).ok()?;
+        let (height, width) = output.trim().split_once(' ')?;
+        Some((width.parse().ok()?, height.parse().ok()?))
+    });
+    stty_size.unwrap_or((80, 24))
+}
+
+/// How `coctus tui` draws attention to a round whose results differ from the
+/// previous round's, for noticing them while looking at the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Notify {
+    /// Ring the terminal bell when all testcases start or stop passing.
+    Bell,
+    /// Show the result of the latest round in the window title.
+    Title,
+}
+
+/// The way the results changed from one round of `coctus tui` to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// Failed before, every testcase passes now.
+    Fixed,
+    /// Every testcase passed before, something fails now.
+    Broke,
+}
+
+impl Transition {
+    /// `None` if nothing changed or this is the first round.
+    pub fn between(previous: Option<bool>, all_passed: bool) -> Option<Self> {
+        match (previous?, all_passed) {
+            (false, true) => Some(Transition::Fixed),
+            (true, false) => Some(Transition::Broke),
+            _ => None,
+        }
+    }
+}
+
+/// Escape sequences that notify about a round that ended with `message`.
+pub fn notification(notify: &[Notify], transition: Option<Transition>, message: &str) -> String {
+    let mut sequences = String::new();
+    if notify.contains(&Notify::Title) {
+        sequences += &window_title(&format!("coctus: {message}"));
+    }
+    if notify.contains(&Notify::Bell) && transition.is_some() {
+        sequences.push('\x07');
+    }
+    sequences
+}
+
+/// Sets the title of the terminal window (OSC 0). Control characters would
+/// end the sequence early, so they are left out.
+fn window_title(title: &str) -> String {
+    let title: String = title.chars().filter(|c| !c.is_control()).collect();
+    format!("\x1b]0;{title}\x07")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen() -> TuiScreen {
+        TuiScreen {
+            title: "=== Some clash ===".to_string(),
+            statement: "A statement that is long enough to wrap\n\nand more".to_string(),
+            testcases: vec![
+                ("#1 Test 1".to_string(), TestStatus::Passed),
+                ("#2 Test 2".to_string(), TestStatus::Failed(FailureCategory::Timeout)),
+                ("#3 Validator 1".to_string(), TestStatus::Running),
+            ],
+            message: "1/3 passed".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_fills_the_screen() {
+        let lines = screen().render(&OutputStyle::plain(), 30, 12);
+        assert_eq!(lines.len(), 12);
+        assert!(lines.iter().all(|line| line.chars().count() <= 30));
+        assert_eq!(lines[2], "A statement that is long enoug");
+        assert_eq!(lines[3], "h to wrap");
+        assert_eq!(lines[7], format!("{:<27}#1 ", "PASS"));
+        assert_eq!(lines[8], "FAIL (timeout)             #2 ");
+        assert_eq!(lines[11], "1/3 passed");
+    }
+
+    #[test]
+    fn testcases_take_precedence_over_the_statement() {
+        let lines = screen().render(&OutputStyle::plain(), 80, 9);
+        assert_eq!(lines.len(), 9);
+        assert_eq!(lines[2], "A statement that is long enough to wrap…");
+        assert_eq!(lines[3], "─".repeat(80));
+        assert!(lines[6].starts_with("RUNNING"));
+    }
+
+    #[test]
+    fn watcher_notices_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("solution.py");
+        let mut watcher = FileWatcher::new(vec![path.clone()]);
+        assert!(!watcher.changed());
+        std::fs::write(&path, "print(1)").unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+        std::fs::remove_file(&path).unwrap();
+        assert!(watcher.changed());
+    }
+
+    #[test]
+    fn bell_only_rings_when_the_results_change() {
+        assert_eq!(Transition::between(None, true), None);
+        assert_eq!(Transition::between(Some(false), true), Some(Transition::Fixed));
+        assert_eq!(Transition::between(Some(true), false), Some(Transition::Broke));
+        assert_eq!(Transition::between(Some(false), false), None);
+
+        let both = [Notify::Bell, Notify::Title];
+        assert_eq!(notification(&both, None, "3/3\npassed"), "\x1b]0;coctus: 3/3passed\x07");
+        assert_eq!(notification(&[Notify::Bell], Some(Transition::Broke), "1/3 passed"), "\x07");
+        assert_eq!(notification(&[], Some(Transition::Fixed), "3/3 passed"), "");
+    }
+}
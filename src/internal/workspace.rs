@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Name of the file that makes a directory the workspace of a clash.
+const WORKSPACE_FILE: &str = "coctus.toml";
+
+/// A directory created by `coctus start` for solving a single clash. Commands
+/// run inside of it (or any of its subdirectories) default to its clash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workspace {
+    /// Handle of the clash the workspace is for.
+    pub handle: String,
+    pub language: String,
+    /// Path of the solution file, relative to the workspace directory.
+    pub solution: PathBuf,
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+impl Workspace {
+    /// Finds the workspace that `path` is in, if any.
+    pub fn find(path: &Path) -> Result<Option<Self>> {
+        match path.ancestors().find(|dir| dir.join(WORKSPACE_FILE).is_file()) {
+            Some(dir) => Ok(Some(Self::load(dir)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(WORKSPACE_FILE);
+        let contents = std::fs::read_to_string(&path)?;
+        let mut workspace: Self =
+            toml::from_str(&contents).with_context(|| format!("Invalid workspace file {:?}", path))?;
+        workspace.dir = dir.to_owned();
+        Ok(workspace)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = self.manifest_path();
+        std::fs::write(&path, toml::to_string(self)?)
+            .with_context(|| format!("Unable to write workspace file {:?}", path))
+    }
+
+    /// Path of the `coctus.toml` file of the workspace.
+    pub fn manifest_path(&self) -> PathBuf {
+        self.dir.join(WORKSPACE_FILE)
+    }
+
+    pub fn solution_path(&self) -> PathBuf {
+        self.dir.join(&self.solution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workspace_is_found_from_subdirectories() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("abc123");
+        std::fs::create_dir_all(dir.join("src/nested")).unwrap();
+        assert_eq!(Workspace::find(&dir).unwrap(), None);
+
+        let workspace = Workspace {
+            handle: "abc123".to_string(),
+            language: "rust".to_string(),
+            solution: PathBuf::from("solution.rs"),
+            dir: dir.clone(),
+        };
+        workspace.save().unwrap();
+        let found = Workspace::find(&dir.join("src/nested")).unwrap().unwrap();
+        assert_eq!(found, workspace);
+        assert_eq!(found.solution_path(), dir.join("solution.rs"));
+    }
+}
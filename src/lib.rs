@@ -1,4 +1,42 @@
+//! Library behind the `coctus` command line tool: deserializing CodinGame
+//! clashes, generating input handling stubs for them, running solutions
+//! against their testcases and comparing the outputs.
+//!
+//! Most users only need the items in [`prelude`]:
+//!
+//! ```
+//! use clashlib::prelude::*;
+//!
+//! let handle: PublicHandle = "682102420fbce0fce95e0ee56095ea2b9924".parse().unwrap();
+//! assert_eq!(handle.to_string(), "682102420fbce0fce95e0ee56095ea2b9924");
+//! ```
+//!
+//! # Stability
+//!
+//! The library follows semantic versioning. Everything that is reachable
+//! and documented is part of the public API, with these exceptions:
+//!
+//! - Enums and structs marked `#[non_exhaustive]` may get new variants or
+//!   fields in minor releases, so match them with a wildcard arm and create
+//!   structs through their constructors.
+//! - Items hidden from the documentation only exist for the `coctus` binary and
+//!   may change at any time.
+//! - The stub templates (and so the exact code that [`stub::generate`]
+//!   produces) are improved in patch releases.
+//!
+//! The `fetch-client`, `runner` and `embedded-templates` features gate the
+//! [`client`] module, the [`solution`] module and the embedded stub
+//! templates respectively. The `json-schema` feature implements
+//! `schemars::JsonSchema` for the types that the `coctus` binary prints as
+//! JSON. They are all enabled by default.
+
 pub mod clash;
+#[cfg(feature = "fetch-client")]
+pub mod client;
+pub mod diff;
+pub mod prelude;
+pub mod scoring;
+#[cfg(feature = "runner")]
 pub mod solution;
 pub mod stub;
 
@@ -1,5 +1,6 @@
 mod internal;
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::Read;
 use std::path::PathBuf;
 use std::process::Command;
@@ -7,13 +8,35 @@ use std::str::FromStr;
 
 use anyhow::{anyhow, Context, Result};
 use clap::ArgMatches;
-use clashlib::clash::{Clash, PublicHandle, Testcase};
+use clashlib::clash::{self, Clash, ClashIdentifier, PublicHandle, TestSelection, Testcase};
+use clashlib::diff::Granularity;
+use clashlib::solution::TestResult;
 use clashlib::stub::StubConfig;
-use clashlib::{solution, stub};
+use clashlib::{client, scoring, solution, stub};
 use directories::ProjectDirs;
-use internal::OutputStyle;
+use internal::{
+    aggregate_stats, clash_files, copy_to_clipboard, file_url, format_clock, format_duration,
+    format_reading_time, format_statement_html, format_utc_timestamp, is_unrelated_solution, notification,
+    parse_duration, resolve_handle_prefix, review_schedule, schema_for_command, terminal_size,
+    write_badge_svg, write_stats_csv, Archive, AutoAdvanceSession, BuildDirs, Calibration, ChangelogEntry,
+    ClashConstraints, ClashSession, ClashSnapshot, ClashStats, Config, ConfigArchive, CustomTestcases,
+    DiffMode, FailureClusters, FileWatcher, GroupStats, History, Journal, JsonReporter, LastRun, Login,
+    Notify, Numbering, Operation, OutputStyle, Overlays, ParseExpectations, ParseReport, PlaygroundInput,
+    Provenance, RememberedRun, RememberedRuns, ReproductionLine, ReverseCommand, ReverseGames, ReviewItem,
+    RunOutcome, RunRecord, RunReporter, RunSummary, SearchIndex, StatementSection, Storage, StubLayout,
+    StubPlayground, StubProblem, StubSummary, Tags, TempSource, TestStatus, TestcaseFiles, TextReporter,
+    TrackedSolution, Transition, Trash, TuiScreen, Workspace, DEFAULT_CONFIG, PLAYGROUND_HELP, REVERSE_HELP,
+    SCHEMA_COMMANDS,
+};
 use rand::seq::IteratorRandom;
 
+/// Inputs of `coctus stress` grow no larger than this (nested loops quickly
+/// make them huge).
+const MAX_STRESS_INPUT_BYTES: usize = 32 << 20;
+
+/// Tag of the clashes that `coctus review --queue` found due for practice.
+const REVIEW_TAG: &str = "review";
+
 fn command_from_argument(cmd_arg: Option<&String>) -> Result<Option<Command>> {
     let cmd = match cmd_arg {
         Some(cmd) => cmd,
@@ -33,6 +56,256 @@ fn command_from_argument(cmd_arg: Option<&String>) -> Result<Option<Command>> {
     }
 }
 
+/// Replaces `placeholder` (such as `{source}`) in `cmd` with the shell quoted
+/// `path`.
+fn expand_path_placeholder(cmd: &str, placeholder: &str, path: &std::path::Path) -> Result<String> {
+    let path = path.to_str().context("File path should be valid UTF-8")?;
+    let quoted_path = shlex::try_quote(path)?;
+    Ok(cmd.replace(placeholder, &quoted_path))
+}
+
+/// Opens `paths` in the editor in $VISUAL (or $EDITOR, or vi if neither is
+/// set) and waits for it to exit.
+fn open_in_editor(paths: &[&std::path::Path]) -> Result<std::process::ExitStatus> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut editor_command = command_from_argument(Some(&editor))?.context("$EDITOR is empty")?;
+    editor_command
+        .args(paths)
+        .status()
+        .with_context(|| format!("Unable to start the editor {editor:?}"))
+}
+
+/// Runs the build command of a solution (if there is one) with
+/// `$COCTUS_BUILD_DIR` set to `build_dir`, in `dir` if it is given. The
+/// output of a failed build goes to stderr to keep stdout clean for reports
+/// like `run --output-format json`.
+fn build_solution(
+    build_command_arg: Option<&String>,
+    build_dir: &std::path::Path,
+    dir: Option<&std::path::Path>,
+) -> Result<()> {
+    if let Some(mut build_command) = command_from_argument(build_command_arg)? {
+        if let Some(dir) = dir {
+            build_command.current_dir(dir);
+        }
+        let build = build_command.env("COCTUS_BUILD_DIR", build_dir).output()?;
+
+        if !build.status.success() {
+            if !build.stderr.is_empty() {
+                eprintln!("Build command STDERR:\n{}", String::from_utf8_lossy(&build.stderr));
+            }
+            if !build.stdout.is_empty() {
+                eprintln!("Build command STDOUT:\n{}", String::from_utf8_lossy(&build.stdout));
+            }
+            return Err(anyhow!("Build failed"))
+        }
+    }
+    Ok(())
+}
+
+/// Returns a shell command line that feeds the input of `testcase` to
+/// `command` so that a failed test can be reproduced by hand. Long inputs
+/// (and all inputs when the command expects an input file) are written to a
+/// file in `build_dir` instead of being inlined, where it stays until the
+/// build directory is cleaned up.
+fn reproduction_line(
+    command: &str,
+    testcase: &Testcase,
+    input_file: bool,
+    build_dir: &std::path::Path,
+) -> Result<String> {
+    let command = &command
+        .replace("{test_index}", &testcase.index.to_string())
+        .replace("{test_title}", &shlex::try_quote(&testcase.title)?);
+    let input = &testcase.test_in;
+    if !input_file && input.len() <= 200 && input.lines().count() <= 5 {
+        return Ok(format!("printf '%s\\n' {} | {}", shlex::try_quote(input)?, command))
+    }
+    let input_path = build_dir.join(format!("test-{}.in", testcase.index));
+    std::fs::write(&input_path, format!("{input}\n"))?;
+    let quoted_path =
+        shlex::try_quote(input_path.to_str().context("Build directory should be valid UTF-8")?)?;
+    if input_file {
+        let command = expand_path_placeholder(command, "{input_file}", &input_path)?;
+        Ok(format!("COCTUS_INPUT_FILE={} {} < {}", quoted_path, command, quoted_path))
+    } else {
+        Ok(format!("{} < {}", command, quoted_path))
+    }
+}
+
+/// Renders the stub generator of `playground` if it changed since the last
+/// time, printing the stub with its new lines highlighted and the problems.
+fn print_stub_rendering(ostyle: &OutputStyle, playground: &mut StubPlayground, config: &StubConfig) {
+    // The parser panics on some invalid generators (the panic becomes an error
+    // diagnostic), the default hook would print it in the middle of the stub
+    let silenced = SilencedPanics::new();
+    let rendering = playground.render_if_changed(config);
+    drop(silenced);
+    let Some(rendering) = rendering else { return };
+
+    if let Some(stub) = &rendering.stub {
+        println!("{}", ostyle.secondary_title.paint("===== STUB ====="));
+        for (line, is_new) in stub.lines().zip(&rendering.new_lines) {
+            match is_new {
+                true => println!("{}", ostyle.diff_green.paint(line)),
+                false => println!("{line}"),
+            }
+        }
+    }
+    for diagnostic in &rendering.diagnostics {
+        let severity = match diagnostic.severity {
+            stub::Severity::Warning => ostyle.failure.paint("warning"),
+            stub::Severity::Error => ostyle.error.paint("error"),
+        };
+        println!("{severity}: {}", diagnostic.message);
+    }
+}
+
+/// Prints the problems with the testcases of a clash (see
+/// [clash::testcase_warnings]) to stderr.
+fn warn_about_testcases(testcases: &[Testcase]) {
+    let ostyle = OutputStyle::from_env(false);
+    for warning in clash::testcase_warnings(testcases) {
+        eprintln!("{} {warning}", ostyle.failure.paint("WARNING"));
+    }
+}
+
+/// Selects the `--option NAME=VALUE` variations of the stub.
+fn set_stub_options(stub_config: &mut StubConfig, args: &ArgMatches) -> Result<()> {
+    for option in args.get_many::<String>("option").into_iter().flatten() {
+        let (name, value) = option
+            .split_once('=')
+            .with_context(|| format!("Invalid stub option {option:?} (expected NAME=VALUE)"))?;
+        stub_config.set_option(name.trim(), value.trim())?;
+    }
+    Ok(())
+}
+
+/// Where `start` and `init LANGUAGE` write the solution file.
+enum SolutionLocation {
+    File(PathBuf),
+    /// The conventionally named file in the directory.
+    Dir(PathBuf),
+    /// A workspace for the clash in the directory.
+    Workspace(PathBuf),
+}
+
+/// Name of the solution files that `coctus start` and `coctus init LANGUAGE`
+/// write for source files with the extension `ext`.
+fn conventional_solution_name(ext: &str) -> String {
+    match ext {
+        "rs" => "main.rs".to_owned(),
+        _ => format!("sol.{ext}"),
+    }
+}
+
+/// The `--overlay` of `addtest` and `import-tests`, which has to work as a
+/// file name.
+fn overlay_name(args: &ArgMatches) -> Result<&str> {
+    let name = args.get_one::<String>("overlay").map_or("local", String::as_str);
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(anyhow!("Invalid overlay name {name:?} (use letters, digits, - and _)"))
+    }
+    Ok(name)
+}
+
+/// Returns true if the environment variable `name` is set to something other
+/// than an empty string or `0`.
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Waits for `duration`, showing a countdown if stdout is a terminal.
+fn take_break(duration: std::time::Duration) -> Result<()> {
+    use std::io::{IsTerminal, Write};
+
+    println!("Take a break! The next clash will be selected in {}", format_duration(duration));
+    let end = std::time::Instant::now() + duration;
+    if !std::io::stdout().is_terminal() {
+        std::thread::sleep(duration);
+        return Ok(())
+    }
+    while let Some(remaining) = end.checked_duration_since(std::time::Instant::now()) {
+        print!(
+            "\r\x1b[K{} remaining",
+            format_duration(std::time::Duration::from_secs(remaining.as_secs()))
+        );
+        std::io::stdout().flush()?;
+        std::thread::sleep(remaining.min(std::time::Duration::from_secs(1)));
+    }
+    println!("\r\x1b[KBreak is over!");
+    Ok(())
+}
+
+/// Replaces the panic hook with one that prints nothing, for as long as it
+/// lives. The default hook is put back on drop, also when returning early.
+struct SilencedPanics;
+
+impl SilencedPanics {
+    fn new() -> Self {
+        std::panic::set_hook(Box::new(|_| {}));
+        SilencedPanics
+    }
+}
+
+impl Drop for SilencedPanics {
+    fn drop(&mut self) {
+        // Taking the hook sets the default one again
+        drop(std::panic::take_hook());
+    }
+}
+
+/// Transient line telling which testcase is running, shown only when stdout
+/// is a terminal. It has to be cleared before anything else is printed.
+struct ProgressLine {
+    enabled: bool,
+    shown: std::cell::Cell<bool>,
+}
+
+impl ProgressLine {
+    /// Keeps the line short enough to not wrap, since only the last line of
+    /// wrapped text could be cleared.
+    const MAX_TITLE_LEN: usize = 60;
+
+    fn new() -> Self {
+        use std::io::IsTerminal;
+
+        ProgressLine {
+            enabled: std::io::stdout().is_terminal(),
+            shown: std::cell::Cell::new(false),
+        }
+    }
+
+    fn show(&self, testcase: &Testcase, numbering: Numbering) {
+        use std::io::Write;
+
+        if !self.enabled {
+            return
+        }
+        let mut title: String = testcase.title.chars().take(Self::MAX_TITLE_LEN).collect();
+        if title.len() < testcase.title.len() {
+            title.push('…');
+        }
+        let label = match numbering {
+            Numbering::Flat => format!("#{} '{}'", testcase.index, title),
+            Numbering::Cg if testcase.is_validator => format!("Validator {} '{}'", testcase.number, title),
+            Numbering::Cg => format!("Test {} '{}'", testcase.number, title),
+        };
+        print!("\r\x1b[KRunning {label}…");
+        // Nothing is lost if the progress line doesn't show up
+        let _ = std::io::stdout().flush();
+        self.shown.set(true);
+    }
+
+    fn clear(&self) {
+        if self.shown.replace(false) {
+            print!("\r\x1b[K");
+        }
+    }
+}
+
 fn cli() -> clap::Command {
     use clap::{arg, value_parser, Command};
 
@@ -41,6 +314,42 @@ fn cli() -> clap::Command {
         .version(clap::crate_version!())
         .subcommand_required(true)
         .arg_required_else_help(true)
+        .subcommand(
+            Command::new("init")
+                .about("Create the data and config directories and a default config file")
+                .arg(arg!([PROGRAMMING_LANGUAGE] "also write the stub of the current clash into a solution file in this language"))
+                .arg(
+                    arg!(--"dir" <DIR> "write the solution file in DIR (which is created if needed) instead of the current directory")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .requires("PROGRAMMING_LANGUAGE")
+                )
+                .arg(
+                    arg!(--"no-track" "only write the solution file, without making it the solution that `coctus run` runs")
+                        .requires("PROGRAMMING_LANGUAGE")
+                )
+                .arg(arg!(--"force" "overwrite the solution file if it already exists").requires("PROGRAMMING_LANGUAGE"))
+                .arg(
+                    arg!(--"option" <NAME_VALUE> "select a variation of the stub, like io=fast (see `coctus generate-stub --help`)")
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append)
+                        .requires("PROGRAMMING_LANGUAGE")
+                )
+                .arg(arg!(--"starter-pack" <URL> "also fetch the clashes listed at URL (same format as fetch --list-url)"))
+                .arg(
+                    arg!(--"tag" <TAG> "tag the clashes of the starter pack with TAG")
+                        .requires("starter-pack")
+                )
+                .after_help(
+                    "Safe to run again: an existing config file is never overwritten, and neither is an existing \
+                    solution file without --force.\
+                    \nThe config file has every option commented out. Command line flags and environment \
+                    variables take precedence over it.\
+                    \nWith a PROGRAMMING_LANGUAGE the stub of the current clash is also written into a solution \
+                    file in the current directory (or --dir) like `coctus start` does, see `coctus start --help`. \
+                    With --no-track the file is only written, and `coctus run` keeps running the solution it ran \
+                    before."
+                )
+        )
         .subcommand(
             Command::new("show")
                 .about("Show clash")
@@ -52,45 +361,170 @@ fn cli() -> clap::Command {
                         .default_missing_value("true")
                 )
                 .arg(
-                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash, or a unique prefix of it")
                         .value_parser(value_parser!(PublicHandle))
                 )
                 .arg(arg!(-'r' --"reverse" "print the clash in reverse mode"))
+                .arg(arg!(--"fetch-missing" "download the clash from codingame.com if it is not stored locally"))
+                .arg(arg!(--"no-example" "do not print the example testcase"))
+                .arg(
+                    arg!(--"prefer-html" "print the HTML version of the statement (like on codingame.com) instead of the plain one when the clash has it")
+                        .conflicts_with_all(["no-example", "reverse", "only-section", "grep", "lint"])
+                )
+                .arg(arg!(--"only-statement" "only print the statement (and the goal and rules of a classic puzzle)"))
+                .arg(arg!(--"only-io-descriptions" "only print the input and output descriptions"))
+                .arg(arg!(--"constraints-only" "only print the constraints"))
+                .arg(
+                    arg!(--"width" <COLUMNS> "cut lines of monospace blocks at COLUMNS characters (0 never cuts them, default: the width of the terminal)")
+                        .value_parser(value_parser!(usize))
+                )
+                .arg(
+                    arg!(--"grep" <PATTERN> "only print the lines that match the regex PATTERN (case-insensitive unless it has uppercase letters) and the lines around them")
+                        .conflicts_with_all(["lint", "reverse", "only-section", "no-example"])
+                )
+                .arg(
+                    arg!(-'C' --"context" <LINES> "how many lines to print around the matches of --grep")
+                        .value_parser(value_parser!(usize))
+                        .default_value("2")
+                        .requires("grep")
+                )
+                .arg(
+                    arg!(--"lint" "list all formatting problems in the statement (and examples that don't match the first testcase) instead of showing it")
+                        .conflicts_with_all(["no-example", "reverse", "only-section"])
+                )
+                .group(
+                    clap::ArgGroup::new("only-section")
+                        .args(["only-statement", "only-io-descriptions", "constraints-only"])
+                        .conflicts_with_all(["no-example", "reverse"])
+                )
         )
         .subcommand(
             Command::new("next")
                 .about("Select next clash")
                 .arg(
-                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash, or a unique prefix of it")
                         .value_parser(value_parser!(PublicHandle))
                         .exclusive(true)
                 )
                 .arg(arg!(-'r' --"reverse" "pick a random clash that has reverse mode"))
                 .arg(arg!(-'s' --"shortest" "pick a random clash that has shortest mode"))
                 .arg(arg!(-'f' --"fastest" "pick a random clash that has fastest mode"))
+                .arg(
+                    arg!(-'t' --"tag" <TAG> "pick a random clash that has been tagged with TAG")
+                        .conflicts_with_all(["reverse", "shortest", "fastest"])
+                )
+                .arg(
+                    arg!(--"statement-lang" <LANG> "pick a random clash whose statement is in LANG (en, fr or es)")
+                        .conflicts_with("tag")
+                )
+                .arg(
+                    arg!(--"max-read-time" <DURATION> "pick a random clash whose statement takes at most DURATION to read")
+                        .value_parser(parse_duration)
+                        .conflicts_with("tag")
+                )
                 .after_help(
                     "Pick a random clash from locally stored clashes when PUBLIC_HANDLE is not given.\
                     \nIf instead flags modes are supplied, it will look for a clash that has at least all of those modes available.\
-                    \nFor example: coctus next --fastest --shortest will return a clash that has BOTH fastest and shortest as options."
+                    \nFor example: coctus next --fastest --shortest will return a clash that has BOTH fastest and shortest as options.\
+                    \nThe language of a statement is guessed from its most common words, so --statement-lang skips \
+                    clashes with statements too short to tell (see the language column of `coctus list`).\
+                    \nReading times are estimated from the number of words in the statement, so for a quick \
+                    practice round try: coctus next --max-read-time 1m"
+                )
+        )
+        .subcommand(
+            Command::new("current")
+                .about("Show or change the selected clash")
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash to select, or a unique prefix of it")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Without PUBLIC_HANDLE the handle of the selected clash is printed. A clash has to be \
+                    stored locally (see `coctus fetch`) before it can be selected.\
+                    \nInside a workspace (see `coctus start`) commands use the clash of the workspace instead \
+                    of the selected one."
                 )
         )
         .subcommand(
             Command::new("run")
                 .about("Test a solution against current clash")
                 .arg(arg!(--"build-command" <COMMAND> "command that compiles the solution"))
-                .arg(arg!(--"command" <COMMAND> "command that executes the solution").required(true))
                 .arg(
-                    arg!(--"timeout" <SECONDS> "how many seconds before execution is timed out (0 for no timeout)")
+                    arg!(--"command" <COMMAND> "command that executes the solution (- to read the solution source from STDIN)")
+                )
+                .arg(arg!(--"eval" <SOURCE> "solution source code to run instead of a file"))
+                .arg(
+                    arg!(--"language" <LANG> "build and run a solution file with the commands of LANG (see below)")
+                        .conflicts_with_all(["command", "eval"])
+                )
+                .arg(
+                    arg!(--"file" <FILE> "solution file for --language (default: sol.EXT or main.rs in the current directory)")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .requires("language")
+                )
+                .arg(arg!(--"input-file" "also pass the input of each testcase as a file (see below)"))
+                .arg(arg!(--"source-ext" <EXT> "file extension of the temporary solution file created for --eval and --command -"))
+                .arg(
+                    arg!(--"timeout" <SECONDS> "how many seconds before execution is timed out (0 for no timeout, the default is scaled to the speed measured by `coctus calibrate`)")
                         .value_parser(value_parser!(f64))
                         .default_value("5")
                 )
+                .arg(
+                    arg!(--"suite-timeout" <DURATION> "stop running testcases once all of them together have taken DURATION (e.g. 30s, 2m)")
+                        .value_parser(parse_duration)
+                )
                 .arg(arg!(--"auto-advance" "automatically move on to next clash if all testcases pass"))
+                .arg(
+                    arg!(--"max-clashes" <N> "end the auto-advance session after solving N clashes")
+                        .value_parser(value_parser!(u32).range(1..))
+                        .requires("auto-advance")
+                )
+                .arg(
+                    arg!(--"total-time" <DURATION> "end the auto-advance session after DURATION (e.g. 1h, 45m)")
+                        .value_parser(parse_duration)
+                        .requires("auto-advance")
+                )
+                .arg(
+                    arg!(--"break" <DURATION> "take a break of DURATION (e.g. 5m) before advancing to the next clash")
+                        .value_parser(parse_duration)
+                        .requires("auto-advance")
+                )
                 .arg(arg!(--"ignore-failures" "run all tests despite failures"))
                 .arg(
-                    arg!(--"testcases" <TESTCASE_INDICES> "indices of the testcases to run (separated by commas)")
-                        .value_parser(value_parser!(u64).range(1..99))
+                    arg!(-j --"jobs" <N> "run up to N testcases at the same time (default: 1)")
+                        .value_parser(value_parser!(u32).range(1..))
+                )
+                .arg(
+                    arg!(--"shuffle" [SEED] "run the testcases in a random order (results are still reported in order)")
+                        .value_parser(value_parser!(u64))
+                )
+                .arg(
+                    arg!(--"testcases" <TESTCASES> "testcases or ranges of them to run, separated by commas (see --numbering)")
                         .value_delimiter(',')
                 )
+                .arg(
+                    arg!(--"failed" "only run the testcases that didn't pass in the previous run of the clash")
+                        .conflicts_with("testcases")
+                )
+                .arg(
+                    arg!(--"tests" "only run the tests, not the validators")
+                        .conflicts_with_all(["testcases", "failed", "validators", "all"])
+                )
+                .arg(
+                    arg!(--"validators" "only run the validators")
+                        .conflicts_with_all(["testcases", "failed", "all"])
+                )
+                .arg(
+                    arg!(--"all" "run all testcases, validators included (the default)")
+                        .conflicts_with_all(["testcases", "failed"])
+                )
+                .arg(
+                    arg!(--"numbering" <MODE> "how testcases are numbered in --testcases and the results")
+                        .value_parser(value_parser!(Numbering))
+                )
+                .arg(arg!(--"custom" "also run the custom testcases saved with `coctus tweak --save-as-custom`"))
+                .arg(arg!(--"no-overlays" "run the testcases of the clash as they are, without its testcase overlays"))
                 .arg(
                     arg!(--"show-whitespace" [BOOL] "render ⏎ and • in place of newlines and spaces")
                         // This means show-whitespace=1 also works
@@ -99,31 +533,308 @@ fn cli() -> clap::Command {
                         .default_missing_value("true")
                 )
                 .arg(
-                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                    arg!(--"diff-style" <STYLE> "how to highlight differences in the output (default: $COCTUS_DIFF_STYLE or color)")
+                        .value_parser(value_parser!(DiffMode))
+                )
+                .arg(
+                    arg!(--"diff-granularity" <GRANULARITY> "how finely changed lines are compared: char, word or line (default: word if the expected output has spaces, otherwise char)")
+                        .value_parser(value_parser!(Granularity))
+                )
+                .arg(
+                    arg!(--"merged-output" "show stdout and stderr of failed tests interleaved in the order they were written")
+                )
+                .arg(arg!(--"all-diffs" "show the diff of every wrong output, also ones that repeat an earlier failure"))
+                .arg(arg!(--"show-length" "print the length of the solution in characters if all testcases pass (see `coctus score`)"))
+                .arg(
+                    arg!(--"output-format" <FORMAT> "print the results as colored text or as a JSON report (see below)")
+                        .value_parser(["text", "json"])
+                        .default_value("text")
+                        .conflicts_with("auto-advance")
+                )
+                .arg(arg!(--"fetch-missing" "download the clash from codingame.com if it is not stored locally"))
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash, or a unique prefix of it")
                         .value_parser(value_parser!(PublicHandle))
                 )
                 .after_help(
                     "If a --build-command is specified, it will be executed once before running any of the testcases. \
-                    The --command is required and will be executed once per testcase.\
+                    The --command will be executed once per testcase.\
+                    \nWithout --command or --eval the solution file created by `coctus start` is built and run with the \
+                    default commands of its language, and a warning is shown if it doesn't look like a solution to \
+                    the clash being run.\
+                    \nWith --language LANG the --file (by default the sol.EXT or main.rs that `coctus start` creates) is run \
+                    with the commands of LANG. Commands in the [languages.LANG] table of the config file take \
+                    the place of the defaults, for example:\
+                    \n  [languages.python]\
+                    \n  run = \"python3 {file}\"\
+                    \n{file} is replaced with the path of the solution file and {executable} with a path in the \
+                    build directory (see below). Solution files created by `coctus start` use the same commands.\
+                    \nThe way a clash is run is remembered, so running it again later without --command uses the \
+                    same --command and --build-command (run in the same directory), or the same solution file, as \
+                    the previous run. A solution \
+                    started for the clash (or the solution of the workspace) still comes first, and \
+                    `coctus status` shows what is remembered for the current clash.\
+                    \nWith --auto-advance the time spent on the clash is printed when all tests pass. Passing --max-clashes \
+                    or --total-time starts a session that keeps track of solved clashes across runs and ends once \
+                    either limit is reached.\
+                    \nWith --eval or --command - the solution source is written to a temporary file. {source} in the commands \
+                    is replaced with the path of that file, and if no other --command is given the file itself is executed.\
+                    \nBuild and run commands get a directory of their own for each clash in the COCTUS_BUILD_DIR \
+                    environment variable, and {build_dir} in the commands is replaced with its path. The default \
+                    commands of compiled languages put the executable there.\
+                    \nWith --input-file (or if the --command contains {input_file}) the input of each testcase is \
+                    written to a temporary file whose path replaces {input_file} and is available in the \
+                    COCTUS_INPUT_FILE environment variable. The input is still written to STDIN too.\
+                    \n{test_index} and {test_title} in the --command are replaced with the number (see --numbering \
+                    flat) and the title of each testcase, for harnesses that take the testcase as an argument:\
+                    \n  $ coctus run --command 'python3 harness.py {test_index} {test_title}'\
+                    \n--numbering flat (the default) numbers all testcases #1, #2, ... in order, validators included. \
+                    --numbering cg numbers tests and validators separately like the CodinGame IDE does. Either way \
+                    --testcases also accepts t2 for the second test, v1 for the first validator and #3 for the \
+                    third testcase.\
+                    \n--testcases (and the TESTCASE arguments of showtests) also take ranges like 2-5 or v3- (from the \
+                    third validator on), t* and v* for all tests or validators, and exclusions starting with !, \
+                    for example --testcases '1-10,!4'. If there are only exclusions, all other testcases are picked.\
+                    \n--tests, --validators and --all (here and in showtests) pick all tests, all validators or \
+                    every testcase, the same as --testcases 't*', 'v*' or '*'.\
+                    \n--failed picks the testcases that failed (or were not run because an earlier one failed) the \
+                    last time the clash was run, so fixing a solution doesn't require running all of them again.\
+                    \nEvery failure is classified as a wrong answer, a presentation error (the output would be \
+                    correct without a byte order mark, \\r line endings or whitespace at the end of lines), empty \
+                    output, a timeout or a runtime error. The counts are shown after the results and recorded in the \
+                    history file.\
+                    \nWrong outputs that differ from the expected output in the same way as an earlier failure \
+                    (for example all off by one on the last line) only get a line pointing to that failure, and the \
+                    repeated failures are listed with their counts after the results. --all-diffs shows the diff of \
+                    every failure.\
+                    \nWith --jobs N up to N testcases are run at the same time, each by a process of its own. The \
+                    results are still shown in order, but solutions that share files between runs may interfere \
+                    with each other, and the timeout applies to each testcase without accounting for the machine \
+                    being busier.\
+                    \nWith --output-format json a single JSON report is printed to stdout once the testcases have \
+                    run, for editor plugins and other programs: the counts, and the result, stdout, stderr and \
+                    duration of every testcase that was run (`coctus schema run` describes it). Messages about \
+                    which solution is run and the output of a failed build go to stderr. The output of passed testcases is not kept, and durations \
+                    are not measured with --jobs.\
+                    \n--show-length counts the characters of the solution file (or of the --eval or stdin source) \
+                    the way the shortest mode of Clash of Code does. It is only printed with the text output.\
+                    \nThe order shown by --merged-output is only as accurate as the solution's flushing: most languages \
+                    buffer stdout when it is not a terminal.\
+                    \nIMPORTANT: The commands you provide will be executed without any sandboxing. Only run code you trust!"
+                )
+        )
+        .subcommand(
+            Command::new("stress")
+                .about("Find out how large inputs a solution can handle within a time budget")
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash, or a unique prefix of it (default: current clash)")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .arg(
+                    arg!(--"budget" <DURATION> "time the solution may spend on one input (e.g. 2s or 0.5s)")
+                        .value_parser(parse_duration)
+                        .default_value("2s")
+                )
+                .arg(arg!(--"build-command" <COMMAND> "command that compiles the solution"))
+                .arg(arg!(--"command" <COMMAND> "command that executes the solution (default: the solution tracked by `coctus start`)"))
+                .arg(
+                    arg!(--"max-size" <N> "stop growing the inputs at size N")
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("10000000")
+                )
+                .arg(
+                    arg!(--"seed" <N> "seed of the random inputs (default: random)")
+                        .value_parser(value_parser!(u64))
+                )
+                .after_help(
+                    "Random inputs are generated from the stub generator of the clash (or from the input of its \
+                    first testcase, for reverse mode clashes without one). The size of an input is the value of every \
+                    loop count and every word or string length in it, and the other numbers are at most the size too. \
+                    The values stay within the constraints of the clash, as far as they can be understood.\
+                    \nThe size starts at 1 and doubles until the solution takes longer than the budget, crashes, or \
+                    the constraints don't allow larger inputs (or the input would be over 32 MB). The output of the \
+                    solution is ignored.\
+                    \nThe input that was too much for the solution is saved to a temporary file so that it can be \
+                    reproduced with the same --seed (or by feeding the file to the solution).\
+                    \nIMPORTANT: The commands you provide will be executed without any sandboxing. Only run code you trust!"
+                )
+        )
+        .subcommand(
+            Command::new("tweak")
+                .about("Edit the input of a testcase and run the solution with it")
+                .arg(arg!(--"testcase" <TESTCASE> "testcase whose input is edited (default: 1, see --numbering)"))
+                .arg(
+                    arg!(--"numbering" <MODE> "how testcases are numbered in --testcase")
+                        .value_parser(value_parser!(Numbering))
+                )
+                .arg(arg!(--"build-command" <COMMAND> "command that compiles the solution"))
+                .arg(arg!(--"command" <COMMAND> "command that executes the solution (default: the solution created with `coctus start`)"))
+                .arg(arg!(--"save-as-custom" "save the edited input as a custom testcase of the clash"))
+                .arg(arg!(--"no-overlays" "edit the testcases of the clash as they are, without its testcase overlays"))
+                .after_help(
+                    "Opens the input of the testcase in $VISUAL (or $EDITOR) and once the editor exits runs the \
+                    solution with the edited input, showing what it prints. Nothing is compared against the \
+                    expected output and the edited input is thrown away afterwards.\
+                    \nWith --save-as-custom the edited input is saved as a custom testcase, with the output of the \
+                    solution as its expected output. Custom testcases are run by `coctus run --custom`.\
+                    \nExample:\
+                    \n  $ coctus tweak --testcase 3 --command 'python3 sol.py'"
+                )
+        )
+        .subcommand(
+            Command::new("open-test")
+                .about("Write the input and expected output of a testcase into files")
+                .arg(arg!([TESTCASE] "testcase to write (default: 1, see --numbering)"))
+                .arg(
+                    arg!(--"numbering" <MODE> "how testcases are numbered in TESTCASE")
+                        .value_parser(value_parser!(Numbering))
+                )
+                .arg(arg!(--"edit" "open both files in $VISUAL (or $EDITOR) instead of printing their paths"))
+                .after_help(
+                    "The files are written into the build directory of the clash (see `coctus run --help`) as \
+                    testcase-N.in and testcase-N.out, where N is the position of the testcase in the flat numbering. \
+                    Without --edit the path of the input is printed on the first line and the path of the expected \
+                    output on the second, so they are easy to use in scripts.\
+                    \nExamples:\
+                    \n  $ python3 sol.py < \"$(coctus open-test 3 | head -1)\"\
+                    \n  $ coctus open-test v2 --edit"
+                )
+        )
+        .subcommand(
+            Command::new("tui")
+                .about("Show the statement and live test results, re-running the solution when it changes")
+                .arg(arg!(--"build-command" <COMMAND> "command that compiles the solution"))
+                .arg(arg!(--"command" <COMMAND> "command that executes the solution (default: as in `coctus run`)"))
+                .arg(
+                    arg!(--"watch" <FILE> "file to watch for changes (default: the solution file)")
+                        .action(clap::ArgAction::Append)
+                )
+                .arg(
+                    arg!(--"timeout" <SECONDS> "how many seconds before execution of a testcase is timed out")
+                        .value_parser(value_parser!(f64))
+                        .default_value("5")
+                )
+                .arg(
+                    arg!(--"numbering" <MODE> "how testcases are numbered in the list")
+                        .value_parser(value_parser!(Numbering))
+                )
+                .arg(
+                    arg!(--"notify" <HOW> "ring the bell and/or show the results in the window title (see below)")
+                        .value_parser(value_parser!(Notify))
+                        .value_delimiter(',')
+                )
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash, or a unique prefix of it")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Fills the terminal with the statement of the clash and the list of its testcases, and runs \
+                    the solution against all of them, updating the list as the results come in. Whenever the \
+                    watched files change the solution is built and run again. Press Ctrl-C to quit.\
+                    \nWithout --command the solution is picked like `coctus run` does, and its source file is \
+                    watched. With --command the files to watch have to be given with --watch.\
+                    \nThe details of failed testcases are not shown, use `coctus run` to see them.\
+                    \n--notify bell rings the terminal bell when all testcases start passing or stop passing, and \
+                    --notify title shows the results of the latest round in the title of the terminal window, so \
+                    they can be noticed while the editor has the focus. Use --notify bell,title for both.\
                     \nIMPORTANT: The commands you provide will be executed without any sandboxing. Only run code you trust!"
                 )
         )
         .subcommand(
             Command::new("status").about("Show status information")
         )
+        .subcommand(
+            Command::new("clash")
+                .about("Solve a clash against the clock")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("start")
+                        .about("Start a timed session for the current clash and show its statement")
+                        .arg(
+                            arg!(--"time-limit" <DURATION> "time there is for solving the clash (e.g. 15m, 5m30s)")
+                                .value_parser(parse_duration)
+                                .default_value("15m")
+                        )
+                        .arg(
+                            arg!([PUBLIC_HANDLE] "select this clash first (hexadecimal handle, or a unique prefix of it)")
+                                .value_parser(value_parser!(PublicHandle))
+                        )
+                )
+                .subcommand(
+                    Command::new("status")
+                        .about("Show the time left in the session")
+                        .arg(arg!(--"follow" "keep counting down until the session ends"))
+                )
+                .subcommand(Command::new("stop").about("End the session without solving the clash"))
+                .after_help(
+                    "`coctus clash start` starts the clock for the current clash like a round of Clash of Code with \
+                    15 minutes on it (see --time-limit). Run the tests whenever you want with `coctus run`: it \
+                    shows the time left after the results, and once every testcase of the clash passes it \
+                    reports the time used and ends the session. The session is stored in the data directory, \
+                    so the clock keeps running between commands (and after the time is up, to show how far over \
+                    it you went).\
+                    \nStarting another session replaces the previous one. `coctus clash status --follow` shows a \
+                    countdown in the terminal."
+                )
+        )
         .subcommand(
             Command::new("fetch")
                 .about("Fetch a clash from codingame.com and save it locally")
                 .arg(
-                    arg!(<PUBLIC_HANDLE> ... "hexadecimal handle of the clash")
-                        .value_parser(value_parser!(PublicHandle))
+                    arg!([CLASH] ... "hexadecimal handle of the clash (or a unique prefix of it), or a link to it")
+                        .value_parser(value_parser!(ClashIdentifier))
+                        .required_unless_present("list-url")
                 )
+                .arg(arg!(--"list-url" <URL> "also fetch every clash listed in the document at URL (one handle or link per line)"))
+                .arg(arg!(--"tag" <TAG> "tag the fetched clashes with TAG (see `coctus next --tag`)"))
                 .after_help(
-                    "The PUBLIC_HANDLE of a puzzle is the last part of the URL when viewing it on the contribution section on CodinGame (1).\
+                    "The handle of a puzzle is the last part of the URL when viewing it on the contribution section on CodinGame (1).\
                     \nYou can fetch both clash of code and classic (in/out) puzzles.\
+                    \nInstead of a handle CLASH can be a link to the contribution, to a Clash of Code game (like \
+                    https://www.codingame.com/clashofcode/clash/report/HANDLE, which fetches the puzzle that was \
+                    played) or to a classic puzzle (like https://www.codingame.com/training/easy/the-descent or \
+                    https://www.codingame.com/ide/puzzle/the-descent). Links to games and classic puzzles are \
+                    looked up on codingame.com to find out the handle.\
+                    \nA --list-url document may contain bare handles or links to the clashes, empty lines and lines \
+                    starting with # are ignored. This makes it easy to share a practice collection as a gist.\
+                    \n`show` and `run` fetch clashes that are not stored locally with --fetch-missing (or if \
+                    COCTUS_FETCH_MISSING=1 is set). Setting COCTUS_OFFLINE=1 disables fetching altogether.\
+                    \nYour own unpublished contributions can be fetched after `coctus login`.\
                     \n (1) https://www.codingame.com/contribute/community"
                 )
         )
+        .subcommand(
+            Command::new("login")
+                .about("Store a CodinGame session for fetching your own unpublished contributions")
+                .arg(arg!(--"cookie" <COOKIE> "cookie of a browser that is logged in to codingame.com (default: read from STDIN)"))
+                .after_help(
+                    "Contributions that are still drafts (or otherwise not public) can only be fetched when logged \
+                    in as their author. coctus can't log in with a password, so copy the cookie from a browser that \
+                    is logged in instead: either the whole Cookie header of a request to codingame.com or just the \
+                    value of the rememberMe cookie.\
+                    \nThe session is stored in login.json in the config directory (readable only by you on unix) and \
+                    sent along with every request for a clash until `coctus logout`. Prefer pasting the cookie to \
+                    STDIN over --cookie, which may end up in the history of your shell.\
+                    \nExample:\
+                    \n  $ coctus login < cookie.txt"
+                )
+        )
+        .subcommand(Command::new("logout").about("Forget the CodinGame session stored by `coctus login`"))
+        .subcommand(
+            Command::new("refresh")
+                .about("Fetch stored clashes again and report the ones that changed upstream")
+                .arg(
+                    arg!([PUBLIC_HANDLE] ... "hexadecimal handle of the clash, or a unique prefix of it (default: current clash)")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .arg(arg!(--"all" "refresh every stored clash").conflicts_with("PUBLIC_HANDLE"))
+                .after_help(
+                    "Clash authors can edit their contributions after they have been accepted, so a stored copy \
+                    may no longer match CodinGame. When the statement, testcases or anything else about the \
+                    puzzle itself has changed, the previous copy is kept in the backups directory next to the \
+                    clash directory."
+                )
+        )
         .subcommand(
             Command::new("showtests")
                 .about("Print testcases and validators of current clash")
@@ -137,37 +848,583 @@ fn cli() -> clap::Command {
                 .arg(arg!(--"in" "only print the testcase input"))
                 .arg(arg!(--"out" "only print the testcase output").conflicts_with("in"))
                 .arg(
-                    arg!([TESTCASE] ... "indices of the testcases to print (default: all)")
-                        .value_parser(value_parser!(u64).range(1..99))
+                    arg!(--"raw" "print the --in or --out of a single testcase byte for byte, for piping it into a program")
+                        .conflicts_with("show-whitespace")
+                )
+                .arg(
+                    arg!(--"copy" "copy the --in or --out of a single testcase to the clipboard instead of printing it")
+                        .conflicts_with_all(["show-whitespace", "raw"])
+                )
+                .arg(arg!(--"no-overlays" "print the testcases of the clash as they are, without its testcase overlays"))
+                .arg(
+                    arg!([TESTCASE] ... "testcases to print (default: all), see `coctus run --help` for the syntax")
                         .value_delimiter(',')
                 )
+                .arg(
+                    arg!(--"tests" "only print the tests, not the validators")
+                        .conflicts_with_all(["TESTCASE", "validators", "all"])
+                )
+                .arg(arg!(--"validators" "only print the validators").conflicts_with_all(["TESTCASE", "all"]))
+                .arg(
+                    arg!(--"all" "print all testcases, validators included (the default)").conflicts_with("TESTCASE")
+                )
+                .arg(
+                    arg!(--"numbering" <MODE> "how testcases are numbered")
+                        .value_parser(value_parser!(Numbering))
+                )
+                .after_help(
+                    "With --raw exactly the input (or expected output) of the testcase is printed: no colors, \
+                    titles or whitespace markers even when printing to a terminal, nothing trimmed or converted and \
+                    no newline added at the end. The input is the same bytes that `coctus run` writes to the STDIN \
+                    of the solution, and the same as in the files written by `coctus open-test`.\
+                    \nWith --copy the same text is copied to the clipboard, ready to be pasted into the custom \
+                    testcase of an IDE (see `coctus generate-stub --help` for the clipboard commands).\
+                    \nExamples:\
+                    \n  $ coctus showtests --raw --in 3 | ./solution\
+                    \n  $ coctus showtests --copy --in 2"
+                )
+        )
+        .subcommand(
+            Command::new("addtest")
+                .about("Add a testcase to a testcase overlay of the current clash")
+                .arg(
+                    arg!(--"input" <FILE> "file with the input of the testcase")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(--"output" <FILE> "file with the expected output of the testcase")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf))
+                )
+                .arg(arg!(--"overlay" <NAME> "overlay to add the testcase to (default: local)"))
+                .arg(arg!(--"title" <TITLE> "title of the testcase (default: Overlay test N)"))
+                .arg(
+                    arg!(--"replace" <TESTCASE> "replace this testcase of the clash instead of adding a new one")
+                        .conflicts_with("title")
+                )
+                .arg(
+                    arg!(--"numbering" <MODE> "how the --replace testcase is numbered")
+                        .value_parser(value_parser!(Numbering))
+                )
+                .after_help(
+                    "Testcase overlays are alternative sets of testcases for a clash, such as easier tests to get \
+                    started with. They are stored next to the clash (so refreshing it never loses them) and are \
+                    used by `coctus run`, `coctus showtests` and `coctus tweak` unless --no-overlays is given.\
+                    \nPrecedence: overlays are applied in the order of their names, later ones taking precedence. \
+                    A testcase of an overlay replaces the testcase of the clash that has the same title, keeping \
+                    its number. Testcases with other titles are added as tests after the testcases of the clash. \
+                    Testcases from overlays are marked as such in the output, and runs that include them are not \
+                    recorded in the history."
+                )
+        )
+        .subcommand(
+            Command::new("import-tests")
+                .about("Replace a testcase overlay of the current clash with the testcases in a JSON file")
+                .arg(
+                    arg!(<FILE> "JSON array of testcases ({\"title\": ..., \"testIn\": ..., \"testOut\": ...})")
+                        .value_parser(clap::value_parser!(PathBuf))
+                )
+                .arg(arg!(--"overlay" <NAME> "overlay to replace (default: local)"))
+                .after_help("See `coctus addtest --help` for how overlays are applied.")
         )
         .subcommand(
             Command::new("json")
                 .about("Print the raw source JSON of a clash")
                 .arg(
-                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash, or a unique prefix of it")
                         .value_parser(value_parser!(PublicHandle))
                 )
         )
         .subcommand(
-            Command::new("generate-stub")
-                .alias("gen")
-                .about("Generate input handling code for a given language")
-                .arg(arg!(<PROGRAMMING_LANGUAGE> "Programming language of the solution stub"))
+            Command::new("meta")
+                .about("Print metadata of a clash")
                 .arg(
-                    arg!(--"from-file" <STUBFILE> "Generate stub from a stub generator file instead of the current clash")
-                        .value_parser(clap::value_parser!(PathBuf))
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash, or a unique prefix of it")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .arg(
+                    arg!(--"format" <FORMAT> "output format")
+                        .value_parser(["text", "json"])
+                        .default_value("text")
                 )
-                .arg(arg!(--"from-reference" "Generate stub from the reference stub generator instead of the current clash").conflicts_with("from-file"))
                 .after_help(
-                    "Prints boilerplate code for the input of the current clash.\
-                    \nIntended to be piped to a file.\
+                    "Prints the title, puzzle type, game modes, number of tests and validators, votes, \
+                    whether the clash has a stub generator and the length and guessed language of the statement.\
+                    \nUse --format json for scripting, for example:\
+                    \n  $ coctus meta --format json | jq .modes"
+                )
+        )
+        .subcommand(
+            Command::new("list")
+                .about("List locally stored clashes")
+                .arg(arg!(--"statement-lang" <LANG> "only list clashes whose statement is in LANG (en, fr or es)"))
+                .arg(
+                    arg!(--"max-read-time" <DURATION> "only list clashes whose statement takes at most DURATION to read")
+                        .value_parser(parse_duration)
+                )
+                .after_help(
+                    "Prints the handle, the guessed language of the statement (- if it couldn't be guessed), \
+                    the estimated time it takes to read the statement, the game modes and the title of every \
+                    stored clash."
+                )
+        )
+        .subcommand(
+            Command::new("search")
+                .about("Search the titles and statements of locally stored clashes")
+                .arg(arg!(<QUERY>... "words to search for"))
+                .arg(arg!(-'r' --"reverse" "only show clashes that have reverse mode"))
+                .arg(arg!(-'s' --"shortest" "only show clashes that have shortest mode"))
+                .arg(arg!(-'f' --"fastest" "only show clashes that have fastest mode"))
+                .arg(arg!(--"handles-only" "only print the handles of the matching clashes"))
+                .after_help(
+                    "Lists the clashes that have every word of QUERY in their title, statement, input and output \
+                    descriptions or constraints, ignoring case. A word also matches the words it is the start of, \
+                    so `coctus search fib` finds clashes about Fibonacci numbers. Clashes with more of the words \
+                    in the title are listed first.\
+                    \nThe words of the stored clashes are indexed in the data directory, so later searches only \
+                    have to read the clashes that changed.\
+                    \nTo select the best match:\
+                    \n  $ coctus search fibonacci --reverse --handles-only | head -n 1 | xargs coctus next"
+                )
+        )
+        .subcommand(
+            Command::new("score")
+                .about("Count the characters of a solution like Clash of Code scores them")
+                .arg(
+                    arg!(--"shortest" <FILE> "solution file to score for the shortest mode")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .required(true)
+                )
+                .arg(arg!(--"language" <LANG> "also run the testcases with the commands of LANG (like `coctus run --language`)"))
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash, or a unique prefix of it")
+                        .value_parser(value_parser!(PublicHandle))
+                        .requires("language")
+                )
+                .after_help(
+                    "In the shortest mode of Clash of Code the solutions that pass the most validators are ranked \
+                    by their length in characters (not bytes). A byte order mark, the \\r of \\r\\n line endings \
+                    and whitespace at the end of the file are not counted, just like in the CodinGame IDE.\
+                    \nWith --language the testcases of the clash (the current one by default) are also run, the \
+                    same way as `coctus run --language LANG --file FILE` runs them. `coctus run --show-length` prints \
+                    the length after a run where every testcase passed."
+                )
+        )
+        .subcommand(
+            Command::new("reverse")
+                .about("Deduce the rule of a reverse mode clash from its tests, revealed one at a time")
+                .arg(arg!(--"build-command" <COMMAND> "command that compiles the solution"))
+                .arg(arg!(--"command" <COMMAND> "command that executes the solution (default: as in `coctus run`)"))
+                .arg(arg!(--"restart" "forget the tests revealed and the guesses made earlier"))
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash, or a unique prefix of it")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Plays a clash like in the reverse mode of Clash of Code: instead of the statement only \
+                    the input and the expected output of the tests are shown, one test at a time. Type commands \
+                    at the prompt to reveal the next test, write down a guess of the rule or run the solution \
+                    against the tests revealed so far (type help for the list). The validators stay hidden, \
+                    `coctus run` runs them once the solution is done.\
+                    \nThe revealed tests and the guesses are saved, so quitting and running `coctus reverse` \
+                    again later continues where the game was left off. --restart starts over."
+                )
+        )
+        .subcommand(
+            Command::new("review")
+                .about("List the failed or slowly solved clashes that are due for practice")
+                .arg(
+                    arg!(--"slow" <DURATION> "solving a clash takes practice if it takes longer than DURATION")
+                        .value_parser(parse_duration)
+                        .default_value("10m")
+                )
+                .arg(arg!(--"all" "also list the clashes that are due later"))
+                .arg(arg!(--"queue" "tag the clashes that are due with `review` (see below)"))
+                .after_help(
+                    "Schedules practice of past clashes from the history of `coctus run` with spaced \
+                    repetition. A clash needs practice if it was run on some day without passing all testcases, \
+                    or was only solved more than --slow after selecting it. It is then due the next day, and \
+                    every later day it is solved in time doubles the wait for the next review. After a month \
+                    without trouble the clash is considered learned and is not scheduled any more.\
+                    \nClashes that are the most overdue are listed first, with the number of days it didn't go \
+                    well.\
+                    \n--queue makes the due clashes the only ones with the tag `review`, so that they can be \
+                    practiced one at a time. The tag is kept in user_tags.json in the data directory, apart from \
+                    the tags of a shared clash collection:\
+                    \n  $ coctus review --queue && coctus next --tag review"
+                )
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Print statistics of your past runs")
+                .arg(
+                    arg!(--"export" <FORMAT> "export per-clash statistics instead of a summary")
+                        .value_parser(["csv"])
+                )
+                .arg(
+                    arg!(--"badge" <FILE> "write an SVG badge with the number of solved clashes to FILE")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .conflicts_with("export")
+                )
+                .after_help(
+                    "Every `coctus run` of a whole test suite is recorded in the history file in the data directory.\
+                    \nThe summary lists the clashes attempted and solved with their average solve time, broken \
+                    down by the modes of the clashes and by the language of the solution (only known for runs of \
+                    a solution file, like with --language). A clash that has several modes counts towards each \
+                    of them.\
+                    \n--export csv prints one row per clash with the columns\
+                    \n  handle,title,attempts,solved,best_time_secs,best_size_bytes,last_run\
+                    \nBest time (seconds since selecting the clash) and best size (bytes of an --eval or stdin \
+                    solution) only count runs that passed every test. The last run is an ISO 8601 UTC timestamp.\
+                    \n--badge writes a badge like \"coctus | 412 solved\" for the README of a repository of solutions."
+                )
+        )
+        .subcommand(
+            Command::new("start")
+                .about("Create a solution file for the current clash")
+                .arg(arg!(<PROGRAMMING_LANGUAGE> "Programming language of the solution"))
+                .arg(
+                    arg!([FILE] "path of the solution file (default: sol.EXT in the current directory, main.rs for Rust)")
+                        .value_parser(clap::value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(--"workspace" <DIR> "create the solution in DIR/HANDLE/ along with the statement and the stub (default: workspace in the config)")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .conflicts_with("FILE")
+                )
+                .arg(arg!(--"force" "overwrite FILE if it already exists"))
+                .arg(
+                    arg!(--"option" <NAME_VALUE> "select a variation of the stub, like io=fast (see `coctus generate-stub --help`)")
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append)
+                )
+                .after_help(
+                    "Writes the input stub of the current clash into the solution file and remembers the file, \
+                    so that `coctus run` can build and run it without a --command.\
+                    \nThe file is named after the source file extension of the language (sol.py, sol.c, ...), \
+                    except for main.rs in Rust, and its build and run commands are the ones in the stub config \
+                    of the language.\
+                    \nWith a workspace (--workspace or the workspace option in the config file) a directory named \
+                    after the clash is created for it, containing the statement (statement.txt), the stub \
+                    (stub.EXT), the solution (sol.EXT) and a coctus.toml file. Commands run inside that \
+                    directory use its clash instead of the current one."
+                )
+        )
+        .subcommand(
+            Command::new("generate-stub")
+                .alias("gen")
+                .about("Generate input handling code for a given language")
+                .arg(
+                    arg!([PROGRAMMING_LANGUAGE] "Programming language of the solution stub")
+                        .required_unless_present("all")
+                )
+                .arg(
+                    arg!(--"all" "Generate the stub for every language with templates (embedded or in the config dir)")
+                        .visible_alias("all-languages")
+                        .conflicts_with_all(["PROGRAMMING_LANGUAGE", "check", "output"])
+                )
+                .arg(
+                    arg!(--"out-dir" <DIR> "Write the stubs of --all-languages into DIR, one file per language, instead of printing them")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .requires("all")
+                )
+                .arg(
+                    arg!(--"stored-clashes" "Generate the stub of every stored clash and report the problems as JSON instead of printing the stubs")
+                        .conflicts_with_all(["from-file", "from-reference", "reverse", "check", "output", "all"])
+                )
+                .arg(
+                    arg!(--"fail-on-warnings" "Exit with an error if there were any warnings, not just errors")
+                        .requires("stored-clashes")
+                )
+                .arg(
+                    arg!(--"from-file" <STUBFILE> "Generate stub from a stub generator file instead of the current clash")
+                        .value_parser(clap::value_parser!(PathBuf))
+                )
+                .arg(arg!(--"from-reference" "Generate stub from the reference stub generator instead of the current clash").conflicts_with("from-file"))
+                .arg(
+                    arg!(--"reverse" "Generate a stub guessed from the first testcase (for reverse mode clashes without a stub generator)")
+                        .conflicts_with_all(["from-file", "from-reference"])
+                )
+                .arg(
+                    arg!(--"check" <FILE> "Check that FILE contains exactly the stub that would be generated instead of printing it")
+                        .value_parser(clap::value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(--"diff-existing" <FILE> "Show how the stub between the markers of FILE differs from the stub that would be generated, without changing FILE")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .conflicts_with_all(["check", "all", "stored-clashes"])
+                )
+                .arg(
+                    arg!(--"output" <FILE> "Write the stub to FILE instead of printing it (refuses to overwrite an existing file)")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .conflicts_with_all(["check", "diff-existing"])
+                )
+                .arg(
+                    arg!(--"force" "Overwrite the --output file (or the files in the --out-dir) if it already exists")
+                        .requires("destination")
+                )
+                .group(clap::ArgGroup::new("destination").args(["output", "out-dir"]).multiple(false))
+                .arg(
+                    arg!(--"copy" "Copy the stub to the clipboard instead of printing it")
+                        .conflicts_with_all(["all", "stored-clashes", "check", "diff-existing", "output"])
+                )
+                .arg(
+                    arg!(--"option" <NAME_VALUE> "Select a variation of the stub of the language, like io=fast (see below)")
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append)
+                        .conflicts_with_all(["all", "stored-clashes"])
+                )
+                .arg(
+                    arg!(--"append-marker" "Add the stub to the --output file between marker comments, keeping the existing code")
+                        .requires("output")
+                        .conflicts_with("force")
+                )
+                .after_help(
+                    "Prints boilerplate code for the input of the current clash.\
+                    \nIntended to be piped to a file.\
+                    \nThe output only depends on the stub generator and the templates, so a stub that is committed to \
+                    version control can be checked for drift with --check (which fails if the file differs).\
+                    \nWith --reverse the variables and their types are guessed from the input of the first testcase, \
+                    so check that they make sense for the other testcases too.\
+                    \nWith --append-marker the stub is written between two marker comments at the end of the file. \
+                    Running it again replaces the marked section, so the stub can be regenerated without losing the \
+                    solution around it.\
+                    \nWith --diff-existing the marked section of a solution is compared to the stub, for example to \
+                    see what changed after the stub generator of the clash was edited. Lines marked with + are only \
+                    in the file and lines marked with - only in the generated stub. The exit status is non-zero if \
+                    they differ.\
+                    \nSome languages have options that change the style of their stubs, which are selected with \
+                    --option NAME=VALUE (separate several with commas or repeat --option). An invalid option lists \
+                    the ones the language has. For example io=fast reads the input with sys.stdin.readline in \
+                    Python, io=scanf reads the numbers with scanf in C++ and io=macro parses them with a \
+                    parse_input! macro in Rust.\
+                    \n--copy (here and in `coctus showtests`) pipes the text to the clipboard command of the system: \
+                    pbcopy on macOS, clip on Windows and wl-copy, xclip, xsel or termux-clipboard-set elsewhere, \
+                    whichever is installed. Set COCTUS_CLIPBOARD to use another command, such as \
+                    `tmux load-buffer -` on a machine without a display.\
+                    \nWith --stored-clashes every problem (generator lines the parser had to guess at, missing or \
+                    broken templates and suspicious looking stubs) is printed to stderr as it is found, and a JSON \
+                    summary of all of them is printed to stdout at the end. The exit status is non-zero if there \
+                    were errors (or warnings with --fail-on-warnings).\
+                    \nWith --all-languages --out-dir DIR the stubs are written into DIR as files named after their \
+                    language, like DIR/python.py, which makes it easy to look through how a stub generator renders \
+                    in every language. Existing files are only overwritten with --force.\
                     \nExamples:\
                     \n  $ coctus generate-stub ruby > sol.rb\
+                    \n  $ coctus generate-stub ruby --check sol.rb\
+                    \n  $ coctus generate-stub ruby --output sol.rb --append-marker\
+                    \n  $ coctus generate-stub --all --from-reference\
+                    \n  $ coctus generate-stub --all-languages --out-dir stubs/\
                     \n  $ coctus generate-stub bash > sol.sh"
             )
         )
+        .subcommand(
+            Command::new("stub-repl")
+                .about("Write a stub generator interactively, seeing the stub and its problems after every line")
+                .arg(arg!(<PROGRAMMING_LANGUAGE> "Programming language of the stub"))
+                .arg(
+                    arg!(--"from-file" <STUBFILE> "Start from the stub generator in STUBFILE instead of an empty one")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .conflicts_with("watch")
+                )
+                .arg(
+                    arg!(--"watch" <FILE> "Instead of reading lines, render the stub generator in FILE every time it changes")
+                        .value_parser(clap::value_parser!(PathBuf))
+                )
+                .after_help(
+                    "Reads a stub generator one line at a time and renders the stub again after every line, \
+                    printing the warnings and errors of the parser right away. The lines of the stub that changed \
+                    are highlighted. Lines starting with a colon are commands, like :undo to remove the last line \
+                    and :save FILE to keep the generator (type :help for all of them). Input is read a line at a \
+                    time, so the line editing of the terminal applies.\
+                    \nWith --watch FILE the generator is read from FILE instead, and the stub is rendered again \
+                    whenever the file is saved (for writing the generator in an editor). Stop with Ctrl-C.\
+                    \nExamples:\
+                    \n  $ coctus stub-repl python\
+                    \n  $ coctus stub-repl rust --watch generator.txt"
+                )
+        )
+        .subcommand(
+            Command::new("parse-all-stubs")
+                .about("Parse the stub generator of every stored clash and summarize the problems")
+                .arg(
+                    arg!(--"dir" <DIR> "Parse the clashes in DIR instead of the stored ones")
+                        .value_parser(clap::value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(--"expectations" <FILE> "Exit with an error if there are problems that FILE doesn't list")
+                        .value_parser(clap::value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(--"update-expectations" "Write the problems that were found to the --expectations FILE")
+                        .requires("expectations")
+                )
+                .arg(
+                    arg!(--"examples" <N> "How many clashes to list for every kind of problem")
+                        .value_parser(value_parser!(usize))
+                        .default_value("3")
+                )
+                .after_help(
+                    "Only parses the generators, so unlike `coctus generate-stub --stored-clashes` the templates \
+                    of the languages don't matter. The problems are grouped by their kind (the message without \
+                    the quoted parts and numbers, which differ from one clash to the next), most common first, \
+                    to show what syntax found in real clashes the parser doesn't handle yet. Generators that make \
+                    the parser panic count as errors.\
+                    \nAn expectations file lists the kinds of problems of every clash that has any. With \
+                    --expectations the command fails if a clash has a problem that isn't listed, so a change to \
+                    the parser can be checked against a collection of clashes it used to handle. Problems that are \
+                    listed but no longer happen are only reported. The file is created (or brought up to date \
+                    after fixing the parser) with --update-expectations.\
+                    \nThe repository keeps the expectations of the clashes in fixtures/puzzles:\
+                    \n  $ coctus parse-all-stubs --dir fixtures/puzzles --expectations fixtures/stub_parse_expectations.json"
+                )
+        )
+        .subcommand(
+            Command::new("undo")
+                .about("Restore the files overwritten by the last destructive command")
+                .arg(arg!(--"list" "List the operations that can be undone instead, most recent last"))
+                .after_help(
+                    "Before fetch, refresh, start, generate-stub --output or verify-store --fix overwrite or remove \
+                    a file, its previous contents are copied into the trash (in the data directory). `coctus undo` \
+                    puts them back and removes the files the command created. Files that have changed since the \
+                    command are left alone, and their previous contents stay in the trash.\
+                    \nOnly the last 20 operations are kept."
+                )
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("Print the JSON Schema of the JSON output of a command")
+                .arg(
+                    arg!(<COMMAND> "command whose output is described")
+                        .value_parser(clap::builder::PossibleValuesParser::new(SCHEMA_COMMANDS))
+                )
+                .after_help(
+                    "The schemas describe the output of `coctus meta --format json`, `coctus log --json` (every line \
+                    of it), `coctus generate-stub --stored-clashes` and `coctus run --output-format json`. They are \
+                    generated from the same types that the output is printed from, so they are always up to date.\
+                    \nExample:\
+                    \n  $ coctus schema meta > meta.schema.json"
+                )
+        )
+        .subcommand(
+            Command::new("log")
+                .about("Show the recent changes to the local collection of clashes")
+                .arg(
+                    arg!(-'n' --"count" <N> "how many of the most recent changes to show")
+                        .value_parser(value_parser!(usize))
+                        .default_value("20")
+                )
+                .arg(arg!(--"handles" "also list the clashes that every change touched"))
+                .arg(arg!(--"json" "print the changes as JSON lines").conflicts_with("handles"))
+                .after_help(
+                    "Fetching, refreshing, tagging, repairing the store (verify-store --fix), adding testcase \
+                    overlays and undoing are recorded in changelog.jsonl in the data directory, oldest first. The \
+                    changelog is never trimmed, and every change records the machine it was made on so that data \
+                    directories synced between machines have a readable history."
+                )
+        )
+        .subcommand(
+            Command::new("calibrate")
+                .about("Measure the speed of this machine to scale the default timeout of `coctus run`")
+                .arg(arg!(--"reset" "Forget the measurement and go back to the unscaled default timeout"))
+                .after_help(
+                    "Runs a small reference workload and compares how long it took to the time it takes on the \
+                    machine the default timeout was chosen on. The default timeout of `coctus run` is then \
+                    multiplied by the ratio (between 0.5 and 10), so that a clash collection behaves the same on a \
+                    slow laptop and a fast desktop. Timeouts given with --timeout are never scaled."
+                )
+        )
+        .subcommand(
+            Command::new("test-templates")
+                .about("Render the stub templates of a language against a set of test generators")
+                .arg(arg!(<PROGRAMMING_LANGUAGE> "Programming language of the templates"))
+                .arg(
+                    arg!([DIR] "Directory to look for the language templates in instead of the user config dir")
+                        .value_parser(clap::value_parser!(PathBuf))
+                )
+                .after_help(
+                    "Renders a built-in battery of stub generators covering every template and checks the \
+                    results for leftover template syntax and unbalanced brackets. If the stub_config.toml of the \
+                    language defines a check_command (e.g. \"gcc -fsyntax-only {file}\") the rendered stubs are \
+                    also checked with it. {out_dir} in the command is replaced with a temporary directory for any \
+                    files that the command writes.\
+                    \nExample:\
+                    \n  $ coctus test-templates mylang ./stub_templates"
+                )
+        )
+        .subcommand(
+            Command::new("which")
+                .about("Show which stub template files are used for a language")
+                .arg(arg!(<PROGRAMMING_LANGUAGE> "Programming language to look up"))
+                .after_help(
+                    "Stub templates in the user config dir take precedence over the ones embedded into coctus. \
+                    A language directory in the config dir replaces the embedded templates of that language \
+                    completely, so every template has to be present there."
+                )
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Bundle all stored clashes into a single archive")
+                .arg(arg!(<FILE> "archive to write (a gzipped tarball)").value_parser(value_parser!(PathBuf)))
+                .arg(arg!(--"force" "overwrite FILE if it exists"))
+                .after_help(
+                    "The archive contains the clash files as they are stored and the handle of the current clash, \
+                    so it can be restored on another machine with `coctus import` without fetching anything.\
+                    \nExample:\
+                    \n  $ coctus export clashes.tar.gz"
+                )
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Restore the clashes of an archive made by `coctus export`")
+                .arg(arg!(<FILE> "archive to import").value_parser(value_parser!(PathBuf)))
+                .arg(arg!(--"keep-current" "don't change the current clash to the one of the archive"))
+                .after_help(
+                    "Every clash in the archive is checked to be valid before any of them are saved. Stored clashes \
+                    that are different in the archive are replaced, and the previous files can be restored with \
+                    `coctus undo`. Clashes that are only stored locally are left alone."
+                )
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Move the configuration to another machine")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("export")
+                        .about("Bundle the config file and the stub templates into a single archive")
+                        .arg(arg!(<FILE> "archive to write (a gzipped tarball)").value_parser(value_parser!(PathBuf)))
+                        .arg(arg!(--"force" "overwrite FILE if it exists"))
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Restore the configuration of an archive made by `coctus config export`")
+                        .arg(arg!(<FILE> "archive to import").value_parser(value_parser!(PathBuf)))
+                )
+                .after_help(
+                    "The archive contains config.toml (with the build and run commands of the languages) and the \
+                    stub templates that override the built-in ones, but not the login of `coctus login`.\
+                    \nThe archive records the version of the config file format. Importing an archive made by an \
+                    older version of coctus upgrades the config file, and the config file and every stub config \
+                    are checked to be valid before anything is written. Files that the import replaces can be \
+                    restored with `coctus undo`.\
+                    \nExample:\
+                    \n  $ coctus config export config.tar.gz\
+                    \n  $ coctus config import config.tar.gz"
+                )
+        )
+        .subcommand(
+            Command::new("verify-store")
+                .about("Check the locally stored clashes for problems")
+                .arg(arg!(--"fix" "rename or remove files to fix the problems when possible"))
+                .after_help(
+                    "Reports stored .json files that can't be read or are not valid clash JSON, files whose name \
+                    does not match the publicHandle they contain, and duplicate clashes whose handles differ \
+                    only in case.\
+                    \nWith --fix, misnamed files are renamed and identical duplicates are removed (`coctus undo` \
+                    brings them back). Invalid files are never touched."
+                )
+        )
         .subcommand(
             Command::new("generate-shell-completion")
                 .about("Generate shell completion")
@@ -185,321 +1442,3328 @@ fn cli() -> clap::Command {
 }
 
 struct App {
+    config: Config,
+    config_file: PathBuf,
+    storage: Storage,
     clash_dir: PathBuf,
     current_clash_file: PathBuf,
+    session_file: PathBuf,
+    clash_session_file: PathBuf,
+    reverse_games_file: PathBuf,
+    tags_file: PathBuf,
+    /// Tags of the user alone, like the `review` queue. They apply on top of
+    /// the tags of the (possibly shared) collection.
+    user_tags_file: PathBuf,
+    history_file: PathBuf,
+    last_run_file: PathBuf,
+    remembered_runs_file: PathBuf,
+    search_index_file: PathBuf,
+    calibration_file: PathBuf,
+    login_file: PathBuf,
+    backup_dir: PathBuf,
+    solution_file: PathBuf,
     stub_templates_dir: PathBuf,
+    custom_testcases_dir: PathBuf,
+    overlays: Overlays,
+    changelog: Journal,
+    build_dirs: BuildDirs,
+    trash: Trash,
 }
 
 impl App {
-    fn new(data_dir: &std::path::Path, config_dir: &std::path::Path) -> App {
-        App {
-            clash_dir: data_dir.join("clashes"),
-            current_clash_file: data_dir.join("current"),
+    fn new(data_dir: &std::path::Path, config_dir: &std::path::Path, config: Config) -> Result<App> {
+        let config_file = Config::file(config_dir);
+        let shared_dir = std::env::var_os("COCTUS_SHARED_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| config.shared_dir.clone());
+        let storage = Storage::new(data_dir, shared_dir.as_deref());
+        let (collection_dir, user_dir) = (storage.collection_dir(), storage.user_dir());
+        Ok(App {
+            clash_dir: storage.clash_dir(),
+            tags_file: collection_dir.join("tags.json"),
+            user_tags_file: user_dir.join("user_tags.json"),
+            current_clash_file: user_dir.join("current"),
+            session_file: user_dir.join("session.json"),
+            clash_session_file: user_dir.join("clash_session.json"),
+            reverse_games_file: user_dir.join("reverse_games.json"),
+            history_file: user_dir.join("history.jsonl"),
+            last_run_file: user_dir.join("last_run.json"),
+            remembered_runs_file: user_dir.join("remembered_runs.json"),
+            search_index_file: user_dir.join("search_index.json"),
+            calibration_file: user_dir.join("calibration.json"),
+            backup_dir: user_dir.join("backups"),
+            solution_file: user_dir.join("solution.json"),
             stub_templates_dir: config_dir.join("stub_templates"),
+            login_file: config_dir.join("login.json"),
+            custom_testcases_dir: user_dir.join("custom_testcases"),
+            overlays: Overlays::new(user_dir.join("overlays")),
+            changelog: Journal::new(user_dir.join("changelog.jsonl")),
+            build_dirs: BuildDirs::new(user_dir.join("build")),
+            trash: Trash::new(user_dir.join("trash")),
+            config,
+            config_file,
+            storage,
+        })
+    }
+
+    /// [OutputStyle::from_env] with the `[theme]` of the config file.
+    fn ostyle(&self, show_whitespace: bool) -> OutputStyle {
+        OutputStyle::themed(show_whitespace, &self.config.theme)
+    }
+
+    /// The workspace that the working directory is in, if any.
+    fn workspace(&self) -> Result<Option<Workspace>> {
+        Workspace::find(&std::env::current_dir()?)
+    }
+
+    /// The clash that commands default to: the clash of the workspace the
+    /// working directory is in, or the clash selected with `next`.
+    fn current_handle(&self) -> Result<PublicHandle> {
+        match self.workspace()? {
+            Some(workspace) => PublicHandle::from_str(&workspace.handle),
+            None => self.selected_handle(),
+        }
+    }
+
+    // This may fail the very first time we call `show` if `next` was never run.
+    fn selected_handle(&self) -> Result<PublicHandle> {
+        let content = std::fs::read_to_string(&self.current_clash_file).with_context(|| {
+            format!(
+                "Unable to read {:?} (no clash has been selected yet, pick one with `coctus next`)",
+                &self.current_clash_file
+            )
+        })?;
+        PublicHandle::from_str(&content).with_context(|| {
+            format!(
+                "Invalid handle in {:?} (select a clash with `coctus current HANDLE`)",
+                &self.current_clash_file
+            )
+        })
+    }
+
+    /// Selects the clash that commands default to outside of workspaces.
+    fn select_handle(&self, handle: &PublicHandle) -> Result<()> {
+        std::fs::write(&self.current_clash_file, format!("{handle}\n"))
+            .with_context(|| format!("Unable to write {:?}", &self.current_clash_file))
+    }
+
+    /// The clash given as the PUBLIC_HANDLE argument, or the current clash.
+    fn handle_arg(&self, args: &ArgMatches) -> Result<PublicHandle> {
+        match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(handle) => self.resolve_handle(handle),
+            None => self.current_handle(),
         }
     }
 
-    // This may fail the very first time we call `show` if `next` was never run.
-    fn current_handle(&self) -> Result<PublicHandle> {
-        let content = std::fs::read_to_string(&self.current_clash_file)
-            .with_context(|| format!("Unable to read {:?}", &self.current_clash_file))?;
-        PublicHandle::from_str(&content)
-    }
+    /// Expands `handle` to the full handle of the stored clash it is a prefix
+    /// of. Handles that don't match any stored clash are returned as is so
+    /// that they can still be fetched.
+    fn resolve_handle(&self, handle: &PublicHandle) -> Result<PublicHandle> {
+        if !self.clash_dir.exists() || self.clash_dir.join(format!("{handle}.json")).exists() {
+            return Ok(handle.to_owned())
+        }
+        let stored: Vec<String> = self.storage.stored_handles()?.map(|handle| handle.to_string()).collect();
+        match resolve_handle_prefix(&handle.to_string(), stored.iter().map(String::as_str))? {
+            Some(full_handle) => PublicHandle::from_str(full_handle),
+            None => Ok(handle.to_owned()),
+        }
+    }
+
+    /// Whether fetching from codingame.com is disabled.
+    fn offline(&self) -> bool {
+        env_flag("COCTUS_OFFLINE") || self.config.offline
+    }
+
+    /// Whether `show` and `run` should download clashes that are missing.
+    fn fetch_missing(&self, args: &ArgMatches) -> bool {
+        args.get_flag("fetch-missing") || env_flag("COCTUS_FETCH_MISSING") || self.config.fetch_missing
+    }
+
+    fn numbering(&self, args: &ArgMatches) -> Numbering {
+        args.get_one::<Numbering>("numbering").copied().unwrap_or(self.config.numbering)
+    }
+
+    /// The testcases picked with --tests, --validators, --all or the
+    /// selection expressions of the `expressions` argument, `None` if none
+    /// of them were given.
+    fn test_selection(&self, args: &ArgMatches, expressions: &str) -> Result<Option<TestSelection>> {
+        if args.get_flag("tests") {
+            Ok(Some(TestSelection::tests()))
+        } else if args.get_flag("validators") {
+            Ok(Some(TestSelection::validators()))
+        } else if args.get_flag("all") {
+            Ok(Some(TestSelection::all()))
+        } else {
+            let selectors = args.get_many::<String>(expressions);
+            selectors
+                .map(|selectors| self.numbering(args).selection(selectors.map(String::as_str)))
+                .transpose()
+        }
+    }
+
+    fn random_handle(&self) -> Result<PublicHandle> {
+        let mut rng = rand::thread_rng();
+        self.storage.stored_handles()?.choose(&mut rng).with_context(|| {
+            format!(
+                "No clashes stored in {:?} (fetch some with `coctus fetch` or `coctus init --starter-pack`)",
+                &self.clash_dir
+            )
+        })
+    }
+
+    fn random_handle_with_modes(
+        &self,
+        fastest: bool,
+        shortest: bool,
+        reverse: bool,
+        statement_lang: Option<&str>,
+        max_read_time: Option<std::time::Duration>,
+    ) -> Result<PublicHandle> {
+        // For the error message if there are no stored clashes at all
+        let _ = self.storage.stored_handles()?;
+        let (index, _) = self.updated_search_index()?;
+        let modes: Vec<&str> = [("fastest", fastest), ("shortest", shortest), ("reverse", reverse)]
+            .into_iter()
+            .filter_map(|(mode, required)| required.then_some(mode))
+            .collect();
+        let handle = index
+            .clashes()
+            .filter(|(_, clash)| {
+                modes.iter().all(|mode| clash.modes.iter().any(|m| m == mode))
+                    && statement_lang.map_or(true, |lang| clash.statement_language.as_deref() == Some(lang))
+                    && max_read_time.map_or(true, |max| clash.reading_time() <= max)
+            })
+            .map(|(handle, _)| handle)
+            .choose(&mut rand::thread_rng())
+            .context("No stored clash has the required modes, statement language and reading time")?;
+        PublicHandle::from_str(handle)
+    }
+
+    fn random_handle_with_tag(&self, tag: &str) -> Result<PublicHandle> {
+        let tags = Tags::load(&self.tags_file)?;
+        let user_tags = Tags::load(&self.user_tags_file)?;
+        let handles: BTreeSet<&str> = tags.handles(tag).chain(user_tags.handles(tag)).collect();
+        let handle = handles
+            .into_iter()
+            .choose(&mut rand::thread_rng())
+            .with_context(|| format!("No clashes tagged with {:?}", tag))?;
+        PublicHandle::from_str(handle)
+    }
+
+    fn read_clash(&self, handle: &PublicHandle) -> Result<Clash> {
+        let clash_file = self.clash_dir.join(format!("{}.json", handle));
+        let contents = std::fs::read_to_string(&clash_file)
+            .with_context(|| format!("Unable to find clash with handle {}", handle))?;
+        let clash: Clash = serde_json::from_str(&contents)
+            .with_context(|| format!("Unable to deserialize clash from {:?}", &clash_file))?;
+        Ok(clash)
+    }
+
+    /// Same as `read_clash` but if the clash is not stored locally it gets
+    /// downloaded first when `fetch_missing` is true.
+    fn read_or_fetch_clash(&self, handle: &PublicHandle, fetch_missing: bool) -> Result<Clash> {
+        self.fetch_if_missing(handle, fetch_missing)?;
+        self.read_clash(handle)
+    }
+
+    /// Only the testcases of `read_or_fetch_clash`, which is considerably
+    /// faster to load for clashes with long statements.
+    /// The snapshot tells if the clash file changes after it was read.
+    fn read_or_fetch_testcases(
+        &self,
+        handle: &PublicHandle,
+        fetch_missing: bool,
+    ) -> Result<(Vec<Testcase>, ClashSnapshot)> {
+        self.fetch_if_missing(handle, fetch_missing)?;
+        let clash_file = self.clash_dir.join(format!("{}.json", handle));
+        let contents = std::fs::read_to_string(&clash_file)
+            .with_context(|| format!("Unable to find clash with handle {}", handle))?;
+        let testcases = clash::testcases_from_json(&contents)
+            .with_context(|| format!("Unable to deserialize testcases from {:?}", &clash_file))?;
+        Ok((testcases, ClashSnapshot::new(&clash_file, contents.as_bytes())))
+    }
+
+    fn fetch_if_missing(&self, handle: &PublicHandle, fetch_missing: bool) -> Result<()> {
+        let clash_file = self.clash_dir.join(format!("{}.json", handle));
+        if !clash_file.exists() {
+            if !fetch_missing {
+                return Err(anyhow!(
+                    "Clash {} is not stored locally (fetch it with `coctus fetch {}` or use --fetch-missing)",
+                    handle,
+                    handle
+                ))
+            }
+            let mut operation = self.trash.begin(format!("fetch clash {handle}"));
+            let clash_file_path = self.download_clash(handle, &mut operation)?;
+            self.trash.commit(operation)?;
+            // Not stdout so that the output of `show` can still be piped
+            eprintln!("Saved clash {} as {}", &handle, &clash_file_path.display());
+        }
+        Ok(())
+    }
+
+    fn show(&self, args: &ArgMatches) -> Result<()> {
+        let handle = self.handle_arg(args)?;
+        let clash = self.read_or_fetch_clash(&handle, self.fetch_missing(args))?;
+
+        let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
+        let mut ostyle = self.ostyle(show_whitespace);
+        // A wrapped line would break up ASCII art and tables
+        use std::io::IsTerminal;
+        ostyle.max_monospace_width = match args.get_one::<usize>("width").copied() {
+            Some(0) => None,
+            Some(width) => Some(width),
+            None if std::io::stdout().is_terminal() => Some(terminal_size().0),
+            None => None,
+        };
+
+        if args.get_flag("lint") {
+            return match ostyle.print_lint_report(&clash) {
+                0 => {
+                    println!("No formatting problems found");
+                    Ok(())
+                }
+                1 => Err(anyhow!("Found 1 formatting problem")),
+                n => Err(anyhow!("Found {} formatting problems", n)),
+            }
+        }
+
+        if let Some(pattern) = args.get_one::<String>("grep") {
+            let has_uppercase = pattern.chars().any(char::is_uppercase);
+            let regex = regex::RegexBuilder::new(pattern)
+                .case_insensitive(!has_uppercase)
+                .build()
+                .with_context(|| format!("Invalid --grep pattern {pattern:?}"))?;
+            let context = *args.get_one::<usize>("context").expect("--context should have a default");
+            return match ostyle.print_grep(&clash, &regex, context) {
+                0 => Err(anyhow!("No lines match {pattern:?}")),
+                _ => Ok(()),
+            }
+        }
+
+        // --reverse flag
+        if args.get_flag("reverse") {
+            if clash.is_reverse() {
+                ostyle.print_reverse_mode(&clash);
+                return Ok(())
+            } else {
+                return Err(anyhow::Error::msg("The clash doesn't have a reverse mode"))
+            }
+        }
+
+        if let Some(html) = clash.statement_html().filter(|_| args.get_flag("prefer-html")) {
+            ostyle.print_headers(&clash);
+            println!("{}", format_statement_html(html, &ostyle));
+            return Ok(())
+        }
+
+        // --only-* flags print a single section without the headers
+        let only_sections: Option<&[StatementSection]> = if args.get_flag("only-statement") {
+            // The goal and rules of a classic puzzle are part of its statement
+            Some(&[
+                StatementSection::Goal,
+                StatementSection::Statement,
+                StatementSection::Rules,
+            ])
+        } else if args.get_flag("only-io-descriptions") {
+            Some(&[StatementSection::IoDescriptions])
+        } else if args.get_flag("constraints-only") {
+            Some(&[StatementSection::Constraints])
+        } else {
+            None
+        };
+
+        if let Some(sections) = only_sections {
+            ostyle.print_sections(&clash, sections);
+        } else if clash.is_reverse_only() {
+            // If the clash is reverse only, print the headers and testcases.
+            ostyle.print_reverse_mode(&clash);
+        } else if args.get_flag("no-example") {
+            ostyle.print_headers(&clash);
+            let sections: Vec<StatementSection> = StatementSection::ALL
+                .iter()
+                .copied()
+                .filter(|section| *section != StatementSection::Example)
+                .collect();
+            ostyle.print_sections(&clash, &sections);
+        } else {
+            ostyle.print_headers(&clash);
+            ostyle.print_statement(&clash);
+        }
+
+        Ok(())
+    }
+
+    fn next(&self, args: &ArgMatches) -> Result<()> {
+        let next_handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => self.resolve_handle(h)?,
+            None => {
+                let fastest = args.get_flag("fastest");
+                let shortest = args.get_flag("shortest");
+                let reverse = args.get_flag("reverse");
+                if let Some(tag) = args.get_one::<String>("tag") {
+                    self.random_handle_with_tag(tag)?
+                } else if reverse
+                    || fastest
+                    || shortest
+                    || args.contains_id("statement-lang")
+                    || args.contains_id("max-read-time")
+                {
+                    let statement_lang = args.get_one::<String>("statement-lang").map(String::as_str);
+                    let max_read_time = args.get_one::<std::time::Duration>("max-read-time").copied();
+                    self.random_handle_with_modes(fastest, shortest, reverse, statement_lang, max_read_time)?
+                } else {
+                    self.random_handle()?
+                }
+            }
+        };
+        let ostyle = self.ostyle(false);
+        let link = format!("https://codingame.com/contribute/view/{}", next_handle);
+        let clash_file = self.clash_dir.join(format!("{}.json", next_handle));
+        println!(" Changed clash to {}", ostyle.hyperlink(&link, &link));
+        println!(" Local file: {}", ostyle.hyperlink(&file_url(&clash_file), clash_file.display()));
+        self.select_handle(&next_handle)?;
+        Ok(())
+    }
+
+    /// The build directory of the clash for its build and run commands.
+    /// Build directories that have not been used in a while are deleted
+    /// first.
+    fn build_dir(&self, handle: &PublicHandle) -> Result<PathBuf> {
+        let now = std::time::SystemTime::now();
+        let max_age_days = self.config.build_dir_max_age_days.unwrap_or(7);
+        self.build_dirs.clean(std::time::Duration::from_secs(max_age_days * 86400), now)?;
+        self.build_dirs.prepare(&handle.to_string(), now)
+    }
+
+    /// Applies the testcase overlays of the clash unless `--no-overlays` was
+    /// given, see [`Overlays`] for the precedence rules.
+    fn apply_overlays(
+        &self,
+        handle: &PublicHandle,
+        testcases: &mut Vec<Testcase>,
+        args: &ArgMatches,
+    ) -> Result<BTreeMap<usize, Provenance>> {
+        if args.get_flag("no-overlays") {
+            return Ok(BTreeMap::new())
+        }
+        self.overlays.apply(&handle.to_string(), testcases)
+    }
+
+    fn addtest(&self, args: &ArgMatches) -> Result<()> {
+        let handle = self.current_handle()?;
+        let overlay = overlay_name(args)?;
+        let read = |arg: &str| -> Result<String> {
+            let path = args.get_one::<PathBuf>(arg).expect("clap should require the file");
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Unable to read --{arg} {:?}", path))?;
+            Ok(contents.trim_end_matches(['\r', '\n']).to_string())
+        };
+        let title = match (args.get_one::<String>("replace"), args.get_one::<String>("title")) {
+            (Some(selector), _) => {
+                let (testcases, _) = self.read_or_fetch_testcases(&handle, false)?;
+                testcases[self.numbering(args).resolve(&testcases, selector)?].title.clone()
+            }
+            (None, Some(title)) => title.to_owned(),
+            (None, None) => {
+                let num_tests = self.overlays.load(&handle.to_string(), overlay)?.len();
+                format!("Overlay test {}", num_tests + 1)
+            }
+        };
+        let testcase = Testcase {
+            index: 0,
+            number: 0,
+            title,
+            test_in: read("input")?,
+            test_out: read("output")?,
+            is_validator: false,
+        };
+        let title = testcase.title.clone();
+        self.overlays.add(&handle.to_string(), overlay, testcase)?;
+        println!("Saved {title:?} in overlay {overlay} of clash {handle}");
+        let description = format!("added testcase {title:?} to overlay {overlay}");
+        self.log_change("addtest", description, vec![handle.to_string()])?;
+        Ok(())
+    }
+
+    fn import_tests(&self, args: &ArgMatches) -> Result<()> {
+        let handle = self.current_handle()?;
+        let overlay = overlay_name(args)?;
+        let source = args.get_one::<PathBuf>("FILE").expect("FILE should be required");
+        let num_testcases = self.overlays.import(&handle.to_string(), overlay, source)?;
+        println!("Imported {num_testcases} testcases into overlay {overlay} of clash {handle}");
+        let description = format!("imported {num_testcases} testcases into overlay {overlay}");
+        self.log_change("import-tests", description, vec![handle.to_string()])?;
+        Ok(())
+    }
+
+    fn custom_testcases_path(&self, handle: &PublicHandle) -> PathBuf {
+        self.custom_testcases_dir.join(format!("{handle}.json"))
+    }
+
+    fn tweak(&self, args: &ArgMatches) -> Result<()> {
+        let handle = self.current_handle()?;
+        let (mut testcases, _) = self.read_or_fetch_testcases(&handle, false)?;
+        self.apply_overlays(&handle, &mut testcases, args)?;
+        let custom_path = self.custom_testcases_path(&handle);
+        testcases.extend(CustomTestcases::load(&custom_path, &testcases)?);
+        let selector = args.get_one::<String>("testcase").map_or("1", String::as_str);
+        let testcase = &testcases[self.numbering(args).resolve(&testcases, selector)?];
+
+        let build_dir = self.build_dir(&handle)?;
+        let (build_command_arg, run_command_arg, dir) = match args.get_one::<String>("command") {
+            Some(command) => (args.get_one::<String>("build-command").cloned(), command.to_owned(), None),
+            None => {
+                let (build_command, run_command, remembered) = self.default_commands(&handle, &build_dir)?;
+                let dir = remembered.dir().map(std::path::Path::to_owned);
+                (args.get_one::<String>("build-command").cloned().or(build_command), run_command, dir)
+            }
+        };
+        let build_command_arg = build_command_arg
+            .map(|cmd| expand_path_placeholder(&cmd, "{build_dir}", &build_dir))
+            .transpose()?;
+        let run_command_arg = expand_path_placeholder(&run_command_arg, "{build_dir}", &build_dir)?;
+
+        let input_file = tempfile::Builder::new().prefix("coctus-input-").suffix(".txt").tempfile()?;
+        std::fs::write(input_file.path(), format!("{}\n", testcase.test_in))?;
+        let status = open_in_editor(&[input_file.path()])?;
+        if !status.success() {
+            return Err(anyhow!("The editor exited with {status}, not running the solution"))
+        }
+        let edited_input = std::fs::read_to_string(input_file.path())?;
+
+        build_solution(build_command_arg.as_ref(), &build_dir, dir.as_deref())?;
+        let mut run_command = command_from_argument(Some(&run_command_arg))?.context("Empty --command")?;
+        if let Some(dir) = dir {
+            run_command.current_dir(dir);
+        }
+        let mut child = run_command
+            .env("COCTUS_BUILD_DIR", &build_dir)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Unable to run {run_command_arg:?}"))?;
+        let mut stdin = child.stdin.take().expect("stdin should be piped");
+        // Written from another thread so that a solution printing a lot before
+        // reading all of its input can't block us. Solutions don't have to
+        // read all of it, so failing to write is fine.
+        let input = edited_input.clone();
+        let writer = std::thread::spawn(move || {
+            use std::io::Write;
+            let _ = stdin.write_all(input.as_bytes());
+        });
+        let output = child.wait_with_output()?;
+        writer.join().expect("writing the input should not panic");
+
+        let ostyle = self.ostyle(false);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        println!(
+            "{}",
+            ostyle
+                .secondary_title
+                .paint(format!("===== STDOUT ({}) =====", ostyle.numbering.label(testcase)))
+        );
+        println!("{}", stdout.trim_end());
+        if !output.status.success() {
+            println!("{} {}", ostyle.error.paint("The solution exited with"), output.status);
+        }
+
+        if args.get_flag("save-as-custom") {
+            let num_custom = CustomTestcases::load(&custom_path, &[])?.len();
+            let custom = Testcase {
+                index: 0,
+                number: 0,
+                title: format!("Custom {}", num_custom + 1),
+                test_in: edited_input.trim_end().to_string(),
+                test_out: stdout.replace("\r\n", "\n").trim_end().to_string(),
+                is_validator: false,
+            };
+            CustomTestcases::append(&custom_path, &custom)?;
+            println!("Saved the input as {:?} (run it with `coctus run --custom`)", custom.title);
+        }
+        Ok(())
+    }
+
+    fn open_test(&self, args: &ArgMatches) -> Result<()> {
+        let handle = self.current_handle()?;
+        let (mut testcases, _) = self.read_or_fetch_testcases(&handle, false)?;
+        self.overlays.apply(&handle.to_string(), &mut testcases)?;
+        testcases.extend(CustomTestcases::load(&self.custom_testcases_path(&handle), &testcases)?);
+        let selector = args.get_one::<String>("TESTCASE").map_or("1", String::as_str);
+        let testcase = &testcases[self.numbering(args).resolve(&testcases, selector)?];
+
+        let files = TestcaseFiles::write(&self.build_dir(&handle)?.join("testcases"), testcase)?;
+        if args.get_flag("edit") {
+            let status = open_in_editor(&[&files.input, &files.expected_output])?;
+            if !status.success() {
+                return Err(anyhow!("The editor exited with {status}"))
+            }
+            return Ok(())
+        }
+        println!("{}\n{}", files.input.display(), files.expected_output.display());
+        Ok(())
+    }
+
+    fn tui(&self, args: &ArgMatches) -> Result<()> {
+        let handle = self.handle_arg(args)?;
+        let clash = self.read_clash(&handle)?;
+        let mut testcases = clash.testcases().to_owned();
+        self.overlays.apply(&handle.to_string(), &mut testcases)?;
+
+        let build_dir = self.build_dir(&handle)?;
+        let (build_command_arg, run_command_arg, watched, dir) = match args.get_one::<String>("command") {
+            Some(command) => (
+                args.get_one::<String>("build-command").cloned(),
+                command.to_owned(),
+                Vec::new(),
+                None,
+            ),
+            None => {
+                let (build_command, run_command, remembered) = self.default_commands(&handle, &build_dir)?;
+                let (watched, dir) = match remembered {
+                    RememberedRun::Solution { path, .. } => (vec![path], None),
+                    RememberedRun::Command { dir, .. } => (Vec::new(), dir),
+                };
+                (
+                    args.get_one::<String>("build-command").cloned().or(build_command),
+                    run_command,
+                    watched,
+                    dir,
+                )
+            }
+        };
+        let watched = match args.get_many::<String>("watch") {
+            Some(paths) => paths.map(PathBuf::from).collect(),
+            None if watched.is_empty() => {
+                return Err(anyhow!("Nothing to watch for changes, use --watch to pick the files"))
+            }
+            None => watched,
+        };
+        let build_command_arg = build_command_arg
+            .map(|cmd| expand_path_placeholder(&cmd, "{build_dir}", &build_dir))
+            .transpose()?;
+        let run_command_arg = expand_path_placeholder(&run_command_arg, "{build_dir}", &build_dir)?;
+        let mut run_command = command_from_argument(Some(&run_command_arg))?.context("Empty --command")?;
+        run_command.env("COCTUS_BUILD_DIR", &build_dir);
+        if let Some(dir) = &dir {
+            run_command.current_dir(dir);
+        }
+        let timeout = match *args.get_one::<f64>("timeout").unwrap_or(&5.0) {
+            secs if secs.is_nan() || secs <= 0.0 => {
+                return Err(anyhow!("Timeout has to be a positive number of seconds"))
+            }
+            secs => std::time::Duration::from_micros((secs * 1e6) as u64),
+        };
+
+        let ostyle = self.ostyle(false);
+        let numbering = self.numbering(args);
+        let watched_names =
+            watched.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(", ");
+        let mut screen = TuiScreen {
+            title: format!("=== {} ===", clash.title()),
+            statement: OutputStyle::plain().statement_text(&clash),
+            testcases: testcases
+                .iter()
+                .map(|testcase| (numbering.label(testcase), TestStatus::Pending))
+                .collect(),
+            message: String::new(),
+        };
+        let draw = |screen: &TuiScreen| -> Result<()> {
+            use std::io::Write;
+            let (width, height) = terminal_size();
+            let mut stdout = std::io::stdout().lock();
+            write!(stdout, "\x1b[H\x1b[2J{}", screen.render(&ostyle, width, height).join("\n"))?;
+            stdout.flush()?;
+            Ok(())
+        };
+        let notify: Vec<Notify> = args.get_many::<Notify>("notify").unwrap_or_default().copied().collect();
+        let mut all_passed_before = None;
+        let mut watcher = FileWatcher::new(watched);
+        loop {
+            for (_, status) in &mut screen.testcases {
+                *status = TestStatus::Pending;
+            }
+            screen.message = "Building...".to_string();
+            draw(&screen)?;
+            let build_error = match command_from_argument(build_command_arg.as_ref())? {
+                Some(mut build_command) => {
+                    if let Some(dir) = &dir {
+                        build_command.current_dir(dir);
+                    }
+                    let build = build_command.env("COCTUS_BUILD_DIR", &build_dir).output()?;
+                    let stderr = String::from_utf8_lossy(&build.stderr);
+                    let first_line = stderr.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+                    (!build.status.success()).then(|| format!("Build failed: {first_line}"))
+                }
+                None => None,
+            };
+            let mut all_passed = false;
+            screen.message = match build_error {
+                Some(error) => error,
+                None => {
+                    if let Some((_, status)) = screen.testcases.first_mut() {
+                        *status = TestStatus::Running;
+                    }
+                    screen.message = "Running...".to_string();
+                    draw(&screen)?;
+                    let options = solution::RunOptions::new(timeout);
+                    let mut num_passed = 0;
+                    let results = solution::lazy_run_with_options(&testcases, &mut run_command, options);
+                    for (idx, (testcase, test_result)) in results.into_iter().enumerate() {
+                        let status = TestStatus::of(testcase, &test_result);
+                        num_passed += usize::from(status == TestStatus::Passed);
+                        screen.testcases[idx].1 = status;
+                        if let Some((_, next_status)) = screen.testcases.get_mut(idx + 1) {
+                            *next_status = TestStatus::Running;
+                        }
+                        draw(&screen)?;
+                    }
+                    all_passed = num_passed == testcases.len();
+                    format!("{num_passed}/{} passed", testcases.len())
+                }
+            };
+            let transition = Transition::between(all_passed_before, all_passed);
+            all_passed_before = Some(all_passed);
+            let sequences = notification(&notify, transition, &screen.message);
+            screen.message += &format!(" - watching {watched_names} for changes (Ctrl-C to quit)");
+            draw(&screen)?;
+            if !sequences.is_empty() {
+                use std::io::Write;
+                let mut stdout = std::io::stdout().lock();
+                write!(stdout, "{sequences}")?;
+                stdout.flush()?;
+            }
+            while !watcher.changed() {
+                std::thread::sleep(std::time::Duration::from_millis(250));
+            }
+        }
+    }
+
+    fn current(&self, args: &ArgMatches) -> Result<()> {
+        let Some(handle) = args.get_one::<PublicHandle>("PUBLIC_HANDLE") else {
+            println!("{}", self.selected_handle()?);
+            if let Some(workspace) = self.workspace()? {
+                eprintln!("Note: commands run in {} use clash {}", workspace.dir.display(), workspace.handle);
+            }
+            return Ok(())
+        };
+        let handle = self.resolve_handle(handle)?;
+        if !self.clash_dir.join(format!("{handle}.json")).exists() {
+            return Err(anyhow!(
+                "Clash {handle} is not stored locally (fetch it with `coctus fetch {handle}`)"
+            ))
+        }
+        self.select_handle(&handle)?;
+        println!("Changed clash to {handle}");
+        Ok(())
+    }
+
+    fn status(&self, _args: &ArgMatches) -> Result<()> {
+        println!("Current clash file: {}", self.current_clash_file.display());
+        match self.selected_handle() {
+            Ok(handle) => println!("Current clash: {}", handle),
+            Err(_) => println!("Current clash: -"),
+        }
+        if let Some(workspace) = self.workspace()? {
+            println!("Workspace: {} (clash {})", workspace.dir.display(), workspace.handle);
+        }
+        match self.storage.is_shared() {
+            true => println!("Clash dir: {} (shared, read-only)", self.clash_dir.display()),
+            false => println!("Clash dir: {}", self.clash_dir.display()),
+        }
+        let num_clashes = self.storage.stored_handles().map_or(0, |handles| handles.count());
+        println!("Number of clashes: {}", num_clashes);
+        let remembered = match self.current_handle() {
+            Ok(handle) => RememberedRuns::load(&self.remembered_runs_file)?.get(&handle.to_string()).cloned(),
+            Err(_) => None,
+        };
+        match remembered {
+            Some(remembered) => println!("Solution of the current clash: {remembered}"),
+            None => println!("Solution of the current clash: -"),
+        }
+        match self.login_file.exists() {
+            true => println!("Logged in to CodinGame: yes ({})", self.login_file.display()),
+            false => println!("Logged in to CodinGame: no"),
+        }
+        Ok(())
+    }
+
+    fn clash(&self, args: &ArgMatches) -> Result<()> {
+        let now = std::time::SystemTime::now();
+        match args.subcommand() {
+            Some(("start", args)) => {
+                let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+                    Some(handle) => {
+                        let handle = self.resolve_handle(handle)?;
+                        self.select_handle(&handle)?;
+                        handle
+                    }
+                    None => self.current_handle()?,
+                };
+                let clash = self.read_clash(&handle)?;
+                if let Some(previous) = ClashSession::load(&self.clash_session_file)? {
+                    eprintln!("Replacing the session of clash {} ({})", previous.handle, previous.clock(now));
+                }
+                let time_limit = *args
+                    .get_one::<std::time::Duration>("time-limit")
+                    .expect("--time-limit has a default");
+                let session = ClashSession::start(handle.to_string(), time_limit, now);
+                session.save(&self.clash_session_file)?;
+
+                let ostyle = self.ostyle(false);
+                ostyle.print_headers(&clash);
+                ostyle.print_statement(&clash);
+                println!(
+                    "\n{} {} to solve the clash. Run the tests with `coctus run`, `coctus clash status --follow` \
+                    counts down the time left.",
+                    ostyle.title.paint("Started:"),
+                    format_clock(session.time_limit())
+                );
+                Ok(())
+            }
+            Some(("status", args)) => {
+                let Some(session) = ClashSession::load(&self.clash_session_file)? else {
+                    println!("No clash session running (start one with `coctus clash start`)");
+                    return Ok(())
+                };
+                if !args.get_flag("follow") {
+                    println!("Clash {}: {}", session.handle, session.clock(now));
+                    return Ok(())
+                }
+                use std::io::Write;
+                loop {
+                    let now = std::time::SystemTime::now();
+                    print!("\r\x1b[KClash {}: {}", session.handle, session.clock(now));
+                    std::io::stdout().flush()?;
+                    // Ends when the time is up or `coctus run` solves the clash
+                    let current = ClashSession::load(&self.clash_session_file)?;
+                    if session.remaining(now).is_none() || current.as_ref() != Some(&session) {
+                        println!();
+                        return Ok(())
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(250));
+                }
+            }
+            Some(("stop", _)) => match ClashSession::load(&self.clash_session_file)? {
+                Some(session) => {
+                    ClashSession::remove(&self.clash_session_file)?;
+                    println!(
+                        "Stopped the session of clash {} after {}",
+                        session.handle,
+                        format_clock(session.elapsed(now))
+                    );
+                    Ok(())
+                }
+                None => Err(anyhow!("No clash session running")),
+            },
+            _ => Err(anyhow!("unimplemented subcommand")),
+        }
+    }
+
+    /// Reports the time left in the clash session after `coctus run`, or the
+    /// time used and the end of the session when the clash got solved.
+    fn report_clash_session(&self, handle: &PublicHandle, solved: bool) -> Result<()> {
+        let Some(session) = ClashSession::load(&self.clash_session_file)? else {
+            return Ok(())
+        };
+        if session.handle != handle.to_string() {
+            return Ok(())
+        }
+        let now = std::time::SystemTime::now();
+        let ostyle = self.ostyle(false);
+        if !solved {
+            println!("{} {}", ostyle.secondary_title.paint("Clash session:"), session.clock(now));
+            return Ok(())
+        }
+        ClashSession::remove(&self.clash_session_file)?;
+        let elapsed = session.elapsed(now);
+        let verdict = match session.remaining(now) {
+            Some(_) => ostyle.success.paint("within the limit"),
+            None => ostyle.failure.paint("over the limit"),
+        };
+        println!(
+            "{} in {} ({verdict} of {})",
+            ostyle.success.paint("Solved"),
+            format_clock(elapsed),
+            format_clock(session.time_limit())
+        );
+        Ok(())
+    }
+
+    fn login(&self, args: &ArgMatches) -> Result<()> {
+        let cookie = match args.get_one::<String>("cookie") {
+            Some(cookie) => cookie.to_owned(),
+            None => {
+                use std::io::IsTerminal;
+                if std::io::stdin().is_terminal() {
+                    eprint!("Paste the cookie of a browser that is logged in to codingame.com: ");
+                }
+                let mut cookie = String::new();
+                std::io::stdin().read_line(&mut cookie)?;
+                cookie
+            }
+        };
+        Login::from_cookie(&cookie)?.save(&self.login_file)?;
+        println!(
+            "Saved the session in {}, it is sent along when fetching clashes",
+            self.login_file.display()
+        );
+        Ok(())
+    }
+
+    fn logout(&self, _args: &ArgMatches) -> Result<()> {
+        match self.login_file.exists() {
+            true => {
+                std::fs::remove_file(&self.login_file)
+                    .with_context(|| format!("Unable to remove {:?}", self.login_file))?;
+                println!("Removed the CodinGame session from {}", self.login_file.display());
+            }
+            false => println!("Not logged in to CodinGame"),
+        }
+        Ok(())
+    }
+
+    fn run(&self, args: &ArgMatches) -> Result<()> {
+        let handle = self.handle_arg(args)?;
+
+        let command_arg = args.get_one::<String>("command");
+        let inline_source = match (command_arg.map(String::as_str), args.get_one::<String>("eval")) {
+            (_, Some(source)) => Some(source.to_owned()),
+            (Some("-"), None) => {
+                let mut source = String::new();
+                std::io::stdin().read_to_string(&mut source)?;
+                Some(source)
+            }
+            _ => None,
+        };
+        let source_size = inline_source.as_ref().map(|source| source.len() as u64);
+        let mut solution_length = inline_source.as_deref().map(scoring::shortest_mode_length);
+        let mut solution_language = None;
+        let source_ext = args.get_one::<String>("source-ext").map(String::as_str);
+        // Needs to stay alive until all of the testcases have been run
+        let temp_source = inline_source.map(|source| TempSource::new(&source, source_ext)).transpose()?;
+
+        let build_dir = self.build_dir(&handle)?;
+        // Only set when replaying remembered commands from another directory
+        let mut working_dir = None;
+        let (build_command_arg, run_command_arg) = match (&temp_source, command_arg) {
+            (Some(temp_source), _) => {
+                let run_command_arg = match command_arg.map(String::as_str) {
+                    None | Some("-") => "{source}",
+                    Some(cmd) => cmd,
+                };
+                let build_command_arg = args.get_one::<String>("build-command");
+                (
+                    build_command_arg
+                        .map(|cmd| expand_path_placeholder(cmd, "{source}", temp_source.path()))
+                        .transpose()?,
+                    Some(expand_path_placeholder(run_command_arg, "{source}", temp_source.path())?),
+                )
+            }
+            (None, None) => {
+                let (build_command, run_command, remembered) = match args.get_one::<String>("language") {
+                    Some(language) => {
+                        let path = match args.get_one::<PathBuf>("file") {
+                            Some(path) => path.to_owned(),
+                            None => {
+                                let ext = self
+                                    .stub_config(language)
+                                    .with_context(|| {
+                                        format!("No file extension known for {language}, use --file")
+                                    })?
+                                    .source_file_ext()
+                                    .to_owned();
+                                PathBuf::from(conventional_solution_name(&ext))
+                            }
+                        };
+                        let solution = TrackedSolution {
+                            path: std::fs::canonicalize(&path)
+                                .with_context(|| format!("Unable to find solution file {:?}", path))?,
+                            language: language.to_owned(),
+                            handle: handle.to_string(),
+                        };
+                        self.solution_commands(&handle, &build_dir, solution)?
+                    }
+                    None => self.default_commands(&handle, &build_dir)?,
+                };
+                if let RememberedRun::Solution { path, language } = &remembered {
+                    solution_length = std::fs::read_to_string(path)
+                        .ok()
+                        .map(|source| scoring::shortest_mode_length(&source));
+                    solution_language = Some(language.to_owned());
+                }
+                working_dir = remembered.dir().map(std::path::Path::to_owned);
+                self.remember_run(&handle, remembered)?;
+                (args.get_one::<String>("build-command").cloned().or(build_command), Some(run_command))
+            }
+            (None, Some(command)) => {
+                let build_command_arg = args.get_one::<String>("build-command").cloned();
+                let remembered = RememberedRun::Command {
+                    command: command.to_owned(),
+                    build_command: build_command_arg.clone(),
+                    dir: std::env::current_dir().ok(),
+                };
+                self.remember_run(&handle, remembered)?;
+                (build_command_arg, Some(command.to_owned()))
+            }
+        };
+        let [build_command_arg, run_command_arg] = [build_command_arg, run_command_arg]
+            .map(|cmd| cmd.map(|cmd| expand_path_placeholder(&cmd, "{build_dir}", &build_dir)).transpose());
+        let (build_command_arg, run_command_arg) = (build_command_arg?, run_command_arg?);
+
+        build_solution(build_command_arg.as_ref(), &build_dir, working_dir.as_deref())?;
+
+        let mut run_command = command_from_argument(run_command_arg.as_ref())?
+            .expect("clap should ensure `run` can't be executed without a --command or --eval");
+        run_command.env("COCTUS_BUILD_DIR", &build_dir);
+        if let Some(dir) = &working_dir {
+            run_command.current_dir(dir);
+        }
+
+        // Float literal patterns warn before rustc 1.77, newer than the MSRV
+        #[allow(clippy::redundant_guards)]
+        let timeout = match *args.get_one::<f64>("timeout").unwrap_or(&5.0) {
+            secs if secs.is_nan() => return Err(anyhow!("Timeout can't be NaN")),
+            secs if secs < 0.0 => return Err(anyhow!("Timeout can't be negative (use 0 for no timeout)")),
+            secs if secs == 0.0 => std::time::Duration::MAX,
+            secs => std::time::Duration::from_micros((secs * 1e6) as u64),
+        };
+        // Only the default timeout adapts to the machine, an explicit one is
+        // taken as is
+        let timeout = match (args.value_source("timeout"), Calibration::load(&self.calibration_file)?) {
+            (Some(clap::parser::ValueSource::DefaultValue), Some(calibration)) => {
+                calibration.scale_timeout(timeout)
+            }
+            _ => timeout,
+        };
+
+        let (mut all_testcases, snapshot) =
+            self.read_or_fetch_testcases(&handle, self.fetch_missing(args))?;
+        let provenance = self.apply_overlays(&handle, &mut all_testcases, args)?;
+        if args.get_flag("custom") {
+            let custom_path = self.custom_testcases_path(&handle);
+            all_testcases.extend(CustomTestcases::load(&custom_path, &all_testcases)?);
+        }
+        if all_testcases.is_empty() {
+            return Err(anyhow!("Clash {handle} has no testcases, there is nothing to run"))
+        }
+        warn_about_testcases(&all_testcases);
+
+        let numbering = self.numbering(args);
+        let json_output = args.get_one::<String>("output-format").is_some_and(|format| format == "json");
+        let selection = self.test_selection(args, "testcases")?;
+        let testcases: Vec<&Testcase> = match &selection {
+            Some(selection) => selection.select(&all_testcases)?,
+            None if args.get_flag("failed") => {
+                let failed = match LastRun::load(&self.last_run_file)? {
+                    Some(last_run) if last_run.handle == handle.to_string() => last_run.failed,
+                    _ => {
+                        return Err(anyhow!(
+                            "Clash {handle} has not been run yet, nothing to re-run with --failed"
+                        ))
+                    }
+                };
+                // The JSON report is still printed, with no testcases
+                if failed.is_empty() && !json_output {
+                    println!("All testcases passed the last time clash {handle} was run");
+                    return Ok(())
+                }
+                all_testcases.iter().filter(|testcase| failed.contains(&testcase.index)).collect()
+            }
+            None => all_testcases.iter().collect(),
+        };
+
+        let num_tests = testcases.len();
+        let suite_timeout = args.get_one::<std::time::Duration>("suite-timeout").copied();
+        let mut run_options = solution::RunOptions::new(timeout);
+        run_options.merged_output = args.get_flag("merged-output");
+        let input_file = args.get_flag("input-file")
+            || run_command_arg.as_ref().is_some_and(|cmd| cmd.contains("{input_file}"));
+        run_options.input_file = input_file;
+        run_options.deadline = suite_timeout.and_then(|budget| std::time::Instant::now().checked_add(budget));
+
+        // Results of a shuffled run get reported in the original order
+        let shuffle_seed = match args.contains_id("shuffle") {
+            true => Some(args.get_one::<u64>("shuffle").copied().unwrap_or_else(rand::random)),
+            false => None,
+        };
+        let execution_order: Vec<usize> = match shuffle_seed {
+            Some(seed) => {
+                if !json_output {
+                    println!("Running testcases in shuffled order (--shuffle {seed})");
+                }
+                solution::shuffled_order(num_tests, seed)
+            }
+            None => (0..num_tests).collect(),
+        };
+        let progress = ProgressLine::new();
+        let in_execution_order = execution_order.iter().map(|&idx| testcases[idx]);
+        let jobs = args.get_one::<u32>("jobs").copied().unwrap_or(1);
+        let mut suite_run: Box<dyn Iterator<Item = (&Testcase, TestResult)>> = match jobs {
+            1 => Box::new(
+                solution::lazy_run_with_options(
+                    in_execution_order.inspect(|testcase| progress.show(testcase, numbering)),
+                    &mut run_command,
+                    run_options,
+                )
+                .into_iter(),
+            ),
+            jobs => {
+                Box::new(solution::parallel_run(in_execution_order, &run_command, run_options, jobs as usize))
+            }
+        };
+
+        let ignore_failures = args.get_flag("ignore-failures");
+        let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
+        let mut ostyle = self.ostyle(show_whitespace);
+        let config_diff_mode =
+            self.config.diff_style.filter(|_| std::env::var_os("COCTUS_DIFF_STYLE").is_none());
+        if let Some(diff_mode) = args.get_one::<DiffMode>("diff-style").copied().or(config_diff_mode) {
+            ostyle.diff_mode = diff_mode;
+        }
+        ostyle.diff_granularity = args.get_one::<Granularity>("diff-granularity").copied();
+        ostyle.numbering = numbering;
+        ostyle.provenance = provenance;
+
+        let mut failures = BTreeMap::new();
+        let mut passed_indices = Vec::new();
+        let mut run_indices = Vec::new();
+        let mut stopped_on_failure = false;
+        let mut aborted = false;
+        let mut deferred_results = Vec::new();
+
+        // The temporary solution file is gone by the time the user could try
+        let repro_command = run_command_arg.filter(|_| temp_source.is_none());
+        let mut reporter: Box<dyn RunReporter> = match json_output {
+            true => Box::new(JsonReporter::new(numbering, shuffle_seed)),
+            false => Box::new(TextReporter {
+                ostyle: &ostyle,
+                all_diffs: args.get_flag("all-diffs"),
+                constraints: ClashConstraints::new(|| {
+                    self.read_clash(&handle)
+                        .ok()
+                        .and_then(|clash| clash.constraints().map(clash::parse_constraints))
+                        .unwrap_or_default()
+                }),
+                reproduction_line: repro_command.map(|command| {
+                    let build_dir = build_dir.clone();
+                    Box::new(move |testcase: &Testcase| {
+                        reproduction_line(&command, testcase, input_file, &build_dir)
+                    }) as ReproductionLine
+                }),
+                failure_clusters: FailureClusters::default(),
+            }),
+        };
+
+        loop {
+            // Parallel testcases overlap, so only sequential ones are timed
+            let started_at = std::time::Instant::now();
+            // The testcases run ahead, the progress line is about the one that
+            // is reported next
+            if let Some(&position) = execution_order.get(run_indices.len()).filter(|_| jobs > 1) {
+                progress.show(testcases[position], numbering);
+            }
+            let Some((testcase, test_result)) = suite_run.next() else {
+                break
+            };
+            let duration = (jobs == 1).then(|| started_at.elapsed());
+            progress.clear();
+            let passed = test_result.is_success();
+            let category = test_result.failure_category(testcase);
+            let was_aborted = matches!(test_result, TestResult::Aborted { .. });
+            if shuffle_seed.is_some() {
+                deferred_results.push((execution_order[run_indices.len()], testcase, test_result, duration));
+            } else {
+                reporter.testcase(testcase, &test_result, duration)?;
+            }
+            run_indices.push(testcase.index);
+            if let Some(category) = category {
+                *failures.entry(category).or_default() += 1;
+            }
+
+            if passed {
+                passed_indices.push(testcase.index);
+            } else if was_aborted {
+                // The suite ends here because of the deadline, not the solution
+                aborted = true;
+            } else if !ignore_failures {
+                stopped_on_failure = true;
+                break
+            }
+        }
+        drop(suite_run);
+        // The suite may stop on the deadline after pulling the next testcase
+        progress.clear();
+        deferred_results.sort_by_key(|(position, _, _, _)| *position);
+        for (_, testcase, test_result, duration) in &deferred_results {
+            reporter.testcase(testcase, test_result, *duration)?;
+        }
+        let num_passed = passed_indices.len();
+        let num_run = run_indices.len();
+
+        let stale = snapshot.is_stale();
+        // The only other reason for the suite to stop early is the deadline
+        let suite_timed_out =
+            suite_timeout.filter(|_| !stopped_on_failure && (aborted || num_run < num_tests));
+        reporter.finish(&RunSummary {
+            handle: handle.to_string(),
+            all_testcases: &all_testcases,
+            num_tests,
+            passed_indices: &passed_indices,
+            run_indices: &run_indices,
+            failures: &failures,
+            suite_timed_out,
+            stale,
+        })?;
+        if args.get_flag("show-length") && !json_output && num_passed == num_tests {
+            match solution_length {
+                Some(length) => println!("Solution length: {length} characters"),
+                None => eprintln!(
+                    "The solution source is not known with --command, use `coctus score --shortest FILE` to count \
+                    its characters"
+                ),
+            }
+        }
+
+        if !json_output {
+            let solved = num_passed == all_testcases.len() && !stale;
+            self.report_clash_session(&handle, solved)?;
+        }
+
+        let last_run = LastRun {
+            handle: handle.to_string(),
+            failed: testcases
+                .iter()
+                .map(|testcase| testcase.index)
+                .filter(|index| !passed_indices.contains(index))
+                .collect(),
+        };
+        last_run.save(&self.last_run_file)?;
+
+        // Runs of hand-picked (or made up) testcases don't count as attempts
+        let made_up = args.get_flag("custom") || !ostyle.provenance.is_empty();
+        let hand_picked = selection.is_some_and(|selection| selection != TestSelection::all());
+        if !hand_picked && !args.get_flag("failed") && !made_up {
+            let outcome = RunOutcome {
+                num_passed,
+                num_tests,
+                failures,
+                source_size,
+                language: solution_language,
+                clash_hash: snapshot.hash().to_owned(),
+                stale,
+            };
+            self.record_run(&handle, outcome)?;
+        }
+
+        // Move on to next clash if --auto-advance is set
+        if num_passed == num_tests && !stale && args.get_flag("auto-advance") {
+            self.auto_advance(args)?;
+        }
+
+        Ok(())
+    }
+
+    /// Build and run commands for running the solution of `handle` without
+    /// --command, along with how they were picked. The solution of the
+    /// workspace or the one started for the clash with `coctus start` come
+    /// first, then the way the clash was run the previous time and finally
+    /// the tracked solution of some other clash.
+    fn default_commands(
+        &self,
+        handle: &PublicHandle,
+        build_dir: &std::path::Path,
+    ) -> Result<(Option<String>, String, RememberedRun)> {
+        let tracked = match self.workspace()? {
+            Some(workspace) => Some(TrackedSolution {
+                path: workspace.solution_path(),
+                language: workspace.language,
+                handle: workspace.handle,
+            }),
+            None => TrackedSolution::load(&self.solution_file)?,
+        };
+        let remembered = RememberedRuns::load(&self.remembered_runs_file)?.get(&handle.to_string()).cloned();
+        let solution = match (tracked, remembered) {
+            (Some(solution), _) if solution.handle == handle.to_string() => solution,
+            (
+                _,
+                Some(RememberedRun::Command {
+                    command,
+                    build_command,
+                    dir,
+                }),
+            ) => {
+                let ostyle = self.ostyle(false);
+                let in_dir = dir.as_ref().map(|dir| format!(" in {}", dir.display())).unwrap_or_default();
+                eprintln!(
+                    "{} {command}{in_dir} (as in the previous run of the clash)",
+                    ostyle.title.paint("Running")
+                );
+                let remembered = RememberedRun::Command {
+                    command: command.clone(),
+                    build_command: build_command.clone(),
+                    dir,
+                };
+                return Ok((build_command, command, remembered))
+            }
+            (_, Some(RememberedRun::Solution { language, path })) if path.exists() => TrackedSolution {
+                path,
+                language,
+                handle: handle.to_string(),
+            },
+            (tracked, _) => tracked.context(
+                "No --command given and no solution file is tracked (create one with `coctus start`)",
+            )?,
+        };
+        self.solution_commands(handle, build_dir, solution)
+    }
+
+    /// Build and run commands of a solution file from the config file or the
+    /// stub config of its language, with a warning if the file doesn't look
+    /// like a solution to the clash.
+    fn solution_commands(
+        &self,
+        handle: &PublicHandle,
+        build_dir: &std::path::Path,
+        solution: TrackedSolution,
+    ) -> Result<(Option<String>, String, RememberedRun)> {
+        let expand = |cmd: &str| -> Result<String> {
+            let cmd = expand_path_placeholder(cmd, "{source}", &solution.path)?;
+            let cmd = expand_path_placeholder(&cmd, "{file}", &solution.path)?;
+            let executable = build_dir.join(solution.path.file_stem().unwrap_or("solution".as_ref()));
+            expand_path_placeholder(&cmd, "{executable}", &executable)
+        };
+        let (build_command, run_command) = self.language_commands(&solution.language)?;
+        let run_command = expand(&run_command)?;
+        let build_command = build_command.as_deref().map(expand).transpose()?;
+
+        let ostyle = self.ostyle(false);
+        eprintln!(
+            "{} {} ({})",
+            ostyle.title.paint("Running"),
+            solution.path.display(),
+            solution.language
+        );
+        let source = std::fs::read_to_string(&solution.path)
+            .with_context(|| format!("Unable to read tracked solution {:?}", solution.path))?;
+        // Only needed to check the variables of a solution to this clash
+        let generator = || {
+            self.read_clash(handle)
+                .ok()
+                .and_then(|clash| clash.stub_generator().map(str::to_owned))
+        };
+        if solution.handle != handle.to_string() {
+            eprintln!(
+                "{} the solution was started for clash {} but you are running {}",
+                ostyle.failure.paint("WARNING"),
+                solution.handle,
+                handle
+            );
+        } else if let (Some(generator), Ok(stub_config)) = (generator(), self.stub_config(&solution.language))
+        {
+            let names = stub::variable_names(&stub_config, &generator)?;
+            if is_unrelated_solution(&source, &names) {
+                eprintln!(
+                    "{} none of the variables of the clash ({}) appear in the solution, was it written for another clash?",
+                    ostyle.failure.paint("WARNING"),
+                    names.join(", ")
+                );
+            }
+        }
+        let remembered = RememberedRun::Solution {
+            language: solution.language,
+            path: solution.path,
+        };
+        Ok((build_command, run_command, remembered))
+    }
+
+    /// Build and run commands of `language` from the config file, or the
+    /// default commands of its stub config if the config file doesn't have
+    /// the language.
+    fn language_commands(&self, language: &str) -> Result<(Option<String>, String)> {
+        if let Some(commands) = self.config.languages.get(language) {
+            let run = commands.run.clone().with_context(|| {
+                format!("[languages.{language}] of {} has no run command", self.config_file.display())
+            })?;
+            return Ok((commands.build.clone(), run))
+        }
+        let stub_config = self.stub_config(language)?;
+        let run = stub_config.run_command().with_context(|| {
+            format!(
+                "No default run command for {language}, add one to [languages.{language}] of {} or use --command",
+                self.config_file.display()
+            )
+        })?;
+        Ok((stub_config.build_command().map(str::to_owned), run.to_owned()))
+    }
+
+    fn remember_run(&self, handle: &PublicHandle, run: RememberedRun) -> Result<()> {
+        let mut remembered_runs = RememberedRuns::load(&self.remembered_runs_file)?;
+        if remembered_runs.remember(&handle.to_string(), run) {
+            remembered_runs.save(&self.remembered_runs_file)?;
+        }
+        Ok(())
+    }
+
+    fn record_run(&self, handle: &PublicHandle, outcome: RunOutcome) -> Result<()> {
+        let now = std::time::SystemTime::now();
+        // Solve times are measured from selecting the clash, which is only
+        // known for the current clash
+        let solve_time = match self.selected_handle() {
+            Ok(current) if current.to_string() == handle.to_string() => {
+                std::fs::metadata(&self.current_clash_file)
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .and_then(|started_at| now.duration_since(started_at).ok())
+                    .map(|elapsed| elapsed.as_secs())
+            }
+            _ => None,
+        };
+        let record = RunRecord {
+            handle: handle.to_string(),
+            timestamp: now.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs(),
+            num_passed: outcome.num_passed,
+            num_tests: outcome.num_tests,
+            solve_time,
+            source_size: outcome.source_size,
+            language: outcome.language,
+            clash_hash: Some(outcome.clash_hash),
+            stale: outcome.stale,
+            failures: outcome.failures,
+        };
+        History::append(&self.history_file, &record)
+    }
+
+    fn auto_advance(&self, args: &ArgMatches) -> Result<()> {
+        let now = std::time::SystemTime::now();
+        // The current clash file is written when the clash is selected so its
+        // modification time tells us when the user started solving it.
+        let clash_started_at =
+            std::fs::metadata(&self.current_clash_file).and_then(|meta| meta.modified()).ok();
+        if let Some(elapsed) = clash_started_at.and_then(|started_at| now.duration_since(started_at).ok()) {
+            println!("Solved in {}", format_duration(elapsed));
+        }
+
+        let max_clashes = args.get_one::<u32>("max-clashes").copied();
+        let total_time = args.get_one::<std::time::Duration>("total-time").copied();
+        if max_clashes.is_some() || total_time.is_some() {
+            let mut session = match AutoAdvanceSession::load(&self.session_file)? {
+                Some(session) => session,
+                None => AutoAdvanceSession::starting_at(clash_started_at.unwrap_or(now)),
+            };
+            session.clashes_solved += 1;
+            let elapsed = session.elapsed();
+
+            let solved_progress = match max_clashes {
+                Some(max) => format!("{}/{}", session.clashes_solved, max),
+                None => session.clashes_solved.to_string(),
+            };
+            let time_progress = match total_time {
+                Some(total) => format!("{} / {}", format_duration(elapsed), format_duration(total)),
+                None => format_duration(elapsed),
+            };
+            println!("Session: {solved_progress} clashes solved, {time_progress} elapsed");
+
+            let reached_max_clashes = max_clashes.is_some_and(|max| session.clashes_solved >= max);
+            let reached_total_time = total_time.is_some_and(|total| elapsed >= total);
+            if reached_max_clashes || reached_total_time {
+                // A session can end on its first run, before it was ever saved
+                match std::fs::remove_file(&self.session_file) {
+                    Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+                    _ => {}
+                }
+                println!("Session finished!");
+                return Ok(())
+            }
+            session.save(&self.session_file)?;
+        }
+
+        if let Some(break_time) = args.get_one::<std::time::Duration>("break") {
+            take_break(*break_time)?;
+        }
+
+        let next_handle = self.random_handle()?;
+        self.select_handle(&next_handle)?;
+        println!("Moving on to next clash...");
+        Ok(())
+    }
+
+    fn fetch(&self, args: &ArgMatches) -> Result<()> {
+        let mut handles: Vec<PublicHandle> = args
+            .get_many::<ClashIdentifier>("CLASH")
+            .into_iter()
+            .flatten()
+            .map(|identifier| match identifier.handle() {
+                Some(handle) => self.resolve_handle(handle),
+                None => self.resolve_identifier(identifier),
+            })
+            .collect::<Result<_>>()?;
+        if let Some(url) = args.get_one::<String>("list-url") {
+            handles.extend(self.fetch_handle_list(url)?);
+        }
+        self.fetch_handles(&handles, args.get_one::<String>("tag").map(String::as_str))
+    }
+
+    /// Downloads a list of clashes (one handle or URL per line, `#` starts a
+    /// comment line) and returns the handles in it.
+    fn fetch_handle_list(&self, url: &str) -> Result<Vec<PublicHandle>> {
+        if self.offline() {
+            return Err(anyhow!("Unable to fetch {}: fetching is disabled (offline mode)", url))
+        }
+        let list = client::fetch_text(url).with_context(|| format!("Unable to fetch list from {}", url))?;
+        let mut handles = Vec::new();
+        for line in list.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+            let identifier = match line.parse::<ClashIdentifier>() {
+                Ok(identifier) => identifier,
+                Err(_) => {
+                    eprintln!("Skipping line without a clash handle: {}", line);
+                    continue
+                }
+            };
+            // One game that can't be looked up should not spoil the whole list
+            match self.resolve_identifier(&identifier) {
+                Ok(handle) => handles.push(handle),
+                Err(err) => eprintln!("Skipping {}: {:#}", line, err),
+            }
+        }
+        Ok(handles)
+    }
+
+    /// Looks up the handle of the puzzle that a link to a game or a classic
+    /// puzzle points at.
+    fn resolve_identifier(&self, identifier: &ClashIdentifier) -> Result<PublicHandle> {
+        if let Some(handle) = identifier.handle() {
+            return Ok(handle.clone())
+        }
+        if self.offline() {
+            return Err(anyhow!("Unable to look up {identifier}: fetching is disabled (offline mode)"))
+        }
+        client::resolve_identifier(identifier)
+    }
+
+    /// Downloads all of `handles`, tagging them with `tag` if one is given.
+    fn fetch_handles(&self, handles: &[PublicHandle], tag: Option<&str>) -> Result<()> {
+        self.storage.check_writable("fetch clashes")?;
+        let mut tags = Tags::load(&self.tags_file)?;
+        let num_handles = handles.len();
+        let mut num_failed = 0;
+        let mut fetched = Vec::new();
+        let mut operation = self.trash.begin(match handles {
+            [handle] => format!("fetch clash {handle}"),
+            _ => format!("fetch {num_handles} clashes"),
+        });
+        for (idx, handle) in handles.iter().enumerate() {
+            // Keep going so that one deleted clash does not spoil a whole list
+            let progress = if num_handles > 1 {
+                format!("[{}/{}] ", idx + 1, num_handles)
+            } else {
+                String::new()
+            };
+            match self.download_clash(handle, &mut operation) {
+                Ok(clash_file_path) => {
+                    println!("{}Saved clash {} as {}", progress, &handle, &clash_file_path.display());
+                    fetched.push(handle.to_string());
+                    if let Some(tag) = tag {
+                        tags.add(tag, &handle.to_string());
+                    }
+                }
+                Err(err) if num_handles > 1 => {
+                    eprintln!("{}Failed to fetch clash {}: {:#}", progress, &handle, err);
+                    num_failed += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        if tag.is_some() {
+            self.trash.preserve(&mut operation, &self.tags_file)?;
+            tags.save(&self.tags_file)?;
+        }
+        self.trash.commit(operation)?;
+        if !fetched.is_empty() {
+            let mut description = match fetched.as_slice() {
+                [handle] => format!("fetched clash {handle}"),
+                _ => format!("fetched {} clashes", fetched.len()),
+            };
+            if let Some(tag) = tag {
+                description += &format!(" and tagged them with {tag}");
+            }
+            self.log_change("fetch", description, fetched)?;
+        }
+
+        match num_failed {
+            0 => Ok(()),
+            _ => Err(anyhow!("Failed to fetch {} out of {} clashes", num_failed, num_handles)),
+        }
+    }
+
+    fn init(&self, args: &ArgMatches) -> Result<()> {
+        let ostyle = self.ostyle(false);
+        if self.storage.is_shared() {
+            let user_dir = self.storage.user_dir();
+            std::fs::create_dir_all(user_dir)
+                .with_context(|| format!("Unable to create data directory {:?}", user_dir))?;
+            println!("Clashes are read from the shared collection in {}", self.clash_dir.display());
+        } else {
+            std::fs::create_dir_all(&self.clash_dir)
+                .with_context(|| format!("Unable to create data directory {:?}", &self.clash_dir))?;
+            println!("Clashes are stored in {}", self.clash_dir.display());
+        }
+        std::fs::create_dir_all(&self.stub_templates_dir)
+            .with_context(|| format!("Unable to create config directory {:?}", &self.stub_templates_dir))?;
+        println!("Custom stub templates go in {}", self.stub_templates_dir.display());
+
+        if self.config_file.exists() {
+            println!("Keeping existing config file {}", self.config_file.display());
+        } else {
+            std::fs::write(&self.config_file, DEFAULT_CONFIG)
+                .with_context(|| format!("Unable to write {:?}", &self.config_file))?;
+            println!("Wrote default config file {}", self.config_file.display());
+        }
+
+        if let Some(url) = args.get_one::<String>("starter-pack") {
+            let handles = self.fetch_handle_list(url)?;
+            self.fetch_handles(&handles, args.get_one::<String>("tag").map(String::as_str))?;
+        }
+
+        if args.contains_id("PROGRAMMING_LANGUAGE") {
+            let dir = args.get_one::<PathBuf>("dir").cloned().unwrap_or_default();
+            self.create_solution("init", args, SolutionLocation::Dir(dir), !args.get_flag("no-track"))?;
+        }
+
+        let num_clashes = self.storage.stored_handles()?.count();
+        println!();
+        println!("{}", ostyle.title.paint("Quickstart"));
+        if num_clashes == 0 {
+            println!("  coctus fetch HANDLE             download a clash (HANDLE is the end of its URL)");
+        }
+        println!("  coctus next                     select a random clash and show it");
+        println!("  coctus start python             create sol.py with the input stub");
+        println!("  coctus run                      test the solution against the clash");
+        println!("  coctus run --command 'CMD'      test any other command instead");
+        println!("Use `coctus help COMMAND` for the details of each command.");
+        Ok(())
+    }
+
+    fn refresh(&self, args: &ArgMatches) -> Result<()> {
+        self.storage.check_writable("refresh clashes")?;
+        let handles: Vec<PublicHandle> = if args.get_flag("all") {
+            self.storage.stored_handles()?.collect()
+        } else {
+            match args.get_many::<PublicHandle>("PUBLIC_HANDLE") {
+                Some(handles) => handles.map(|handle| self.resolve_handle(handle)).collect::<Result<_>>()?,
+                None => vec![self.current_handle()?],
+            }
+        };
+
+        let ostyle = self.ostyle(false);
+        let num_handles = handles.len();
+        let mut changed = Vec::new();
+        let mut num_failed = 0;
+        let mut operation = self.trash.begin(format!("refresh {num_handles} clashes"));
+        for (idx, handle) in handles.iter().enumerate() {
+            let progress = if num_handles > 1 {
+                format!("[{}/{}] ", idx + 1, num_handles)
+            } else {
+                String::new()
+            };
+            match self.refresh_clash(handle, &mut operation) {
+                Ok(None) => println!("{}{} is up to date", progress, handle),
+                Ok(Some(message)) => {
+                    println!("{}{} {}", progress, ostyle.failure.paint("CHANGED"), message);
+                    changed.push(handle.to_string());
+                }
+                Err(err) if num_handles > 1 => {
+                    eprintln!("{}Failed to refresh clash {}: {:#}", progress, handle, err);
+                    num_failed += 1;
+                }
+                Err(err) => {
+                    self.trash.commit(operation)?;
+                    return Err(err)
+                }
+            }
+        }
+        self.trash.commit(operation)?;
+        if num_handles > 1 {
+            println!("{} of {num_handles} clashes changed upstream", changed.len());
+        }
+        if !changed.is_empty() {
+            let description = format!("updated {} clashes that changed upstream", changed.len());
+            self.log_change("refresh", description, changed)?;
+        }
+        match num_failed {
+            0 => Ok(()),
+            _ => Err(anyhow!("Failed to refresh {} out of {} clashes", num_failed, num_handles)),
+        }
+    }
+
+    /// Downloads a contribution, logged in if a session has been stored with
+    /// `coctus login`.
+    fn find_contribution(&self, handle: &PublicHandle) -> Result<client::Contribution> {
+        let login = Login::load(&self.login_file)?;
+        client::find_contribution_with_cookie(handle, login.as_ref().map(|login| login.cookie.as_str()))
+    }
+
+    /// Re-fetches a stored clash. Returns a description of what changed if
+    /// the puzzle itself is different upstream, in which case the previous
+    /// copy is backed up.
+    fn refresh_clash(&self, handle: &PublicHandle, operation: &mut Operation) -> Result<Option<String>> {
+        let old_clash = self.read_clash(handle)?;
+        if self.offline() {
+            return Err(anyhow!("Unable to fetch clash {}: fetching is disabled (offline mode)", handle))
+        }
+        let client::Contribution {
+            clash: new_clash,
+            json: content,
+            ..
+        } = self.find_contribution(handle)?;
+        let clash_file_path = self.clash_dir.join(format!("{}.json", handle));
+
+        let changed_parts = old_clash.changed_parts(&new_clash);
+        if changed_parts.is_empty() {
+            // Votes and such change all the time, no need for a backup
+            std::fs::write(&clash_file_path, &content)?;
+            return Ok(None)
+        }
+
+        std::fs::create_dir_all(&self.backup_dir)?;
+        let backup_path = self.backup_dir.join(format!("{}.v{}.json", handle, old_clash.version()));
+        std::fs::copy(&clash_file_path, &backup_path)
+            .with_context(|| format!("Unable to back up {:?}", &clash_file_path))?;
+        self.trash.preserve(operation, &clash_file_path)?;
+        std::fs::write(&clash_file_path, &content)?;
+        Ok(Some(format!(
+            "{} (version {} -> {}): {} changed, previous version saved as {}",
+            handle,
+            old_clash.version(),
+            new_clash.version(),
+            changed_parts.join(", "),
+            backup_path.display()
+        )))
+    }
+
+    /// Downloads a clash from codingame.com into the clash directory and
+    /// returns the path of the saved file.
+    fn download_clash(&self, handle: &PublicHandle, operation: &mut Operation) -> Result<PathBuf> {
+        self.storage.check_writable(&format!("fetch clash {handle}"))?;
+        if self.offline() {
+            return Err(anyhow!("Unable to fetch clash {}: fetching is disabled (offline mode)", handle))
+        }
+        std::fs::create_dir_all(&self.clash_dir)?;
+        let content = self.find_contribution(handle)?.json;
+        let clash_file_path = self.clash_dir.join(format!("{}.json", handle));
+        self.trash.preserve(operation, &clash_file_path)?;
+        std::fs::write(&clash_file_path, &content)?;
+        Ok(clash_file_path)
+    }
+
+    fn showtests(&self, args: &ArgMatches) -> Result<()> {
+        let handle = self.current_handle()?;
+        let clash = self.read_clash(&handle)?;
+        let mut all_testcases = clash.testcases().clone();
+
+        let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
+        let mut ostyle = self.ostyle(show_whitespace);
+        ostyle.numbering = self.numbering(args);
+        ostyle.provenance = self.apply_overlays(&handle, &mut all_testcases, args)?;
+        warn_about_testcases(&all_testcases);
+
+        let testcases: Vec<&Testcase> = match self.test_selection(args, "TESTCASE")? {
+            Some(selection) => selection.select(&all_testcases)?,
+            None => all_testcases.iter().collect(),
+        };
+
+        let only_in = args.get_flag("in");
+        let only_out = args.get_flag("out");
+
+        let copy = args.get_flag("copy");
+        if args.get_flag("raw") || copy {
+            let flag = if copy { "--copy" } else { "--raw" };
+            let testcase = match testcases.as_slice() {
+                [testcase] => testcase,
+                _ => {
+                    return Err(anyhow!(
+                        "{flag} takes a single testcase but {} were selected",
+                        testcases.len()
+                    ))
+                }
+            };
+            let text = match (only_in, only_out) {
+                (true, _) => &testcase.test_in,
+                (_, true) => &testcase.test_out,
+                _ => return Err(anyhow!("{flag} needs --in or --out to pick what to copy or print")),
+            };
+            if copy {
+                let command = copy_to_clipboard(text)?;
+                let part = if only_in { "input" } else { "expected output" };
+                println!(
+                    "Copied the {part} of {} to the clipboard (with {command})",
+                    ostyle.numbering.label(testcase)
+                );
+                return Ok(())
+            }
+            use std::io::Write;
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(text.as_bytes())?;
+            stdout.flush()?;
+            return Ok(())
+        }
+
+        for testcase in testcases {
+            if !(only_in || only_out) {
+                println!("{}", ostyle.styled_testcase_title(testcase));
+                println!("{}", ostyle.secondary_title.paint("===== INPUT ======"));
+            }
+            if !only_out {
+                println!("{}", ostyle.styled_testcase_input(testcase));
+            }
+            if !(only_in || only_out) {
+                println!("{}", ostyle.secondary_title.paint("==== EXPECTED ===="));
+            }
+            if !only_in {
+                println!("{}", ostyle.styled_testcase_output(testcase));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn start(&self, args: &ArgMatches) -> Result<()> {
+        let workspace_root = args.get_one::<PathBuf>("workspace").or(self.config.workspace.as_ref());
+        let location = match (args.get_one::<PathBuf>("FILE"), workspace_root) {
+            (Some(path), _) => SolutionLocation::File(path.to_owned()),
+            (None, Some(root)) => SolutionLocation::Workspace(root.to_owned()),
+            (None, None) => SolutionLocation::Dir(PathBuf::new()),
+        };
+        self.create_solution("start", args, location, true)
+    }
+
+    /// Writes the stub of the current clash into a new solution file for
+    /// `start` and `init LANGUAGE`, and remembers the file for `run` if
+    /// `track` is set. `args` has the language, --option and --force.
+    fn create_solution(
+        &self,
+        command: &str,
+        args: &ArgMatches,
+        location: SolutionLocation,
+        track: bool,
+    ) -> Result<()> {
+        let lang_arg = args
+            .get_one::<String>("PROGRAMMING_LANGUAGE")
+            .context("Should have a programming language")?;
+        let handle = self.current_handle()?;
+        let mut stub_config = self.stub_config(lang_arg)?;
+        set_stub_options(&mut stub_config, args)?;
+        let solution_name = PathBuf::from(conventional_solution_name(stub_config.source_file_ext()));
+        let (path, workspace) = match &location {
+            SolutionLocation::File(path) => (path.to_owned(), None),
+            SolutionLocation::Dir(dir) => (dir.join(solution_name), None),
+            SolutionLocation::Workspace(root) => {
+                let workspace = Workspace {
+                    handle: handle.to_string(),
+                    language: lang_arg.to_owned(),
+                    solution: solution_name,
+                    dir: root.join(handle.to_string()),
+                };
+                (workspace.solution_path(), Some(workspace))
+            }
+        };
+        if path.exists() && !args.get_flag("force") {
+            return Err(anyhow!("{} already exists (use --force to overwrite it)", path.display()))
+        }
+
+        let clash = self.read_clash(&handle)?;
+        let stub_ext = stub_config.source_file_ext().to_owned();
+        let stub_string = match clash.stub_generator() {
+            Some(generator) => stub::generate_from_config(stub_config, generator)? + "\n",
+            None => String::new(),
+        };
+        self.trash.record(format!("{command} {lang_arg} for clash {handle}"), |operation| {
+            if let Some(workspace) = &workspace {
+                std::fs::create_dir_all(&workspace.dir)
+                    .with_context(|| format!("Unable to create workspace {:?}", workspace.dir))?;
+                let statement_path = workspace.dir.join("statement.txt");
+                let stub_path = workspace.dir.join(format!("stub.{stub_ext}"));
+                for file in [&statement_path, &stub_path, &workspace.manifest_path()] {
+                    self.trash.preserve(operation, file)?;
+                }
+                let statement = OutputStyle::plain().statement_text(&clash);
+                std::fs::write(statement_path, statement)?;
+                std::fs::write(stub_path, &stub_string)?;
+                workspace.save()?;
+            }
+            if let SolutionLocation::Dir(dir) = &location {
+                std::fs::create_dir_all(dir)
+                    .with_context(|| format!("Unable to create directory {:?}", dir))?;
+            }
+            self.trash.preserve(operation, &path)?;
+            std::fs::write(&path, &stub_string).with_context(|| format!("Unable to write {:?}", path))?;
+
+            if track {
+                let solution = TrackedSolution {
+                    path: std::fs::canonicalize(&path)?,
+                    language: lang_arg.to_owned(),
+                    handle: handle.to_string(),
+                };
+                self.trash.preserve(operation, &self.solution_file)?;
+                solution.save(&self.solution_file)?;
+            }
+            Ok(())
+        })?;
+        match &workspace {
+            Some(workspace) => println!(
+                "Created workspace {} for clash {} (cd into it to run the solution)",
+                workspace.dir.display(),
+                handle
+            ),
+            None => println!("Created {} for clash {}", path.display(), handle),
+        }
+        Ok(())
+    }
+
+    fn generate_stub(&self, args: &ArgMatches) -> Result<()> {
+        if args.get_flag("stored-clashes") {
+            return self.generate_stored_clash_stubs(args)
+        }
+        let stub_generator = match args.get_one::<PathBuf>("from-file") {
+            Some(fname) if fname.to_str() == Some("-") => {
+                let mut input = String::new();
+                std::io::stdin().read_to_string(&mut input)?;
+                input
+            }
+            Some(fname) => std::fs::read_to_string(fname)?,
+            None if args.get_flag("from-reference") => stub::SIMPLE_REFERENCE_STUB.to_owned(),
+            None if args.get_flag("reverse") => {
+                let handle = self.current_handle()?;
+                let clash = self.read_clash(&handle)?;
+                let first_testcase = clash.testcases().first().context("Current clash has no testcases")?;
+                stub::reverse_generator(&first_testcase.test_in)
+            }
+            None => {
+                let handle = self.current_handle()?;
+                self.read_clash(&handle)?
+                    .stub_generator()
+                    .with_context(|| "Current clash provides no input stub generator")?
+                    .to_owned()
+            }
+        };
+
+        if args.get_flag("all") {
+            let layout = args.get_one::<PathBuf>("out-dir").map(|dir| StubLayout::new(dir));
+            return self.generate_all_stubs(&stub_generator, layout, args.get_flag("force"))
+        }
+        let lang_arg = args
+            .get_one::<String>("PROGRAMMING_LANGUAGE")
+            .context("Should have a programming language")?;
+        let mut stub_config = self.stub_config(lang_arg)?;
+        set_stub_options(&mut stub_config, args)?;
+        let comment_prefix = stub_config.comment_prefix().map(str::to_owned);
+        let stub_string = stub::generate_from_config(stub_config, &stub_generator)?;
+        if let Some(path) = args.get_one::<PathBuf>("diff-existing") {
+            let comment_prefix = comment_prefix.with_context(|| {
+                format!("The stub config of {lang_arg} has no comment_prefix, so stubs can't be marked")
+            })?;
+            let existing =
+                std::fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?;
+            let marked = stub::marked_stub(&existing, &comment_prefix).with_context(|| {
+                format!(
+                    "{} has no marked stub section (add one with --output {} --append-marker)",
+                    path.display(),
+                    path.display()
+                )
+            })?;
+            if marked == stub_string {
+                println!("The stub in {} is up to date", path.display());
+                return Ok(())
+            }
+            let ostyle = self.ostyle(false);
+            println!(
+                "{}",
+                ostyle
+                    .secondary_title
+                    .paint(format!("===== {} (+) vs generated stub (-) =====", path.display()))
+            );
+            ostyle.print_text_diff(&format!("{stub_string}\n"), &format!("{marked}\n"));
+            return Err(anyhow!(
+                "The stub in {} is out of date (update it with --output {} --append-marker)",
+                path.display(),
+                path.display()
+            ))
+        }
+        if let Some(path) = args.get_one::<PathBuf>("check") {
+            let existing =
+                std::fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?;
+            // Same as what gets written by redirecting the output to a file
+            let expected = format!("{stub_string}\n");
+            if existing == expected {
+                println!("{} is up to date", path.display());
+                return Ok(())
+            }
+            let mut existing_lines = existing.split('\n');
+            let mut expected_lines = expected.split('\n');
+            for line_number in 1.. {
+                match (existing_lines.next(), expected_lines.next()) {
+                    (Some(found), Some(wanted)) if found == wanted => continue,
+                    (found, wanted) => {
+                        return Err(anyhow!(
+                        "{} differs from the generated stub on line {}:\n  expected: {:?}\n     found: {:?}",
+                        path.display(),
+                        line_number,
+                        wanted.unwrap_or("<end of file>"),
+                        found.unwrap_or("<end of file>")
+                    ))
+                    }
+                }
+            }
+        }
+        if let Some(path) = args.get_one::<PathBuf>("output") {
+            let contents = if args.get_flag("append-marker") {
+                let comment_prefix = comment_prefix.with_context(|| {
+                    format!(
+                        "The stub config of {lang_arg} has no comment_prefix, so the stub can't be marked"
+                    )
+                })?;
+                let existing = match path.exists() {
+                    true => {
+                        std::fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?
+                    }
+                    false => String::new(),
+                };
+                stub::insert_marked_stub(&existing, &stub_string, &comment_prefix)
+            } else if path.exists() && !args.get_flag("force") {
+                return Err(anyhow!(
+                    "{} already exists (use --force to overwrite it or --append-marker to keep its contents)",
+                    path.display()
+                ))
+            } else {
+                format!("{stub_string}\n")
+            };
+            return self.trash.record(
+                format!("generate-stub {lang_arg} --output {}", path.display()),
+                |operation| {
+                    self.trash.preserve(operation, path)?;
+                    std::fs::write(path, contents).with_context(|| format!("Unable to write {:?}", path))
+                },
+            )
+        }
+        if args.get_flag("copy") {
+            let command = copy_to_clipboard(&format!("{stub_string}\n"))?;
+            println!("Copied the {lang_arg} stub to the clipboard (with {command})");
+            return Ok(())
+        }
+        println!("{stub_string}");
+        Ok(())
+    }
+
+    fn generate_stored_clash_stubs(&self, args: &ArgMatches) -> Result<()> {
+        let lang_arg = args
+            .get_one::<String>("PROGRAMMING_LANGUAGE")
+            .context("Should have a programming language")?;
+        let stub_config = self.stub_config(lang_arg)?;
+        let ostyle = self.ostyle(false);
+        // Panics are reported as diagnostics, the default hook would only
+        // clutter the output
+        let silenced = SilencedPanics::new();
+        let mut num_clashes = 0;
+        let mut problems = Vec::new();
+        for handle in self.storage.stored_handles()? {
+            let diagnostics = match self.read_clash(&handle) {
+                Ok(clash) => {
+                    let Some(generator) = clash.stub_generator() else {
+                        continue
+                    };
+                    stub::generate_with_diagnostics(stub_config.clone(), generator).1
+                }
+                Err(err) => vec![stub::Diagnostic::error(format!("{err:#}"))],
+            };
+            num_clashes += 1;
+            for diagnostic in diagnostics {
+                let severity = match diagnostic.severity {
+                    stub::Severity::Warning => ostyle.failure.paint("warning"),
+                    stub::Severity::Error => ostyle.error.paint("error"),
+                };
+                eprintln!("{handle}: {severity}: {}", diagnostic.message);
+                problems.push(StubProblem {
+                    handle: handle.to_string(),
+                    diagnostic,
+                });
+            }
+        }
+        drop(silenced);
+
+        let count =
+            |severity| problems.iter().filter(|problem| problem.diagnostic.severity == severity).count();
+        let (num_errors, num_warnings) = (count(stub::Severity::Error), count(stub::Severity::Warning));
+        let summary = StubSummary {
+            language: lang_arg.to_owned(),
+            clashes: num_clashes,
+            errors: num_errors,
+            warnings: num_warnings,
+            problems,
+        };
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+
+        if num_errors > 0 || (num_warnings > 0 && args.get_flag("fail-on-warnings")) {
+            return Err(anyhow!(
+                "Found {} errors and {} warnings in the stubs of {} clashes",
+                num_errors,
+                num_warnings,
+                num_clashes
+            ))
+        }
+        Ok(())
+    }
+
+    fn parse_all_stubs(&self, args: &ArgMatches) -> Result<()> {
+        let clash_dir = args.get_one::<PathBuf>("dir").unwrap_or(&self.clash_dir);
+        let num_examples = *args.get_one::<usize>("examples").expect("--examples has a default");
+        let ostyle = self.ostyle(false);
+
+        // Panics are reported as errors, the default hook would only clutter
+        // the output
+        let silenced = SilencedPanics::new();
+        let report = ParseReport::of_dir(clash_dir);
+        drop(silenced);
+        let report = report?;
+
+        for (kind, problems) in report.kinds() {
+            let style = match kind.starts_with("error") {
+                true => ostyle.error,
+                false => ostyle.failure,
+            };
+            println!("{} ({})", style.paint(kind), problems.len());
+            for (handle, message) in problems.iter().take(num_examples) {
+                println!("  {handle}: {message}");
+            }
+            if problems.len() > num_examples {
+                println!("  ...and {} more", problems.len() - num_examples);
+            }
+        }
+        println!(
+            "Parsed the stub generators of {} clashes: {} failed and {} more had warnings",
+            report.clashes,
+            report.num_failed(),
+            report.expectations().len() - report.num_failed()
+        );
+
+        let Some(expectations_file) = args.get_one::<PathBuf>("expectations") else {
+            return Ok(())
+        };
+        if args.get_flag("update-expectations") {
+            let json = serde_json::to_string_pretty(report.expectations())?;
+            std::fs::write(expectations_file, format!("{json}\n"))
+                .with_context(|| format!("Unable to write {expectations_file:?}"))?;
+            println!("Wrote the expectations to {}", expectations_file.display());
+            return Ok(())
+        }
+        let contents = std::fs::read_to_string(expectations_file)
+            .with_context(|| format!("Unable to read {expectations_file:?}"))?;
+        let expected: ParseExpectations = serde_json::from_str(&contents)
+            .with_context(|| format!("Unable to parse {expectations_file:?}"))?;
+        let fixed = report.fixed(&expected);
+        if !fixed.is_empty() {
+            println!("\nNo longer happen (update the expectations with --update-expectations):");
+            for (handle, kind) in &fixed {
+                println!("  {handle}: {kind}");
+            }
+        }
+        let regressions = report.regressions(&expected);
+        if !regressions.is_empty() {
+            println!("\n{}", ostyle.error.paint("Not in the expectations:"));
+            for (handle, kind) in &regressions {
+                println!("  {handle}: {kind}");
+            }
+            return Err(anyhow!("{} problems are not in {}", regressions.len(), expectations_file.display()))
+        }
+        Ok(())
+    }
+
+    /// Prints the stub of every language, or writes them into the directory
+    /// of `layout`.
+    fn generate_all_stubs(
+        &self,
+        stub_generator: &str,
+        layout: Option<StubLayout>,
+        force: bool,
+    ) -> Result<()> {
+        let mut languages = StubConfig::embedded_languages();
+        let user_languages: Vec<String> = match std::fs::read_dir(&self.stub_templates_dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                .filter(|name| self.stub_templates_dir.join(name).is_dir())
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        languages.extend(user_languages.iter().map(String::as_str));
+        languages.sort();
+        languages.dedup();
+
+        let ostyle = self.ostyle(false);
+        let mut extensions = BTreeMap::new();
+        let stubs = stub::generate_many_with(stub_generator, &languages, |lang| {
+            let stub_config = self.stub_config(lang)?;
+            extensions.insert(lang.to_owned(), stub_config.source_file_ext().to_owned());
+            Ok(stub_config)
+        });
+        let mut num_failed = 0;
+        let mut files = Vec::new();
+        for (lang, stub_string) in stubs {
+            match (stub_string, &layout) {
+                (Ok(stub_string), None) => {
+                    println!("{}\n{stub_string}\n", ostyle.title.paint(format!("==== {lang} ====")))
+                }
+                (Ok(stub_string), Some(layout)) => {
+                    files.push((layout.stub_path(&lang, &extensions[&lang]), format!("{stub_string}\n")))
+                }
+                (Err(err), _) => {
+                    eprintln!("{} {lang}: {err:#}\n", ostyle.error.paint("ERROR"));
+                    num_failed += 1;
+                }
+            }
+        }
+        if let Some(layout) = &layout {
+            if let Some((existing, _)) = files.iter().find(|(path, _)| path.exists() && !force) {
+                return Err(anyhow!("{} already exists (use --force to overwrite it)", existing.display()))
+            }
+            std::fs::create_dir_all(layout.dir())
+                .with_context(|| format!("Unable to create {:?}", layout.dir()))?;
+            let description = format!("generate-stub --all-languages --out-dir {}", layout.dir().display());
+            self.trash.record(description, |operation| {
+                for (path, contents) in &files {
+                    self.trash.preserve(operation, path)?;
+                    std::fs::write(path, contents).with_context(|| format!("Unable to write {:?}", path))?;
+                    println!("Wrote {}", path.display());
+                }
+                Ok(())
+            })?;
+        }
+        match num_failed {
+            0 => Ok(()),
+            _ => Err(anyhow!(
+                "Failed to generate the stub for {} out of {} languages",
+                num_failed,
+                languages.len()
+            )),
+        }
+    }
+
+    /// Records a change to the local collection of clashes for `coctus log`.
+    fn log_change(&self, command: &str, description: String, handles: Vec<String>) -> Result<()> {
+        self.changelog.append(&ChangelogEntry::new(command, description, handles))
+    }
+
+    fn schema(&self, args: &ArgMatches) -> Result<()> {
+        let command = args.get_one::<String>("COMMAND").expect("COMMAND should be required");
+        let schema = schema_for_command(command).expect("clap should only allow commands that have a schema");
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        Ok(())
+    }
+
+    fn log(&self, args: &ArgMatches) -> Result<()> {
+        let entries: Vec<ChangelogEntry> = self.changelog.load()?;
+        let count = *args.get_one::<usize>("count").expect("--count should have a default");
+        let recent = &entries[entries.len().saturating_sub(count)..];
+        if args.get_flag("json") {
+            for entry in recent {
+                println!("{}", serde_json::to_string(entry)?);
+            }
+            return Ok(())
+        }
+        if recent.is_empty() {
+            println!("No changes to the collection have been recorded yet");
+        }
+        let ostyle = self.ostyle(false);
+        for entry in recent {
+            let machine =
+                entry.machine.as_ref().map(|machine| format!(" (on {machine})")).unwrap_or_default();
+            println!(
+                "{} {} {}{}",
+                ostyle.dim_color.paint(format_utc_timestamp(entry.timestamp)),
+                ostyle.title.paint(&entry.command),
+                entry.description,
+                ostyle.dim_color.paint(machine)
+            );
+            if args.get_flag("handles") {
+                for handle in &entry.handles {
+                    println!("  {handle}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn stress(&self, args: &ArgMatches) -> Result<()> {
+        let handle = self.handle_arg(args)?;
+        let clash = self.read_clash(&handle)?;
+        let generator = match clash.stub_generator() {
+            Some(generator) => generator.to_owned(),
+            None => {
+                let first_testcase = clash
+                    .testcases()
+                    .first()
+                    .context("Clash has neither a stub generator nor a testcase to guess one from")?;
+                stub::reverse_generator(&first_testcase.test_in)
+            }
+        };
+        let constraints = clash.constraints().map(clash::parse_constraints).unwrap_or_default();
+        let budget = *args
+            .get_one::<std::time::Duration>("budget")
+            .expect("--budget should have a default");
+        let max_size = *args.get_one::<u64>("max-size").expect("--max-size should have a default");
+        let seed = args.get_one::<u64>("seed").copied().unwrap_or_else(rand::random);
+
+        let build_dir = self.build_dir(&handle)?;
+        let build_command_arg = args.get_one::<String>("build-command").cloned();
+        let (build_command_arg, run_command_arg) = match args.get_one::<String>("command") {
+            Some(command) => (build_command_arg, command.to_owned()),
+            None => {
+                let (build_command, run_command, _) = self.default_commands(&handle, &build_dir)?;
+                (build_command_arg.or(build_command), run_command)
+            }
+        };
+        let build_command_arg = build_command_arg
+            .map(|cmd| expand_path_placeholder(&cmd, "{build_dir}", &build_dir))
+            .transpose()?;
+        let run_command_arg = expand_path_placeholder(&run_command_arg, "{build_dir}", &build_dir)?;
+        build_solution(build_command_arg.as_ref(), &build_dir, None)?;
+        let mut run_command = command_from_argument(Some(&run_command_arg))?.context("Empty COMMAND")?;
+        run_command.env("COCTUS_BUILD_DIR", &build_dir);
+
+        println!("Stress testing with --seed {seed} and a budget of {}", format_duration(budget));
+        let options = solution::RunOptions::new(budget);
+        let mut largest = None;
+        let mut previous_input = None;
+        let mut size = 1;
+        let exceeded = loop {
+            let test_in = stub::random_input(&generator, &constraints, size, seed)?;
+            if previous_input.as_ref() == Some(&test_in) {
+                println!("The constraints don't allow larger inputs");
+                break None
+            }
+            if test_in.len() > MAX_STRESS_INPUT_BYTES {
+                println!("Stopped before the inputs grow over {} MB", MAX_STRESS_INPUT_BYTES >> 20);
+                break None
+            }
+            let testcase = Testcase {
+                index: 1,
+                number: 1,
+                title: format!("Size {size}"),
+                test_in,
+                // The output is not checked, but an expected output that no
+                // solution prints keeps a timeout or a crash without any
+                // output from counting as a success
+                test_out: "(output of coctus stress is ignored)".to_string(),
+                is_validator: false,
+            };
+            let description = format!(
+                "size {size}, {} lines, {} bytes",
+                testcase.test_in.lines().count(),
+                testcase.test_in.len()
+            );
+            let started_at = std::time::Instant::now();
+            let result = solution::run_testcase_with_options(&testcase, &mut run_command, &options);
+            let elapsed = started_at.elapsed();
+            match result {
+                TestResult::UnableToRun { error_msg } => return Err(anyhow!(error_msg)),
+                TestResult::Timeout { .. } => {
+                    println!("{description}: over the budget");
+                    break Some(testcase)
+                }
+                TestResult::RuntimeError { stderr, exit, .. } => {
+                    println!("{description}: crashed ({exit})");
+                    if !stderr.is_empty() {
+                        println!("{}", stderr.trim_end());
+                    }
+                    break Some(testcase)
+                }
+                _ => println!("{description}: {:.3}s", elapsed.as_secs_f64()),
+            }
+            largest = Some(description);
+            if size >= max_size {
+                println!("Reached --max-size {max_size}");
+                break None
+            }
+            previous_input = Some(testcase.test_in);
+            size = size.saturating_mul(2).min(max_size);
+        };
+
+        if let Some(testcase) = &exceeded {
+            let input_path = build_dir.join("stress.in");
+            std::fs::write(&input_path, format!("{}\n", testcase.test_in))?;
+            println!("The input is saved in {}", input_path.display());
+        }
+        match largest {
+            Some(largest) => {
+                println!("Largest input handled within {}: {largest}", format_duration(budget));
+                Ok(())
+            }
+            None => Err(anyhow!("The solution could not handle even the smallest input")),
+        }
+    }
+
+    fn calibrate(&self, args: &ArgMatches) -> Result<()> {
+        if args.get_flag("reset") {
+            if self.calibration_file.exists() {
+                std::fs::remove_file(&self.calibration_file)?;
+            }
+            println!("Default timeouts are no longer scaled");
+            return Ok(())
+        }
+        println!("Measuring...");
+        let calibration = Calibration::measure();
+        calibration.save(&self.calibration_file)?;
+        let default_timeout = std::time::Duration::from_secs(5);
+        println!(
+            "The reference workload took {:.0}ms, default timeouts are scaled by {:.2} (5s becomes {})",
+            calibration.measured_secs * 1000.0,
+            calibration.scale,
+            format_duration(calibration.scale_timeout(default_timeout))
+        );
+        Ok(())
+    }
+
+    fn undo(&self, args: &ArgMatches) -> Result<()> {
+        if args.get_flag("list") {
+            let operations = self.trash.operations()?;
+            if operations.is_empty() {
+                println!("Nothing to undo");
+            }
+            for operation in operations {
+                println!("{} ({} files)", operation.description, operation.files.len());
+                for file in operation.files {
+                    let action = if file.copy.is_some() {
+                        "overwrote"
+                    } else {
+                        "created"
+                    };
+                    println!("  {action} {}", file.original.display());
+                }
+            }
+            return Ok(())
+        }
+
+        match self.trash.undo()? {
+            Some((operation, changed_since)) => {
+                for file in &changed_since {
+                    match &file.copy {
+                        Some(copy) => eprintln!(
+                            "Skipped {} because it has changed since (its previous contents are kept in {})",
+                            file.original.display(),
+                            copy.display()
+                        ),
+                        None => eprintln!("Skipped {} because it has changed since", file.original.display()),
+                    }
+                }
+                println!(
+                    "Undid {} ({} files)",
+                    operation.description,
+                    operation.files.len() - changed_since.len()
+                );
+                self.log_change("undo", format!("undid {}", operation.description), Vec::new())?;
+            }
+            None => println!("Nothing to undo"),
+        }
+        Ok(())
+    }
+
+    fn stub_repl(&self, args: &ArgMatches) -> Result<()> {
+        let lang = args
+            .get_one::<String>("PROGRAMMING_LANGUAGE")
+            .context("Should have a programming language")?;
+        let config = self.stub_config(lang)?;
+        let ostyle = self.ostyle(true);
+        let mut playground = StubPlayground::default();
+
+        if let Some(path) = args.get_one::<PathBuf>("watch") {
+            let mut watcher = FileWatcher::new(vec![path.clone()]);
+            let mut first = true;
+            loop {
+                if std::mem::take(&mut first) || watcher.changed() {
+                    match std::fs::read_to_string(path) {
+                        Ok(generator) => {
+                            playground.set_generator(&generator);
+                            print_stub_rendering(&ostyle, &mut playground, &config);
+                        }
+                        Err(err) => {
+                            println!("{} Unable to read {:?}: {err}", ostyle.error.paint("ERROR"), path)
+                        }
+                    }
+                }
+                std::thread::sleep(std::time::Duration::from_millis(200));
+            }
+        }
 
-    fn clashes(&self) -> Result<std::fs::ReadDir> {
-        std::fs::read_dir(&self.clash_dir).with_context(|| "No clashes stored")
+        if let Some(path) = args.get_one::<PathBuf>("from-file") {
+            let generator =
+                std::fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?;
+            playground.set_generator(&generator);
+            print_stub_rendering(&ostyle, &mut playground, &config);
+        }
+        println!("Type the stub generator one line at a time (:help for the commands)");
+        let mut lines = std::io::stdin().lines();
+        loop {
+            print!("{} ", ostyle.dim_color.paint(format!("{:>3} >", playground.lines().len() + 1)));
+            std::io::Write::flush(&mut std::io::stdout())?;
+            let Some(line) = lines.next().transpose()? else {
+                println!();
+                break
+            };
+            match line.parse::<PlaygroundInput>() {
+                Err(err) => println!("{}", ostyle.failure.paint(err.to_string())),
+                Ok(PlaygroundInput::Line(line)) => playground.push_line(&line),
+                Ok(PlaygroundInput::Undo) => match playground.undo() {
+                    Some(line) => println!("{}", ostyle.dim_color.paint(format!("Removed: {line}"))),
+                    None => println!("Nothing to undo"),
+                },
+                Ok(PlaygroundInput::Clear) => playground.clear(),
+                Ok(PlaygroundInput::Show) => {
+                    for (idx, line) in playground.lines().iter().enumerate() {
+                        println!("{} {line}", ostyle.dim_color.paint(format!("{:>3} |", idx + 1)));
+                    }
+                }
+                Ok(PlaygroundInput::Load(path)) => match std::fs::read_to_string(&path) {
+                    Ok(generator) => playground.set_generator(&generator),
+                    Err(err) => println!("{} Unable to read {:?}: {err}", ostyle.error.paint("ERROR"), path),
+                },
+                Ok(PlaygroundInput::Save(path)) => {
+                    let mut operation = self.trash.begin(format!("stub-repl :save {}", path.display()));
+                    self.trash.preserve(&mut operation, &path)?;
+                    let written = std::fs::write(&path, format!("{}\n", playground.generator()));
+                    // A failed write may have truncated the file already, so
+                    // the old contents stay recoverable with undo either way
+                    self.trash.commit(operation)?;
+                    match written {
+                        Ok(()) => println!("Wrote {}", path.display()),
+                        Err(err) => {
+                            println!("{} Unable to write {:?}: {err}", ostyle.error.paint("ERROR"), path)
+                        }
+                    }
+                }
+                Ok(PlaygroundInput::Help) => println!("{PLAYGROUND_HELP}"),
+                Ok(PlaygroundInput::Quit) => break,
+            }
+            print_stub_rendering(&ostyle, &mut playground, &config);
+        }
+        Ok(())
     }
 
-    fn random_handle(&self) -> Result<PublicHandle> {
-        let mut rng = rand::thread_rng();
-        if let Ok(entry) = self.clashes()?.choose(&mut rng).expect("No clashes to choose from!") {
-            let filename =
-                entry.file_name().into_string().expect("unable to convert OsString to String (?!?)");
-            PublicHandle::from_str(match filename.strip_suffix(".json") {
-                Some(handle) => handle,
-                None => &filename,
-            })
+    fn stub_config(&self, lang: &str) -> Result<StubConfig> {
+        // Language config files are stored in: (ordered by precedence)
+        // 1. The user config dir, where {CONF} is the OS dependent config folder:
+        //    `{CONF}/stub_templates/LANG/stub_config.toml`
+        // 2. This repo, embedded into the binary:
+        //    `config/stub_templates/LANG/stub_config.toml`
+        let lang_template_dir = self.stub_templates_dir.join(lang);
+        if lang_template_dir.is_dir() {
+            StubConfig::read_from_dir(lang_template_dir)
         } else {
-            Err(anyhow!("Unable to randomize next clash"))
+            StubConfig::read_from_embedded(lang)
         }
     }
 
-    fn random_handle_with_modes(&self, fastest: bool, shortest: bool, reverse: bool) -> Result<PublicHandle> {
-        let max_attemps = 100;
-        for _ in 0..max_attemps {
-            let handle = self.random_handle()?;
-            let clash = self.read_clash(&handle)?;
-            if (!reverse || clash.is_reverse())
-                && (!fastest || clash.is_fastest())
-                && (!shortest || clash.is_shortest())
-            {
-                return Ok(handle)
+    fn which(&self, args: &ArgMatches) -> Result<()> {
+        let lang_arg = args
+            .get_one::<String>("PROGRAMMING_LANGUAGE")
+            .context("Should have a programming language")?;
+        let ostyle = self.ostyle(false);
+        let embedded_files = StubConfig::embedded_files(lang_arg);
+        let user_dir = self.stub_templates_dir.join(lang_arg);
+
+        // Same resolution order as `stub_config`
+        if user_dir.is_dir() {
+            println!("{} user config dir {}", ostyle.title.paint("Using"), user_dir.display());
+            let mut user_files: Vec<String> = std::fs::read_dir(&user_dir)?
+                .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                .filter(|name| name == "stub_config.toml" || name.ends_with(".jinja"))
+                .collect();
+            user_files.sort();
+            for file in &user_files {
+                println!("  {}", user_dir.join(file).display());
+            }
+            if let Some(embedded_files) = embedded_files {
+                let missing: Vec<&str> = embedded_files
+                    .into_iter()
+                    .filter(|file| !user_files.iter().any(|user_file| user_file == file))
+                    .collect();
+                println!("Embedded templates for {lang_arg} are ignored.");
+                if !missing.is_empty() {
+                    println!(
+                        "{} files that exist in the embedded templates but not in the user config dir: {}",
+                        ostyle.failure.paint("WARNING"),
+                        missing.join(", ")
+                    );
+                }
             }
+        } else if let Some(embedded_files) = embedded_files {
+            println!(
+                "{} embedded templates (no {} found)",
+                ostyle.title.paint("Using"),
+                user_dir.display()
+            );
+            for file in embedded_files {
+                println!("  config/stub_templates/{lang_arg}/{file}");
+            }
+        } else {
+            let mut languages = StubConfig::embedded_languages();
+            languages.sort();
+            return Err(anyhow!(
+                "No stub templates for '{}' in {} or embedded into coctus (available: {})",
+                lang_arg,
+                self.stub_templates_dir.display(),
+                languages.join(", ")
+            ))
         }
-        Err(anyhow!(format!(
-            "Failed to find a clash with the required modes after {} attempts.",
-            max_attemps
-        )))
-    }
-
-    fn read_clash(&self, handle: &PublicHandle) -> Result<Clash> {
-        let clash_file = self.clash_dir.join(format!("{}.json", handle));
-        let contents = std::fs::read_to_string(&clash_file)
-            .with_context(|| format!("Unable to find clash with handle {}", handle))?;
-        let clash: Clash = serde_json::from_str(&contents)
-            .with_context(|| format!("Unable to deserialize clash from {:?}", &clash_file))?;
-        Ok(clash)
+        Ok(())
     }
 
-    fn show(&self, args: &ArgMatches) -> Result<()> {
-        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
-            Some(h) => h.to_owned(),
-            None => self.current_handle()?,
+    fn test_templates(&self, args: &ArgMatches) -> Result<()> {
+        let lang_arg = args
+            .get_one::<String>("PROGRAMMING_LANGUAGE")
+            .context("Should have a programming language")?;
+        let stub_config = match args.get_one::<PathBuf>("DIR") {
+            Some(dir) => StubConfig::read_from_dir(dir.join(lang_arg))?,
+            None => self.stub_config(lang_arg)?,
         };
-        let clash = self.read_clash(&handle)?;
+        let ostyle = self.ostyle(false);
 
-        let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
-        let ostyle = OutputStyle::from_env(show_whitespace);
+        let mut num_failed = 0;
+        for check in stub::TEMPLATE_CHECKS {
+            let (code, mut problems) = match stub::generate_from_config(stub_config.clone(), check.generator)
+            {
+                Ok(code) => {
+                    let problems = stub::lint_rendered_stub(&code);
+                    (code, problems)
+                }
+                Err(err) => (String::new(), vec![format!("{:#}", err)]),
+            };
 
-        // --reverse flag
-        if args.get_flag("reverse") {
-            if clash.is_reverse() {
-                ostyle.print_reverse_mode(&clash);
-                return Ok(())
+            let mut notes = Vec::new();
+            if let (true, Some(check_command)) = (problems.is_empty(), stub_config.check_command()) {
+                let temp_source = TempSource::new(&code, Some(stub_config.source_file_ext()))?;
+                let out_dir = tempfile::tempdir()?;
+                let check_command = expand_path_placeholder(check_command, "{file}", temp_source.path())?;
+                let check_command = expand_path_placeholder(&check_command, "{out_dir}", out_dir.path())?;
+                let mut cmd = command_from_argument(Some(&check_command))?.context("Empty check_command")?;
+                match cmd.output() {
+                    Ok(output) if output.status.success() => {
+                        notes.push(format!("`{check_command}` succeeded"))
+                    }
+                    Ok(output) => problems.push(format!(
+                        "check command failed:\n{}",
+                        String::from_utf8_lossy(&output.stderr).trim_end()
+                    )),
+                    Err(err) => notes.push(format!("skipped check command ({err})")),
+                }
+            }
+
+            let status = if problems.is_empty() {
+                ostyle.success.paint("PASS")
             } else {
-                return Err(anyhow::Error::msg("The clash doesn't have a reverse mode"))
+                num_failed += 1;
+                ostyle.failure.paint("FAIL")
+            };
+            println!("{} {}", status, ostyle.title.paint(check.name));
+            println!("{}", ostyle.secondary_title.paint("==== GENERATOR ===="));
+            println!("{}", check.generator.trim_end());
+            println!("{}", ostyle.secondary_title.paint("===== RENDERED ====="));
+            println!("{code}");
+            for problem in problems {
+                println!("{}", ostyle.stderr.paint(format!("- {problem}")));
             }
+            for note in notes {
+                println!("{}", ostyle.dim_color.paint(format!("- {note}")));
+            }
+            println!();
         }
 
-        // If the clash is reverse only, print the headers and testcases.
-        if clash.is_reverse_only() {
-            ostyle.print_reverse_mode(&clash);
-        } else {
-            ostyle.print_headers(&clash);
-            ostyle.print_statement(&clash);
+        let num_checks = stub::TEMPLATE_CHECKS.len();
+        println!("{}/{} template checks passed", num_checks - num_failed, num_checks);
+        if num_failed > 0 {
+            return Err(anyhow!("Some of the templates for {} are broken", lang_arg))
         }
+        Ok(())
+    }
+
+    fn json(&self, args: &ArgMatches) -> Result<()> {
+        let handle = self.handle_arg(args)?;
+        let clash_file = self.clash_dir.join(format!("{}.json", handle));
+        let contents = std::fs::read_to_string(clash_file)
+            .with_context(|| format!("Unable to find clash with handle {}", handle))?;
 
+        println!("{}", &contents);
         Ok(())
     }
 
-    fn next(&self, args: &ArgMatches) -> Result<()> {
-        let next_handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
-            Some(h) => h.to_owned(),
-            None => {
-                let fastest = args.get_flag("fastest");
-                let shortest = args.get_flag("shortest");
-                let reverse = args.get_flag("reverse");
-                if reverse || fastest || shortest {
-                    self.random_handle_with_modes(fastest, shortest, reverse)?
-                } else {
-                    self.random_handle()?
-                }
+    fn meta(&self, args: &ArgMatches) -> Result<()> {
+        let handle = self.handle_arg(args)?;
+        let meta = self.read_clash(&handle)?.metadata();
+
+        match args.get_one::<String>("format").map(String::as_str) {
+            Some("json") => println!("{}", serde_json::to_string_pretty(&meta)?),
+            _ => {
+                println!("Handle: {}", meta.public_handle);
+                println!("Title: {}", meta.title);
+                println!("Version: {}", meta.version);
+                println!("Type: {}", meta.puzzle_type);
+                println!("Modes: {}", meta.modes.join(", "));
+                println!("Tests: {}", meta.num_tests);
+                println!("Validators: {}", meta.num_validators);
+                println!("Votes: +{} -{}", meta.upvotes, meta.downvotes);
+                println!("Stub generator: {}", if meta.has_stub_generator { "yes" } else { "no" });
+                println!("Statement length: {}", meta.statement_length);
+                println!("Statement language: {}", meta.statement_language.unwrap_or("unknown"));
+                println!(
+                    "Statement words: {} (about {} to read)",
+                    meta.statement_words,
+                    format_reading_time(meta.reading_time())
+                );
             }
-        };
-        println!(" Changed clash to https://codingame.com/contribute/view/{}", next_handle);
-        println!(" Local file: {}/{}.json", &self.clash_dir.to_str().unwrap(), next_handle);
-        std::fs::write(&self.current_clash_file, next_handle.to_string())?;
+        }
         Ok(())
     }
 
-    fn status(&self, _args: &ArgMatches) -> Result<()> {
-        println!("Current clash file: {}", self.current_clash_file.display());
-        match self.current_handle() {
-            Ok(handle) => println!("Current clash: {}", handle),
-            Err(_) => println!("Current clash: -"),
+    fn list(&self, args: &ArgMatches) -> Result<()> {
+        let statement_lang = args.get_one::<String>("statement-lang").map(String::as_str);
+        let max_read_time = args.get_one::<std::time::Duration>("max-read-time");
+        // For the error message if there are no stored clashes at all
+        let _ = self.storage.stored_handles()?;
+        let (index, unreadable) = self.updated_search_index()?;
+        let ostyle = self.ostyle(false);
+        for handle in unreadable {
+            eprintln!("{} skipped {handle}, it is not a valid clash file", ostyle.failure.paint("WARNING"));
+        }
+        for (handle, clash) in index.clashes() {
+            let statement_language = clash.statement_language.as_deref();
+            if statement_lang.is_some_and(|lang| statement_language != Some(lang))
+                || max_read_time.is_some_and(|max| clash.reading_time() > *max)
+            {
+                continue
+            }
+            println!(
+                "{}  {:2}  {:>4}  {:24}  {}",
+                handle,
+                statement_language.unwrap_or("-"),
+                format_reading_time(clash.reading_time()),
+                clash.modes.join(","),
+                clash.title
+            );
         }
-        println!("Clash dir: {}", self.clash_dir.display());
-        let num_clashes = match self.clashes() {
-            Ok(clashes) => clashes.count(),
-            Err(_) => 0,
-        };
-        println!("Number of clashes: {}", num_clashes);
         Ok(())
     }
 
-    fn run(&self, args: &ArgMatches) -> Result<()> {
-        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
-            Some(h) => h.to_owned(),
-            None => self.current_handle()?,
-        };
+    /// The search index with the stored clashes that changed since it was
+    /// saved indexed again, and the handles of the unreadable clash files.
+    fn updated_search_index(&self) -> Result<(SearchIndex, Vec<String>)> {
+        let mut index = SearchIndex::load(&self.search_index_file);
+        let update = index.update(&self.clash_dir)?;
+        if update.changed {
+            index.save(&self.search_index_file)?;
+        }
+        Ok((index, update.unreadable))
+    }
 
-        if let Some(mut build_command) = command_from_argument(args.get_one::<String>("build-command"))? {
-            let build = build_command.output()?;
+    fn search(&self, args: &ArgMatches) -> Result<()> {
+        let query: Vec<&str> = args
+            .get_many::<String>("QUERY")
+            .expect("QUERY should be required")
+            .map(String::as_str)
+            .collect();
+        let query = query.join(" ");
+        let modes: Vec<&str> = ["fastest", "shortest", "reverse"]
+            .into_iter()
+            .filter(|mode| args.get_flag(mode))
+            .collect();
 
-            if !build.status.success() {
-                if !build.stderr.is_empty() {
-                    println!("Build command STDERR:\n{}", String::from_utf8(build.stderr)?);
-                }
-                if !build.stdout.is_empty() {
-                    println!("Build command STDOUT:\n{}", String::from_utf8(build.stdout)?);
-                }
-                return Err(anyhow!("Build failed"))
+        // For the error message if there are no stored clashes at all
+        let _ = self.storage.stored_handles()?;
+        let (index, _) = self.updated_search_index()?;
+        let hits: Vec<_> = index
+            .search(&query)
+            .into_iter()
+            .filter(|(_, clash)| modes.iter().all(|mode| clash.modes.iter().any(|m| m == mode)))
+            .collect();
+        if hits.is_empty() {
+            return Err(anyhow!("No stored clashes match {query:?}"))
+        }
+        for (handle, clash) in hits {
+            match args.get_flag("handles-only") {
+                true => println!("{handle}"),
+                false => println!("{}  {:24}  {}", handle, clash.modes.join(","), clash.title),
             }
         }
+        Ok(())
+    }
 
-        let mut run_command = command_from_argument(args.get_one::<String>("command"))?
-            .expect("clap should ensure `run` can't be executed without a --command");
-
-        let timeout = match *args.get_one::<f64>("timeout").unwrap_or(&5.0) {
-            secs if secs.is_nan() => return Err(anyhow!("Timeout can't be NaN")),
-            secs if secs < 0.0 => return Err(anyhow!("Timeout can't be negative (use 0 for no timeout)")),
-            secs if secs == 0.0 => std::time::Duration::MAX,
-            secs => std::time::Duration::from_micros((secs * 1e6) as u64),
+    fn score(&self, args: &ArgMatches) -> Result<()> {
+        let path = args.get_one::<PathBuf>("shortest").expect("--shortest should be required by clap");
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Unable to read solution file {:?}", path))?;
+        let length = scoring::shortest_mode_length(&source);
+        println!("{length} characters");
+        let Some(language) = args.get_one::<String>("language") else {
+            return Ok(())
         };
 
-        let all_testcases = self.read_clash(&handle)?.testcases().to_owned();
-
-        let testcases: Vec<&Testcase> = if let Some(testcase_indices) = args.get_many::<u64>("testcases") {
-            testcase_indices.map(|idx| &all_testcases[(idx - 1) as usize]).collect()
-        } else {
-            all_testcases.iter().collect()
-        };
+        // The testcases are run exactly like `coctus run --language LANG
+        // --file FILE` runs them, with its timeouts, overlays and reports
+        let mut run_args: Vec<std::ffi::OsString> =
+            vec!["--language".into(), language.into(), "--file".into()];
+        run_args.push(path.into());
+        if let Some(handle) = args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            run_args.push(handle.to_string().into());
+        }
+        self.run_with_args(run_args)
+    }
 
-        let num_tests = testcases.len();
-        let suite_run = solution::lazy_run(testcases, &mut run_command, &timeout);
+    /// Runs `coctus run` with `run_args`, for the commands that run testcases
+    /// as a part of what they do.
+    fn run_with_args(&self, run_args: Vec<std::ffi::OsString>) -> Result<()> {
+        let run_args = ["coctus".into(), "run".into()].into_iter().chain(run_args);
+        let matches = cli().try_get_matches_from(run_args)?;
+        let (_, run_args) = matches.subcommand().expect("the arguments start with the run subcommand");
+        self.run(run_args)
+    }
 
-        let ignore_failures = args.get_flag("ignore-failures");
-        let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
-        let ostyle = OutputStyle::from_env(show_whitespace);
+    fn reverse(&self, args: &ArgMatches) -> Result<()> {
+        let handle = self.handle_arg(args)?;
+        let clash = self.read_clash(&handle)?;
+        let mut testcases = clash.testcases().to_owned();
+        self.overlays.apply(&handle.to_string(), &mut testcases)?;
+        let tests = TestSelection::tests().select(&testcases)?;
+        if tests.is_empty() {
+            return Err(anyhow!("Clash {handle} has no tests to reveal"))
+        }
 
-        let mut num_passed = 0;
+        let mut games = ReverseGames::load(&self.reverse_games_file)?;
+        let mut progress = match args.get_flag("restart") {
+            true => Default::default(),
+            false => games.progress(&handle.to_string()),
+        };
+        let ostyle = self.ostyle(true);
+        let print_test = |test: &Testcase| {
+            println!("{}", ostyle.styled_testcase_title(test));
+            println!("{}", ostyle.secondary_title.paint("===== INPUT ======"));
+            println!("{}", ostyle.styled_testcase_input(test));
+            println!("{}", ostyle.secondary_title.paint("==== EXPECTED ===="));
+            println!("{}", ostyle.styled_testcase_output(test));
+        };
 
-        for (testcase, test_result) in suite_run {
-            ostyle.print_result(testcase, &test_result);
+        println!("{}", ostyle.title.paint(format!("=== {} ===", clash.title())));
+        if !clash.is_reverse() {
+            println!(
+                "{}",
+                ostyle
+                    .dim_color
+                    .paint("This clash has no reverse mode, its statement explains the rule")
+            );
+        }
+        println!(
+            "Find the rule from the {} tests of the clash. Type help for the commands.\n",
+            tests.len()
+        );
+        for test in progress.revealed_tests(&tests) {
+            print_test(test);
+        }
+        if progress.revealed.is_empty() {
+            progress.reveal_next(&tests).into_iter().for_each(print_test);
+        }
+        games.set_progress(&handle.to_string(), progress.clone());
+        games.save(&self.reverse_games_file)?;
 
-            if test_result.is_success() {
-                num_passed += 1;
-            } else if !ignore_failures {
+        let mut lines = std::io::stdin().lines();
+        loop {
+            let num_revealed = progress.revealed_tests(&tests).len();
+            print!("{} ", ostyle.dim_color.paint(format!("[{num_revealed}/{} revealed] >", tests.len())));
+            std::io::Write::flush(&mut std::io::stdout())?;
+            let Some(line) = lines.next().transpose()? else {
+                println!();
                 break
+            };
+            match line.parse::<ReverseCommand>() {
+                Err(err) => println!("{}", ostyle.failure.paint(err.to_string())),
+                Ok(ReverseCommand::Next) => match progress.reveal_next(&tests) {
+                    Some(test) => print_test(test),
+                    None => {
+                        println!("All tests have been revealed, the validators are hidden until `coctus run`")
+                    }
+                },
+                Ok(ReverseCommand::Seen) => progress.revealed_tests(&tests).into_iter().for_each(print_test),
+                Ok(ReverseCommand::Guess(rule)) => {
+                    progress.guess(&rule);
+                    println!("Guess noted after {num_revealed} tests (type run to try a solution)");
+                }
+                Ok(ReverseCommand::Guesses) => match progress.guesses.is_empty() {
+                    true => println!("No guesses yet"),
+                    false => {
+                        for guess in &progress.guesses {
+                            println!("after {:>2} tests: {}", guess.num_revealed, guess.rule);
+                        }
+                    }
+                },
+                Ok(ReverseCommand::Run) => {
+                    // A broken solution shouldn't end the game
+                    if let Err(err) = self.run_revealed_tests(&handle, args, &progress.revealed_tests(&tests))
+                    {
+                        eprintln!("{} {err:#}", ostyle.error.paint("ERROR"));
+                    }
+                }
+                Ok(ReverseCommand::Help) => println!("{REVERSE_HELP}"),
+                Ok(ReverseCommand::Quit) => break,
             }
+            games.set_progress(&handle.to_string(), progress.clone());
+            games.save(&self.reverse_games_file)?;
         }
-        println!("{num_passed}/{num_tests} tests passed");
+        Ok(())
+    }
 
-        // Move on to next clash if --auto-advance is set
-        if num_passed == num_tests && args.get_flag("auto-advance") {
-            let next_handle = self.random_handle()?;
-            std::fs::write(&self.current_clash_file, next_handle.to_string())?;
-            println!("Moving on to next clash...");
+    /// Runs the solution against the tests revealed in `coctus reverse`, the
+    /// same way as `coctus run --testcases` does.
+    fn run_revealed_tests(
+        &self,
+        handle: &PublicHandle,
+        args: &ArgMatches,
+        tests: &[&Testcase],
+    ) -> Result<()> {
+        let selection: Vec<String> = tests.iter().map(|test| test.index.to_string()).collect();
+        let mut run_args: Vec<std::ffi::OsString> = vec![
+            "--ignore-failures".into(),
+            "--numbering".into(),
+            "flat".into(),
+            "--testcases".into(),
+            selection.join(",").into(),
+        ];
+        for name in ["command", "build-command"] {
+            if let Some(command) = args.get_one::<String>(name) {
+                run_args.extend([format!("--{name}").into(), command.into()]);
+            }
         }
-
-        Ok(())
+        run_args.push(handle.to_string().into());
+        self.run_with_args(run_args)
     }
 
-    fn fetch(&self, args: &ArgMatches) -> Result<()> {
-        std::fs::create_dir_all(&self.clash_dir)?;
-        let handles = args
-            .get_many::<PublicHandle>("PUBLIC_HANDLE")
-            .with_context(|| "Should have many handles")?;
-        for handle in handles {
-            let req = ureq::post("https://www.codingame.com/services/Contribution/findContribution")
-                .set("Content-Type", "application/json");
-            let content = match req.send_string(&format!(r#"["{}", true]"#, handle)) {
-                Err(ureq::Error::Status(status, res)) => {
-                    return Err(anyhow!("HTTP {} {} from {}", status, res.status_text(), res.get_url()))
-                }
-                res => res?.into_string()?,
+    fn review(&self, args: &ArgMatches) -> Result<()> {
+        let slow = *args.get_one::<std::time::Duration>("slow").expect("--slow should have a default");
+        let schedule = review_schedule(&History::load(&self.history_file)?, slow);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let today = now.as_secs() / 86400;
+        let mut items: Vec<(&String, &ReviewItem)> = schedule
+            .iter()
+            .filter(|(_, item)| args.get_flag("all") || item.due_day <= today)
+            .collect();
+        items.sort_by_key(|(_, item)| item.due_day);
+
+        let ostyle = self.ostyle(false);
+        let days = |n: u64| {
+            if n == 1 {
+                "1 day".to_string()
+            } else {
+                format!("{n} days")
+            }
+        };
+        for (handle, item) in &items {
+            let (due, style) = match item.due_day.cmp(&today) {
+                std::cmp::Ordering::Less => {
+                    (format!("{} overdue", days(today - item.due_day)), ostyle.failure)
+                }
+                std::cmp::Ordering::Equal => ("due today".to_string(), ostyle.title),
+                std::cmp::Ordering::Greater => {
+                    (format!("due in {}", days(item.due_day - today)), ostyle.dim_color)
+                }
             };
-            let clash_file_path = self.clash_dir.join(format!("{}.json", handle));
-            std::fs::write(&clash_file_path, &content)?;
-            println!("Saved clash {} as {}", &handle, &clash_file_path.display());
+            let title = PublicHandle::from_str(handle)
+                .ok()
+                .and_then(|handle| self.read_clash(&handle).ok())
+                .map(|clash| clash.title().to_string())
+                .unwrap_or_default();
+            println!(
+                "{handle}  {}  {:<9}  {title}",
+                style.paint(format!("{due:<16}")),
+                match item.lapses {
+                    1 => "1 lapse".to_string(),
+                    n => format!("{n} lapses"),
+                }
+            );
+        }
+        let due: Vec<String> = schedule
+            .iter()
+            .filter(|(_, item)| item.due_day <= today)
+            .map(|(handle, _)| handle.clone())
+            .collect();
+        if due.is_empty() {
+            println!("Nothing is due for review today");
+        }
+
+        if args.get_flag("queue") {
+            // The schedule is of this user alone, so it stays out of the
+            // tags of the collection that others may share
+            let mut tags = Tags::load(&self.user_tags_file)?;
+            let mut operation = self.trash.begin("queue reviews");
+            self.trash.preserve(&mut operation, &self.user_tags_file)?;
+            let num_due = due.len();
+            tags.replace(REVIEW_TAG, due);
+            tags.save(&self.user_tags_file)?;
+            self.trash.commit(operation)?;
+            if num_due > 0 {
+                println!("Tagged {num_due} clashes with {REVIEW_TAG}, pick one with `coctus next --tag {REVIEW_TAG}`");
+            }
         }
         Ok(())
     }
 
-    fn showtests(&self, args: &ArgMatches) -> Result<()> {
-        let handle = self.current_handle()?;
-        let clash = self.read_clash(&handle)?;
-        let all_testcases = clash.testcases();
-
-        let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
-        let ostyle = OutputStyle::from_env(show_whitespace);
+    fn stats(&self, args: &ArgMatches) -> Result<()> {
+        let records = History::load(&self.history_file)?;
+        let stats = aggregate_stats(&records);
 
-        let num_testcases = all_testcases.len();
-        let testcase_indices: Vec<u64> = match args.get_many::<u64>("TESTCASE") {
-            Some(nums) => nums.cloned().collect(),
-            None => (1u64..=num_testcases as u64).collect(),
-        };
+        if let Some(path) = args.get_one::<PathBuf>("badge") {
+            let num_solved = stats.values().filter(|clash| clash.solved).count();
+            let mut svg = Vec::new();
+            write_badge_svg(&mut svg, "coctus", &format!("{num_solved} solved"))?;
+            std::fs::write(path, svg).with_context(|| format!("Unable to write badge to {:?}", path))?;
+            println!("Wrote a badge of {num_solved} solved clashes to {}", path.display());
+            return Ok(())
+        }
 
-        let only_in = args.get_flag("in");
-        let only_out = args.get_flag("out");
+        match args.get_one::<String>("export").map(String::as_str) {
+            Some("csv") => {
+                let title = |handle: &str| {
+                    let handle = PublicHandle::from_str(handle).ok()?;
+                    self.read_clash(&handle).ok().map(|clash| clash.title().to_string())
+                };
+                write_stats_csv(std::io::stdout().lock(), &stats, title)?;
+            }
+            _ => {
+                let num_attempts: usize = stats.values().map(|clash| clash.attempts).sum();
+                let total = GroupStats::of(stats.values());
+                println!("Runs: {num_attempts}");
+                println!("Clashes attempted: {}", total.attempted);
+                println!("Clashes solved: {}", total.solved);
+                if let Some(average_time) = total.average_time {
+                    println!(
+                        "Average solve time: {}",
+                        format_duration(std::time::Duration::from_secs(average_time))
+                    );
+                }
 
-        for idx in testcase_indices {
-            let testcase = match all_testcases.get((idx - 1) as usize) {
-                Some(x) => x,
-                None => {
-                    return Err(anyhow!(
-                        "Invalid testcase index {idx} (the current clash only has {num_testcases} testcases)"
-                    ))
+                // Modes come from the stored clashes, so clashes that have been
+                // removed since they were run are left out
+                let mut by_mode: BTreeMap<&str, Vec<&ClashStats>> = BTreeMap::new();
+                for (handle, clash_stats) in &stats {
+                    let Some(clash) =
+                        PublicHandle::from_str(handle).ok().and_then(|h| self.read_clash(&h).ok())
+                    else {
+                        continue
+                    };
+                    let modes = [
+                        ("fastest", clash.is_fastest()),
+                        ("shortest", clash.is_shortest()),
+                        ("reverse", clash.is_reverse()),
+                    ];
+                    for (mode, _) in modes.into_iter().filter(|(_, has_mode)| *has_mode) {
+                        by_mode.entry(mode).or_default().push(clash_stats);
+                    }
                 }
-            };
+                // Runs without --language (or a remembered solution) have no language
+                let mut languages: Vec<&str> =
+                    records.iter().filter_map(|record| record.language.as_deref()).collect();
+                languages.sort_unstable();
+                languages.dedup();
+                let by_language: Vec<(&str, GroupStats)> = languages
+                    .into_iter()
+                    .map(|language| {
+                        let records: Vec<RunRecord> = records
+                            .iter()
+                            .filter(|record| record.language.as_deref() == Some(language))
+                            .cloned()
+                            .collect();
+                        (language, GroupStats::of(aggregate_stats(&records).values()))
+                    })
+                    .collect();
 
-            if !(only_in || only_out) {
-                let styled_title = ostyle.title.paint(format!("#{} {}", idx, testcase.title));
-                println!("{styled_title}");
-                println!("{}", ostyle.secondary_title.paint("===== INPUT ======"));
-            }
-            if !only_out {
-                println!("{}", ostyle.styled_testcase_input(testcase));
-            }
-            if !(only_in || only_out) {
-                println!("{}", ostyle.secondary_title.paint("==== EXPECTED ===="));
-            }
-            if !only_in {
-                println!("{}", ostyle.styled_testcase_output(testcase));
+                let by_mode = by_mode.into_iter().map(|(mode, clashes)| (mode, GroupStats::of(clashes)));
+                for (heading, groups) in [
+                    ("By mode", by_mode.collect::<Vec<_>>()),
+                    ("By language", by_language),
+                ] {
+                    if groups.is_empty() {
+                        continue
+                    }
+                    println!("\n{heading}:");
+                    println!("  {:<12} {:>9} {:>7} {:>14}", "", "attempted", "solved", "avg solve time");
+                    for (name, group) in groups {
+                        let average_time = group
+                            .average_time
+                            .map(|secs| format_duration(std::time::Duration::from_secs(secs)))
+                            .unwrap_or_else(|| "-".to_string());
+                        println!(
+                            "  {name:<12} {:>9} {:>7} {average_time:>14}",
+                            group.attempted, group.solved
+                        );
+                    }
+                }
             }
         }
+        Ok(())
+    }
+
+    fn export(&self, args: &ArgMatches) -> Result<()> {
+        let path = args.get_one::<PathBuf>("FILE").expect("FILE should be required");
+        if path.exists() && !args.get_flag("force") {
+            return Err(anyhow!("{} already exists (use --force to overwrite it)", path.display()))
+        }
+        let mut archive = Archive {
+            clashes: Vec::new(),
+            current: self.selected_handle().ok().map(|handle| handle.to_string()),
+        };
+        for handle in self.storage.stored_handles()? {
+            let clash_path = self.clash_dir.join(format!("{handle}.json"));
+            let contents =
+                std::fs::read(&clash_path).with_context(|| format!("Unable to read {:?}", clash_path))?;
+            archive.clashes.push((handle.to_string(), contents));
+        }
 
+        let file = std::fs::File::create(path).with_context(|| format!("Unable to create {:?}", path))?;
+        archive.write(std::io::BufWriter::new(file))?;
+        println!("Exported {} clashes to {}", archive.clashes.len(), path.display());
         Ok(())
     }
 
-    fn generate_stub(&self, args: &ArgMatches) -> Result<()> {
-        let lang_arg = args
-            .get_one::<String>("PROGRAMMING_LANGUAGE")
-            .context("Should have a programming language")?;
+    fn config(&self, args: &ArgMatches) -> Result<()> {
+        match args.subcommand() {
+            Some(("export", args)) => {
+                let path = args.get_one::<PathBuf>("FILE").expect("FILE should be required");
+                if path.exists() && !args.get_flag("force") {
+                    return Err(anyhow!("{} already exists (use --force to overwrite it)", path.display()))
+                }
+                let config_dir = self.config_file.parent().expect("config file should be in a directory");
+                let archive = ConfigArchive::of_dir(config_dir)?;
+                let file =
+                    std::fs::File::create(path).with_context(|| format!("Unable to create {:?}", path))?;
+                archive.write(std::io::BufWriter::new(file))?;
+                let config = match archive.config {
+                    Some(_) => "the config file",
+                    None => "no config file",
+                };
+                println!(
+                    "Exported {config} and {} stub template files to {}",
+                    archive.stub_templates.len(),
+                    path.display()
+                );
+                Ok(())
+            }
+            Some(("import", args)) => {
+                let path = args.get_one::<PathBuf>("FILE").expect("FILE should be required");
+                let file = std::fs::File::open(path).with_context(|| format!("Unable to open {:?}", path))?;
+                let mut archive = ConfigArchive::read(std::io::BufReader::new(file))
+                    .with_context(|| format!("Unable to import {:?}", path))?;
+                archive.migrate().with_context(|| format!("Unable to import {:?}", path))?;
+                // The templates of a language only work if all of them do
+                let check_dir = tempfile::tempdir()?;
+                for (template_path, contents) in &archive.stub_templates {
+                    let check_path = check_dir.path().join(template_path);
+                    std::fs::create_dir_all(check_path.parent().expect("template should be in a directory"))?;
+                    std::fs::write(check_path, contents)?;
+                }
+                for language in archive.languages() {
+                    StubConfig::read_from_dir(check_dir.path().join(language)).with_context(|| {
+                        format!("Invalid stub templates for {language} in {:?}, nothing was imported", path)
+                    })?;
+                }
 
-        let stub_generator = match args.get_one::<PathBuf>("from-file") {
-            Some(fname) if fname.to_str() == Some("-") => {
-                let mut input = String::new();
-                std::io::stdin().read_to_string(&mut input)?;
-                input
+                let mut operation = self.trash.begin(format!("import config {}", path.display()));
+                let mut files: Vec<(PathBuf, &[u8])> = archive
+                    .stub_templates
+                    .iter()
+                    .map(|(template_path, contents)| {
+                        (self.stub_templates_dir.join(template_path), &contents[..])
+                    })
+                    .collect();
+                if let Some(config) = &archive.config {
+                    files.push((self.config_file.clone(), config.as_bytes()));
+                }
+                let mut num_changed = 0;
+                for (file_path, contents) in files {
+                    if std::fs::read(&file_path).is_ok_and(|existing| existing == contents) {
+                        continue
+                    }
+                    self.trash.preserve(&mut operation, &file_path)?;
+                    std::fs::create_dir_all(file_path.parent().expect("file should be in a directory"))?;
+                    std::fs::write(&file_path, contents)
+                        .with_context(|| format!("Unable to write {:?}", file_path))?;
+                    num_changed += 1;
+                }
+                self.trash.commit(operation)?;
+                println!("Imported the configuration from {}: {num_changed} files changed", path.display());
+                Ok(())
             }
-            Some(fname) => std::fs::read_to_string(fname)?,
-            None if args.get_flag("from-reference") => stub::SIMPLE_REFERENCE_STUB.to_owned(),
-            None => {
-                let handle = self.current_handle()?;
-                self.read_clash(&handle)?
-                    .stub_generator()
-                    .with_context(|| "Current clash provides no input stub generator")?
-                    .to_owned()
+            _ => Err(anyhow!("unimplemented subcommand")),
+        }
+    }
+
+    fn import(&self, args: &ArgMatches) -> Result<()> {
+        self.storage.check_writable("import clashes")?;
+        let path = args.get_one::<PathBuf>("FILE").expect("FILE should be required");
+        let file = std::fs::File::open(path).with_context(|| format!("Unable to open {:?}", path))?;
+        let archive = Archive::read(std::io::BufReader::new(file))
+            .with_context(|| format!("Unable to import {:?}", path))?;
+        for (handle, contents) in &archive.clashes {
+            serde_json::from_slice::<Clash>(contents)
+                .with_context(|| format!("Clash {handle} in {:?} is invalid, nothing was imported", path))?;
+        }
+
+        std::fs::create_dir_all(&self.clash_dir)?;
+        let mut operation = self.trash.begin(format!("import {}", path.display()));
+        let (mut num_new, mut changed) = (0, Vec::new());
+        for (handle, contents) in &archive.clashes {
+            let clash_path = self.clash_dir.join(format!("{handle}.json"));
+            match std::fs::read(&clash_path) {
+                Ok(existing) if existing == *contents => continue,
+                Ok(_) => {}
+                Err(_) => num_new += 1,
             }
-        };
+            self.trash.preserve(&mut operation, &clash_path)?;
+            std::fs::write(&clash_path, contents)
+                .with_context(|| format!("Unable to write {:?}", clash_path))?;
+            changed.push(handle.to_owned());
+        }
+        let current = archive.current.filter(|_| !args.get_flag("keep-current"));
+        if let Some(current) = &current {
+            self.trash.preserve(&mut operation, &self.current_clash_file)?;
+            self.select_handle(&PublicHandle::from_str(current)?)?;
+        }
+        self.trash.commit(operation)?;
 
-        // Language config files are stored in: (ordered by precedence)
-        // 1. The user config dir, where {CONF} is the OS dependent config folder:
-        //    `{CONF}/stub_templates/LANG/stub_config.toml`
-        // 2. This repo, embedded into the binary:
-        //    `config/stub_templates/LANG/stub_config.toml`
-        let lang_template_dir = self.stub_templates_dir.join(lang_arg);
-        let stub_string = if lang_template_dir.is_dir() {
-            let stub_config = StubConfig::read_from_dir(lang_template_dir)?;
-            stub::generate_from_config(stub_config, &stub_generator)?
-        } else {
-            stub::generate(lang_arg, &stub_generator)?
-        };
-        println!("{stub_string}");
+        println!(
+            "Imported {} clashes from {}: {} new, {} updated, {} unchanged",
+            archive.clashes.len(),
+            path.display(),
+            num_new,
+            changed.len() - num_new,
+            archive.clashes.len() - changed.len()
+        );
+        if let Some(current) = current {
+            println!("Changed clash to {current}");
+        }
+        if !changed.is_empty() {
+            let description = format!("imported {} clashes from {}", changed.len(), path.display());
+            self.log_change("import", description, changed)?;
+        }
         Ok(())
     }
 
-    fn json(&self, args: &ArgMatches) -> Result<()> {
-        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
-            Some(h) => h.to_owned(),
-            None => self.current_handle()?,
-        };
-        let clash_file = self.clash_dir.join(format!("{}.json", handle));
-        let contents = std::fs::read_to_string(clash_file)
-            .with_context(|| format!("Unable to find clash with handle {}", handle))?;
+    fn verify_store(&self, args: &ArgMatches) -> Result<()> {
+        let fix = args.get_flag("fix");
+        if fix {
+            self.storage.check_writable("fix the stored clashes")?;
+        }
+        let files = clash_files(&self.clash_dir).with_context(|| {
+            format!("No clashes stored in {:?} (run `coctus init` to get started)", &self.clash_dir)
+        })?;
 
-        println!("{}", &contents);
+        let mut num_problems = 0;
+        let mut num_fixed = 0;
+        let (mut renamed, mut removed) = (Vec::new(), Vec::new());
+
+        // (path, handle in the file name, handle in the contents, contents)
+        let mut stored_clashes = Vec::<(PathBuf, String, String, String)>::new();
+        for (file_handle, path) in files {
+            let contents = match std::fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(err) => {
+                    println!("{}: unable to read: {}", path.display(), err);
+                    num_problems += 1;
+                    continue
+                }
+            };
+            match serde_json::from_str::<Clash>(&contents) {
+                Ok(clash) => {
+                    let handle = clash.public_handle().to_string();
+                    stored_clashes.push((path, file_handle, handle, contents));
+                }
+                Err(err) => {
+                    println!("{}: invalid clash JSON: {}", path.display(), err);
+                    num_problems += 1;
+                }
+            }
+        }
+
+        // Correctly named files go first so they are the ones that get kept
+        stored_clashes.sort_by_key(|(path, file_handle, handle, _)| (file_handle != handle, path.clone()));
+
+        // lowercased handle => path of the first file that contained it
+        let mut seen_handles = BTreeMap::<String, PathBuf>::new();
+
+        // Only files that --fix renames or removes are added to the operation
+        self.trash.record("verify-store --fix", |operation| -> Result<()> {
+            for (mut path, file_handle, handle, contents) in stored_clashes {
+                let mut unfixed_problems = 0;
+                if file_handle != handle {
+                    println!("{}: file name does not match publicHandle {}", path.display(), handle);
+                    num_problems += 1;
+                    unfixed_problems += 1;
+                    let target = self.clash_dir.join(format!("{handle}.json"));
+                    if fix && !target.exists() {
+                        self.trash.preserve(operation, &path)?;
+                        self.trash.preserve(operation, &target)?;
+                        std::fs::rename(&path, &target)?;
+                        println!("  renamed to {}", target.display());
+                        renamed.push(handle.clone());
+                        num_fixed += 1;
+                        unfixed_problems -= 1;
+                        path = target;
+                    }
+                }
+
+                match seen_handles.entry(handle.to_lowercase()) {
+                    std::collections::btree_map::Entry::Vacant(entry) => {
+                        entry.insert(path);
+                    }
+                    std::collections::btree_map::Entry::Occupied(entry) => {
+                        let original = entry.get();
+                        println!("{}: duplicate of {}", path.display(), original.display());
+                        num_problems += 1;
+                        unfixed_problems += 1;
+                        if fix && std::fs::read_to_string(original)? == contents {
+                            self.trash.preserve(operation, &path)?;
+                            std::fs::remove_file(&path)?;
+                            println!("  removed identical duplicate");
+                            removed.push(handle.clone());
+                            // Removing the file also takes care of its other problems
+                            num_fixed += unfixed_problems;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })?;
+
+        if !renamed.is_empty() || !removed.is_empty() {
+            let description = [
+                ("renamed", renamed.len(), "misnamed clash files"),
+                ("removed", removed.len(), "duplicates"),
+            ]
+            .iter()
+            .filter(|(_, count, _)| *count > 0)
+            .map(|(action, count, what)| format!("{action} {count} {what}"))
+            .collect::<Vec<_>>()
+            .join(" and ");
+            self.log_change("verify-store", description, [renamed, removed].concat())?;
+        }
+
+        let num_clashes = seen_handles.len();
+        match num_problems - num_fixed {
+            0 if num_problems == 0 => println!("All {num_clashes} stored clashes are OK"),
+            0 => println!("Fixed all {num_problems} problems"),
+            remaining => {
+                return Err(anyhow!(
+                    "Found {num_problems} problems ({num_fixed} fixed, {remaining} remaining)"
+                ))
+            }
+        }
         Ok(())
     }
 
@@ -520,17 +4784,58 @@ fn main() -> Result<()> {
     // We look for the locally stored clashes here:
     let project_dirs = ProjectDirs::from("", "CoCtus", "coctus").expect("Unable to find project directory");
 
-    let app = App::new(project_dirs.data_dir(), project_dirs.config_dir());
+    let matches = cli().get_matches();
+    let config = match Config::load(&Config::file(project_dirs.config_dir())) {
+        // `init` tells where the broken file is and `config import` can replace it
+        Err(err) if matches!(matches.subcommand_name(), Some("init" | "config")) => {
+            eprintln!("WARNING {err:#}");
+            Config::default()
+        }
+        config => config?,
+    };
+    let app = App::new(project_dirs.data_dir(), project_dirs.config_dir(), config)?;
 
-    match cli().get_matches().subcommand() {
+    match matches.subcommand() {
+        Some(("init", args)) => app.init(args),
         Some(("show", args)) => app.show(args),
         Some(("next", args)) => app.next(args),
+        Some(("current", args)) => app.current(args),
         Some(("status", args)) => app.status(args),
+        Some(("clash", args)) => app.clash(args),
+        Some(("tweak", args)) => app.tweak(args),
+        Some(("open-test", args)) => app.open_test(args),
+        Some(("tui", args)) => app.tui(args),
+        Some(("stress", args)) => app.stress(args),
         Some(("run", args)) => app.run(args),
         Some(("fetch", args)) => app.fetch(args),
+        Some(("refresh", args)) => app.refresh(args),
+        Some(("login", args)) => app.login(args),
+        Some(("logout", args)) => app.logout(args),
         Some(("showtests", args)) => app.showtests(args),
         Some(("json", args)) => app.json(args),
+        Some(("meta", args)) => app.meta(args),
+        Some(("start", args)) => app.start(args),
         Some(("generate-stub", args)) => app.generate_stub(args),
+        Some(("parse-all-stubs", args)) => app.parse_all_stubs(args),
+        Some(("stub-repl", args)) => app.stub_repl(args),
+        Some(("addtest", args)) => app.addtest(args),
+        Some(("import-tests", args)) => app.import_tests(args),
+        Some(("calibrate", args)) => app.calibrate(args),
+        Some(("log", args)) => app.log(args),
+        Some(("schema", args)) => app.schema(args),
+        Some(("undo", args)) => app.undo(args),
+        Some(("test-templates", args)) => app.test_templates(args),
+        Some(("which", args)) => app.which(args),
+        Some(("list", args)) => app.list(args),
+        Some(("search", args)) => app.search(args),
+        Some(("score", args)) => app.score(args),
+        Some(("reverse", args)) => app.reverse(args),
+        Some(("review", args)) => app.review(args),
+        Some(("stats", args)) => app.stats(args),
+        Some(("export", args)) => app.export(args),
+        Some(("import", args)) => app.import(args),
+        Some(("config", args)) => app.config(args),
+        Some(("verify-store", args)) => app.verify_store(args),
         Some(("generate-shell-completion", args)) => app.generate_completions(args),
         _ => Err(anyhow!("unimplemented subcommand")),
     }
@@ -0,0 +1,12 @@
+//! The types and functions of the stable API that most programs using the
+//! library need, for glob importing with `use clashlib::prelude::*`.
+
+pub use crate::clash::{Clash, PublicHandle, Testcase};
+#[cfg(feature = "fetch-client")]
+pub use crate::client::fetch_clash_json;
+pub use crate::diff::{compute as compute_diff, Granularity, Hunk, HunkKind};
+#[cfg(feature = "runner")]
+pub use crate::solution::{run_testcase, RunOptions, TestResult};
+#[cfg(feature = "embedded-templates")]
+pub use crate::stub::generate as generate_stub;
+pub use crate::stub::{generate_from_config, StubConfig};
@@ -0,0 +1,35 @@
+//! Scores of Clash of Code solutions.
+
+/// What CodinGame counts as the length of a solution in the "shortest" mode
+/// of Clash of Code: the number of characters (not bytes) of the source.
+///
+/// Like the CodinGame IDE this doesn't count a byte order mark, the `\r` of
+/// `\r\n` line endings or whitespace at the end of the file.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::scoring::shortest_mode_length;
+///
+/// assert_eq!(shortest_mode_length("print(input())\n"), 14);
+/// assert_eq!(shortest_mode_length("a=1\r\nprint(a*2)\r\n\r\n"), 14);
+/// assert_eq!(shortest_mode_length("print('π')"), 10);
+/// ```
+pub fn shortest_mode_length(source: &str) -> usize {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source).trim_end();
+    source.chars().count() - source.matches("\r\n").count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_trailing_whitespace_is_stripped() {
+        assert_eq!(shortest_mode_length(""), 0);
+        assert_eq!(shortest_mode_length(" \n\t\n"), 0);
+        assert_eq!(shortest_mode_length("\u{feff}  x = 1 \n"), 7);
+        assert_eq!(shortest_mode_length("a\r\n\r\nb"), 4);
+        assert_eq!(shortest_mode_length("a\rb"), 3);
+    }
+}
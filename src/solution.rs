@@ -1,15 +1,91 @@
+mod output;
+mod runner;
 mod test_result;
 
 use std::io::Write;
 use std::process::Command;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use output::OutputReader;
+pub use output::{OutputChunk, OutputStream};
+pub use runner::Runner;
 use test_result::CommandExit;
-pub use test_result::TestResult;
+pub use test_result::{FailureCategory, ProcessExit, ResultKind, TestResult, TestResultReport};
 use wait_timeout::ChildExt;
 
 use crate::clash::Testcase;
 
+/// Options that control how a solution gets run against testcases.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct RunOptions {
+    /// Maximum time the solution may spend on a single testcase.
+    pub timeout: Duration,
+    /// Also record stdout and stderr interleaved in the order the output was
+    /// read, see [OutputChunk].
+    pub merged_output: bool,
+    /// Write the input of each testcase into a temporary file whose path is
+    /// given to the command in the `COCTUS_INPUT_FILE` environment variable
+    /// and in place of `{input_file}` in its arguments. The input is still
+    /// written to STDIN as well.
+    ///
+    /// The other placeholders in the arguments of the command, `{test_index}`
+    /// and `{test_title}`, are replaced with the [Testcase::index] and the
+    /// title of the testcase either way.
+    pub input_file: bool,
+    /// Point in time by which the whole suite has to be finished. A testcase
+    /// that is running when the deadline passes is stopped and reported as
+    /// [TestResult::Aborted], and the remaining testcases are not run at all.
+    pub deadline: Option<Instant>,
+    /// Testcases whose input is longer than this many bytes are not run, they
+    /// fail with [TestResult::UnableToRun] instead.
+    pub max_input_bytes: Option<usize>,
+    /// Only this many bytes of stdout and of stderr are kept. The rest of the
+    /// output is still read (so that the solution doesn't block on a full
+    /// pipe) but thrown away.
+    pub max_output_bytes: Option<usize>,
+}
+
+impl RunOptions {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            merged_output: false,
+            input_file: false,
+            deadline: None,
+            max_input_bytes: None,
+            max_output_bytes: None,
+        }
+    }
+
+    /// Returns true if the suite deadline is set and has passed.
+    pub fn deadline_passed(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    // The per-testcase timeout, cut short if the suite deadline is closer
+    fn effective_timeout(&self) -> Duration {
+        match self.deadline {
+            Some(deadline) => self.timeout.min(deadline.saturating_duration_since(Instant::now())),
+            None => self.timeout,
+        }
+    }
+}
+
+/// Returns the positions `0..len` in a random order determined by `seed`.
+/// Used for running testcases in a different order than they are reported in
+/// to catch solutions (or wrapper scripts) that depend on an earlier test.
+pub fn shuffled_order(len: usize, seed: u64) -> Vec<usize> {
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    let mut order: Vec<usize> = (0..len).collect();
+    order.shuffle(&mut rand::rngs::StdRng::seed_from_u64(seed));
+    order
+}
+
 /// Run a command against testcases one at a time.
 ///
 /// # Examples
@@ -21,6 +97,7 @@ use crate::clash::Testcase;
 /// let testcases = [
 ///     Testcase {
 ///         index: 1,
+///         number: 1,
 ///         title: String::from("Test #1"),
 ///         test_in: String::from("hey"),
 ///         test_out: String::from("hey"),
@@ -40,14 +117,314 @@ pub fn lazy_run<'a>(
     run_command: &'a mut Command,
     timeout: &'a Duration,
 ) -> impl IntoIterator<Item = (&'a Testcase, TestResult)> {
-    testcases.into_iter().map(|test| {
-        let result = run_testcase(test, run_command, timeout);
-        (test, result)
+    lazy_run_with_options(testcases, run_command, RunOptions::new(*timeout))
+}
+
+/// Same as [lazy_run] but with more control over how the command is run.
+/// Iteration stops early once the [RunOptions::deadline] has passed, and a
+/// testcase that was still running then is [TestResult::Aborted].
+pub fn lazy_run_with_options<'a>(
+    testcases: impl IntoIterator<Item = &'a Testcase>,
+    run_command: &'a mut Command,
+    options: RunOptions,
+) -> impl IntoIterator<Item = (&'a Testcase, TestResult)> {
+    let mut deadline_reached = false;
+    testcases.into_iter().map_while(move |test| {
+        if deadline_reached || options.deadline_passed() {
+            return None
+        }
+        let timeout = options.effective_timeout();
+        let (result, timed_out) = run_testcase_with_timeout(test, run_command, &options, timeout);
+        // The clock used for waiting may wake up just before the deadline
+        deadline_reached = timed_out && timeout < options.timeout;
+        match deadline_reached {
+            true => Some((test, result.aborted_on_timeout())),
+            false => Some((test, result)),
+        }
     })
 }
 
+/// Same as [lazy_run_with_options] but runs up to `jobs` testcases at the
+/// same time, each in a process of its own. The results are still yielded in
+/// the order of `testcases`. Dropping the iterator kills the solutions that
+/// are still running, and the testcases that haven't been started yet are
+/// not run at all.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::clash::Testcase;
+/// use clashlib::solution::{parallel_run, RunOptions};
+///
+/// let testcases: Vec<Testcase> = (1..=4)
+///     .map(|index| Testcase {
+///         index,
+///         number: index,
+///         title: format!("Test #{index}"),
+///         test_in: index.to_string(),
+///         test_out: index.to_string(),
+///         is_validator: false,
+///     })
+///     .collect();
+/// let command = std::process::Command::new("cat");
+/// let options = RunOptions::new(std::time::Duration::from_secs(5));
+///
+/// let results: Vec<_> = parallel_run(&testcases, &command, options, 2).collect();
+/// assert_eq!(results.len(), 4);
+/// assert!(results.iter().all(|(_, test_result)| test_result.is_success()));
+/// assert_eq!(results[3].0.title, "Test #4");
+/// ```
+pub fn parallel_run<'a>(
+    testcases: impl IntoIterator<Item = &'a Testcase>,
+    run_command: &Command,
+    options: RunOptions,
+    jobs: usize,
+) -> impl Iterator<Item = (&'a Testcase, TestResult)> {
+    let testcases: Vec<&Testcase> = testcases.into_iter().collect();
+    let queue: std::collections::VecDeque<(usize, Testcase)> =
+        testcases.iter().map(|testcase| (*testcase).clone()).enumerate().collect();
+    let queue = Arc::new(Mutex::new(queue));
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let workers = (0..jobs.clamp(1, testcases.len().max(1)))
+        .map(|_| {
+            let (queue, cancelled, sender) = (queue.clone(), cancelled.clone(), sender.clone());
+            let spec = CommandSpec::of(run_command);
+            let options = options.clone();
+            std::thread::spawn(move || loop {
+                if cancelled.load(Ordering::Relaxed) {
+                    return
+                }
+                let Some((position, testcase)) =
+                    queue.lock().expect("the queue is not locked while running").pop_front()
+                else {
+                    return
+                };
+                // Skipped testcases end the suite like in lazy_run_with_options
+                let result = match options.deadline_passed() {
+                    true => None,
+                    false => {
+                        let mut command = spec.to_command();
+                        let run = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                            let timeout = options.effective_timeout();
+                            let (result, timed_out) = run_testcase_cancellable(
+                                &testcase,
+                                &mut command,
+                                &options,
+                                timeout,
+                                Some(&cancelled),
+                            );
+                            match timed_out && timeout < options.timeout {
+                                true => result.aborted_on_timeout(),
+                                false => result,
+                            }
+                        }));
+                        Some(run.unwrap_or_else(|panic| TestResult::UnableToRun {
+                            error_msg: format!("Running the testcase failed: {}", panic_message(&*panic)),
+                        }))
+                    }
+                };
+                if sender.send((position, result)).is_err() {
+                    return
+                }
+            })
+        })
+        .collect();
+    ParallelRun {
+        testcases,
+        receiver,
+        finished: std::collections::BTreeMap::new(),
+        next: 0,
+        cancelled,
+        workers,
+    }
+}
+
+struct ParallelRun<'a> {
+    testcases: Vec<&'a Testcase>,
+    receiver: std::sync::mpsc::Receiver<(usize, Option<TestResult>)>,
+    // Results that arrived before the ones of earlier testcases
+    finished: std::collections::BTreeMap<usize, Option<TestResult>>,
+    next: usize,
+    cancelled: Arc<AtomicBool>,
+    workers: Vec<std::thread::JoinHandle<()>>,
+}
+
+impl<'a> Iterator for ParallelRun<'a> {
+    type Item = (&'a Testcase, TestResult);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let testcase = *self.testcases.get(self.next)?;
+        let result = loop {
+            if let Some(result) = self.finished.remove(&self.next) {
+                break result
+            }
+            match self.receiver.recv() {
+                Ok((position, result)) => self.finished.insert(position, result),
+                // Every worker is gone without running the testcase
+                Err(_) => {
+                    let error_msg = "The testcase was never run".to_string();
+                    break Some(TestResult::UnableToRun { error_msg })
+                }
+            };
+        };
+        match result {
+            Some(result) => {
+                self.next += 1;
+                Some((testcase, result))
+            }
+            None => {
+                self.next = self.testcases.len();
+                None
+            }
+        }
+    }
+}
+
+impl Drop for ParallelRun<'_> {
+    fn drop(&mut self) {
+        // The workers kill the solutions they are running when they notice
+        self.cancelled.store(true, Ordering::Relaxed);
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    match panic.downcast_ref::<String>() {
+        Some(message) => message.clone(),
+        None => panic
+            .downcast_ref::<&str>()
+            .map_or_else(|| "unknown error".to_string(), |msg| msg.to_string()),
+    }
+}
+
+// The parts of a command that are needed for making copies of it in other
+// threads
+struct CommandSpec {
+    program: std::ffi::OsString,
+    args: Vec<std::ffi::OsString>,
+    envs: Vec<(std::ffi::OsString, Option<std::ffi::OsString>)>,
+    current_dir: Option<std::path::PathBuf>,
+}
+
+impl CommandSpec {
+    fn of(cmd: &Command) -> Self {
+        CommandSpec {
+            program: cmd.get_program().to_owned(),
+            args: cmd.get_args().map(ToOwned::to_owned).collect(),
+            envs: cmd
+                .get_envs()
+                .map(|(key, value)| (key.to_owned(), value.map(ToOwned::to_owned)))
+                .collect(),
+            current_dir: cmd.get_current_dir().map(ToOwned::to_owned),
+        }
+    }
+
+    fn to_command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args);
+        self.configure(&mut cmd);
+        cmd
+    }
+
+    /// A new command for running `testcase` alone, with the placeholders of
+    /// the testcase (see [RunOptions::input_file]) replaced in the
+    /// arguments. Building a command for every testcase keeps anything that
+    /// one run sets on it from leaking into the next.
+    fn for_testcase(&self, testcase: &Testcase, input_file: Option<&std::path::Path>) -> Command {
+        let index = testcase.index.to_string();
+        let input_path = input_file.map(|path| path.to_string_lossy());
+        let mut cmd = Command::new(&self.program);
+        for arg in &self.args {
+            match arg.to_str() {
+                Some(arg) => {
+                    let mut arg =
+                        arg.replace("{test_index}", &index).replace("{test_title}", &testcase.title);
+                    if let Some(path) = &input_path {
+                        arg = arg.replace("{input_file}", path);
+                    }
+                    cmd.arg(arg)
+                }
+                None => cmd.arg(arg),
+            };
+        }
+        self.configure(&mut cmd);
+        if let Some(path) = input_file {
+            cmd.env("COCTUS_INPUT_FILE", path);
+        }
+        cmd
+    }
+
+    fn configure(&self, cmd: &mut Command) {
+        for (key, value) in &self.envs {
+            match value {
+                Some(value) => cmd.env(key, value),
+                None => cmd.env_remove(key),
+            };
+        }
+        if let Some(dir) = &self.current_dir {
+            cmd.current_dir(dir);
+        }
+    }
+}
+
 /// Run a command against a single testcase.
 pub fn run_testcase(testcase: &Testcase, run_command: &mut Command, timeout: &Duration) -> TestResult {
+    run_testcase_with_options(testcase, run_command, &RunOptions::new(*timeout))
+}
+
+/// Same as [run_testcase] but with more control over how the command is run.
+pub fn run_testcase_with_options(
+    testcase: &Testcase,
+    run_command: &mut Command,
+    options: &RunOptions,
+) -> TestResult {
+    run_testcase_with_timeout(testcase, run_command, options, options.effective_timeout()).0
+}
+
+// Also returns whether the testcase timed out
+fn run_testcase_with_timeout(
+    testcase: &Testcase,
+    run_command: &mut Command,
+    options: &RunOptions,
+    timeout: Duration,
+) -> (TestResult, bool) {
+    run_testcase_cancellable(testcase, run_command, options, timeout, None)
+}
+
+// The solution gets killed as soon as `cancelled` is set, the result is
+// meaningless then
+fn run_testcase_cancellable(
+    testcase: &Testcase,
+    run_command: &mut Command,
+    options: &RunOptions,
+    timeout: Duration,
+    cancelled: Option<&AtomicBool>,
+) -> (TestResult, bool) {
+    if let Some(max_bytes) = options.max_input_bytes.filter(|max| testcase.test_in.len() > *max) {
+        let error_msg = format!(
+            "The input is {} bytes, more than the limit of {max_bytes} bytes",
+            testcase.test_in.len()
+        );
+        return (TestResult::UnableToRun { error_msg }, false)
+    }
+    // Needs to stay alive until the process has finished
+    let input_file = match options.input_file {
+        true => match write_input_file(testcase) {
+            Ok(file) => Some(file),
+            Err(err) => {
+                let error_msg = format!("Unable to write input file: {err}");
+                return (TestResult::UnableToRun { error_msg }, false)
+            }
+        },
+        false => None,
+    };
+    let run_command =
+        &mut CommandSpec::of(run_command).for_testcase(testcase, input_file.as_ref().map(|file| file.path()));
+
+    let started_at = Instant::now();
     let mut run = match run_command
         .stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
@@ -58,40 +435,94 @@ pub fn run_testcase(testcase: &Testcase, run_command: &mut Command, timeout: &Du
         Err(error) => {
             let program = run_command.get_program().to_str().unwrap_or("Unable to run command");
             let error_msg = format!("{}: {}", program, error);
-            return TestResult::UnableToRun { error_msg }
+            return (TestResult::UnableToRun { error_msg }, false)
         }
     };
 
-    run.stdin
-        .as_mut()
-        .expect("STDIN of child process should be captured")
-        .write_all(testcase.test_in.as_bytes())
-        .expect("STDIN of child process should be writable");
+    // Output has to be read while the process is running, otherwise a
+    // solution that fills up the pipe's buffer would never finish.
+    let output_reader = OutputReader::spawn(&mut run, started_at);
 
-    let timed_out = run
-        .wait_timeout(*timeout)
-        .expect("Process should be able to wait for execution")
-        .is_none();
+    // The input is written in the background too: a solution that doesn't
+    // read its input would otherwise block the write (and the timeout) when
+    // the input is larger than the pipe's buffer.
+    let mut stdin = run.stdin.take().expect("STDIN of child process should be captured");
+    let input = testcase.test_in.clone();
+    let input_writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let timed_out = match cancelled {
+        None => run
+            .wait_timeout(timeout)
+            .expect("Process should be able to wait for execution")
+            .is_none(),
+        Some(cancelled) => {
+            // Waking up every now and then to check whether to give up
+            let started_waiting = Instant::now();
+            loop {
+                let remaining = timeout.saturating_sub(started_waiting.elapsed());
+                let slice = remaining.min(Duration::from_millis(50));
+                if run
+                    .wait_timeout(slice)
+                    .expect("Process should be able to wait for execution")
+                    .is_some()
+                {
+                    break false
+                }
+                if remaining <= slice || cancelled.load(Ordering::Relaxed) {
+                    break true
+                }
+            }
+        }
+    };
 
     if timed_out {
         run.kill().expect("Process should have been killed");
     }
+    if cancelled.is_some_and(|cancelled| cancelled.load(Ordering::Relaxed)) {
+        // Processes started by the solution may still hold on to its output,
+        // and nobody is waiting for the result anymore
+        let _ = run.wait();
+        let error_msg = "The run was cancelled".to_string();
+        return (TestResult::UnableToRun { error_msg }, false)
+    }
 
-    let output = run.wait_with_output().expect("Process should allow waiting for its execution");
+    let status = run.wait().expect("Process should allow waiting for its execution");
+    // A solution is allowed to exit without reading all of its input, and
+    // once it has exited the write can't block anymore
+    let write_error = match input_writer.join().expect("Input writer thread should not panic") {
+        Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => Some(err),
+        _ => None,
+    };
+    let output = output_reader.finish(options.merged_output, options.max_output_bytes);
+    if let Some(err) = write_error {
+        let result =
+            TestResult::unwritable_input(output.stdout, output.stderr, output.merged, status.into(), &err);
+        return (result, timed_out)
+    }
 
     let exit_status = if timed_out {
         CommandExit::Timeout
-    } else if output.status.success() {
+    } else if status.success() {
         CommandExit::Ok
     } else {
-        CommandExit::Error
+        CommandExit::Error(status.into())
     };
-    TestResult::from_output(&testcase.test_out, output.stdout, output.stderr, exit_status)
+    let result =
+        TestResult::from_output(&testcase.test_out, output.stdout, output.stderr, output.merged, exit_status);
+    (result, timed_out)
+}
+
+fn write_input_file(testcase: &Testcase) -> std::io::Result<tempfile::NamedTempFile> {
+    let mut file = tempfile::Builder::new().prefix("coctus-input-").suffix(".txt").tempfile()?;
+    file.write_all(testcase.test_in.as_bytes())?;
+    file.write_all(b"\n")?;
+    Ok(file)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_helper::testcase;
 
     #[test]
     fn test_passing_solution() {
@@ -105,6 +536,214 @@ mod tests {
             .all(|(_, test_result)| test_result.is_success()))
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn test_runtime_error_reports_exit_code() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let testcase = &clash.testcases()[0];
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "exit 3"]);
+        let timeout = Duration::from_secs(1);
+        match run_testcase(testcase, &mut run_cmd, &timeout) {
+            TestResult::RuntimeError { exit, .. } => assert_eq!(exit, ProcessExit::Code(3)),
+            other => panic!("expected TestResult::RuntimeError but found {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_runtime_error_reports_signal() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let testcase = &clash.testcases()[0];
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "kill -SEGV $$"]);
+        let timeout = Duration::from_secs(1);
+        match run_testcase(testcase, &mut run_cmd, &timeout) {
+            TestResult::RuntimeError { exit, .. } => assert_eq!(exit, ProcessExit::Signal(11)),
+            other => panic!("expected TestResult::RuntimeError but found {:?}", other),
+        }
+    }
+
+    fn large_testcase(num_lines: usize) -> Testcase {
+        let text: String = (0..num_lines).map(|idx| format!("line {idx} of a large testcase\n")).collect();
+        testcase(1, &text, &text)
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_large_output_while_reading_large_input() {
+        // Several megabytes, many times the size of a pipe's buffer
+        let testcase = large_testcase(200_000);
+        assert!(testcase.test_in.len() > 4_000_000);
+        let timeout = Duration::from_secs(20);
+        let result = run_testcase(&testcase, &mut Command::new("cat"), &timeout);
+        assert!(result.is_success(), "expected TestResult::Success but found {:?}", result);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_timeout_when_large_input_is_not_read() {
+        let testcase = large_testcase(200_000);
+        let mut run_cmd = Command::new("sleep");
+        run_cmd.arg("10");
+        let started_at = Instant::now();
+        let result = run_testcase(&testcase, &mut run_cmd, &Duration::from_millis(300));
+        assert!(matches!(result, TestResult::Timeout { .. }));
+        assert!(started_at.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_merged_output_keeps_order() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let testcase = &clash.testcases()[0];
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "echo out1; sleep 0.1; echo err >&2; sleep 0.1; echo out2"]);
+        let mut options = RunOptions::new(Duration::from_secs(5));
+        options.merged_output = true;
+        match run_testcase_with_options(testcase, &mut run_cmd, &options) {
+            TestResult::WrongOutput {
+                stdout,
+                stderr,
+                merged,
+                ..
+            } => {
+                assert_eq!(stdout, "out1\nout2");
+                assert_eq!(stderr, "err\n");
+                let streams: Vec<_> =
+                    merged.iter().map(|chunk| (chunk.stream, chunk.text.as_str())).collect();
+                assert_eq!(
+                    streams,
+                    vec![
+                        (OutputStream::Stdout, "out1\n"),
+                        (OutputStream::Stderr, "err\n"),
+                        (OutputStream::Stdout, "out2\n"),
+                    ]
+                );
+            }
+            other => panic!("expected TestResult::WrongOutput but found {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_deadline_stops_suite() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let mut run_cmd = Command::new("sleep");
+        run_cmd.arg("5");
+        let mut options = RunOptions::new(Duration::from_secs(10));
+        options.deadline = Some(Instant::now() + Duration::from_millis(200));
+        let results: Vec<_> =
+            lazy_run_with_options(clash.testcases(), &mut run_cmd, options).into_iter().collect();
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0].1, TestResult::Aborted { .. }));
+        assert_eq!(results[0].1.failure_category(results[0].0), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_parallel_run_reports_in_order() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let mut run_cmd = Command::new("sh");
+        // The first testcases take the longest, so the results arrive out of order
+        run_cmd.args([
+            "-c",
+            "input=$(cat); case $input in a*) sleep 0.5;; esac; echo \"$input\" | tr X b",
+        ]);
+        let options = RunOptions::new(Duration::from_secs(5));
+        let results: Vec<_> = parallel_run(clash.testcases(), &run_cmd, options, 4).collect();
+        assert_eq!(results.len(), clash.testcases().len());
+        for ((testcase, test_result), expected) in results.iter().zip(clash.testcases()) {
+            assert_eq!(testcase.index, expected.index);
+            assert!(test_result.is_success());
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_parallel_run_stops_on_deadline() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let mut run_cmd = Command::new("sleep");
+        run_cmd.arg("5");
+        let mut options = RunOptions::new(Duration::from_secs(10));
+        // Long enough for both workers to start even on a busy machine
+        options.deadline = Some(Instant::now() + Duration::from_secs(1));
+        let results: Vec<_> = parallel_run(clash.testcases(), &run_cmd, options, 2).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .all(|(_, test_result)| matches!(test_result, TestResult::Aborted { .. })));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_dropping_parallel_run_kills_the_solutions() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("finished");
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "sleep 1; touch \"$0\""]).arg(&marker);
+        let options = RunOptions::new(Duration::from_secs(10));
+        let run = parallel_run(clash.testcases(), &run_cmd, options, 2);
+        std::thread::sleep(Duration::from_millis(200));
+        let started_at = Instant::now();
+        drop(run);
+        assert!(started_at.elapsed() < Duration::from_millis(900));
+        std::thread::sleep(Duration::from_millis(1500));
+        assert!(!marker.exists(), "a solution kept running after the run was dropped");
+    }
+
+    #[test]
+    fn test_shuffled_order_is_a_permutation_determined_by_seed() {
+        let order = shuffled_order(20, 42);
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+        assert_eq!(order, shuffled_order(20, 42));
+        assert_ne!(order, shuffled_order(20, 43));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_input_file_placeholder_and_env() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let mut options = RunOptions::new(Duration::from_secs(1));
+        options.input_file = true;
+
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "tr X b < \"$0\"", "{input_file}"]);
+        assert!(lazy_run_with_options(clash.testcases(), &mut run_cmd, options.clone())
+            .into_iter()
+            .all(|(_, test_result)| test_result.is_success()));
+
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "tr X b < \"$COCTUS_INPUT_FILE\""]);
+        assert!(lazy_run_with_options(clash.testcases(), &mut run_cmd, options)
+            .into_iter()
+            .all(|(_, test_result)| test_result.is_success()));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_testcase_placeholders() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let testcases: Vec<Testcase> = clash
+            .testcases()
+            .iter()
+            .map(|testcase| Testcase {
+                test_out: format!("{} {}", testcase.index, testcase.title),
+                ..testcase.clone()
+            })
+            .collect();
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "echo \"$0 $1\"", "{test_index}", "{test_title}"]);
+        assert!(lazy_run(&testcases, &mut run_cmd, &Duration::from_secs(1))
+            .into_iter()
+            .all(|(_, test_result)| test_result.is_success()));
+        // The placeholders are still there for the next run
+        assert_eq!(run_cmd.get_args().nth(2).unwrap(), "{test_index}");
+    }
+
     #[test]
     fn test_failing_solution() {
         let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
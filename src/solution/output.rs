@@ -0,0 +1,189 @@
+use std::io::Read;
+use std::process::Child;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Merged output is only recorded up to this many bytes so that a solution
+/// stuck in an infinite loop does not keep eating memory twice as fast. The
+/// separate stdout and stderr are captured in full unless
+/// [RunOptions::max_output_bytes](super::RunOptions::max_output_bytes) is set.
+const MAX_MERGED_BYTES: usize = 64 * 1024;
+
+/// The output stream a solution wrote an [OutputChunk] to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// A piece of output read from a solution process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputChunk {
+    pub stream: OutputStream,
+    /// Time between starting the process and reading the chunk.
+    pub elapsed: Duration,
+    pub text: String,
+}
+
+pub(crate) struct CapturedOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub merged: Vec<OutputChunk>,
+}
+
+type Message = (OutputStream, Instant, Vec<u8>);
+
+/// Reads stdout and stderr of a child process in background threads as the
+/// output gets written, recording when each chunk arrived.
+pub(crate) struct OutputReader {
+    started_at: Instant,
+    receiver: mpsc::Receiver<Message>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl OutputReader {
+    pub fn spawn(child: &mut Child, started_at: Instant) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let mut threads = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            threads.push(read_in_background(stdout, OutputStream::Stdout, sender.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            threads.push(read_in_background(stderr, OutputStream::Stderr, sender));
+        }
+        Self {
+            started_at,
+            receiver,
+            threads,
+        }
+    }
+
+    /// Waits until the child process has closed both of its output streams.
+    /// The merged stream is only assembled if `merged_output` is true. Only
+    /// the first `max_bytes` bytes of stdout and stderr are kept if it is set,
+    /// without the part of a character that the limit cuts in two.
+    pub fn finish(self, merged_output: bool, max_bytes: Option<usize>) -> CapturedOutput {
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut truncated = [false; 2];
+        let mut chunks: Vec<(OutputStream, Instant, Vec<u8>)> = Vec::new();
+        let mut merged_bytes = 0;
+
+        for (stream, read_at, bytes) in self.receiver.iter() {
+            let (captured, truncated) = match stream {
+                OutputStream::Stdout => (&mut stdout, &mut truncated[0]),
+                OutputStream::Stderr => (&mut stderr, &mut truncated[1]),
+            };
+            let room = max_bytes.map_or(bytes.len(), |max| max.saturating_sub(captured.len()));
+            captured.extend_from_slice(&bytes[..room.min(bytes.len())]);
+            *truncated |= room < bytes.len();
+            if merged_output && merged_bytes < MAX_MERGED_BYTES {
+                merged_bytes += bytes.len();
+                chunks.push((stream, read_at, bytes));
+            }
+        }
+        for (captured, truncated) in [(&mut stdout, truncated[0]), (&mut stderr, truncated[1])] {
+            if truncated {
+                drop_cut_character(captured);
+            }
+        }
+        for thread in self.threads {
+            // The reader threads never panic, the only way they end is EOF
+            // or a read error which just means there is no more output.
+            let _ = thread.join();
+        }
+
+        // Messages from the two threads may arrive slightly out of order
+        chunks.sort_by_key(|(_, read_at, _)| *read_at);
+        let merged = merge_adjacent(chunks)
+            .into_iter()
+            .map(|(stream, read_at, bytes)| OutputChunk {
+                stream,
+                elapsed: read_at.duration_since(self.started_at),
+                text: String::from_utf8_lossy(&bytes).into_owned(),
+            })
+            .collect();
+
+        CapturedOutput {
+            stdout,
+            stderr,
+            merged,
+        }
+    }
+}
+
+/// Removes the start of a multi-byte UTF-8 character from the end of
+/// `bytes`, so that cutting the output doesn't count as invalid output.
+fn drop_cut_character(bytes: &mut Vec<u8>) {
+    // A character is at most 4 bytes, so the cut one starts in the last 3
+    let tail = bytes.len().saturating_sub(3);
+    let Some(start) = (tail..bytes.len()).rev().find(|&idx| bytes[idx] & 0xC0 != 0x80) else {
+        return
+    };
+    if let Err(err) = std::str::from_utf8(&bytes[start..]) {
+        if err.error_len().is_none() {
+            bytes.truncate(start);
+        }
+    }
+}
+
+fn read_in_background(
+    mut pipe: impl Read + Send + 'static,
+    stream: OutputStream,
+    sender: mpsc::Sender<Message>,
+) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut buf = [0; 8192];
+        loop {
+            match pipe.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if sender.send((stream, Instant::now(), buf[..n].to_vec())).is_err() {
+                        break
+                    }
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            }
+        }
+    })
+}
+
+// Joins consecutive chunks from the same stream so that multi-byte characters
+// split between two reads get decoded correctly.
+fn merge_adjacent(chunks: Vec<Message>) -> Vec<Message> {
+    let mut merged: Vec<Message> = Vec::new();
+    for (stream, read_at, bytes) in chunks {
+        match merged.last_mut() {
+            Some((prev_stream, _, prev_bytes)) if *prev_stream == stream => prev_bytes.extend(bytes),
+            _ => merged.push((stream, read_at, bytes)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_adjacent_joins_same_stream() {
+        let t = Instant::now();
+        let chunks = vec![
+            (OutputStream::Stdout, t, b"a".to_vec()),
+            (OutputStream::Stdout, t, b"b".to_vec()),
+            (OutputStream::Stderr, t, b"c".to_vec()),
+            (OutputStream::Stdout, t, b"d".to_vec()),
+        ];
+        let merged: Vec<_> = merge_adjacent(chunks).into_iter().map(|(s, _, b)| (s, b)).collect();
+        assert_eq!(
+            merged,
+            vec![
+                (OutputStream::Stdout, b"ab".to_vec()),
+                (OutputStream::Stderr, b"c".to_vec()),
+                (OutputStream::Stdout, b"d".to_vec()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use super::{lazy_run_with_options, run_testcase_with_timeout, RunOptions, TestResult};
+use crate::clash::Testcase;
+
+/// Callback that is given every testcase as soon as its result is known.
+type ProgressCallback<'a> = Box<dyn FnMut(&Testcase, &TestResult) + 'a>;
+
+/// Runs a command against testcases, for tools that embed coctus instead of
+/// calling `coctus run`. A builder for the command, the [RunOptions] and a
+/// progress callback in one place.
+///
+/// The default timeout is 5 seconds per testcase, like in `coctus run`.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::clash::Testcase;
+/// use clashlib::solution::Runner;
+///
+/// let testcases: Vec<Testcase> = (1..=3)
+///     .map(|index| Testcase {
+///         index,
+///         number: index,
+///         title: format!("Test #{index}"),
+///         test_in: format!("{index} apples"),
+///         test_out: format!("{index} oranges"),
+///         is_validator: false,
+///     })
+///     .collect();
+///
+/// let mut num_finished = 0;
+/// let results = Runner::new("sed")
+///     .arg("s/apples/oranges/")
+///     .timeout(std::time::Duration::from_secs(2))
+///     .env("LC_ALL", "C")
+///     .max_output_bytes(1024)
+///     .on_result(|_testcase, _test_result| num_finished += 1)
+///     .run(&testcases);
+///
+/// assert!(results.iter().all(|(_, test_result)| test_result.is_success()));
+/// assert_eq!(num_finished, 3);
+/// ```
+pub struct Runner<'a> {
+    command: Command,
+    options: RunOptions,
+    on_result: Option<ProgressCallback<'a>>,
+}
+
+impl<'a> Runner<'a> {
+    /// Runs `program` (which is looked up in PATH like in [Command::new]).
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        Self::from_command(Command::new(program))
+    }
+
+    /// Runs a command that has already been set up. Its stdin, stdout and
+    /// stderr get replaced with pipes.
+    pub fn from_command(command: Command) -> Self {
+        Runner {
+            command,
+            options: RunOptions::new(Duration::from_secs(5)),
+            on_result: None,
+        }
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.command.arg(arg);
+        self
+    }
+
+    pub fn args(mut self, args: impl IntoIterator<Item = impl AsRef<OsStr>>) -> Self {
+        self.command.args(args);
+        self
+    }
+
+    /// Sets an environment variable of the command.
+    pub fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.command.env(key, value);
+        self
+    }
+
+    /// Directory the command is run in (the current one by default).
+    pub fn current_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.command.current_dir(dir);
+        self
+    }
+
+    /// Maximum time the command may spend on a single testcase.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = timeout;
+        self
+    }
+
+    /// See [RunOptions::deadline].
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.options.deadline = Some(deadline);
+        self
+    }
+
+    /// See [RunOptions::max_input_bytes].
+    pub fn max_input_bytes(mut self, max_bytes: usize) -> Self {
+        self.options.max_input_bytes = Some(max_bytes);
+        self
+    }
+
+    /// See [RunOptions::max_output_bytes].
+    pub fn max_output_bytes(mut self, max_bytes: usize) -> Self {
+        self.options.max_output_bytes = Some(max_bytes);
+        self
+    }
+
+    /// See [RunOptions::merged_output].
+    pub fn merged_output(mut self, merged_output: bool) -> Self {
+        self.options.merged_output = merged_output;
+        self
+    }
+
+    /// See [RunOptions::input_file].
+    pub fn input_file(mut self, input_file: bool) -> Self {
+        self.options.input_file = input_file;
+        self
+    }
+
+    /// Calls `callback` with every testcase and its result as soon as the
+    /// testcase has been run, for showing progress during long suites.
+    pub fn on_result(mut self, callback: impl FnMut(&Testcase, &TestResult) + 'a) -> Self {
+        self.on_result = Some(Box::new(callback));
+        self
+    }
+
+    pub fn options(&self) -> &RunOptions {
+        &self.options
+    }
+
+    /// Runs the testcases one at a time, in order. Stops early once the
+    /// deadline has passed, so there may be fewer results than testcases.
+    pub fn run<'t>(
+        &mut self,
+        testcases: impl IntoIterator<Item = &'t Testcase>,
+    ) -> Vec<(&'t Testcase, TestResult)> {
+        let testcases: Vec<&'t Testcase> = testcases.into_iter().collect();
+        let on_result = &mut self.on_result;
+        // The results borrow from the command, they are paired with the
+        // testcases again so that they live as long as the testcases
+        let results =
+            lazy_run_with_options(testcases.iter().copied(), &mut self.command, self.options.clone())
+                .into_iter()
+                .map(|(testcase, result)| {
+                    if let Some(callback) = on_result {
+                        callback(testcase, &result);
+                    }
+                    result
+                });
+        testcases.iter().copied().zip(results).collect()
+    }
+
+    /// Runs a single testcase. The progress callback is not called.
+    pub fn run_testcase(&mut self, testcase: &Testcase) -> TestResult {
+        let timeout = self.options.effective_timeout();
+        run_testcase_with_timeout(testcase, &mut self.command, &self.options, timeout).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::testcase;
+
+    #[test]
+    #[cfg(unix)]
+    fn runner_sets_up_the_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let expected = format!("{} hello", dir.path().canonicalize().unwrap().display());
+        let mut runner = Runner::new("sh")
+            .args(["-c", "echo \"$(pwd -P) $GREETING\""])
+            .env("GREETING", "hello")
+            .current_dir(dir.path());
+        assert!(runner.run_testcase(&testcase(1, "", &expected)).is_success());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn runner_enforces_the_size_limits() {
+        let mut runner = Runner::new("cat").max_input_bytes(5).max_output_bytes(3);
+        match runner.run_testcase(&testcase(1, "123456", "123456")) {
+            TestResult::UnableToRun { error_msg } => assert!(error_msg.contains("limit of 5 bytes")),
+            other => panic!("expected TestResult::UnableToRun but found {:?}", other),
+        }
+        match runner.run_testcase(&testcase(1, "12345", "12345")) {
+            TestResult::WrongOutput { stdout, .. } => assert_eq!(stdout, "123"),
+            other => panic!("expected TestResult::WrongOutput but found {:?}", other),
+        }
+        // The limit falls in the middle of the two bytes of é
+        match runner.run_testcase(&testcase(1, "aaé", "aaé")) {
+            TestResult::WrongOutput {
+                stdout, invalid_utf8, ..
+            } => assert_eq!((stdout.as_str(), invalid_utf8), ("aa", 0)),
+            other => panic!("expected TestResult::WrongOutput but found {:?}", other),
+        }
+    }
+}
@@ -1,13 +1,123 @@
-pub enum CommandExit {
+use serde::{Deserialize, Serialize};
+
+use super::OutputChunk;
+use crate::clash::Testcase;
+use crate::diff;
+
+pub(crate) enum CommandExit {
     Ok,
-    Error,
+    Error(ProcessExit),
+    Timeout,
+}
+
+/// Describes how a solution process that did not exit successfully died.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProcessExit {
+    /// The process exited with a (non-zero) exit code.
+    Code(i32),
+    /// The process was terminated by a signal (only on unix).
+    Signal(i32),
+    /// The platform did not report an exit code or a signal.
+    Unknown,
+}
+
+impl From<std::process::ExitStatus> for ProcessExit {
+    fn from(status: std::process::ExitStatus) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            if let Some(signal) = status.signal() {
+                return ProcessExit::Signal(signal)
+            }
+        }
+        match status.code() {
+            Some(code) => ProcessExit::Code(code),
+            None => ProcessExit::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for ProcessExit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ProcessExit::Code(code) => write!(f, "exit code {code}"),
+            ProcessExit::Signal(signal) => match signal_name(*signal) {
+                Some(name) => write!(f, "{name}"),
+                None => write!(f, "signal {signal}"),
+            },
+            ProcessExit::Unknown => write!(f, "unknown exit status"),
+        }
+    }
+}
+
+// Only the signals whose numbers are the same on all common unix platforms.
+fn signal_name(signal: i32) -> Option<&'static str> {
+    let name = match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// What kind of a failure a testcase that did not pass was, see
+/// [TestResult::failure_category].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum FailureCategory {
+    /// The solution printed something other than the expected output.
+    WrongAnswer,
+    /// The output would match if differences that are hard to see (such as
+    /// a byte order mark or whitespace at the end of lines) were ignored,
+    /// see [diff::invisible_differences].
+    PresentationError,
+    /// The solution exited normally without printing anything.
+    EmptyOutput,
+    /// The solution did not finish in time.
     Timeout,
+    /// The solution crashed or could not be run at all.
+    RuntimeError,
+}
+
+impl std::fmt::Display for FailureCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FailureCategory::WrongAnswer => write!(f, "wrong answer"),
+            FailureCategory::PresentationError => write!(f, "presentation error"),
+            FailureCategory::EmptyOutput => write!(f, "empty output"),
+            FailureCategory::Timeout => write!(f, "timeout"),
+            FailureCategory::RuntimeError => write!(f, "runtime error"),
+        }
+    }
 }
 
 /// Represents the outcome of running a testcase. [TestResult::Success] means
 /// the output of a solution command matched the `test_out` field of the
 /// [Testcase](crate::clash::Testcase).
+///
+/// The `merged` field of the failure variants contains stdout and stderr
+/// interleaved in the order they were written. It is only recorded when
+/// [RunOptions::merged_output](super::RunOptions::merged_output) is set and
+/// empty otherwise.
+///
+/// Output that is not valid UTF-8 is decoded lossily: the invalid bytes
+/// are replaced with U+FFFD (�), and the `invalid_utf8` field of the failure
+/// variants counts how many bytes of stdout were replaced.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum TestResult {
     /// Solution command produced the expected output. A test run is considered
     /// a success even if it runs into a runtime error or times out if its
@@ -19,11 +129,38 @@ pub enum TestResult {
     UnableToRun { error_msg: String },
     /// Solution command exited normally but did not produce the expected
     /// output.
-    WrongOutput { stdout: String, stderr: String },
-    /// Solution command encountered a runtime error (exited non-zero).
-    RuntimeError { stdout: String, stderr: String },
+    WrongOutput {
+        stdout: String,
+        stderr: String,
+        merged: Vec<OutputChunk>,
+        invalid_utf8: usize,
+    },
+    /// Solution command encountered a runtime error (exited non-zero or was
+    /// killed by a signal).
+    RuntimeError {
+        stdout: String,
+        stderr: String,
+        merged: Vec<OutputChunk>,
+        exit: ProcessExit,
+        invalid_utf8: usize,
+    },
     /// Solution command timed out.
-    Timeout { stdout: String, stderr: String },
+    Timeout {
+        stdout: String,
+        stderr: String,
+        merged: Vec<OutputChunk>,
+        invalid_utf8: usize,
+    },
+    /// The [RunOptions::deadline](super::RunOptions::deadline) of the whole
+    /// suite passed while the solution was running, so it was stopped before
+    /// its own timeout. This says nothing about the solution, so it is not a
+    /// failure.
+    Aborted {
+        stdout: String,
+        stderr: String,
+        merged: Vec<OutputChunk>,
+        invalid_utf8: usize,
+    },
 }
 
 impl TestResult {
@@ -31,20 +168,79 @@ impl TestResult {
         expected: &str,
         stdout: Vec<u8>,
         stderr: Vec<u8>,
+        merged: Vec<OutputChunk>,
         exit_status: CommandExit,
     ) -> Self {
-        let stdout = String::from_utf8(stdout)
-            .unwrap_or_default()
-            .replace("\r\n", "\n")
-            .trim_end()
-            .to_string();
-        let stderr = String::from_utf8(stderr).unwrap_or_default();
+        let (stdout, invalid_utf8) = decode_lossy(&stdout);
+        let stdout = stdout.replace("\r\n", "\n").trim_end().to_string();
+        let stderr = String::from_utf8_lossy(&stderr).into_owned();
 
         match exit_status {
             _ if stdout == expected.trim_end() => TestResult::Success,
-            CommandExit::Timeout => TestResult::Timeout { stdout, stderr },
-            CommandExit::Ok => TestResult::WrongOutput { stdout, stderr },
-            CommandExit::Error => TestResult::RuntimeError { stdout, stderr },
+            CommandExit::Timeout => TestResult::Timeout {
+                stdout,
+                stderr,
+                merged,
+                invalid_utf8,
+            },
+            CommandExit::Ok => TestResult::WrongOutput {
+                stdout,
+                stderr,
+                merged,
+                invalid_utf8,
+            },
+            CommandExit::Error(exit) => TestResult::RuntimeError {
+                stdout,
+                stderr,
+                merged,
+                exit,
+                invalid_utf8,
+            },
+        }
+    }
+
+    /// A [TestResult::RuntimeError] for a solution whose input could not be
+    /// written to its stdin, whatever it printed. The error is added to the
+    /// end of stderr.
+    pub(crate) fn unwritable_input(
+        stdout: Vec<u8>,
+        stderr: Vec<u8>,
+        merged: Vec<OutputChunk>,
+        exit: ProcessExit,
+        error: &std::io::Error,
+    ) -> Self {
+        let (stdout, invalid_utf8) = decode_lossy(&stdout);
+        let stdout = stdout.replace("\r\n", "\n").trim_end().to_string();
+        let mut stderr = String::from_utf8_lossy(&stderr).into_owned();
+        if !stderr.is_empty() && !stderr.ends_with('\n') {
+            stderr.push('\n');
+        }
+        stderr.push_str(&format!("Unable to write the input to STDIN: {error}\n"));
+        TestResult::RuntimeError {
+            stdout,
+            stderr,
+            merged,
+            exit,
+            invalid_utf8,
+        }
+    }
+
+    /// Turns a timeout into [TestResult::Aborted], for a testcase whose
+    /// timeout was cut short by the suite deadline.
+    pub(crate) fn aborted_on_timeout(self) -> Self {
+        match self {
+            TestResult::Timeout {
+                stdout,
+                stderr,
+                merged,
+                invalid_utf8,
+            } => TestResult::Aborted {
+                stdout,
+                stderr,
+                merged,
+                invalid_utf8,
+            },
+            other => other,
         }
     }
 
@@ -53,35 +249,173 @@ impl TestResult {
     pub fn is_success(&self) -> bool {
         matches!(self, TestResult::Success)
     }
+
+    /// Classifies the failure of `testcase`, or returns `None` if it passed
+    /// or was [aborted](TestResult::Aborted). Timeouts and runtime errors are
+    /// reported as such even if the output would also have been wrong.
+    pub fn failure_category(&self, testcase: &Testcase) -> Option<FailureCategory> {
+        match self {
+            TestResult::Success | TestResult::Aborted { .. } => None,
+            TestResult::UnableToRun { .. } | TestResult::RuntimeError { .. } => {
+                Some(FailureCategory::RuntimeError)
+            }
+            TestResult::Timeout { .. } => Some(FailureCategory::Timeout),
+            TestResult::WrongOutput { stdout, .. } if stdout.is_empty() => Some(FailureCategory::EmptyOutput),
+            TestResult::WrongOutput { stdout, .. } => {
+                match diff::invisible_differences(testcase.test_out.trim_end(), stdout).is_empty() {
+                    true => Some(FailureCategory::WrongAnswer),
+                    false => Some(FailureCategory::PresentationError),
+                }
+            }
+        }
+    }
+}
+
+/// Which variant of [TestResult] a [TestResultReport] is of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum ResultKind {
+    Success,
+    UnableToRun,
+    WrongOutput,
+    RuntimeError,
+    Timeout,
+    Aborted,
+}
+
+/// Serializable version of a [TestResult], for reporting the results to
+/// other programs (`coctus run --output-format json`). The interleaved
+/// output is left out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+pub struct TestResultReport {
+    pub result: ResultKind,
+    /// See [TestResult::failure_category], `None` if the testcase passed.
+    pub category: Option<FailureCategory>,
+    /// Output of the solution. `None` if it passed (the output was the
+    /// expected one) or could not be run.
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+    /// How a crashed solution exited, like "exit code 1" or "SIGSEGV".
+    pub exit: Option<String>,
+    /// Why the solution could not be run.
+    pub error: Option<String>,
+    /// Number of bytes of stdout that were not valid UTF-8.
+    pub invalid_utf8: usize,
+}
+
+impl TestResultReport {
+    pub fn new(testcase: &Testcase, test_result: &TestResult) -> Self {
+        let mut report = TestResultReport {
+            result: ResultKind::Success,
+            category: test_result.failure_category(testcase),
+            stdout: None,
+            stderr: None,
+            exit: None,
+            error: None,
+            invalid_utf8: 0,
+        };
+        let (kind, stdout, stderr, invalid_utf8) = match test_result {
+            TestResult::Success => return report,
+            TestResult::UnableToRun { error_msg } => {
+                report.result = ResultKind::UnableToRun;
+                report.error = Some(error_msg.clone());
+                return report
+            }
+            TestResult::WrongOutput {
+                stdout,
+                stderr,
+                invalid_utf8,
+                ..
+            } => (ResultKind::WrongOutput, stdout, stderr, invalid_utf8),
+            TestResult::RuntimeError {
+                stdout,
+                stderr,
+                exit,
+                invalid_utf8,
+                ..
+            } => {
+                report.exit = Some(exit.to_string());
+                (ResultKind::RuntimeError, stdout, stderr, invalid_utf8)
+            }
+            TestResult::Timeout {
+                stdout,
+                stderr,
+                invalid_utf8,
+                ..
+            } => (ResultKind::Timeout, stdout, stderr, invalid_utf8),
+            TestResult::Aborted {
+                stdout,
+                stderr,
+                invalid_utf8,
+                ..
+            } => (ResultKind::Aborted, stdout, stderr, invalid_utf8),
+        };
+        report.result = kind;
+        report.stdout = Some(stdout.clone());
+        report.stderr = Some(stderr.clone());
+        report.invalid_utf8 = *invalid_utf8;
+        report
+    }
+}
+
+/// Decodes `bytes` replacing invalid UTF-8 with U+FFFD (like
+/// [String::from_utf8_lossy]), and also returns the number of bytes that
+/// were replaced.
+fn decode_lossy(mut bytes: &[u8]) -> (String, usize) {
+    let mut text = String::with_capacity(bytes.len());
+    let mut num_invalid = 0;
+    loop {
+        match std::str::from_utf8(bytes) {
+            Ok(valid) => {
+                text.push_str(valid);
+                return (text, num_invalid)
+            }
+            Err(err) => {
+                let (valid, rest) = bytes.split_at(err.valid_up_to());
+                text.push_str(std::str::from_utf8(valid).expect("bytes up to the error should be valid"));
+                // A sequence cut short by the end of the output has no length
+                let invalid_len = err.error_len().unwrap_or(rest.len());
+                text.push(char::REPLACEMENT_CHARACTER);
+                num_invalid += invalid_len;
+                bytes = &rest[invalid_len..];
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_helper::testcase;
 
     #[test]
     fn test_testresult_success() {
-        let result = TestResult::from_output("123", "123".into(), vec![], CommandExit::Ok);
+        let result = TestResult::from_output("123", "123".into(), vec![], vec![], CommandExit::Ok);
         assert!(matches!(result, TestResult::Success));
     }
 
     #[test]
     fn test_testresult_success_with_trailing_whitespace() {
-        let result = TestResult::from_output("abc\n", "abc".into(), vec![], CommandExit::Ok);
+        let result = TestResult::from_output("abc\n", "abc".into(), vec![], vec![], CommandExit::Ok);
         assert!(matches!(result, TestResult::Success));
-        let result = TestResult::from_output("abc", "abc\r\n".into(), vec![], CommandExit::Ok);
+        let result = TestResult::from_output("abc", "abc\r\n".into(), vec![], vec![], CommandExit::Ok);
         assert!(matches!(result, TestResult::Success));
     }
 
     #[test]
     fn test_testresult_success_normalized_line_endings() {
-        let result = TestResult::from_output("a\nb\nc", "a\r\nb\r\nc".into(), vec![], CommandExit::Ok);
+        let result =
+            TestResult::from_output("a\nb\nc", "a\r\nb\r\nc".into(), vec![], vec![], CommandExit::Ok);
         assert!(matches!(result, TestResult::Success));
     }
 
     #[test]
     fn test_testresult_success_on_timeout() {
-        let result = TestResult::from_output("123", "123".into(), vec![], CommandExit::Timeout);
+        let result = TestResult::from_output("123", "123".into(), vec![], vec![], CommandExit::Timeout);
         assert!(
             matches!(result, TestResult::Success),
             "TestResult should be `Success` when stdout is correct even if execution timed out"
@@ -90,18 +424,61 @@ mod tests {
 
     #[test]
     fn test_testresult_success_on_runtime_error() {
-        let result = TestResult::from_output("123", "123".into(), vec![], CommandExit::Error);
+        let result = TestResult::from_output(
+            "123",
+            "123".into(),
+            vec![],
+            vec![],
+            CommandExit::Error(ProcessExit::Code(1)),
+        );
         assert!(
             matches!(result, TestResult::Success),
             "TestResult should be `Success` when stdout is correct even if a runtime error occurred"
         )
     }
 
+    #[test]
+    fn test_testresult_unwritable_input() {
+        let error = std::io::Error::other("disk on fire");
+        let result =
+            TestResult::unwritable_input("123".into(), "oops".into(), vec![], ProcessExit::Code(0), &error);
+        match result {
+            TestResult::RuntimeError { stdout, stderr, .. } => {
+                assert_eq!(stdout, "123");
+                assert_eq!(stderr, "oops\nUnable to write the input to STDIN: disk on fire\n");
+            }
+            other => panic!("expected a runtime error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_report_of_runtime_error() {
+        let testcase = testcase(1, "", "123");
+        let result = TestResult::from_output(
+            "123",
+            "12".into(),
+            "oops".into(),
+            vec![],
+            CommandExit::Error(ProcessExit::Code(2)),
+        );
+        let report = TestResultReport::new(&testcase, &result);
+        assert_eq!(report.result, ResultKind::RuntimeError);
+        assert_eq!(report.category, Some(FailureCategory::RuntimeError));
+        assert_eq!(report.stdout.as_deref(), Some("12"));
+        assert_eq!(report.exit.as_deref(), Some("exit code 2"));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["result"], "runtime-error");
+        assert_eq!(json["category"], "runtime-error");
+
+        let report = TestResultReport::new(&testcase, &TestResult::Success);
+        assert_eq!((report.result, report.category, report.stdout), (ResultKind::Success, None, None));
+    }
+
     #[test]
     fn test_testresult_wrong_output() {
-        let result = TestResult::from_output("x\ny\nz", "yyy".into(), "zzz".into(), CommandExit::Ok);
+        let result = TestResult::from_output("x\ny\nz", "yyy".into(), "zzz".into(), vec![], CommandExit::Ok);
         match result {
-            TestResult::WrongOutput { stdout, stderr } => {
+            TestResult::WrongOutput { stdout, stderr, .. } => {
                 assert_eq!(stdout, "yyy");
                 assert_eq!(stderr, "zzz");
             }
@@ -111,9 +488,9 @@ mod tests {
 
     #[test]
     fn test_testresult_timed_out() {
-        let result = TestResult::from_output("xxx", "yyy".into(), "zzz".into(), CommandExit::Timeout);
+        let result = TestResult::from_output("xxx", "yyy".into(), "zzz".into(), vec![], CommandExit::Timeout);
         match result {
-            TestResult::Timeout { stdout, stderr } => {
+            TestResult::Timeout { stdout, stderr, .. } => {
                 assert_eq!(stdout, "yyy");
                 assert_eq!(stderr, "zzz");
             }
@@ -123,13 +500,58 @@ mod tests {
 
     #[test]
     fn test_testresult_runtime_error() {
-        let result = TestResult::from_output("xxx", "yyy".into(), "zzz".into(), CommandExit::Error);
+        let exit_status = CommandExit::Error(ProcessExit::Signal(11));
+        let result = TestResult::from_output("xxx", "yyy".into(), "zzz".into(), vec![], exit_status);
         match result {
-            TestResult::RuntimeError { stdout, stderr } => {
+            TestResult::RuntimeError {
+                stdout, stderr, exit, ..
+            } => {
                 assert_eq!(stdout, "yyy");
                 assert_eq!(stderr, "zzz");
+                assert_eq!(exit, ProcessExit::Signal(11));
             }
             other => panic!("expected TestResult::RuntimeError but found {:?}", other),
         }
     }
+
+    #[test]
+    fn test_testresult_invalid_utf8_is_kept() {
+        let stdout = b"caf\xc3\xa9 \xff\xfe!\n\xe2\x82".to_vec();
+        let result = TestResult::from_output("café", stdout, vec![], vec![], CommandExit::Ok);
+        match result {
+            TestResult::WrongOutput {
+                stdout, invalid_utf8, ..
+            } => {
+                assert_eq!(stdout, "café \u{fffd}\u{fffd}!\n\u{fffd}");
+                assert_eq!(invalid_utf8, 4);
+            }
+            other => panic!("expected TestResult::WrongOutput but found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_failure_categories() {
+        let category = |expected: &str, stdout: &str, exit_status| {
+            let result = TestResult::from_output(expected, stdout.into(), vec![], vec![], exit_status);
+            result.failure_category(&testcase(1, "", expected))
+        };
+        assert_eq!(category("1\n2", "1\n2", CommandExit::Ok), None);
+        assert_eq!(category("1\n2", "1\n3", CommandExit::Ok), Some(FailureCategory::WrongAnswer));
+        assert_eq!(category("1\n2", "1 \n2", CommandExit::Ok), Some(FailureCategory::PresentationError));
+        assert_eq!(
+            category("abc", "\u{feff}abc", CommandExit::Ok),
+            Some(FailureCategory::PresentationError)
+        );
+        assert_eq!(category("1\n2", "\n", CommandExit::Ok), Some(FailureCategory::EmptyOutput));
+        assert_eq!(category("1", "", CommandExit::Timeout), Some(FailureCategory::Timeout));
+        let crashed = CommandExit::Error(ProcessExit::Code(1));
+        assert_eq!(category("1", "2", crashed), Some(FailureCategory::RuntimeError));
+    }
+
+    #[test]
+    fn test_process_exit_display() {
+        assert_eq!(ProcessExit::Code(3).to_string(), "exit code 3");
+        assert_eq!(ProcessExit::Signal(11).to_string(), "SIGSEGV");
+        assert_eq!(ProcessExit::Signal(64).to_string(), "signal 64");
+    }
 }
@@ -1,30 +1,245 @@
 mod language;
 mod parser;
 mod preprocessor;
+mod random_input;
 mod renderer;
+mod reverse;
 mod stub_config;
+mod template_check;
 
-use anyhow::Result;
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
 use indoc::indoc;
 use language::Language;
 use preprocessor::Renderable;
+pub use random_input::random_input;
+pub use reverse::reverse_generator;
 use serde::Serialize;
 pub use stub_config::StubConfig;
+// Only used by `coctus test-templates`
+#[doc(hidden)]
+pub use template_check::{lint_rendered_stub, TemplateCheck, TEMPLATE_CHECKS};
 
 pub fn generate_from_config(config: StubConfig, generator: &str) -> Result<String> {
-    let mut stub = parser::parse_generator_stub(generator)?;
+    let stub = parser::parse_generator_stub(generator)?;
+    render_stub(config, stub)
+}
+
+/// How bad a [`Diagnostic`] is. A stub either has been generated or not, so
+/// there will be no other severities and matches on it can be exhaustive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The stub was generated but it may be wrong.
+    Warning,
+    /// No stub could be generated.
+    Error,
+}
+
+/// A problem found while generating a stub.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warning(message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+}
+
+/// Same as [`generate_from_config`] but instead of stopping at the first
+/// problem it returns everything that went wrong: the parts of the generator
+/// that the parser had to recover from, templates that are missing or fail
+/// to render, and rendered stubs that look broken (see
+/// [`lint_rendered_stub`]). The stub is `None` if there were errors.
+///
+/// Generators that make the parser panic are reported as errors too, but the
+/// panic message still goes through the panic hook.
+pub fn generate_with_diagnostics(config: StubConfig, generator: &str) -> (Option<String>, Vec<Diagnostic>) {
+    let (stub, mut diagnostics) = parse_catching_panics(generator);
+    let Some(stub) = stub else {
+        return (None, diagnostics)
+    };
+
+    match render_stub(config, stub) {
+        Ok(code) => {
+            diagnostics.extend(lint_rendered_stub(&code).into_iter().map(Diagnostic::warning));
+            // Templates for commands that the language can't express
+            if let Some(line) = code.lines().position(|line| line.contains("UNSUPPORTED")) {
+                diagnostics.push(Diagnostic::warning(format!("unsupported command on line {}", line + 1)));
+            }
+            (Some(code), diagnostics)
+        }
+        Err(err) => {
+            diagnostics.push(Diagnostic::error(format!("{err:#}")));
+            (None, diagnostics)
+        }
+    }
+}
+
+/// The problems that the parser finds in `generator`, without rendering it
+/// in any language: an error if it can't be parsed (or makes the parser
+/// panic) and the warnings otherwise.
+pub fn parse_with_diagnostics(generator: &str) -> Vec<Diagnostic> {
+    parse_catching_panics(generator).1
+}
 
+fn parse_catching_panics(generator: &str) -> (Option<Stub>, Vec<Diagnostic>) {
+    let parsed = std::panic::catch_unwind(|| parser::parse_generator_stub_with_warnings(generator));
+    match parsed {
+        Ok(Ok((stub, warnings))) => (Some(stub), warnings.into_iter().map(Diagnostic::warning).collect()),
+        Ok(Err(err)) => (None, vec![Diagnostic::error(format!("{err:#}"))]),
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| panic.downcast_ref::<&str>().map(|msg| msg.to_string()))
+                .unwrap_or_else(|| "the parser panicked".to_string());
+            (None, vec![Diagnostic::error(message)])
+        }
+    }
+}
+
+/// Generates the stubs of several languages at once, parsing the generator
+/// only once. `stub_config` looks up the config of each language (failing
+/// for one language doesn't stop the others). If the generator can't be
+/// parsed every language gets the same error.
+pub fn generate_many_with(
+    generator: &str,
+    language_names: &[&str],
+    mut stub_config: impl FnMut(&str) -> Result<StubConfig>,
+) -> BTreeMap<String, Result<String>> {
+    let stub = parser::parse_generator_stub(generator).map_err(|err| format!("{err:#}"));
+    language_names
+        .iter()
+        .map(|&name| {
+            let result = match &stub {
+                Ok(stub) => stub_config(name).and_then(|config| render_stub(config, stub.clone())),
+                Err(msg) => Err(anyhow!("{msg}")),
+            };
+            (name.to_owned(), result)
+        })
+        .collect()
+}
+
+fn render_stub(config: StubConfig, mut stub: Stub) -> Result<String> {
+    let loop_indices = renderer::loop_index_names(&config.language, &stub);
     if let Some(processor) = config.language.preprocessor {
         processor(&mut stub)
     }
 
-    let renderer = renderer::Renderer::new(config, stub)?;
-    let output_str = renderer.render();
+    let renderer = renderer::Renderer::new(config, stub, loop_indices)?;
+    let output_str = renderer.render()?;
+
+    // Templates checked out with CRLF line endings must not change the stub
+    Ok(output_str.trim().replace("\r\n", "\n"))
+}
+
+/// Names of the variables read by the stub of `generator`, cased the same
+/// way as they are in the stub generated with `config`. Each name is listed
+/// once, in the order it first appears in the generator.
+pub fn variable_names(config: &StubConfig, generator: &str) -> Result<Vec<String>> {
+    fn collect<'a>(cmd: &'a Cmd, names: &mut Vec<&'a str>) {
+        match cmd {
+            Cmd::Read(vars) => names.extend(vars.iter().map(|var| var.ident.as_str())),
+            Cmd::Loop { count_var, command } => {
+                names.push(count_var);
+                collect(command, names);
+            }
+            Cmd::LoopLine { count_var, variables } => {
+                names.push(count_var);
+                names.extend(variables.iter().map(|var| var.ident.as_str()));
+            }
+            Cmd::Write { .. } | Cmd::WriteJoin { .. } | Cmd::External(_) => {}
+        }
+    }
+
+    let stub = parser::parse_generator_stub(generator)?;
+    let mut names = Vec::new();
+    for cmd in &stub.commands {
+        collect(cmd, &mut names);
+    }
 
-    Ok(output_str.as_str().trim().to_string())
+    let name_options = &config.language.variable_name_options;
+    let mut cased_names: Vec<String> = Vec::new();
+    for name in names {
+        let cased = name_options.transform_variable_name(name);
+        if !cased_names.contains(&cased) {
+            cased_names.push(cased);
+        }
+    }
+    Ok(cased_names)
+}
+
+/// First line of the section written by [`insert_marked_stub`], after the
+/// comment prefix of the language.
+pub const STUB_BEGIN_MARKER: &str = "--- coctus stub begin (regenerated, don't edit) ---";
+/// Last line of the section written by [`insert_marked_stub`].
+pub const STUB_END_MARKER: &str = "--- coctus stub end ---";
+
+/// Puts `stub` between marker comments in `existing` source code. An earlier
+/// marked section is replaced, otherwise the section is appended to the end
+/// so that none of the existing code is lost.
+pub fn insert_marked_stub(existing: &str, stub: &str, comment_prefix: &str) -> String {
+    let begin = format!("{comment_prefix} {STUB_BEGIN_MARKER}");
+    let end = format!("{comment_prefix} {STUB_END_MARKER}");
+    let section = format!("{begin}\n{stub}\n{end}\n");
+
+    let marked_section = existing.find(&begin).and_then(|start| {
+        let after_end = &existing[start + existing[start..].find(&end)? + end.len()..];
+        // The line break after the end marker belongs to the section
+        let rest = after_end
+            .strip_prefix("\r\n")
+            .or_else(|| after_end.strip_prefix('\n'))
+            .unwrap_or(after_end);
+        Some((&existing[..start], rest))
+    });
+    match marked_section {
+        Some((before, after)) => format!("{before}{section}{after}"),
+        None if existing.is_empty() => section,
+        None if existing.ends_with('\n') => format!("{existing}\n{section}"),
+        None => format!("{existing}\n\n{section}"),
+    }
+}
+
+/// The stub between the marker comments written by [`insert_marked_stub`]
+/// (without the markers themselves), `None` if `existing` has no marked
+/// section.
+pub fn marked_stub<'a>(existing: &'a str, comment_prefix: &str) -> Option<&'a str> {
+    let begin = format!("{comment_prefix} {STUB_BEGIN_MARKER}");
+    let end = format!("{comment_prefix} {STUB_END_MARKER}");
+    let after_begin = &existing[existing.find(&begin)? + begin.len()..];
+    let section = &after_begin[..after_begin.find(&end)?];
+    let section = section
+        .strip_prefix("\r\n")
+        .or_else(|| section.strip_prefix('\n'))
+        .unwrap_or(section);
+    Some(
+        section
+            .strip_suffix("\r\n")
+            .or_else(|| section.strip_suffix('\n'))
+            .unwrap_or(section),
+    )
 }
 
 /// Generate a stub string from a (supported) language and a generator.
+/// Requires the `embedded-templates` feature.
 ///
 /// # Examples
 ///
@@ -35,11 +250,30 @@ pub fn generate_from_config(config: StubConfig, generator: &str) -> Result<Strin
 /// let stub_str = generate("python", generator).unwrap();
 /// assert_eq!(stub_str, "an_int = int(input())\nprint(\"solution\")");
 /// ```
+#[cfg(feature = "embedded-templates")]
 pub fn generate(language_name: &str, generator: &str) -> Result<String> {
     let config = StubConfig::read_from_embedded(language_name)?;
     generate_from_config(config, generator)
 }
 
+/// Same as [`generate`] for several languages at once, keyed by language
+/// name. Requires the `embedded-templates` feature.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::stub::generate_many;
+///
+/// let generator = "read anInt:int\nwrite solution";
+/// let stubs = generate_many(generator, &["python", "ruby"]);
+/// assert_eq!(stubs["python"].as_ref().unwrap(), "an_int = int(input())\nprint(\"solution\")");
+/// assert!(stubs["ruby"].is_ok());
+/// ```
+#[cfg(feature = "embedded-templates")]
+pub fn generate_many(generator: &str, language_names: &[&str]) -> BTreeMap<String, Result<String>> {
+    generate_many_with(generator, language_names, StubConfig::read_from_embedded)
+}
+
 #[derive(Clone, Default)]
 struct Stub {
     commands: Vec<Cmd>,
@@ -140,6 +374,7 @@ enum Cmd {
     External(Box<dyn Renderable>),
 }
 
+#[doc(hidden)]
 pub const SIMPLE_REFERENCE_STUB: &str = indoc! {r##"
     read anInt:int
     read aFloat:float
@@ -171,7 +406,7 @@ pub const SIMPLE_REFERENCE_STUB: &str = indoc! {r##"
     anInt: An input comment over anInt
 "##};
 
-#[cfg(test)]
+#[cfg(all(test, feature = "embedded-templates"))]
 mod tests {
     use super::*;
 
@@ -307,6 +542,97 @@ mod tests {
         }
     }
 
+    const PARSE_STRATEGY_GENERATOR: &str =
+        "read n:int\nread s:string(50)\nread x:int name:string(20)\nloopline n v:int";
+
+    #[test]
+    fn test_c_parse_strategies() {
+        // Numbers are read token by token and strings line by line
+        let received = generate("c", PARSE_STRATEGY_GENERATOR).unwrap();
+        let expected = indoc! {r#"
+            int n; 
+            scanf("%d", &n); fgetc(stdin);
+            char s[50 + 2]; 
+            fgets(s, 50 + 2, stdin); s[strcspn(s, "\r\n")] = '\0';
+            int x;
+            char name[20 + 1];
+            scanf("%d %[^\n]%*[^\n]", &x, name); fgetc(stdin);
+            for (int i = 0; i < n; i++) {
+                int v;
+                scanf("%d", &v);
+            }
+            fgetc(stdin);
+        "#};
+        assert_eq!(main_body(&received), expected);
+
+        let mut config = StubConfig::read_from_embedded("c").unwrap();
+        config.language.parse_strategies = toml::from_str("Int = \"line\"\nString = \"token\"").unwrap();
+        let received = generate_from_config(config, PARSE_STRATEGY_GENERATOR).unwrap();
+        let expected = indoc! {r#"
+            int n; 
+            scanf("%d%*[^\n]", &n); fgetc(stdin);
+            char s[50 + 1]; 
+            scanf(" %[^\n]", s); fgetc(stdin);
+            int x;
+            char name[20 + 1];
+            scanf("%d %[^\n]%*[^\n]", &x, name); fgetc(stdin);
+            for (int i = 0; i < n; i++) {
+                int v;
+                scanf("%d", &v);
+            }
+            scanf("%*[^\n]"); fgetc(stdin);
+        "#};
+        assert_eq!(main_body(&received), expected);
+    }
+
+    #[test]
+    fn test_cpp_parse_strategies() {
+        let received = generate("cpp", PARSE_STRATEGY_GENERATOR).unwrap();
+        let expected = indoc! {r#"
+            int n; 
+            cin >> n; cin.ignore();
+            string s; 
+            getline(cin, s);
+            int x;
+            string name;
+            cin >> x; cin.ignore(); getline(cin, name);
+            for (int i = 0; i < n; i++) {
+                int v;
+                cin >> v; cin.ignore();
+            }
+        "#};
+        assert_eq!(main_body(&received), expected);
+
+        let mut config = StubConfig::read_from_embedded("cpp").unwrap();
+        config.language.parse_strategies = toml::from_str("String = \"token\"").unwrap();
+        let received = generate_from_config(config, PARSE_STRATEGY_GENERATOR).unwrap();
+        let expected = indoc! {r#"
+            int n; 
+            cin >> n; cin.ignore();
+            string s; 
+            cin >> s; cin.ignore();
+            int x;
+            string name;
+            cin >> x >> name; cin.ignore();
+            for (int i = 0; i < n; i++) {
+                int v;
+                cin >> v; cin.ignore();
+            }
+        "#};
+        assert_eq!(main_body(&received), expected);
+    }
+
+    /// The lines of the `main` function of a C or C++ stub without the
+    /// indentation, the closing `return 0;` and the empty lines.
+    fn main_body(stub: &str) -> String {
+        let body = stub.split_once("int main() {\n").expect("stub should have a main function").1;
+        body.lines()
+            .take_while(|line| line.trim() != "return 0;")
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| format!("{}\n", line.strip_prefix("    ").unwrap_or(line)))
+            .collect()
+    }
+
     // Just test that it compiles
     #[test]
     fn test_reference_stub_rust() {
@@ -327,4 +653,131 @@ mod tests {
     fn test_reference_stub_pascal() {
         generate("pascal", COMPLEX_REFERENCE_STUB).unwrap();
     }
+
+    #[test]
+    fn test_variable_names_are_cased_and_unique() {
+        let generator =
+            "read anInt:int\nloop anInt read xCoord:int aWord:word(5)\nloopline anInt xCoord:int\nwrite x";
+        let config = StubConfig::read_from_embedded("python").unwrap();
+        assert_eq!(variable_names(&config, generator).unwrap(), vec!["an_int", "x_coord", "a_word"]);
+    }
+
+    #[test]
+    fn test_cpp_scanf_option() {
+        let mut config = StubConfig::read_from_embedded("cpp").unwrap();
+        config.set_option("io", "scanf").unwrap();
+        let generator = "read n:int\nread a:long b:bool\nread w:word(5) k:int\nloopline n x:float";
+        let stub = generate_from_config(config, generator).unwrap();
+        assert!(stub.contains("#include <cstdio>"));
+        assert!(stub.contains("int n; \n    scanf(\"%d\", &n); getchar();"));
+        assert!(stub.contains("scanf(\"%lld%d\", &a, &b); getchar();"));
+        // Strings can't be read with scanf
+        assert!(stub.contains("cin >> w >> k; cin.ignore();"));
+        assert!(stub.contains("float x;\n        scanf(\"%f\", &x); getchar();"));
+    }
+
+    #[test]
+    fn test_rust_macro_option() {
+        let mut config = StubConfig::read_from_embedded("rust").unwrap();
+        config.set_option("io", "macro").unwrap();
+        let generator = "read n:int\nread a:long w:word(5)\nloopline n x:float";
+        let stub = generate_from_config(config, generator).unwrap();
+        assert!(stub.contains("macro_rules! parse_input {"));
+        assert!(stub.contains("let n = parse_input!(read_line(), i32);"));
+        assert!(
+            stub.contains("(parse_input!(inputs.next().unwrap(), i64), inputs.next().unwrap().to_string())")
+        );
+        assert!(stub.contains(".map(|i| parse_input!(i, f64))"));
+    }
+
+    #[test]
+    fn test_loop_indices_with_a_prefix() {
+        let mut config = StubConfig::read_from_embedded("pascal").unwrap();
+        config.language.loop_index_naming = language::LoopIndexNaming::Prefix("idx".to_string());
+        let stub = generate_from_config(config, "read n:int idx0:int\nloop n loop n read x:int").unwrap();
+        assert!(stub.contains("Idx1 : Int32;\n    Idx2 : Int32;"));
+        assert!(stub.contains("for idx1 := 0 to N - 1 do"));
+        assert!(stub.contains("for idx2 := 0 to N - 1 do"));
+    }
+
+    #[test]
+    fn test_marked_stub_is_appended_then_replaced() {
+        let existing = "fn solve() {}";
+        let first = insert_marked_stub(existing, "let a = 1;", "//");
+        assert_eq!(
+            first,
+            format!("fn solve() {{}}\n\n// {STUB_BEGIN_MARKER}\nlet a = 1;\n// {STUB_END_MARKER}\n")
+        );
+
+        let edited = format!("{first}solve();\n");
+        let second = insert_marked_stub(&edited, "let b = 2;", "//");
+        assert_eq!(
+            second,
+            format!(
+                "fn solve() {{}}\n\n// {STUB_BEGIN_MARKER}\nlet b = 2;\n// {STUB_END_MARKER}\nsolve();\n"
+            )
+        );
+        assert_eq!(
+            insert_marked_stub("", "x = 1", "#"),
+            format!("# {STUB_BEGIN_MARKER}\nx = 1\n# {STUB_END_MARKER}\n")
+        );
+    }
+
+    #[test]
+    fn test_marked_stub_round_trip() {
+        let solution = insert_marked_stub("fn solve() {}\n", "let a = 1;\nlet b = 2;", "//");
+        assert_eq!(marked_stub(&solution, "//"), Some("let a = 1;\nlet b = 2;"));
+        assert_eq!(marked_stub(&solution.replace('\n', "\r\n"), "//"), Some("let a = 1;\r\nlet b = 2;"));
+        assert_eq!(marked_stub(&solution, "#"), None);
+        assert_eq!(marked_stub("fn solve() {}", "//"), None);
+    }
+
+    #[test]
+    fn test_generate_many_matches_generate() {
+        let stubs = generate_many(COMPLEX_REFERENCE_STUB, &["python", "rust", "not-a-language"]);
+        assert_eq!(stubs.len(), 3);
+        for lang in ["python", "rust"] {
+            assert_eq!(stubs[lang].as_ref().unwrap(), &generate(lang, COMPLEX_REFERENCE_STUB).unwrap());
+        }
+        assert!(stubs["not-a-language"].is_err());
+    }
+
+    #[test]
+    fn test_generate_many_unparseable_generator() {
+        let stubs = generate_many("gameloop\nread x:int", &["python", "ruby"]);
+        assert!(stubs
+            .values()
+            .all(|stub| stub.as_ref().is_err_and(|err| err.to_string().contains("gameloop"))));
+    }
+
+    #[test]
+    fn test_literals_are_escaped() {
+        let generator = "read x:int\nwrite a \"b\" \\ #$ 'c' é\n\nwrite join(\"\"q\"\", x)";
+        let python = generate("python", generator).unwrap();
+        assert!(python.contains(r#"print("a \"b\" \\ #$ 'c' é")"#), "{python}");
+        assert!(python.contains(r#"print("\"q\" " + str(x))"#), "{python}");
+        let ruby = generate("ruby", generator).unwrap();
+        assert!(ruby.contains(r#"puts "a \"b\" \\ \#$ 'c' é""#), "{ruby}");
+        let pascal = generate("pascal", generator).unwrap();
+        assert!(pascal.contains(r#"writeln('a "b" \ #$ ''c'' é');"#), "{pascal}");
+    }
+
+    #[test]
+    fn test_diagnostics_do_not_stop_generation() {
+        let config = StubConfig::read_from_embedded("pascal").unwrap();
+        let (stub, diagnostics) =
+            generate_with_diagnostics(config, "read n:int\nwrite join(\"n\", n)\n\nINPUT\nm: unknown");
+        assert!(stub.is_some());
+        assert!(diagnostics.iter().all(|diagnostic| diagnostic.severity == Severity::Warning));
+        assert!(diagnostics[0].message.contains("\"m\""));
+        assert!(diagnostics[1].message.contains("unsupported command"));
+    }
+
+    #[test]
+    fn test_diagnostics_of_broken_generators() {
+        let config = StubConfig::read_from_embedded("python").unwrap();
+        let (stub, diagnostics) = generate_with_diagnostics(config, "gameloop");
+        assert_eq!(stub, None);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
 }
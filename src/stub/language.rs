@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::de::Error;
 use serde::{Deserialize, Serialize};
 
@@ -5,6 +7,7 @@ mod variable_name_options;
 use variable_name_options::VariableNameOptions;
 
 use super::preprocessor::{self, Preprocessor};
+use super::renderer::ALPHABET;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "PascalCase")]
@@ -17,6 +20,105 @@ pub(super) struct TypeTokens {
     string: Option<String>,
 }
 
+/// How the value of a variable is read from the input.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum ParseStrategy {
+    /// Read the next whitespace separated token (for example with `scanf`).
+    #[default]
+    Token,
+    /// Read the whole line and parse the variable(s) from it, so that reads
+    /// never leave the rest of a line behind for the next read.
+    Line,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase", default)]
+pub(super) struct ParseStrategies {
+    int: ParseStrategy,
+    float: ParseStrategy,
+    long: ParseStrategy,
+    bool: ParseStrategy,
+    word: ParseStrategy,
+    string: ParseStrategy,
+}
+
+/// How the `escape_string` template filter escapes text to put it in a
+/// string literal of the language.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum StringEscape {
+    /// Leave the text as is.
+    #[default]
+    None,
+    /// Backslash before `\` and `"`, like in C, Python and Rust.
+    Backslash,
+    /// Same as `Backslash` but `#` is also escaped so that it can't start an
+    /// interpolation, like in Ruby.
+    BackslashHash,
+    /// Double every `'`, like in Pascal.
+    DoubledSingleQuote,
+}
+
+impl StringEscape {
+    pub fn escape(self, text: &str) -> String {
+        let special: &[char] = match self {
+            StringEscape::None => return text.to_string(),
+            StringEscape::DoubledSingleQuote => return text.replace('\'', "''"),
+            StringEscape::Backslash => &['\\', '"'],
+            StringEscape::BackslashHash => &['\\', '"', '#'],
+        };
+        let mut escaped = String::with_capacity(text.len());
+        for ch in text.chars() {
+            if special.contains(&ch) {
+                escaped.push('\\');
+            }
+            escaped.push(ch);
+        }
+        escaped
+    }
+}
+
+/// How the indices of nested loops are named, from the outermost loop in.
+/// Names that are taken by the variables of the stub are skipped (ignoring
+/// case), so a puzzle with a variable called `i` gets `j` as its first index.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum LoopIndexNaming {
+    /// `i`, `j`, `k` and so on (with a number after them once the letters
+    /// run out).
+    #[default]
+    Alphabet,
+    /// The prefix followed by a number starting from 0, like `idx0`, `idx1`.
+    Prefix(String),
+}
+
+impl LoopIndexNaming {
+    /// Names for the indices of `count` nested loops, none of which is in
+    /// `taken`.
+    pub fn names(&self, count: usize, taken: &[String]) -> Vec<String> {
+        let candidates: Box<dyn Iterator<Item = String>> = match self {
+            LoopIndexNaming::Alphabet => Box::new(ALPHABET.iter().map(char::to_string).chain(
+                (2..).flat_map(|number| ALPHABET.iter().map(move |letter| format!("{letter}{number}"))),
+            )),
+            LoopIndexNaming::Prefix(prefix) => Box::new((0..).map(move |number| format!("{prefix}{number}"))),
+        };
+        candidates
+            .filter(|name| !taken.iter().any(|taken| taken.eq_ignore_ascii_case(name)))
+            .take(count)
+            .collect()
+    }
+}
+
+/// A variation of the stubs of a language, such as a faster way of reading
+/// the input, declared in the `[options]` table of `stub_config.toml`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub(super) struct StubOption {
+    pub description: String,
+    /// The possible values, the first one is the default.
+    pub values: Vec<String>,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub(super) struct Language {
     pub variable_name_options: VariableNameOptions,
@@ -35,6 +137,35 @@ pub(super) struct Language {
     pub type_parsers: Option<TypeTokens>,
     #[serde(deserialize_with = "deser_preprocessor", default)]
     pub preprocessor: Option<Preprocessor>,
+    // Parsing strategy for each type, it's up to the templates to honor it.
+    // Not every language needs this, so all types default to "token".
+    #[serde(default)]
+    pub parse_strategies: ParseStrategies,
+    // Command that checks that a stub compiles, {file} is replaced with the
+    // path of the stub and {out_dir} with a temporary directory for anything
+    // the command writes. Used by `coctus test-templates`.
+    pub check_command: Option<String>,
+    // Default commands for building and running a solution file, {source} is
+    // replaced with the path of the solution and {executable} with a path for
+    // the compiled solution in the build directory of the clash. Used by
+    // `coctus run` for tracked solutions.
+    pub build_command: Option<String>,
+    pub run_command: Option<String>,
+    // Prefix of single line comments, used by `coctus generate-stub
+    // --append-marker` to mark where the stub is in a solution file.
+    pub comment_prefix: Option<String>,
+    // How the escape_string filter escapes the text of `write` commands and
+    // literal join terms, none by default.
+    #[serde(default)]
+    pub string_escape: StringEscape,
+    // Variations of the stubs selected with `generate-stub --option
+    // NAME=VALUE`, the templates get the selected values in `options`.
+    #[serde(default)]
+    pub options: BTreeMap<String, StubOption>,
+    // Names of the loop indices, `loop_index_naming = { prefix = "idx" }`
+    // for idx0, idx1 and so on instead of i, j.
+    #[serde(default)]
+    pub loop_index_naming: LoopIndexNaming,
 }
 
 fn deser_preprocessor<'de, D>(deserializer: D) -> Result<Option<Preprocessor>, D::Error>
@@ -27,8 +27,10 @@ pub struct VariableNameOptions {
     case_insensitive_keywords: bool,
 }
 
+// Identifiers are cased with ASCII rules only so that stubs come out the same
+// regardless of the locale and never split a multibyte character.
 fn is_uppercase_string(string: &str) -> bool {
-    string.chars().all(|c| c.is_uppercase())
+    string.chars().all(|c| c.is_ascii_uppercase())
 }
 
 impl VariableNameOptions {
@@ -38,7 +40,7 @@ impl VariableNameOptions {
         // In others (such as ruby where constants are uppercase) they get downcased.
         let converted_variable_name = match is_uppercase_string(variable_name) {
             true if self.allow_uppercase_vars => variable_name.to_string(),
-            true => variable_name.to_lowercase(),
+            true => variable_name.to_ascii_lowercase(),
             false => self.convert(variable_name),
         };
 
@@ -96,7 +98,7 @@ impl VariableNameOptions {
                     char_iter.peeking_take_while(|c| !c.is_ascii_lowercase()).collect();
                 word_chars.extend(char_iter.peeking_take_while(|c| c.is_ascii_lowercase()));
 
-                Some(String::from_iter(word_chars).to_lowercase())
+                Some(String::from_iter(word_chars).to_ascii_lowercase())
             })
             .collect()
     }
@@ -110,11 +112,19 @@ impl VariableNameOptions {
     }
 
     fn convert_to_pascal_case(variable_name: &str) -> String {
-        variable_name[0..1].to_uppercase() + &variable_name[1..]
+        Self::map_first_char(variable_name, char::to_ascii_uppercase)
     }
 
     fn convert_to_camel_case(variable_name: &str) -> String {
-        variable_name[0..1].to_lowercase() + &variable_name[1..]
+        Self::map_first_char(variable_name, char::to_ascii_lowercase)
+    }
+
+    fn map_first_char(variable_name: &str, f: impl Fn(&char) -> char) -> String {
+        let mut chars = variable_name.chars();
+        match chars.next() {
+            Some(first) => format!("{}{}", f(&first), chars.as_str()),
+            None => String::new(),
+        }
     }
 }
 
@@ -154,6 +164,13 @@ mod tests {
         assert_eq!("craneASCIIRepresentation", convert("craneASCIIRepresentation"));
     }
 
+    #[test]
+    fn test_casing_is_ascii_only() {
+        assert_eq!("Émile", VariableNameOptions::convert_to_pascal_case("Émile"));
+        assert_eq!("ärger", VariableNameOptions::convert_to_camel_case("ärger"));
+        assert!(!is_uppercase_string("ÄBC"));
+    }
+
     #[test]
     fn test_keywords_case_sensitive() {
         let variable_name_options = VariableNameOptions {
@@ -5,6 +5,12 @@ use anyhow::{anyhow, Result};
 use super::{Cmd, JoinTerm, Stub, VarType, VariableCommand};
 
 pub fn parse_generator_stub(generator: &str) -> Result<Stub> {
+    Ok(Parser::new(generator).parse()?.0)
+}
+
+/// Same as [`parse_generator_stub`] but also returns warnings about the
+/// parts of the generator that the parser recovered from instead of failing.
+pub fn parse_generator_stub_with_warnings(generator: &str) -> Result<(Stub, Vec<String>)> {
     Parser::new(generator).parse()
 }
 
@@ -15,6 +21,7 @@ pub fn parse_generator_stub(generator: &str) -> Result<Stub> {
 struct Parser<'a> {
     token_stream: Box<dyn Iterator<Item = &'a str> + 'a>,
     read_pairings: std::collections::BTreeMap<String, VarType>,
+    warnings: Vec<String>,
 }
 
 impl<'a> Parser<'a> {
@@ -28,11 +35,12 @@ impl<'a> Parser<'a> {
         Self {
             token_stream: Box::new(token_stream),
             read_pairings: std::collections::BTreeMap::new(),
+            warnings: Vec::new(),
         }
     }
 
     #[rustfmt::skip]
-    fn parse(mut self) -> Result<Stub> {
+    fn parse(mut self) -> Result<(Stub, Vec<String>)> {
         let mut stub = Stub::default();
 
         while let Some(token) = self.next_token() {
@@ -43,14 +51,22 @@ impl<'a> Parser<'a> {
                 "loopline"  => stub.commands.push(self.parse_loopline()),
                 "OUTPUT"    => self.parse_output_comment(&mut stub.commands),
                 "INPUT"     => self.parse_input_comment(&mut stub.commands),
-                "STATEMENT" => stub.statement = self.parse_text_block(),
+                "STATEMENT" => self.parse_statement(&mut stub.statement),
                 "gameloop"  => return Err(anyhow!("Stub generator does not currently support the 'gameloop' command")),
                 "\n" | ""   => continue,
                 thing => panic!("Unknown token stub generator: '{}'", thing),
             };
         }
 
-        Ok(stub)
+        Ok((stub, self.warnings))
+    }
+
+    fn parse_statement(&mut self, statement: &mut Vec<String>) {
+        if !statement.is_empty() {
+            self.warnings
+                .push("STATEMENT given more than once, only the last one is used".to_string());
+        }
+        *statement = self.parse_text_block();
     }
 
     fn parse_read(&mut self) -> Cmd {
@@ -77,7 +93,7 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn check_for_write_join(&self, line: &str) -> Option<Cmd> {
+    fn check_for_write_join(&mut self, line: &str) -> Option<Cmd> {
         // NOTE: write•join()•rest⏎, with NOTHING inside the parens,
         //       gets parsed as a write and not as a write_join
         match line.replace("join()", "").split_once("join(") {
@@ -87,6 +103,8 @@ impl<'a> Parser<'a> {
                 if terms_string.split(',').any(|t| t.trim().is_empty()) {
                     // write•join("hi",,,•"Jim")⏎ should be rendered as a Write Cmd
                     // (I guess the CG parser fails due to consecutive commas)
+                    self.warnings
+                        .push(format!("{line:?} has an empty join term, so it is written out as text"));
                     Some(Cmd::Write {
                         lines: vec![line.to_string()],
                         output_comment: Vec::new(),
@@ -106,8 +124,9 @@ impl<'a> Parser<'a> {
         let join_terms = terms_string
             .split(',')
             .map(|term| {
-                if term.contains('"') {
-                    let ident = term.trim_matches(|c| c != '"').trim_matches('"').to_string();
+                if let (Some(start), Some(end)) = (term.find('"'), term.rfind('"')) {
+                    // Quotes inside of the literal are part of it
+                    let ident = term.get(start + 1..end).unwrap_or_default().to_string();
                     JoinTerm::new(ident, None)
                 } else {
                     let ident = term.trim().to_string();
@@ -199,6 +218,12 @@ impl<'a> Parser<'a> {
 
         while let Some(line) = self.rest_of_line() {
             if let Some((ic_ident, ic_comment)) = line.split_once(':') {
+                if !self.read_pairings.contains_key(ic_ident.trim()) {
+                    self.warnings.push(format!(
+                        "INPUT comment for {:?}, which is not read before it",
+                        ic_ident.trim()
+                    ));
+                }
                 for cmd in previous_commands.iter_mut() {
                     Self::update_cmd_with_input_comment(cmd, ic_ident.trim(), ic_comment.trim());
                 }
@@ -363,3 +363,33 @@ fn parse_output_comment_adds_comment_to_write_join() {
     let Cmd::WriteJoin { ref output_comment, .. } = commands[0] else { panic!() };
     assert_eq!(output_comment[0], "Mama said");
 }
+
+#[test]
+fn parse_warns_about_recovered_problems() {
+    let (stub, warnings) = parse_generator_stub_with_warnings(indoc! {r##"
+        STATEMENT
+        First
+
+        read n:int
+        write join("hi",,, "Jim")
+
+        INPUT
+        n: the count
+        m: not read anywhere
+
+        STATEMENT
+        Second
+    "##}).unwrap();
+
+    assert_eq!(stub.statement, vec!["Second"]);
+    assert_eq!(warnings.len(), 3);
+    assert!(warnings[0].contains("empty join term"));
+    assert!(warnings[1].contains("\"m\""));
+    assert!(warnings[2].contains("STATEMENT given more than once"));
+}
+
+#[test]
+fn parse_has_no_warnings_for_valid_generators() {
+    let (_, warnings) = parse_generator_stub_with_warnings("read n:int\nwrite join(\"a\", n)\n\nINPUT\nn: count").unwrap();
+    assert!(warnings.is_empty(), "{warnings:?}");
+}
@@ -1,6 +1,7 @@
 pub mod forward_declarations;
 pub mod lisp_like;
 
+use anyhow::Result;
 use dyn_clone::DynClone;
 
 use super::renderer::Renderer;
@@ -19,7 +20,7 @@ pub type Preprocessor = fn(&mut Stub) -> ();
 /// may be wrapped in Cmd::External and its render method will be called by
 /// Renderer.
 pub trait Renderable: std::fmt::Debug + DynClone {
-    fn render(&self, renderer: &Renderer) -> String;
+    fn render(&self, renderer: &Renderer) -> Result<String>;
 }
 
 dyn_clone::clone_trait_object!(Renderable);
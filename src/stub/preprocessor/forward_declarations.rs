@@ -1,5 +1,6 @@
+use anyhow::Result;
+
 use super::Renderable;
-use crate::stub::renderer::ALPHABET;
 use crate::stub::{Cmd, Stub, VarType, VariableCommand};
 
 /// Change the Stub structure into: [ReadDeclarations, MainContents(old_cmds)]
@@ -10,6 +11,9 @@ struct MainWrapper {
     // render declaration: int c;
     // render read (usual): int c;\nscanf("%d", c);
     pub forward_declarations: Vec<VariableCommand>,
+    // How deeply the loops are nested, the indices are declared along with
+    // the other variables.
+    pub num_loop_indices: usize,
     // The main function contents.
     pub main_content: Vec<Cmd>,
 }
@@ -52,19 +56,12 @@ pub fn transform(stub: &mut Stub) {
         .flatten()
         .collect();
 
-    // Add the loop indices to the declarations
-    forward_declarations.extend(ALPHABET[0..max_nested_depth].iter().map(|loop_var| VariableCommand {
-        ident: loop_var.to_string(),
-        var_type: VarType::Int,
-        max_length: None,
-        input_comment: String::new(),
-    }));
-
     let mut seen = std::collections::BTreeSet::<String>::new();
     forward_declarations.retain(|var_cmd| seen.insert(var_cmd.ident.clone()));
 
     let wrapper = MainWrapper {
         forward_declarations,
+        num_loop_indices: max_nested_depth,
         main_content: stub.commands.drain(..).collect(),
     };
 
@@ -83,13 +80,28 @@ fn unpack_cmd(cmd: &Cmd, nested_depth: usize) -> (Cmd, usize) {
 }
 
 impl Renderable for MainWrapper {
-    fn render(&self, renderer: &crate::stub::renderer::Renderer) -> String {
-        let main_contents_str: String =
-            self.main_content.iter().map(|cmd| renderer.render_command(cmd, 0)).collect();
+    fn render(&self, renderer: &crate::stub::renderer::Renderer) -> Result<String> {
+        let main_contents_str: String = self
+            .main_content
+            .iter()
+            .map(|cmd| renderer.render_command(cmd, 0))
+            .collect::<Result<_>>()?;
         let main_contents: Vec<&str> = main_contents_str.lines().collect();
 
-        let forward_declarations: Vec<String> =
-            self.forward_declarations.iter().map(|vc| vc.render(renderer)).collect();
+        // The loop indices never collide with the variables
+        let loop_indices = (0..self.num_loop_indices).map(|depth| VariableCommand {
+            ident: renderer.loop_index(depth).to_string(),
+            var_type: VarType::Int,
+            max_length: None,
+            input_comment: String::new(),
+        });
+        let forward_declarations: Vec<String> = self
+            .forward_declarations
+            .iter()
+            .cloned()
+            .chain(loop_indices)
+            .map(|vc| vc.render(renderer))
+            .collect::<Result<_>>()?;
 
         let mut context = tera::Context::new();
         context.insert("forward_declarations", &forward_declarations);
@@ -99,10 +111,10 @@ impl Renderable for MainWrapper {
 }
 
 impl Renderable for VariableCommand {
-    fn render(&self, renderer: &crate::stub::renderer::Renderer) -> String {
+    fn render(&self, renderer: &crate::stub::renderer::Renderer) -> Result<String> {
         let mut context =
             tera::Context::from_serialize(self).expect("VariableCommand should be serializable");
         context.insert("ident", &renderer.lang.variable_name_options.transform_variable_name(&self.ident));
-        renderer.tera_render("forward_declarations", &mut context).trim().to_string()
+        Ok(renderer.tera_render("forward_declarations", &mut context)?.trim().to_string())
     }
 }
@@ -0,0 +1,239 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::{parser, Cmd, VarType, VariableCommand};
+use crate::clash::Constraint;
+
+/// Generates a random input that follows the stub of `generator`, for
+/// measuring how a solution copes with large inputs. The same `seed` always
+/// generates the same input.
+///
+/// `size` is the value given to every variable that is used as a loop count
+/// or as the length of a word or a string, and it is also the upper bound of
+/// all other numbers. Both are kept within the `constraints` of the
+/// variables (matched by name), so once `size` goes past the largest values
+/// the constraints allow, growing it no longer changes the input.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::stub::random_input;
+///
+/// let input = random_input("read n:int\nloop n read x:int\nwrite answer", &[], 3, 42).unwrap();
+/// assert_eq!(input.lines().count(), 4);
+/// assert_eq!(input.lines().next(), Some("3"));
+/// ```
+pub fn random_input(generator: &str, constraints: &[Constraint], size: u64, seed: u64) -> Result<String> {
+    let stub = parser::parse_generator_stub(generator)?;
+    let mut sizes = BTreeSet::new();
+    for cmd in &stub.commands {
+        collect_sizes(cmd, &mut sizes);
+    }
+    let mut generator = InputGenerator {
+        constraints,
+        sizes,
+        size,
+        values: BTreeMap::new(),
+        rng: StdRng::seed_from_u64(seed),
+        lines: Vec::new(),
+    };
+    for cmd in &stub.commands {
+        generator.command(cmd);
+    }
+    Ok(generator.lines.join("\n"))
+}
+
+/// Names of the variables that determine how much input there is. Literal
+/// counts and lengths (`loop 3 ...`, `word(5)`) are not variables.
+fn collect_sizes<'a>(cmd: &'a Cmd, sizes: &mut BTreeSet<&'a str>) {
+    let is_variable = |size: &&str| size.parse::<u64>().is_err();
+    let max_lengths = |vars: &'a [VariableCommand]| {
+        vars.iter().filter_map(|var| var.max_length.as_deref()).filter(is_variable)
+    };
+    match cmd {
+        Cmd::Read(vars) => sizes.extend(max_lengths(vars)),
+        Cmd::Loop { count_var, command } => {
+            sizes.extend(Some(count_var.as_str()).filter(is_variable));
+            collect_sizes(command, sizes);
+        }
+        Cmd::LoopLine { count_var, variables } => {
+            sizes.extend(Some(count_var.as_str()).filter(is_variable));
+            sizes.extend(max_lengths(variables));
+        }
+        Cmd::Write { .. } | Cmd::WriteJoin { .. } | Cmd::External(_) => {}
+    }
+}
+
+struct InputGenerator<'a> {
+    constraints: &'a [Constraint],
+    sizes: BTreeSet<&'a str>,
+    size: u64,
+    /// Latest value of every numeric variable, for loop counts and lengths.
+    values: BTreeMap<String, f64>,
+    rng: StdRng,
+    lines: Vec<String>,
+}
+
+impl InputGenerator<'_> {
+    fn command(&mut self, cmd: &Cmd) {
+        match cmd {
+            Cmd::Read(vars) => {
+                let line = self.tokens(vars, 1);
+                self.lines.push(line);
+            }
+            Cmd::Loop { count_var, command } => {
+                for _ in 0..self.count(count_var) {
+                    self.command(command);
+                }
+            }
+            Cmd::LoopLine { count_var, variables } => {
+                let count = self.count(count_var);
+                let line = self.tokens(variables, count);
+                self.lines.push(line);
+            }
+            Cmd::Write { .. } | Cmd::WriteJoin { .. } | Cmd::External(_) => {}
+        }
+    }
+
+    /// One line with `repeat` values of each of `vars`.
+    fn tokens(&mut self, vars: &[VariableCommand], repeat: u64) -> String {
+        let mut tokens = Vec::new();
+        for _ in 0..repeat {
+            for var in vars {
+                tokens.push(self.value(var));
+            }
+        }
+        tokens.join(" ")
+    }
+
+    fn value(&mut self, var: &VariableCommand) -> String {
+        match var.var_type {
+            VarType::Int | VarType::Long | VarType::Float => {
+                let type_max = match var.var_type {
+                    VarType::Int => i32::MAX as f64,
+                    VarType::Long => i64::MAX as f64,
+                    _ => f64::MAX,
+                };
+                let (min, max) = self.bounds(&var.ident);
+                let max = max.min(type_max).max(min);
+                let (int_min, int_max) = (min.ceil(), max.floor().max(min.ceil()));
+                let value = match var.var_type {
+                    VarType::Float => self.rng.gen_range(min..=max),
+                    _ if self.sizes.contains(var.ident.as_str()) => int_max,
+                    _ => self.rng.gen_range(int_min..=int_max).round(),
+                };
+                self.values.insert(var.ident.clone(), value);
+                match var.var_type {
+                    VarType::Float => format!("{value:.2}"),
+                    _ => format!("{value}"),
+                }
+            }
+            VarType::Bool => self.rng.gen_range(0..=1).to_string(),
+            VarType::Word => {
+                let length = self.length(var);
+                (0..length).map(|_| self.rng.gen_range(b'a'..=b'z') as char).collect()
+            }
+            VarType::String => {
+                let length = self.length(var);
+                let mut string = String::new();
+                // Words of random length separated by single spaces
+                while (string.len() as u64) < length {
+                    let room_for_word = string.len() as u64 + 1 < length;
+                    if !string.is_empty()
+                        && !string.ends_with(' ')
+                        && room_for_word
+                        && self.rng.gen_ratio(1, 6)
+                    {
+                        string.push(' ');
+                    } else {
+                        string.push(self.rng.gen_range(b'a'..=b'z') as char);
+                    }
+                }
+                string
+            }
+        }
+    }
+
+    /// The range of values of a number: `1..=size`, narrowed down by the
+    /// constraints of the variable.
+    fn bounds(&self, ident: &str) -> (f64, f64) {
+        let constraint = self.constraints.iter().find(|constraint| constraint.subject.trim() == ident);
+        let min = constraint.and_then(|constraint| constraint.min).unwrap_or(1.0);
+        let max = self.size as f64;
+        let max = constraint.and_then(|constraint| constraint.max).map_or(max, |bound| max.min(bound));
+        (min, max.max(min))
+    }
+
+    /// Number of repetitions of a loop: a literal count as it is, otherwise
+    /// the latest value of the variable (or `size` if it was never read).
+    fn count(&self, count_var: &str) -> u64 {
+        if let Ok(count) = count_var.parse::<u64>() {
+            return count
+        }
+        match self.values.get(count_var) {
+            Some(&value) => value.max(0.0) as u64,
+            None => self.size,
+        }
+    }
+
+    /// Length of a word or string: the maximum length of the variable, but
+    /// no more than `size`.
+    fn length(&self, var: &VariableCommand) -> u64 {
+        let max_length = match var.max_length.as_deref() {
+            Some(max_length) => max_length.parse::<u64>().unwrap_or_else(|_| self.count(max_length)),
+            None => self.size,
+        };
+        max_length.min(self.size).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clash::parse_constraints;
+
+    #[test]
+    fn counts_follow_the_size() {
+        let generator = "read n:int\nloop n read x:int y:float\nloopline n w:word(5)\nwrite answer";
+        let input = random_input(generator, &[], 10, 1).unwrap();
+        let lines: Vec<&str> = input.lines().collect();
+        assert_eq!(lines.len(), 12);
+        assert_eq!(lines[0], "10");
+        assert_eq!(lines[11].split(' ').count(), 10);
+        assert!(lines[11].split(' ').all(|word| word.len() == 5));
+        for line in &lines[1..11] {
+            let x: i64 = line.split(' ').next().unwrap().parse().unwrap();
+            assert!((1..=10).contains(&x));
+        }
+    }
+
+    #[test]
+    fn literal_counts_are_kept() {
+        let generator = "read n:int\nloop 3 read x:int\nloopline 2 w:word(4)\nwrite answer";
+        let input = random_input(generator, &[], 100, 1).unwrap();
+        let lines: Vec<&str> = input.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[4].split(' ').count(), 2);
+        assert!(lines[4].split(' ').all(|word| word.len() == 4));
+        assert_eq!(random_input(generator, &[], 1, 1).unwrap().lines().count(), 5);
+    }
+
+    #[test]
+    fn constraints_limit_the_values() {
+        let generator = "read n:int k:int\nloop n read s:string(k)\nwrite answer";
+        let constraints = parse_constraints("1 ≤ N ≤ 3\n5 ≤ k ≤ 100");
+        // The subject of a constraint has to match the name exactly
+        let input = random_input(generator, &constraints, 1000, 7).unwrap();
+        assert_eq!(input.lines().next(), Some("1000 100"));
+
+        let constraints = parse_constraints("1 ≤ n ≤ 3\n5 ≤ k ≤ 100");
+        let input = random_input(generator, &constraints, 1000, 7).unwrap();
+        let lines: Vec<&str> = input.lines().collect();
+        assert_eq!(lines, ["3 100", lines[1], lines[2], lines[3]]);
+        assert!(lines[1..].iter().all(|line| line.len() == 100 && line.trim() == *line));
+        assert_eq!(random_input(generator, &constraints, 1000, 7).unwrap(), input);
+    }
+}
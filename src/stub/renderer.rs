@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use anyhow::{Context as _, Result}; // To distinguish it from tera::Context
 use itertools::Itertools;
 use serde_json::json;
@@ -13,18 +15,30 @@ pub struct Renderer {
     pub(super) lang: Language,
     tera: Tera,
     stub: Stub,
+    options: BTreeMap<String, String>,
+    loop_indices: Vec<String>,
 }
 
 impl Renderer {
-    pub(super) fn new(config: StubConfig, stub: Stub) -> Result<Renderer> {
+    /// `loop_indices` are the names of the loop indices from the outermost
+    /// loop in, see [`loop_index_names`].
+    pub(super) fn new(config: StubConfig, stub: Stub, loop_indices: Vec<String>) -> Result<Renderer> {
         Ok(Self {
+            options: config.option_values(),
             lang: config.language,
             tera: config.tera,
             stub,
+            loop_indices,
         })
     }
 
-    pub(super) fn tera_render(&self, template_name: &str, context: &mut Context) -> String {
+    /// Name of the index of a loop that is nested in `nesting_depth` other
+    /// loops.
+    pub(super) fn loop_index(&self, nesting_depth: usize) -> &str {
+        &self.loop_indices[nesting_depth]
+    }
+
+    pub(super) fn tera_render(&self, template_name: &str, context: &mut Context) -> Result<String> {
         // Since these are (generally) shared across languages, it makes sense to
         // store it in the "global" context instead of accepting it as parameters.
         let format_symbols = json!({
@@ -41,17 +55,23 @@ impl Renderer {
 
         context.insert("type_tokens", &self.lang.type_tokens);
         context.insert("type_parsers", &self.lang.type_parsers);
+        context.insert("parse_strategies", &self.lang.parse_strategies);
+        context.insert("options", &self.options);
 
         self.tera
             .render(&format!("{template_name}.{}.jinja", self.lang.source_file_ext), context)
             .with_context(|| format!("Failed to render {} template.", template_name))
-            .unwrap()
     }
 
-    pub(super) fn render(&self) -> String {
+    pub(super) fn render(&self) -> Result<String> {
         let mut context = Context::new();
 
-        let code: String = self.stub.commands.iter().map(|cmd| self.render_command(cmd, 0)).collect();
+        let code: String = self
+            .stub
+            .commands
+            .iter()
+            .map(|cmd| self.render_command(cmd, 0))
+            .collect::<Result<_>>()?;
         let code_lines: Vec<&str> = code.lines().collect();
 
         context.insert("statement", &self.stub.statement);
@@ -60,7 +80,7 @@ impl Renderer {
         self.tera_render("main", &mut context)
     }
 
-    pub(super) fn render_command(&self, cmd: &Cmd, nesting_depth: usize) -> String {
+    pub(super) fn render_command(&self, cmd: &Cmd, nesting_depth: usize) -> Result<String> {
         match cmd {
             Cmd::Read(vars) => self.render_read(vars, nesting_depth),
             Cmd::Write {
@@ -79,7 +99,7 @@ impl Renderer {
         }
     }
 
-    fn render_write(&self, lines: &[String], output_comments: &[String]) -> String {
+    fn render_write(&self, lines: &[String], output_comments: &[String]) -> Result<String> {
         let mut context = Context::new();
 
         context.insert("messages", lines);
@@ -88,7 +108,7 @@ impl Renderer {
         self.tera_render("write", &mut context)
     }
 
-    fn render_write_join(&self, terms: &[JoinTerm], output_comments: &[String]) -> String {
+    fn render_write_join(&self, terms: &[JoinTerm], output_comments: &[String]) -> Result<String> {
         let mut context = Context::new();
 
         let terms: Vec<JoinTerm> = terms
@@ -108,14 +128,14 @@ impl Renderer {
         self.tera_render("write_join", &mut context)
     }
 
-    fn render_read(&self, vars: &Vec<VariableCommand>, nesting_depth: usize) -> String {
+    fn render_read(&self, vars: &Vec<VariableCommand>, nesting_depth: usize) -> Result<String> {
         match vars.as_slice() {
             [var] => self.render_read_one(var),
             _ => self.render_read_many(vars, nesting_depth),
         }
     }
 
-    fn render_read_one(&self, var: &VariableCommand) -> String {
+    fn render_read_one(&self, var: &VariableCommand) -> Result<String> {
         let mut context = Context::new();
         let var = self.lang.variable_name_options.transform_variable_command(var);
 
@@ -124,7 +144,7 @@ impl Renderer {
         self.tera_render("read_one", &mut context)
     }
 
-    fn render_read_many(&self, vars: &[VariableCommand], nesting_depth: usize) -> String {
+    fn render_read_many(&self, vars: &[VariableCommand], nesting_depth: usize) -> Result<String> {
         let mut context = Context::new();
         let vars = vars
             .iter()
@@ -137,7 +157,7 @@ impl Renderer {
             _ => context.insert("single_type", &false),
         }
 
-        let index_ident = ALPHABET[nesting_depth];
+        let index_ident = self.loop_index(nesting_depth);
 
         context.insert("vars", &vars);
         context.insert("index_ident", &index_ident);
@@ -145,11 +165,11 @@ impl Renderer {
         self.tera_render("read_many", &mut context)
     }
 
-    fn render_loop(&self, count_var: &str, cmd: &Cmd, nesting_depth: usize) -> String {
+    fn render_loop(&self, count_var: &str, cmd: &Cmd, nesting_depth: usize) -> Result<String> {
         let mut context = Context::new();
-        let inner_text = self.render_command(cmd, nesting_depth + 1);
+        let inner_text = self.render_command(cmd, nesting_depth + 1)?;
         let cased_count_var = self.lang.variable_name_options.transform_variable_name(count_var);
-        let index_ident = ALPHABET[nesting_depth];
+        let index_ident = self.loop_index(nesting_depth);
         context.insert("count_var", &cased_count_var);
         context.insert("inner", &inner_text.lines().collect::<Vec<&str>>());
         context.insert("index_ident", &index_ident);
@@ -157,7 +177,12 @@ impl Renderer {
         self.tera_render("loop", &mut context)
     }
 
-    fn render_loopline(&self, count_var: &str, vars: &[VariableCommand], nesting_depth: usize) -> String {
+    fn render_loopline(
+        &self,
+        count_var: &str,
+        vars: &[VariableCommand],
+        nesting_depth: usize,
+    ) -> Result<String> {
         let vars = vars
             .iter()
             .map(|var| self.lang.variable_name_options.transform_variable_command(var))
@@ -166,7 +191,7 @@ impl Renderer {
         let mut context = Context::new();
 
         let cased_count_var = self.lang.variable_name_options.transform_variable_name(count_var);
-        let index_ident = ALPHABET[nesting_depth];
+        let index_ident = self.loop_index(nesting_depth);
 
         context.insert("count_var", &cased_count_var);
         context.insert("vars", &vars);
@@ -175,3 +200,42 @@ impl Renderer {
         self.tera_render("loopline", &mut context)
     }
 }
+
+/// Names for the loop indices of `stub` in `lang` that don't collide with
+/// the variables of the stub. Has to be called before the stub is
+/// preprocessed, while all of its commands are still visible.
+pub(super) fn loop_index_names(lang: &Language, stub: &Stub) -> Vec<String> {
+    fn visit(cmd: &Cmd, nesting_depth: usize, taken: &mut Vec<String>, lang: &Language) -> usize {
+        let mut take = |ident: &str| taken.push(lang.variable_name_options.transform_variable_name(ident));
+        match cmd {
+            Cmd::Read(vars) => {
+                vars.iter().for_each(|var| take(&var.ident));
+                nesting_depth + 1
+            }
+            Cmd::LoopLine { count_var, variables } => {
+                take(count_var);
+                variables.iter().for_each(|var| take(&var.ident));
+                nesting_depth + 1
+            }
+            Cmd::Loop { count_var, command } => {
+                take(count_var);
+                visit(command, nesting_depth + 1, taken, lang).max(nesting_depth + 1)
+            }
+            Cmd::WriteJoin { join_terms, .. } => {
+                join_terms
+                    .iter()
+                    .filter(|term| term.var_type.is_some())
+                    .for_each(|term| take(&term.ident));
+                0
+            }
+            Cmd::Write { .. } | Cmd::External(_) => 0,
+        }
+    }
+
+    let mut taken = Vec::new();
+    let mut num_indices = 0;
+    for cmd in &stub.commands {
+        num_indices = num_indices.max(visit(cmd, 0, &mut taken, lang));
+    }
+    lang.loop_index_naming.names(num_indices, &taken)
+}
@@ -0,0 +1,214 @@
+use itertools::Itertools;
+
+/// Length given to words and strings whose real maximum length is unknown.
+const UNKNOWN_LENGTH: usize = 256;
+
+/// Guesses a stub generator for `input`, which is usually the input of the
+/// first testcase of a reverse mode clash (they don't have a stub generator).
+///
+/// Every line becomes a `read`, with the types of the variables guessed from
+/// their values. When an integer is followed by that many lines that look
+/// alike (or by a line with that many tokens of the same type) they are read
+/// with a `loop` (or a `loopline`) instead.
+pub fn reverse_generator(input: &str) -> String {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut names = VariableNames::default();
+    let mut generator = String::from(
+        "STATEMENT\nReverse mode: the input handling below is guessed from the first testcase\n\n",
+    );
+
+    let mut idx = 0;
+    while idx < lines.len() {
+        let tokens = line_tokens(lines[idx]);
+        let line_names: Vec<String> = tokens.iter().map(|_| names.next_name()).collect();
+        generator += &format!("read {}\n", variables(&line_names, &tokens));
+        idx += 1;
+
+        // Try the integers of the line as counts of the lines that follow
+        for (name, token) in line_names.iter().zip(&tokens) {
+            let Some(count) = token.loop_count() else { continue };
+            let rest = &lines[idx..];
+            if count <= rest.len() && count > 1 {
+                let body: Vec<Vec<Token>> = rest[..count].iter().map(|line| line_tokens(line)).collect();
+                if body.iter().map(|tokens| signature(tokens)).all_equal() {
+                    let body_names: Vec<String> = body[0].iter().map(|_| names.next_name()).collect();
+                    generator += &format!("loop {} read {}\n", name, variables(&body_names, &body[0]));
+                    idx += count;
+                    break
+                }
+            }
+            if let Some(next_line) = rest.first() {
+                let next_tokens = line_tokens(next_line);
+                let single_type = next_tokens.iter().map(Token::var_type).all_equal();
+                if next_tokens.len() == count && count > 1 && single_type && next_tokens[0].is_separate() {
+                    let var_name = names.next_name();
+                    generator +=
+                        &format!("loopline {} {}\n", name, variables(&[var_name], &next_tokens[..1]));
+                    idx += 1;
+                    break
+                }
+            }
+        }
+    }
+
+    generator += "write answer\n";
+    generator
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Int(i64),
+    Long,
+    Float,
+    Word,
+    /// A line that has to be read as a whole.
+    String,
+}
+
+impl Token {
+    fn guess(value: &str) -> Self {
+        if let Ok(int) = value.parse::<i32>() {
+            Token::Int(int.into())
+        } else if value.parse::<i64>().is_ok() {
+            Token::Long
+        } else if value.parse::<f64>().is_ok() && value.chars().any(|c| c.is_ascii_digit()) {
+            Token::Float
+        } else {
+            Token::Word
+        }
+    }
+
+    fn var_type(&self) -> String {
+        match self {
+            Token::Int(_) => "int".to_string(),
+            Token::Long => "long".to_string(),
+            Token::Float => "float".to_string(),
+            Token::Word => format!("word({UNKNOWN_LENGTH})"),
+            Token::String => format!("string({UNKNOWN_LENGTH})"),
+        }
+    }
+
+    fn loop_count(&self) -> Option<usize> {
+        match self {
+            Token::Int(count) => usize::try_from(*count).ok(),
+            _ => None,
+        }
+    }
+
+    /// Whether the token can be read on its own (as opposed to a whole line).
+    fn is_separate(&self) -> bool {
+        *self != Token::String
+    }
+}
+
+fn line_tokens(line: &str) -> Vec<Token> {
+    let tokens: Vec<Token> = line.split(' ').map(Token::guess).collect();
+    // Lines made of several words (or with irregular spacing) are most
+    // likely free form text
+    let all_words = tokens.len() > 1 && tokens.iter().all(|token| *token == Token::Word);
+    if line.is_empty() || line.contains("  ") || line.trim() != line || all_words {
+        vec![Token::String]
+    } else {
+        tokens
+    }
+}
+
+/// Types of the tokens of a line, loop counts included.
+fn signature(tokens: &[Token]) -> Vec<String> {
+    tokens.iter().map(Token::var_type).collect()
+}
+
+fn variables(names: &[String], tokens: &[Token]) -> String {
+    names
+        .iter()
+        .zip(tokens)
+        .map(|(name, token)| format!("{}:{}", name, token.var_type()))
+        .join(" ")
+}
+
+/// Generates the variable names a, b, ..., z, aa, ab, ...
+#[derive(Default)]
+struct VariableNames {
+    count: usize,
+}
+
+impl VariableNames {
+    fn next_name(&mut self) -> String {
+        let mut n = self.count;
+        self.count += 1;
+        let mut name = Vec::new();
+        loop {
+            name.push(b'a' + (n % 26) as u8);
+            if n < 26 {
+                break
+            }
+            n = n / 26 - 1;
+        }
+        name.reverse();
+        String::from_utf8(name).expect("variable names should be ASCII")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(generator: &str) -> Vec<&str> {
+        generator
+            .lines()
+            .skip_while(|line| !line.is_empty())
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn guesses_types_per_token() {
+        let generator = reverse_generator("5 2.5 9999999999 hello\nsome free text\n-3");
+        assert_eq!(
+            commands(&generator),
+            vec![
+                "read a:int b:float c:long d:word(256)",
+                "read e:string(256)",
+                "read f:int",
+                "write answer"
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_loops() {
+        let generator = reverse_generator("3\n1 a\n2 b\n3 c\n4\n1 2 3 4");
+        assert_eq!(
+            commands(&generator),
+            vec![
+                "read a:int",
+                "loop a read b:int c:word(256)",
+                "read d:int",
+                "loopline d e:int",
+                "write answer"
+            ]
+        );
+    }
+
+    #[test]
+    fn count_must_match_the_lines() {
+        let generator = reverse_generator("3\n1\n2");
+        assert_eq!(commands(&generator), vec!["read a:int", "read b:int", "read c:int", "write answer"]);
+    }
+
+    #[test]
+    fn generated_names() {
+        let mut names = VariableNames::default();
+        let names: Vec<String> = (0..28).map(|_| names.next_name()).collect();
+        assert_eq!(names[0], "a");
+        assert_eq!(names[25], "z");
+        assert_eq!(names[26], "aa");
+        assert_eq!(names[27], "ab");
+    }
+
+    #[test]
+    fn generator_can_be_parsed() {
+        let generator = reverse_generator("2\nfoo bar baz\nqux quux\n\n7");
+        crate::stub::parser::parse_generator_stub(&generator).unwrap();
+    }
+}
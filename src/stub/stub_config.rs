@@ -1,11 +1,14 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+#[cfg(feature = "embedded-templates")]
 use include_dir::include_dir;
 use tera::Tera;
 
 use super::Language;
 
+#[cfg(feature = "embedded-templates")]
 const HARDCODED_EMBEDDED_TEMPLATE_DIR: include_dir::Dir<'static> =
     include_dir!("$CARGO_MANIFEST_DIR/config/stub_templates");
 
@@ -13,20 +16,132 @@ const HARDCODED_EMBEDDED_TEMPLATE_DIR: include_dir::Dir<'static> =
 pub struct StubConfig {
     pub(super) language: Language,
     pub(super) tera: Tera,
+    /// Values of the options of the language set with
+    /// [`StubConfig::set_option`].
+    pub(super) selected_options: BTreeMap<String, String>,
 }
 
 impl StubConfig {
+    fn new(language: Language, tera: Tera) -> Self {
+        Self {
+            language,
+            tera,
+            selected_options: BTreeMap::new(),
+        }
+    }
+
     pub fn read_from_dir(dir: std::path::PathBuf) -> Result<Self> {
         let toml_file = dir.join("stub_config.toml");
         let toml_str = fs::read_to_string(toml_file)?;
         let language: Language = toml::from_str(&toml_str)?;
         let jinja_glob = dir.join("*.jinja");
-        let tera = Tera::new(jinja_glob.to_str().expect("language directory path should be valid utf8"))
+        let mut tera = Tera::new(jinja_glob.to_str().expect("language directory path should be valid utf8"))
             .context("Failed to create Tera instance")?;
-        Ok(Self { language, tera })
+        register_filters(&mut tera, &language);
+        Ok(Self::new(language, tera))
+    }
+
+    /// File extension (without the dot) of source files in the language.
+    pub fn source_file_ext(&self) -> &str {
+        &self.language.source_file_ext
+    }
+
+    /// Command template for checking that a generated stub compiles, with
+    /// `{file}` in place of the path of the stub file.
+    pub fn check_command(&self) -> Option<&str> {
+        self.language.check_command.as_deref()
+    }
+
+    /// Command template for compiling a solution, with `{source}` in place of
+    /// the path of the solution and `{executable}` in place of the path of the
+    /// compiled solution (in the build directory of the clash).
+    pub fn build_command(&self) -> Option<&str> {
+        self.language.build_command.as_deref()
+    }
+
+    /// Command template for running a solution, with the same placeholders
+    /// as [`StubConfig::build_command`].
+    pub fn run_command(&self) -> Option<&str> {
+        self.language.run_command.as_deref()
+    }
+
+    /// Prefix of single line comments in the language, such as `//`.
+    pub fn comment_prefix(&self) -> Option<&str> {
+        self.language.comment_prefix.as_deref()
+    }
+
+    /// Selects `value` for the option `name` of the language, such as
+    /// `io=fast` for faster input reading in Python.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "embedded-templates")] {
+    /// use clashlib::stub::{generate_from_config, StubConfig};
+    ///
+    /// let mut config = StubConfig::read_from_embedded("python").unwrap();
+    /// config.set_option("io", "fast").unwrap();
+    /// assert!(config.set_option("io", "slow").is_err());
+    /// let stub = generate_from_config(config, "read n:int\nwrite answer").unwrap();
+    /// assert!(stub.starts_with("import sys"));
+    /// # }
+    /// ```
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<()> {
+        let known_options =
+            || self.language.options.keys().map(String::as_str).collect::<Vec<_>>().join(", ");
+        let option =
+            self.language
+                .options
+                .get(name)
+                .with_context(|| match self.language.options.is_empty() {
+                    true => anyhow!("The stubs of this language have no options"),
+                    false => anyhow!("Unknown stub option {name:?} (the options are {})", known_options()),
+                })?;
+        if !option.values.iter().any(|known| known == value) {
+            return Err(anyhow!(
+                "Invalid value {value:?} for stub option {name} (expected {})",
+                option.values.join(" or ")
+            ))
+        }
+        self.selected_options.insert(name.to_owned(), value.to_owned());
+        Ok(())
+    }
+
+    /// The value of every option of the language, the default unless
+    /// another one has been selected.
+    pub(super) fn option_values(&self) -> BTreeMap<String, String> {
+        let defaults = self
+            .language
+            .options
+            .iter()
+            .filter_map(|(name, option)| Some((name.clone(), option.values.first()?.clone())));
+        defaults.chain(self.selected_options.clone()).collect()
+    }
+
+    /// Names of the languages with stub templates embedded into the library,
+    /// in alphabetical order.
+    #[cfg(feature = "embedded-templates")]
+    pub fn embedded_languages() -> Vec<&'static str> {
+        let mut languages: Vec<&str> = HARDCODED_EMBEDDED_TEMPLATE_DIR
+            .dirs()
+            .filter_map(|dir| dir.path().file_name()?.to_str())
+            .collect();
+        languages.sort();
+        languages
+    }
+
+    /// Names of the files (`stub_config.toml` and the templates) embedded for
+    /// `lang_name`, or `None` if there are no embedded templates for it.
+    #[cfg(feature = "embedded-templates")]
+    pub fn embedded_files(lang_name: &str) -> Option<Vec<&'static str>> {
+        let dir = HARDCODED_EMBEDDED_TEMPLATE_DIR.get_dir(lang_name)?;
+        let mut files: Vec<&str> = dir.files().filter_map(|file| file.path().file_name()?.to_str()).collect();
+        files.sort();
+        Some(files)
     }
 
-    pub(super) fn read_from_embedded(lang_name: &str) -> Result<Self> {
+    #[cfg(feature = "embedded-templates")]
+    pub fn read_from_embedded(lang_name: &str) -> Result<Self> {
         // If you just created a new template for a language and you get:
         // Error: No stub generator found for 'language'
         // you may need to recompile the binaries to update: `cargo build`
@@ -51,6 +166,32 @@ impl StubConfig {
         let mut tera = Tera::default();
 
         tera.add_raw_templates(templates)?;
-        Ok(Self { language, tera })
+        register_filters(&mut tera, &language);
+        Ok(Self::new(language, tera))
+    }
+}
+
+/// Filters that depend on the language, on top of the builtin Tera ones.
+fn register_filters(tera: &mut Tera, language: &Language) {
+    let string_escape = language.string_escape;
+    tera.register_filter("escape_string", move |value: &tera::Value, _: &HashMap<String, tera::Value>| {
+        let text = tera::try_get_value!("escape_string", "value", String, value);
+        Ok(tera::Value::String(string_escape.escape(&text)))
+    });
+}
+
+#[cfg(all(test, feature = "embedded-templates"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_files_include_config_and_templates() {
+        let files = StubConfig::embedded_files("python").unwrap();
+        assert!(files.contains(&"stub_config.toml"));
+        assert!(files.contains(&"main.py.jinja"));
+        assert!(StubConfig::embedded_files("not-a-language").is_none());
+        let languages = StubConfig::embedded_languages();
+        assert!(languages.contains(&"python"));
+        assert!(languages.windows(2).all(|pair| pair[0] < pair[1]));
     }
 }
@@ -0,0 +1,161 @@
+use indoc::indoc;
+
+/// A small stub generator that exercises a specific part of the stub
+/// templates of a language.
+pub struct TemplateCheck {
+    pub name: &'static str,
+    pub generator: &'static str,
+}
+
+/// A battery of generator snippets that together use every template of a
+/// language. Used by `coctus test-templates` to give template authors quick
+/// feedback without having to write Rust tests.
+pub const TEMPLATE_CHECKS: &[TemplateCheck] = &[
+    TemplateCheck {
+        name: "read_one",
+        generator: indoc! {"
+            read anInt:int
+            read aFloat:float
+            read aLong:long
+            read aBool:bool
+            read aWord:word(50)
+            read aString:string(256)
+        "},
+    },
+    TemplateCheck {
+        name: "read_many_single_type",
+        generator: "read x:int y:int z:int\nread a:word(10) b:word(10)\n",
+    },
+    TemplateCheck {
+        name: "read_many_mixed_types",
+        generator: "read n:int name:word(50) ratio:float isOk:bool big:long\n",
+    },
+    TemplateCheck {
+        // Strings with spaces (or empty strings) after numeric tokens used to
+        // break templates that read token by token.
+        name: "strings_after_numbers",
+        generator: indoc! {"
+            read n:int
+            read line:string(100)
+            read x:int name:string(50)
+            loopline n v:int
+            read after:string(20)
+            loop n read k:int text:string(30)
+        "},
+    },
+    TemplateCheck {
+        name: "loop",
+        generator: "read n:int\nloop n read x:int\nloop n read a:int b:word(20)\nloop 3 write hello\n",
+    },
+    TemplateCheck {
+        name: "nested_loops",
+        generator: "read n:int m:int\nloop n loop m read cell:int\nloop n loop m loop 2 read x:int y:float\n",
+    },
+    TemplateCheck {
+        name: "loopline",
+        generator: indoc! {"
+            read n:int
+            loopline n x:int
+            loopline n w:word(50)
+            loopline n x:int f:float w:word(50)
+        "},
+    },
+    TemplateCheck {
+        name: "write",
+        generator: "write answer\nwrite many  spaces   here\nwrite two\nlines\n",
+    },
+    TemplateCheck {
+        name: "write_join",
+        generator: indoc! {r#"
+            read a:int b:float c:long d:bool e:word(10) f:string(100)
+            write join(a, b, c, d, e, f)
+            write join("literal", a, "another literal")
+        "#},
+    },
+    TemplateCheck {
+        // Literals have to be escaped to end up in the output unchanged
+        name: "special_characters",
+        generator: indoc! {r#"
+            read x:int
+            write She said "hi" \ 100% #$ 'single' é ✓
+
+            write join("say "hi"", x, "C:\dir 'é' #$")
+        "#},
+    },
+    TemplateCheck {
+        name: "comments",
+        generator: indoc! {"
+            read n:int
+            read name:word(20)
+            write answer
+
+            INPUT
+            n: the number of things
+            name: the name of the thing
+
+            OUTPUT
+            The answer
+
+            STATEMENT
+            Solve the puzzle
+        "},
+    },
+];
+
+/// Checks a rendered stub for common signs of broken templates and returns a
+/// description of every problem found.
+///
+/// These are heuristics: a stub that passes them is not necessarily valid
+/// code, but one that fails them most likely isn't.
+pub fn lint_rendered_stub(code: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if code.trim().is_empty() {
+        problems.push("rendered stub is empty".to_string());
+    }
+
+    for tera_syntax in ["{{", "}}", "{%", "%}", "{#", "#}"] {
+        if let Some(line) = code.lines().position(|line| line.contains(tera_syntax)) {
+            problems.push(format!("leftover template syntax {tera_syntax:?} on line {}", line + 1));
+        }
+    }
+
+    for (open, close) in [('(', ')'), ('[', ']'), ('{', '}')] {
+        let num_open = code.chars().filter(|&ch| ch == open).count();
+        let num_close = code.chars().filter(|&ch| ch == close).count();
+        if num_open != num_close {
+            problems.push(format!("unbalanced {open}{close}: {num_open} opening and {num_close} closing"));
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "embedded-templates")]
+    fn embedded_templates_pass_checks() {
+        for lang in ["c", "cpp", "pascal", "python", "ruby", "rust"] {
+            for check in TEMPLATE_CHECKS {
+                let code = crate::stub::generate(lang, check.generator).unwrap();
+                let problems = lint_rendered_stub(&code);
+                assert!(problems.is_empty(), "{lang} {}: {:?}\n{code}", check.name, problems);
+            }
+        }
+    }
+
+    #[test]
+    fn lint_finds_leftover_tera_syntax() {
+        let problems = lint_rendered_stub("x = {{ var.ident }}");
+        assert_eq!(problems.len(), 2);
+    }
+
+    #[test]
+    fn lint_finds_unbalanced_braces() {
+        let problems = lint_rendered_stub("int main() {\n    return 0;\n");
+        assert_eq!(problems, vec!["unbalanced {}: 1 opening and 0 closing"]);
+    }
+}
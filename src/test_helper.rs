@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
-use clash::Clash;
+use clash::{Clash, Testcase};
 
 use super::*;
 
@@ -14,3 +14,18 @@ pub fn sample_puzzle(name: &str) -> Result<Clash> {
 
     Ok(clash)
 }
+
+/// The testcase `index` of a clash without validators, with `test_in` as the
+/// input and `test_out` as the expected output. Tests that need some other
+/// title or a validator override the fields:
+/// `Testcase { is_validator: true, ..testcase(2, "", "") }`.
+pub fn testcase(index: usize, test_in: &str, test_out: &str) -> Testcase {
+    Testcase {
+        index,
+        number: index,
+        title: format!("Test {index}"),
+        test_in: test_in.to_string(),
+        test_out: test_out.to_string(),
+        is_validator: false,
+    }
+}
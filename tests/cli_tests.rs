@@ -0,0 +1,171 @@
+// The data directory is found through XDG_DATA_HOME, which only linux uses
+#![cfg(target_os = "linux")]
+
+use std::path::Path;
+use std::process::Command;
+
+const HANDLE: &str = "1234567890abcdef1234567890abcdef12345";
+
+/// `coctus` with its data and config directories in `home`, which has the
+/// stub_and_solution_tester fixture as the only stored clash.
+fn coctus(home: &Path) -> Command {
+    let clash_dir = home.join("data/coctus/clashes");
+    std::fs::create_dir_all(&clash_dir).unwrap();
+    std::fs::copy(
+        "fixtures/puzzles/stub_and_solution_tester.json",
+        clash_dir.join(format!("{HANDLE}.json")),
+    )
+    .unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_coctus"));
+    cmd.env("HOME", home)
+        .env("XDG_DATA_HOME", home.join("data"))
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env_remove("COCTUS_SHARED_DIR");
+    cmd
+}
+
+#[test]
+fn raw_input_is_what_run_writes_to_stdin() {
+    let home = tempfile::tempdir().unwrap();
+    assert!(coctus(home.path()).args(["next", HANDLE]).output().unwrap().status.success());
+    let raw = coctus(home.path()).args(["showtests", "--raw", "--in", "1"]).output().unwrap();
+    assert!(raw.status.success(), "{}", String::from_utf8_lossy(&raw.stderr));
+
+    let received = home.path().join("received.txt");
+    let command = format!("sh -c 'cat > {}'", received.display());
+    let run = coctus(home.path())
+        .args(["run", "--testcases", "1", "--command", &command])
+        .output()
+        .unwrap();
+    assert!(received.exists(), "{}", String::from_utf8_lossy(&run.stderr));
+    assert_eq!(std::fs::read(&received).unwrap(), raw.stdout);
+}
+
+#[test]
+fn init_runs_with_an_invalid_config_file() {
+    let home = tempfile::tempdir().unwrap();
+    let config_dir = home.path().join("config/coctus");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(config_dir.join("config.toml"), "ofline = true\n").unwrap();
+
+    let init = coctus(home.path()).arg("init").output().unwrap();
+    assert!(init.status.success(), "{}", String::from_utf8_lossy(&init.stderr));
+    assert!(String::from_utf8_lossy(&init.stderr).contains("Invalid config file"));
+    let list = coctus(home.path()).arg("list").output().unwrap();
+    assert!(!list.status.success());
+}
+
+#[test]
+fn init_and_start_write_the_same_solution_file() {
+    let home = tempfile::tempdir().unwrap();
+    assert!(coctus(home.path()).args(["next", HANDLE]).output().unwrap().status.success());
+    let dir = home.path().join("solutions");
+    let init = coctus(home.path()).arg("init").arg("rust").arg("--dir").arg(&dir).output().unwrap();
+    assert!(init.status.success(), "{}", String::from_utf8_lossy(&init.stderr));
+    let stub = std::fs::read_to_string(dir.join("main.rs")).unwrap();
+    assert!(stub.contains("fn main()"));
+    let tracked = std::fs::read_to_string(home.path().join("data/coctus/solution.json")).unwrap();
+    assert!(tracked.contains("main.rs"));
+
+    // Existing solutions are only overwritten with --force, like with start
+    std::fs::write(dir.join("main.rs"), "// solved\n").unwrap();
+    for command in [&["init", "rust"], &["start", "rust"]] {
+        let again = coctus(home.path()).args(command).current_dir(&dir).output().unwrap();
+        assert!(!again.status.success());
+        assert!(String::from_utf8_lossy(&again.stderr).contains("already exists"));
+    }
+    assert_eq!(std::fs::read_to_string(dir.join("main.rs")).unwrap(), "// solved\n");
+    let forced = coctus(home.path())
+        .args(["init", "rust", "--force", "--option", "io=macro"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(forced.status.success(), "{}", String::from_utf8_lossy(&forced.stderr));
+    assert!(std::fs::read_to_string(dir.join("main.rs")).unwrap().contains("parse_input!"));
+}
+
+/// The clash dir of `coctus(home)` and the publicHandle of the fixture,
+/// which doesn't match the name it is stored under.
+fn clash_dir(home: &Path) -> (std::path::PathBuf, &'static str) {
+    (home.join("data/coctus/clashes"), "90435e82d1d5e3fe5f9d3dd813770f0d5a7d2")
+}
+
+#[test]
+fn verify_store_renames_misnamed_files() {
+    let home = tempfile::tempdir().unwrap();
+    let (clash_dir, public_handle) = clash_dir(home.path());
+    let check = coctus(home.path()).arg("verify-store").output().unwrap();
+    assert!(!check.status.success());
+    assert!(String::from_utf8_lossy(&check.stdout).contains("file name does not match publicHandle"));
+    assert!(clash_dir.join(format!("{HANDLE}.json")).exists());
+
+    let fix = coctus(home.path()).args(["verify-store", "--fix"]).output().unwrap();
+    assert!(fix.status.success(), "{}", String::from_utf8_lossy(&fix.stdout));
+    assert!(!clash_dir.join(format!("{HANDLE}.json")).exists());
+    assert!(clash_dir.join(format!("{public_handle}.json")).exists());
+}
+
+#[test]
+fn verify_store_reports_case_only_duplicates() {
+    let home = tempfile::tempdir().unwrap();
+    let (clash_dir, public_handle) = clash_dir(home.path());
+    assert!(coctus(home.path())
+        .args(["verify-store", "--fix"])
+        .output()
+        .unwrap()
+        .status
+        .success());
+    let original = std::fs::read_to_string(clash_dir.join(format!("{public_handle}.json"))).unwrap();
+    let upper = public_handle.to_uppercase();
+    let duplicate = clash_dir.join(format!("{upper}.json"));
+    std::fs::write(&duplicate, original.replace(public_handle, &upper)).unwrap();
+
+    let fix = coctus(home.path()).args(["verify-store", "--fix"]).output().unwrap();
+    assert!(!fix.status.success());
+    assert!(String::from_utf8_lossy(&fix.stdout).contains("duplicate of"));
+    // Only identical duplicates are removed
+    assert!(duplicate.exists());
+}
+
+#[test]
+fn verify_store_removes_identical_duplicates() {
+    let home = tempfile::tempdir().unwrap();
+    let mut fix = coctus(home.path());
+    let (clash_dir, public_handle) = clash_dir(home.path());
+    let copy = clash_dir.join("copy.json");
+    std::fs::copy(clash_dir.join(format!("{HANDLE}.json")), &copy).unwrap();
+
+    let fix = fix.args(["verify-store", "--fix"]).output().unwrap();
+    assert!(fix.status.success(), "{}", String::from_utf8_lossy(&fix.stdout));
+    assert!(String::from_utf8_lossy(&fix.stdout).contains("removed identical duplicate"));
+    let remaining: Vec<_> = std::fs::read_dir(&clash_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(remaining, vec![std::ffi::OsString::from(format!("{public_handle}.json"))]);
+}
+
+#[test]
+fn verify_store_leaves_invalid_files_alone() {
+    let home = tempfile::tempdir().unwrap();
+    let mut fix = coctus(home.path());
+    let (clash_dir, _) = clash_dir(home.path());
+    let invalid = clash_dir.join("invalid.json");
+    let not_utf8 = clash_dir.join("binary.json");
+    std::fs::write(&invalid, "{").unwrap();
+    std::fs::write(&not_utf8, [0xff, 0xfe]).unwrap();
+    std::fs::create_dir(clash_dir.join("dir.json")).unwrap();
+
+    let fix = fix.args(["verify-store", "--fix"]).output().unwrap();
+    assert!(!fix.status.success());
+    let stdout = String::from_utf8_lossy(&fix.stdout);
+    for path in [&invalid, &not_utf8, &clash_dir.join("dir.json")] {
+        assert!(
+            stdout.contains(&path.display().to_string()),
+            "{path:?} should be reported in:\n{stdout}"
+        );
+    }
+    assert_eq!(std::fs::read_to_string(&invalid).unwrap(), "{");
+    assert_eq!(std::fs::read(&not_utf8).unwrap(), [0xff, 0xfe]);
+    assert!(String::from_utf8_lossy(&fix.stderr).contains("3 remaining"));
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "embedded-templates")]
+
 use clashlib::stub;
 
 fn test_stub_builder(generator: &str, expected: &str) {
@@ -93,6 +95,23 @@ for i in range(n_loop):
     test_stub_builder(generator, expected);
 }
 
+#[test]
+fn test_stub_loop_indices_avoid_variables() {
+    let generator = r##"read i:int J:int
+loop i loop J read x:int y:int
+loopline i k:int
+"##;
+    let expected = r##"i, j = [int(l) for l in input().split()]
+for l in range(i):
+    for m in range(j):
+        x, y = [int(n) for n in input().split()]
+for l in input().split():
+    k = int(l)
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
 #[test]
 fn test_stub_write_1() {
     let generator = r##"write Never
@@ -129,9 +148,9 @@ print("write")
 print("care, here   spaces   everywhere")
 print("and some  more")
 print("and dont do this, this breaks paitong")
-print(""")
+print("\"")
 print("'")
-print(""")
+print("\"")
 "##;
 
     test_stub_builder(generator, expected);
@@ -227,7 +246,7 @@ write join("YOU", join("JOIN"))
     let expected = r##"nonsense = int(input())
 print("hi it's me Jim")
 print("hi " + str(nonsense) + " it's me Jim")
-print("join("hi",,, "Jim")")
+print("join(\"hi\",,, \"Jim\")")
 print("hi Jim")
 print("NEVER")
 print("GONNA")
@@ -0,0 +1,733 @@
+#![cfg(feature = "embedded-templates")]
+
+use clashlib::stub;
+
+// Every Rust stub starts with this, the tests leave it out
+const READ_LINE_HELPER: &str = r##"use std::io;
+
+fn read_line() -> String {
+    let mut line = String::new();
+    io::stdin().read_line(&mut line).unwrap();
+    line.trim_end_matches(['\n', '\r']).to_string()
+}
+"##;
+
+fn test_stub_builder(generator: &str, expected: &str) {
+    let received = stub::generate("rust", generator).unwrap();
+    let received = received
+        .strip_prefix(READ_LINE_HELPER)
+        .expect("the stub should start with the read_line helper")
+        .trim();
+    let expected = expected.trim();
+
+    assert_eq!(expected.lines().count(), received.lines().count());
+    for (r, e) in expected.lines().zip(received.lines()) {
+        assert_eq!(r, e)
+    }
+}
+
+#[test]
+fn test_stub_read_1() {
+    let generator = r##"read anInt:int
+read I:int
+read aFloat:float
+read aLong:long
+read aString:string(256)
+read aWord:word(256)
+read    Spaces:string(10)     
+read aBOOL:bool
+"##;
+    let expected = r##"fn main() {
+    let an_int: i32 = read_line().trim().parse().unwrap();
+    let i: i32 = read_line().trim().parse().unwrap();
+    let a_float: f64 = read_line().trim().parse().unwrap();
+    let a_long: i64 = read_line().trim().parse().unwrap();
+    let a_string = read_line();
+    let a_word = read_line().trim().to_string();
+    let spaces = read_line();
+    let a_bool = read_line().trim() != "0";
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_loopline_count_avoids_variables() {
+    let generator = r##"read inputs:int
+loopline inputs a:int b:int
+read line:int
+loopline line count:int c:int
+"##;
+    let expected = r##"fn main() {
+    let inputs: i32 = read_line().trim().parse().unwrap();
+    {
+        // The count is read before anything named line or inputs is shadowed
+        let count = inputs;
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        for _ in 0..count {
+            let (a, b): (i32, i32) = (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().parse().unwrap());
+        }
+    }
+    let line: i32 = read_line().trim().parse().unwrap();
+    {
+        // The count is read before anything named line or inputs is shadowed
+        let count = line;
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        for _ in 0..count {
+            let (count, c): (i32, i32) = (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().parse().unwrap());
+        }
+    }
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_read_2() {
+    let generator = r##"read x:int y:int
+read x:int y:float
+read two:word(50) words:word(50)
+read aWord:word(50) x:int
+read inputs:string(256)
+"##;
+    let expected = r##"fn main() {
+    let (x, y): (i32, i32) = {
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().parse().unwrap())
+    };
+    let (x, y): (i32, f64) = {
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().parse().unwrap())
+    };
+    let (two, words): (String, String) = {
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        (inputs.next().unwrap().to_string(), inputs.next().unwrap().to_string())
+    };
+    let (a_word, x): (String, i32) = {
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        (inputs.next().unwrap().to_string(), inputs.next().unwrap().parse().unwrap())
+    };
+    let inputs = read_line();
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_loop() {
+    let generator = r##"read nLoop:int
+loop nLoop read anInt:int
+loop nLoop read anInt:int aFloat:float aWord:word(256)
+read nLoopLines:int
+loopline nLoopLines anInt:int
+loopline nLoopLines aFloat:float aLong:long
+loop nLoop loopline 5 word:word(1)
+loop nLoop loopline 5 string:string(1)
+"##;
+    let expected = r##"fn main() {
+    let n_loop: i32 = read_line().trim().parse().unwrap();
+    for _ in 0..n_loop {
+        let an_int: i32 = read_line().trim().parse().unwrap();
+    }
+    for _ in 0..n_loop {
+        let (an_int, a_float, a_word): (i32, f64, String) = {
+            let line = read_line();
+            let mut inputs = line.split_whitespace();
+            (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().to_string())
+        };
+    }
+    let n_loop_lines: i32 = read_line().trim().parse().unwrap();
+    for an_int in read_line().split_whitespace().map(|i| i.parse::<i32>().unwrap()) {
+    }
+    {
+        // The count is read before anything named line or inputs is shadowed
+        let count = n_loop_lines;
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        for _ in 0..count {
+            let (a_float, a_long): (f64, i64) = (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().parse().unwrap());
+        }
+    }
+    for _ in 0..n_loop {
+        for word in read_line().split_whitespace() {
+        }
+    }
+    for _ in 0..n_loop {
+        for string in read_line().split_whitespace() {
+        }
+    }
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_write_1() {
+    let generator = r##"write Never
+
+read n:int
+loop n write gonna
+
+loop n loop n write let
+
+write you
+down
+
+write write
+
+write    care, here   spaces   everywhere    
+    and some  more   
+
+write and dont do this, this breaks paitong
+
+write "
+'
+"
+"##;
+    let expected = r##"fn main() {
+    println!("Never");
+    let n: i32 = read_line().trim().parse().unwrap();
+    for _ in 0..n {
+        println!("gonna");
+    }
+    for _ in 0..n {
+        for _ in 0..n {
+            println!("let");
+        }
+    }
+    println!("you");
+    println!("down");
+    println!("write");
+    println!("care, here   spaces   everywhere");
+    println!("and some  more");
+    println!("and dont do this, this breaks paitong");
+    println!("\"");
+    println!("'");
+    println!("\"");
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_write_2() {
+    let generator = r##"read n:int
+read aBc:int
+
+write join("a", "b")
+write join("a", n)
+write join(n, "a")
+write join(n, aBc)
+
+write THIS IS IGNORED join(n, aBc)
+write something join(n, aBc, n) something
+
+write join(n, "potato", aBc, n)
+"##;
+    let expected = r##"fn main() {
+    let n: i32 = read_line().trim().parse().unwrap();
+    let a_bc: i32 = read_line().trim().parse().unwrap();
+    println!("a b");
+    println!("a {n}");
+    println!("{n} a");
+    println!("{n} {a_bc}");
+    println!("{n} {a_bc}");
+    println!("{n} {a_bc} {n}");
+    println!("{n} potato {a_bc} {n}");
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_write_3() {
+    let generator = r##"read n:int
+read aBc:int
+write join(  "  le  "  ,  " spaces  "  )
+
+write join(
+
+write join (baited)
+This only works because the next join gets parsed as raw text 
+write join(       ) 
+
+write join("a")
+write join("b") join("IGNORED")
+write join("c") join(
+
+write join(join("a"), n, join("b")))))
+
+write join("d", write("writeception"))
+write join("d", write(join("a", aBc)))
+write join("d", write join("a", aBc) )
+"##;
+    let expected = r##"fn main() {
+    let n: i32 = read_line().trim().parse().unwrap();
+    let a_bc: i32 = read_line().trim().parse().unwrap();
+    println!("  le    spaces  ");
+    println!("join(");
+    println!("join (baited)");
+    println!("This only works because the next join gets parsed as raw text");
+    println!("write join(       )");
+    println!("a");
+    println!("b");
+    println!("c");
+    println!("a");
+    println!("d writeception");
+    println!("d a {a_bc}");
+    println!("d a {a_bc}");
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_write_4() {
+    let generator = r##"read NONSENSE:int
+write join("hi", NONSENSE INJECTED "it's me Jim" WHAT THE F$)
+write join("hi", NONSENSE, INJECTED "it's me Jim" WHAT THE F$)
+write join("hi",,, "Jim")
+
+write join(join("hi" , (((( "Jim") )
+
+write join("NEVER") IGNORED join("GONNA")
+write join() IGNORED join("GONNA")
+write join() LET
+
+write join("YOU", join("JOIN"))
+"##;
+    let expected = r##"fn main() {
+    let nonsense: i32 = read_line().trim().parse().unwrap();
+    println!("hi it's me Jim");
+    println!("hi {nonsense} it's me Jim");
+    println!("join(\"hi\",,, \"Jim\")");
+    println!("hi Jim");
+    println!("NEVER");
+    println!("GONNA");
+    println!("join() LET");
+    println!("YOU JOIN");
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_statement() {
+    let generator = r##"STATEMENT
+There can only be one per stub
+
+STATEMENT this gets ignored
+This should start here
+STATEMENT
+override the previous statement
+     and end here (no spaces both sides)   
+
+
+read n:int
+loop n loop n loop n write crazy
+right?
+"##;
+    let expected = r##"// This should start here
+// STATEMENT
+// override the previous statement
+// and end here (no spaces both sides)
+
+fn main() {
+    let n: i32 = read_line().trim().parse().unwrap();
+    for _ in 0..n {
+        for _ in 0..n {
+            for _ in 0..n {
+                println!("crazy");
+                println!("right?");
+            }
+        }
+    }
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_output() {
+    let generator = r##"write answer0
+
+write answer1
+
+OUTPUT
+This goes to answer 0 and 1
+OUTPUT
+Together with this
+
+OUTPUT
+THis goes to Narnia
+
+write answer2
+
+OUTPUT       oops!
+This goes to answer 2 but not 3
+
+write answer3
+STATEMENT     
+baited,     care spaces
+
+STATEMENT
+Hello world!
+"##;
+    let expected = r##"// Hello world!
+
+fn main() {
+    // This goes to answer 0 and 1
+    // OUTPUT
+    // Together with this
+    println!("answer0");
+    // This goes to answer 0 and 1
+    // OUTPUT
+    // Together with this
+    println!("answer1");
+    // This goes to answer 2 but not 3
+    println!("answer2");
+    println!("answer3");
+    println!("STATEMENT");
+    println!("baited,     care spaces");
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_input_1() {
+    let generator = r##"read init:int
+read x:int
+
+INPUT   ignored
+x: some variable
+y: some other inexistent variable
+
+read x:int
+
+INPUT
+y: some variable that now exists but gets ignored due to previous INPUT
+
+read two:word(50) words:word(50)
+read i:int f:float
+
+INPUT
+init: the first variable
+words:         some words
+
+INPUT
+two:a number, duh
+i: int
+f: float
+"##;
+    let expected = r##"fn main() {
+    let init: i32 = read_line().trim().parse().unwrap(); // the first variable
+    let x: i32 = read_line().trim().parse().unwrap(); // some variable
+    let x: i32 = read_line().trim().parse().unwrap();
+    // two: a number, duh
+    // words: some words
+    let (two, words): (String, String) = {
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        (inputs.next().unwrap().to_string(), inputs.next().unwrap().to_string())
+    };
+    // i: int
+    // f: float
+    let (i, f): (i32, f64) = {
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().parse().unwrap())
+    };
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_input_2() {
+    let generator = r##"read A:int
+
+INPUT case sensitive???
+a : NEIN NEIN
+A : ????
+"##;
+
+    let expected = r##"fn main() {
+    let a: i32 = read_line().trim().parse().unwrap(); // ????
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_everything() {
+    let generator = r##"write many  spaces   here
+
+read L:string(20)
+
+OUTPUT
+The spacemaster
+
+read a:word(50) b:word(50)
+read aBc:string(256)
+read ROW:string(1024)
+
+INPUT
+ROW: Your boat
+This is ignored
+aBc: The alphabet
+
+loop N read EXT:word(100) MT:word(100)
+loop N read count:int name:word(50)
+
+loop Q read FNAME:string(500)
+
+loop 4 read number:int
+
+loop 4 write 0 0
+
+STATEMENT
+Head, shoulders knees and toes
+Knees and toes
+
+read xCount:int
+loopline xCount x:int
+loopline xCount y:int z:word(50)
+"##;
+    let expected = r##"// Head, shoulders knees and toes
+// Knees and toes
+
+fn main() {
+    // The spacemaster
+    println!("many  spaces   here");
+    let l = read_line();
+    let (a, b): (String, String) = {
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        (inputs.next().unwrap().to_string(), inputs.next().unwrap().to_string())
+    };
+    let a_bc = read_line(); // The alphabet
+    let row = read_line(); // Your boat
+    for _ in 0..n {
+        let (ext, mt): (String, String) = {
+            let line = read_line();
+            let mut inputs = line.split_whitespace();
+            (inputs.next().unwrap().to_string(), inputs.next().unwrap().to_string())
+        };
+    }
+    for _ in 0..n {
+        let (count, name): (i32, String) = {
+            let line = read_line();
+            let mut inputs = line.split_whitespace();
+            (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().to_string())
+        };
+    }
+    for _ in 0..q {
+        let fname = read_line();
+    }
+    for _ in 0..4 {
+        let number: i32 = read_line().trim().parse().unwrap();
+    }
+    for _ in 0..4 {
+        println!("0 0");
+    }
+    let x_count: i32 = read_line().trim().parse().unwrap();
+    for x in read_line().split_whitespace().map(|i| i.parse::<i32>().unwrap()) {
+    }
+    {
+        // The count is read before anything named line or inputs is shadowed
+        let count = x_count;
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        for _ in 0..count {
+            let (y, z): (i32, String) = (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().to_string());
+        }
+    }
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_loops_spaces_and_newlines() {
+    let generator = r##"read n:int
+loop  
+  n    
+    
+
+  loop 4 
+write thing
+
+loop n    
+  loop 4      
+  loopline
+n x:int"##;
+    let expected = r##"fn main() {
+    let n: i32 = read_line().trim().parse().unwrap();
+    for _ in 0..n {
+        for _ in 0..4 {
+            println!("thing");
+        }
+    }
+    for _ in 0..n {
+        for _ in 0..4 {
+            for x in read_line().split_whitespace().map(|k| k.parse::<i32>().unwrap()) {
+            }
+        }
+    }
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_variable_length() {
+    let generator = r##"read n:int
+read k:string(n)
+loop n read a:string(5)
+write answer
+
+INPUT
+k: this string is n-sized (irrelevant in python but be wary!)
+"##;
+    let expected = r##"fn main() {
+    let n: i32 = read_line().trim().parse().unwrap();
+    let k = read_line(); // this string is n-sized (irrelevant in python but be wary!)
+    for _ in 0..n {
+        let a = read_line();
+    }
+    println!("answer");
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_stub_summary() {
+    let generator = r##"read anInt:int
+read aFloat:float
+read Long:long
+read aWord:word(1)
+read boolean:bool
+read ABC1ABc1aBC1AbC1abc1:int
+read STRING:string(256)
+read anInt2:int aFloat2:float Long2:long aWord2:word(1) boolean2:bool
+loop anInt read x:int
+loop anInt read x:int f:float
+loop anInt loop anInt read x:int y:int
+loopline anInt x:int
+loopline anInt w:word(50)
+loopline anInt x:int f:float w:word(50)
+write result
+
+OUTPUT
+An output comment
+
+write join(anInt, aFloat, Long, boolean)
+
+write join(aWord, "literal", STRING)
+
+STATEMENT
+This is the statement
+
+INPUT
+anInt: An input comment over anInt
+"##;
+    let expected = r##"// This is the statement
+
+fn main() {
+    let an_int: i32 = read_line().trim().parse().unwrap(); // An input comment over anInt
+    let a_float: f64 = read_line().trim().parse().unwrap();
+    let long: i64 = read_line().trim().parse().unwrap();
+    let a_word = read_line().trim().to_string();
+    let boolean = read_line().trim() != "0";
+    let abc1abc_1a_bc1ab_c1abc_1: i32 = read_line().trim().parse().unwrap();
+    let string = read_line();
+    let (an_int_2, a_float_2, long_2, a_word_2, boolean_2): (i32, f64, i64, String, bool) = {
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().to_string(), inputs.next().unwrap() != "0")
+    };
+    for _ in 0..an_int {
+        let x: i32 = read_line().trim().parse().unwrap();
+    }
+    for _ in 0..an_int {
+        let (x, f): (i32, f64) = {
+            let line = read_line();
+            let mut inputs = line.split_whitespace();
+            (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().parse().unwrap())
+        };
+    }
+    for _ in 0..an_int {
+        for _ in 0..an_int {
+            let (x, y): (i32, i32) = {
+                let line = read_line();
+                let mut inputs = line.split_whitespace();
+                (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().parse().unwrap())
+            };
+        }
+    }
+    for x in read_line().split_whitespace().map(|i| i.parse::<i32>().unwrap()) {
+    }
+    for w in read_line().split_whitespace() {
+    }
+    {
+        // The count is read before anything named line or inputs is shadowed
+        let count = an_int;
+        let line = read_line();
+        let mut inputs = line.split_whitespace();
+        for _ in 0..count {
+            let (x, f, w): (i32, f64, String) = (inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().parse().unwrap(), inputs.next().unwrap().to_string());
+        }
+    }
+    // An output comment
+    println!("result");
+    println!("{an_int} {a_float} {long} {boolean}");
+    println!("{a_word} literal {string}");
+}
+"##;
+
+    test_stub_builder(generator, expected);
+}
+
+#[test]
+fn test_reference_stub_compiles() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stub.rs");
+    std::fs::write(&path, stub::generate("rust", stub::SIMPLE_REFERENCE_STUB).unwrap().as_str()).unwrap();
+    let status = std::process::Command::new("rustc")
+        .args([
+            "--edition",
+            "2021",
+            "--crate-type",
+            "bin",
+            "--emit=metadata",
+            "--out-dir",
+        ])
+        .arg(dir.path())
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}